@@ -1,9 +1,127 @@
 use serde::Deserialize;
 
+#[derive(Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageBackend {
+    #[default]
+    Local,
+    S3,
+}
+
 #[derive(Deserialize, Clone, Debug, Default)]
 pub struct Config {
     pub database_url: String,
     pub jwt_secret: String,
+    /// Upload size limit in bytes
+    #[serde(default = "default_max_upload_size")]
+    pub max_upload_size: usize,
+    #[serde(default)]
+    pub storage_backend: StorageBackend,
+    /// Root directory for the local storage backend
+    #[serde(default = "default_storage_path")]
+    pub storage_path: String,
+    pub s3_endpoint: Option<String>,
+    pub s3_bucket: Option<String>,
+    #[serde(default = "default_s3_region")]
+    pub s3_region: String,
+    pub s3_access_key: Option<String>,
+    pub s3_secret_key: Option<String>,
+    /// Total upload size allowed per user, in bytes
+    #[serde(default = "default_user_storage_quota")]
+    pub user_storage_quota: i64,
+    /// Total upload size allowed per workspace, in bytes
+    #[serde(default = "default_workspace_storage_quota")]
+    pub workspace_storage_quota: i64,
+    /// Seconds between attachment garbage collection runs
+    #[serde(default = "default_attachment_gc_interval")]
+    pub attachment_gc_interval: u64,
+    /// Seconds an attachment stays unreferenced before it is deleted
+    #[serde(default = "default_attachment_gc_grace_period")]
+    pub attachment_gc_grace_period: i64,
+    /// Seconds a bookmark preview fetch may take
+    #[serde(default = "default_link_preview_timeout")]
+    pub link_preview_timeout: u64,
+    /// Largest page read for a bookmark preview, in bytes
+    #[serde(default = "default_link_preview_max_size")]
+    pub link_preview_max_size: usize,
+    #[serde(default = "default_link_preview_max_redirects")]
+    pub link_preview_max_redirects: usize,
+    /// Lets previews reach private and loopback addresses, for development only
+    #[serde(default)]
+    pub link_preview_allow_private: bool,
+    /// Program rendering LaTeX from stdin to SVG on stdout, e.g. a MathJax CLI
+    pub math_render_command: Option<String>,
+    /// Program rendering Mermaid from stdin to SVG on stdout, e.g. `mmdc -i - -o - -e svg`
+    pub diagram_render_command: Option<String>,
+    /// Seconds a render command may take
+    #[serde(default = "default_render_timeout")]
+    pub render_timeout: u64,
+    /// Seconds a webhook endpoint has to answer
+    #[serde(default = "default_webhook_timeout")]
+    pub webhook_timeout: u64,
+    /// Seconds between runs of the webhook delivery worker
+    #[serde(default = "default_webhook_worker_interval")]
+    pub webhook_worker_interval: u64,
+    /// Lets webhooks reach private and loopback addresses, for development only
+    #[serde(default)]
+    pub webhook_allow_private: bool,
+    /// Number of proxies in front of the server appending to
+    /// `X-Forwarded-For`. The client address for the audit log is the entry
+    /// the outermost of them added, 0 ignores the header.
+    #[serde(default)]
+    pub trusted_proxies: usize,
+}
+
+fn default_max_upload_size() -> usize {
+    10 * 1024 * 1024
+}
+
+fn default_user_storage_quota() -> i64 {
+    1024 * 1024 * 1024
+}
+
+fn default_workspace_storage_quota() -> i64 {
+    512 * 1024 * 1024
+}
+
+fn default_attachment_gc_interval() -> u64 {
+    60 * 60
+}
+
+fn default_attachment_gc_grace_period() -> i64 {
+    24 * 60 * 60
+}
+
+fn default_link_preview_timeout() -> u64 {
+    5
+}
+
+fn default_link_preview_max_size() -> usize {
+    512 * 1024
+}
+
+fn default_link_preview_max_redirects() -> usize {
+    3
+}
+
+fn default_render_timeout() -> u64 {
+    10
+}
+
+fn default_webhook_timeout() -> u64 {
+    10
+}
+
+fn default_webhook_worker_interval() -> u64 {
+    5
+}
+
+fn default_storage_path() -> String {
+    "./uploads".to_string()
+}
+
+fn default_s3_region() -> String {
+    "us-east-1".to_string()
 }
 
 impl Config {
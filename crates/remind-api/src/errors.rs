@@ -1,9 +1,12 @@
+use crate::routes::etag;
 use axum::Json;
+use axum::extract::multipart::MultipartError;
 use axum::extract::rejection::JsonRejection;
-use axum::http::StatusCode;
+use axum::http::{HeaderMap, HeaderValue, StatusCode, header};
 use axum::response::{IntoResponse, Response};
 use remind_core::errors::{AuthError, CoreError};
-use serde_json::json;
+use serde::Serialize;
+use serde_json::{Value, json};
 use validator::ValidationErrors;
 
 #[derive(Debug, thiserror::Error)]
@@ -14,15 +17,33 @@ pub enum ApiError {
     ValidationError(#[from] ValidationErrors),
     #[error(transparent)]
     AxumJsonRejection(#[from] JsonRejection),
+    #[error(transparent)]
+    Multipart(#[from] MultipartError),
+    #[error("{0}")]
+    BadRequest(String),
+    #[error("If-Match header is required")]
+    PreconditionRequired,
+    /// Stale `If-Match`, with the current state of the resource
+    #[error("Changed by someone else in the meantime")]
+    PreconditionFailed { version: i64, current: Value },
 }
 
 pub type Result<T> = core::result::Result<T, ApiError>;
 
-impl IntoResponse for ApiError {
-    fn into_response(self) -> Response {
+impl ApiError {
+    /// 412 for an edit of an outdated version, carrying the current one
+    pub(crate) fn stale(version: i64, current: impl Serialize) -> Self {
+        ApiError::PreconditionFailed {
+            version,
+            current: serde_json::to_value(current).unwrap_or_default(),
+        }
+    }
+
+    /// Status code and message shown to the client
+    pub(crate) fn status_and_message(self) -> (StatusCode, String) {
         let msg = self.to_string();
         tracing::error!("New error: {}", msg);
-        let (status, message) = match self {
+        match self {
             ApiError::CoreError(e) => match e {
                 CoreError::Database(_) => (
                     StatusCode::INTERNAL_SERVER_ERROR,
@@ -38,22 +59,67 @@ impl IntoResponse for ApiError {
                 },
                 CoreError::ServerError => (StatusCode::INTERNAL_SERVER_ERROR, msg),
                 CoreError::NotFound => (StatusCode::NOT_FOUND, msg),
+                CoreError::AlreadyExists => (StatusCode::CONFLICT, msg),
                 CoreError::TooManyWorkspaces => (StatusCode::FORBIDDEN, msg),
                 CoreError::AccessDenied => (StatusCode::FORBIDDEN, msg),
                 CoreError::BlockTypeNotMatches => (StatusCode::BAD_REQUEST, msg),
+                CoreError::SharePasswordInvalid => (StatusCode::UNAUTHORIZED, msg),
+                CoreError::Storage(_) => (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Server error".to_string(),
+                ),
+                CoreError::FileTooLarge(_) => (StatusCode::PAYLOAD_TOO_LARGE, msg),
+                CoreError::NotAnImage => (StatusCode::BAD_REQUEST, msg),
+                CoreError::StorageQuotaExceeded => (StatusCode::FORBIDDEN, msg),
+                CoreError::InvalidRichText(_) => (StatusCode::BAD_REQUEST, msg),
+                CoreError::InvalidBlockContent(_) => (StatusCode::BAD_REQUEST, msg),
+                CoreError::InvalidBlockParent(_) => (StatusCode::BAD_REQUEST, msg),
+                CoreError::InvalidBlockOrder(_) => (StatusCode::BAD_REQUEST, msg),
+                CoreError::InvalidProperty(_) => (StatusCode::BAD_REQUEST, msg),
+                CoreError::InvalidView(_) => (StatusCode::BAD_REQUEST, msg),
+                CoreError::InvalidTag(_) => (StatusCode::BAD_REQUEST, msg),
+                CoreError::InvalidWebhook(_) => (StatusCode::BAD_REQUEST, msg),
+                CoreError::InvalidComment(_) => (StatusCode::BAD_REQUEST, msg),
+                CoreError::InvalidTemplate(_) => (StatusCode::BAD_REQUEST, msg),
+                CoreError::VersionMismatch => (StatusCode::PRECONDITION_FAILED, msg),
+                CoreError::TextOutOfSync(_) => (StatusCode::CONFLICT, msg),
+                CoreError::ConcurrentEdits => (StatusCode::CONFLICT, msg),
+                CoreError::LinkPreviewBlocked => (StatusCode::BAD_REQUEST, msg),
+                CoreError::LinkPreview(_) => (StatusCode::BAD_GATEWAY, msg),
+                CoreError::Render(_) => (StatusCode::INTERNAL_SERVER_ERROR, msg),
             },
             Self::ValidationError(_) => (
                 StatusCode::BAD_REQUEST,
                 format!("Validation errors: [{}]", self).replace('\n', ","),
             ),
+            Self::Multipart(e) => (e.status(), msg),
+            Self::PreconditionRequired => (StatusCode::PRECONDITION_REQUIRED, msg),
+            Self::PreconditionFailed { .. } => (StatusCode::PRECONDITION_FAILED, msg),
             _ => (StatusCode::BAD_REQUEST, msg),
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let stale = match &self {
+            ApiError::PreconditionFailed { version, current } => Some((*version, current.clone())),
+            _ => None,
         };
+        let (status, message) = self.status_and_message();
 
-        (
-            status,
-            [(axum::http::header::CONTENT_TYPE, "application/json")],
-            Json(json!({ "status_code": status.as_u16(), "message": message })),
-        )
-            .into_response()
+        let mut body = json!({ "status_code": status.as_u16(), "message": message });
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/json"),
+        );
+        if let Some((version, current)) = stale {
+            body["current"] = current;
+            if let Ok(value) = etag(version).parse() {
+                headers.insert(header::ETAG, value);
+            }
+        }
+        (status, headers, Json(body)).into_response()
     }
 }
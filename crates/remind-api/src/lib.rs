@@ -0,0 +1,76 @@
+pub mod config;
+pub mod errors;
+pub mod routes;
+pub mod schemas;
+pub mod state;
+pub mod tasks;
+pub mod utils;
+
+use crate::state::AppState;
+use axum::extract::Request;
+use axum::http::HeaderValue;
+use axum::routing::get;
+use axum::{Json, Router, http};
+use serde_json::json;
+use tower_http::cors::CorsLayer;
+use tower_http::trace::TraceLayer;
+
+/// Routes of the API with their middleware. Serve it with
+/// `into_make_service_with_connect_info::<SocketAddr>()`, the audit log
+/// reads the address of the client from it.
+pub fn app(state: AppState) -> Router {
+    let cors = CorsLayer::new()
+        .allow_methods([
+            http::Method::GET,
+            http::Method::POST,
+            http::Method::PUT,
+            http::Method::DELETE,
+            http::Method::OPTIONS,
+        ])
+        .allow_origin("http://localhost:3000".parse::<HeaderValue>().unwrap())
+        .allow_headers([
+            http::header::CONTENT_TYPE,
+            http::header::AUTHORIZATION,
+            http::HeaderName::from_static(routes::public::SHARE_PASSWORD_HEADER),
+        ])
+        .allow_credentials(false);
+    Router::new()
+        .route(
+            "/",
+            get(|| async { Json(json!({"message": "Hello, world!", "ok": true})) }),
+        )
+        .nest("/auth", routes::auth::router(state.clone()))
+        .nest("/workspaces", routes::workspace::router(state.clone()))
+        .nest("/notes", routes::note::router(state.clone()))
+        .nest("/blocks", routes::block::router(state.clone()))
+        .nest("/collections", routes::collection::router(state.clone()))
+        .nest("/attachments", routes::attachment::router(state.clone()))
+        .nest("/tags", routes::tag::router(state.clone()))
+        .nest("/templates", routes::template::router(state.clone()))
+        .nest("/comments", routes::comment::router(state.clone()))
+        .nest(
+            "/notifications",
+            routes::notification::router(state.clone()),
+        )
+        .nest("/sync", routes::sync::router(state.clone()))
+        .nest("/webhooks", routes::webhook::router(state.clone()))
+        .nest("/public", routes::public::router())
+        .fallback(routes::handler_404)
+        .layer(
+            TraceLayer::new_for_http().make_span_with(|request: &Request<_>| {
+                let matched_path = request
+                    .extensions()
+                    .get::<axum::extract::MatchedPath>()
+                    .map(axum::extract::MatchedPath::as_str);
+
+                tracing::info_span!(
+                    "http_request",
+                    method = ?request.method(),
+                    matched_path,
+                    some_other_field = tracing::field::Empty,
+                )
+            }),
+        )
+        .layer(cors)
+        .with_state(state)
+}
@@ -0,0 +1,148 @@
+use crate::errors::{ApiError, Result};
+use crate::schemas::OkResponseSchema;
+use crate::schemas::attachment::{AttachmentContentQuery, AttachmentSchema};
+use crate::state::AppState;
+use axum::extract::{DefaultBodyLimit, Multipart, Path, Query, State};
+use axum::http::header;
+use axum::response::IntoResponse;
+use axum::routing::{delete, get, post};
+use axum::{Extension, Json, Router};
+use remind_core::errors::CoreError;
+use remind_core::storage::is_inline_content_type;
+use remind_core::{AttachmentCreateDTO, AttachmentDTO, UserDTO};
+use uuid::Uuid;
+
+/// Room for multipart boundaries and the non-file fields
+const MULTIPART_OVERHEAD: usize = 64 * 1024;
+
+pub(crate) fn router(state: AppState) -> Router<AppState> {
+    let body_limit = state.config.max_upload_size + MULTIPART_OVERHEAD;
+    Router::new()
+        .route("/", post(upload_attachment))
+        .route("/{id}", get(get_attachment))
+        .route("/{id}", delete(delete_attachment))
+        .route("/{id}/content", get(get_attachment_content))
+        .layer(DefaultBodyLimit::max(body_limit))
+        .layer(axum::middleware::from_fn_with_state(
+            state,
+            super::auth_middleware,
+        ))
+}
+
+/// Expects a `workspace_id` text field and a `file` field
+async fn upload_attachment(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserDTO>,
+    mut multipart: Multipart,
+) -> Result<Json<AttachmentSchema>> {
+    let mut workspace_id: Option<Uuid> = None;
+    let mut file: Option<(String, Vec<u8>)> = None;
+
+    while let Some(field) = multipart.next_field().await? {
+        match field.name() {
+            Some("workspace_id") => {
+                let text = field.text().await?;
+                workspace_id = Some(
+                    Uuid::parse_str(text.trim())
+                        .map_err(|_| ApiError::BadRequest("Invalid workspace_id".to_string()))?,
+                );
+            }
+            Some("file") => {
+                let file_name = field.file_name().unwrap_or("file").to_string();
+                let data = field.bytes().await?;
+                file = Some((file_name, data.to_vec()));
+            }
+            _ => continue,
+        }
+    }
+
+    let workspace_id =
+        workspace_id.ok_or(ApiError::BadRequest("Missing workspace_id".to_string()))?;
+    let (file_name, data) = file.ok_or(ApiError::BadRequest("Missing file".to_string()))?;
+
+    let workspace = state.workspace_service.get(workspace_id).await?;
+    if workspace.user_id != user.id {
+        return Err(CoreError::AccessDenied.into());
+    }
+
+    let attachment = state
+        .attachment_service
+        .upload(AttachmentCreateDTO {
+            workspace_id,
+            user_id: user.id,
+            file_name,
+            data,
+        })
+        .await?;
+    Ok(Json(attachment.into()))
+}
+
+async fn get_attachment(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserDTO>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<AttachmentSchema>> {
+    let attachment = state.attachment_service.find_one(id).await?;
+    let workspace = state.workspace_service.get(attachment.workspace_id).await?;
+    if workspace.user_id != user.id {
+        return Err(CoreError::AccessDenied.into());
+    }
+
+    Ok(Json(attachment.into()))
+}
+
+async fn get_attachment_content(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserDTO>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<AttachmentContentQuery>,
+) -> Result<impl IntoResponse> {
+    let attachment = state.attachment_service.find_one(id).await?;
+    let workspace = state.workspace_service.get(attachment.workspace_id).await?;
+    if workspace.user_id != user.id {
+        return Err(CoreError::AccessDenied.into());
+    }
+
+    let (attachment, data) = state.attachment_service.get_content(id, query.w).await?;
+    Ok(content_response(attachment, data))
+}
+
+/// Content of an attachment under its file name. Only raster images are
+/// shown inline, the rest is downloaded, and nothing runs as a document.
+pub(super) fn content_response(attachment: AttachmentDTO, data: Vec<u8>) -> impl IntoResponse {
+    let file_name = attachment.file_name.replace('"', "");
+    let (content_type, disposition) = match is_inline_content_type(&attachment.content_type) {
+        true => (
+            attachment.content_type,
+            format!("inline; filename=\"{file_name}\""),
+        ),
+        false => (
+            "application/octet-stream".to_string(),
+            format!("attachment; filename=\"{file_name}\""),
+        ),
+    };
+    (
+        [
+            (header::CONTENT_TYPE, content_type),
+            (header::CONTENT_DISPOSITION, disposition),
+            (header::X_CONTENT_TYPE_OPTIONS, "nosniff".to_string()),
+            (header::CONTENT_SECURITY_POLICY, "sandbox".to_string()),
+        ],
+        data,
+    )
+}
+
+async fn delete_attachment(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserDTO>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<OkResponseSchema>> {
+    let attachment = state.attachment_service.find_one(id).await?;
+    let workspace = state.workspace_service.get(attachment.workspace_id).await?;
+    if workspace.user_id != user.id {
+        return Err(CoreError::AccessDenied.into());
+    }
+
+    state.attachment_service.delete(id).await?;
+    Ok(Json(OkResponseSchema::new(true)))
+}
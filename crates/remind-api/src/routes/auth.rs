@@ -1,11 +1,13 @@
-use crate::errors::Result;
+use crate::errors::{ApiError, Result};
+use crate::schemas::audit::{AuditPageSchema, AuditQuery, DEFAULT_AUDIT_LIMIT};
 use crate::schemas::auth::{
     AuthTokenSchema, LoginByEmailSchema, LoginByUsernameSchema, RegisterUserSchema,
 };
-use crate::schemas::user::UserSchema;
+use crate::schemas::user::{MeSchema, UserSchema, UserStorageSchema, WorkspaceStorageUsageSchema};
 use crate::state::AppState;
+use crate::utils::audit::RequestContext;
 use crate::utils::validator::ValidatedJson;
-use axum::extract::State;
+use axum::extract::{Query, State};
 use axum::routing::{get, post};
 use axum::{Extension, Json, Router};
 use remind_core::UserDTO;
@@ -15,12 +17,14 @@ pub(crate) fn router(state: AppState) -> Router<AppState> {
         .route("/register", post(register))
         .route("/login/username", post(login_by_username))
         .route("/login/email", post(login_by_email))
-        .route(
-            "/me",
-            get(get_me).layer(axum::middleware::from_fn_with_state(
-                state,
-                super::auth_middleware,
-            )),
+        .merge(
+            Router::new()
+                .route("/me", get(get_me))
+                .route("/me/audit", get(get_my_audit))
+                .layer(axum::middleware::from_fn_with_state(
+                    state,
+                    super::auth_middleware,
+                )),
         )
 }
 
@@ -36,10 +40,11 @@ async fn register(
 
 async fn login_by_username(
     State(state): State<AppState>,
+    RequestContext(context): RequestContext,
     Json(data): Json<LoginByUsernameSchema>,
 ) -> Result<Json<AuthTokenSchema>> {
     let dto = data.into();
-    let token = state.user_service.login_by_username(dto).await?;
+    let token = state.user_service.login_by_username(dto, &context).await?;
     Ok(Json(AuthTokenSchema {
         access_token: token,
         token_type: "Bearer".to_string(),
@@ -48,16 +53,55 @@ async fn login_by_username(
 
 async fn login_by_email(
     State(state): State<AppState>,
+    RequestContext(context): RequestContext,
     ValidatedJson(data): ValidatedJson<LoginByEmailSchema>,
 ) -> Result<Json<AuthTokenSchema>> {
     let dto = data.into();
-    let token = state.user_service.login_by_email(dto).await?;
+    let token = state.user_service.login_by_email(dto, &context).await?;
     Ok(Json(AuthTokenSchema {
         access_token: token,
         token_type: "Bearer".to_string(),
     }))
 }
 
-async fn get_me(Extension(user): Extension<UserDTO>) -> Json<UserSchema> {
-    Json(user.into())
+/// `GET /auth/me/audit`: logins and token creations of the account, newest
+/// first, with the filters of the workspace audit log
+async fn get_my_audit(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserDTO>,
+    Query(query): Query<AuditQuery>,
+) -> Result<Json<AuditPageSchema>> {
+    let filter = query.filter().map_err(ApiError::BadRequest)?;
+    let limit = query.limit.unwrap_or(DEFAULT_AUDIT_LIMIT);
+    let entries = state
+        .audit_service
+        .get_for_user(user.id, filter, limit)
+        .await?;
+    Ok(Json(AuditPageSchema::new(entries, limit)))
+}
+
+async fn get_me(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserDTO>,
+) -> Result<Json<MeSchema>> {
+    let usage = state.attachment_service.user_usage(user.id).await?;
+    let mut workspaces = Vec::new();
+    for workspace in state.workspace_service.get_all_by_user(user.id).await? {
+        let usage = state
+            .attachment_service
+            .workspace_usage(workspace.id)
+            .await?;
+        workspaces.push(WorkspaceStorageUsageSchema {
+            workspace_id: workspace.id,
+            usage: usage.into(),
+        });
+    }
+
+    Ok(Json(MeSchema {
+        user: user.into(),
+        storage: UserStorageSchema {
+            usage: usage.into(),
+            workspaces,
+        },
+    }))
 }
@@ -1,12 +1,22 @@
-use crate::errors::Result;
+use crate::errors::{ApiError, Result};
+use crate::routes::collab::{publish_block, publish_deleted};
+use crate::routes::{etag, if_match};
 use crate::schemas::OkResponseSchema;
-use crate::schemas::block::{BlockSchema, CreateBlockSchema, UpdateBlockSchema};
+use crate::schemas::block::{
+    AddTableRowSchema, BlockSchema, CreateBlockSchema, DeleteBlockQuery, EditTextSchema,
+    MoveBlockSchema, TextEditedSchema, TextStateSchema, UpdateBlockSchema, UpdateTableCellSchema,
+};
 use crate::state::AppState;
-use axum::extract::{Path, State};
-use axum::routing::{delete, post, put};
+use crate::utils::audit::RequestContext;
+use axum::extract::{Path, Query, State};
+use axum::http::{HeaderMap, header};
+use axum::response::IntoResponse;
+use axum::routing::{delete, get, post, put};
 use axum::{Extension, Json, Router};
+use remind_core::collab::NoteEvent;
 use remind_core::errors::CoreError;
-use remind_core::{BlockUpdateDTO, UserDTO};
+use remind_core::render::render_table_csv;
+use remind_core::{BlockContent, BlockUpdateDTO, UserDTO};
 use uuid::Uuid;
 
 pub(crate) fn router(state: AppState) -> Router<AppState> {
@@ -14,6 +24,17 @@ pub(crate) fn router(state: AppState) -> Router<AppState> {
         .route("/", post(add_block))
         .route("/{id}", put(update_block))
         .route("/{id}", delete(delete_block))
+        .route("/{id}/parent", put(move_block))
+        .route("/{id}/table/rows", post(add_table_row))
+        .route("/{id}/table/rows/{row_id}", delete(delete_table_row))
+        .route(
+            "/{id}/table/rows/{row_id}/cells/{column_id}",
+            put(update_table_cell),
+        )
+        .route("/{id}/table/csv", get(export_table_csv))
+        .route("/{id}/preview", post(refresh_preview))
+        .route("/{id}/text", get(get_text_state))
+        .route("/{id}/text", post(edit_text))
         .layer(axum::middleware::from_fn_with_state(
             state,
             super::auth_middleware,
@@ -31,6 +52,9 @@ async fn add_block(
         return Err(CoreError::AccessDenied.into());
     }
     let block = state.block_service.create(data.into()).await?;
+    state
+        .collab
+        .publish(block.note_id, NoteEvent::BlockCreated(block.clone()));
     Ok(Json(block.into()))
 }
 
@@ -38,28 +62,103 @@ async fn update_block(
     State(state): State<AppState>,
     Extension(user): Extension<UserDTO>,
     Path(id): Path<Uuid>,
+    headers: HeaderMap,
     Json(data): Json<UpdateBlockSchema>,
-) -> Result<Json<OkResponseSchema>> {
+) -> Result<impl IntoResponse> {
     let block = state.block_service.find_one(id).await?;
     let note = state.note_service.find_one(block.note_id).await?;
     let workspace = state.workspace_service.get(note.workspace_id).await?;
     if workspace.user_id != user.id {
         return Err(CoreError::AccessDenied.into());
     }
+    let version = if_match(&headers)?;
 
     let dto = BlockUpdateDTO {
         id,
         block_type: data.block_type,
         content: data.content,
+        version,
     };
-    state.block_service.update(dto).await?;
-    Ok(Json(OkResponseSchema::new(true)))
+    match state.block_service.update(dto).await {
+        Err(CoreError::VersionMismatch) => {
+            let current = state.block_service.find_one(id).await?;
+            return Err(ApiError::stale(current.version, BlockSchema::from(current)));
+        }
+        result => result?,
+    }
+    let block = publish_block(&state, id).await?;
+    Ok((
+        [(header::ETAG, etag(block.version))],
+        Json(OkResponseSchema::new(true)),
+    ))
 }
 
 async fn delete_block(
     State(state): State<AppState>,
     Extension(user): Extension<UserDTO>,
     Path(id): Path<Uuid>,
+    Query(query): Query<DeleteBlockQuery>,
+    RequestContext(context): RequestContext,
+) -> Result<Json<OkResponseSchema>> {
+    let block = state.block_service.find_one(id).await?;
+    let note = state.note_service.find_one(block.note_id).await?;
+    let workspace = state.workspace_service.get(note.workspace_id).await?;
+    if workspace.user_id != user.id {
+        return Err(CoreError::AccessDenied.into());
+    }
+
+    state
+        .block_service
+        .delete(id, query.promote_children, &context)
+        .await?;
+    let promoted_to = query.promote_children.then_some(block.parent_block);
+    publish_deleted(&state, note.id, id, promoted_to).await?;
+    Ok(Json(OkResponseSchema::new(true)))
+}
+
+async fn move_block(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserDTO>,
+    Path(id): Path<Uuid>,
+    Json(data): Json<MoveBlockSchema>,
+) -> Result<Json<BlockSchema>> {
+    let block = state.block_service.find_one(id).await?;
+    let note = state.note_service.find_one(block.note_id).await?;
+    let workspace = state.workspace_service.get(note.workspace_id).await?;
+    if workspace.user_id != user.id {
+        return Err(CoreError::AccessDenied.into());
+    }
+
+    let block = state
+        .block_service
+        .move_block(id, data.parent_block)
+        .await?;
+    publish_block(&state, id).await?;
+    Ok(Json(block.into()))
+}
+
+async fn add_table_row(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserDTO>,
+    Path(id): Path<Uuid>,
+    Json(data): Json<AddTableRowSchema>,
+) -> Result<Json<BlockSchema>> {
+    let block = state.block_service.find_one(id).await?;
+    let note = state.note_service.find_one(block.note_id).await?;
+    let workspace = state.workspace_service.get(note.workspace_id).await?;
+    if workspace.user_id != user.id {
+        return Err(CoreError::AccessDenied.into());
+    }
+
+    let block = state.block_service.add_table_row(id, data.cells).await?;
+    publish_block(&state, id).await?;
+    Ok(Json(block.into()))
+}
+
+async fn delete_table_row(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserDTO>,
+    Path((id, row_id)): Path<(Uuid, Uuid)>,
 ) -> Result<Json<OkResponseSchema>> {
     let block = state.block_service.find_one(id).await?;
     let note = state.note_service.find_one(block.note_id).await?;
@@ -68,6 +167,109 @@ async fn delete_block(
         return Err(CoreError::AccessDenied.into());
     }
 
-    state.block_service.delete(id).await?;
+    state.block_service.delete_table_row(id, row_id).await?;
+    publish_block(&state, id).await?;
     Ok(Json(OkResponseSchema::new(true)))
 }
+
+async fn update_table_cell(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserDTO>,
+    Path((id, row_id, column_id)): Path<(Uuid, Uuid, Uuid)>,
+    Json(data): Json<UpdateTableCellSchema>,
+) -> Result<Json<BlockSchema>> {
+    let block = state.block_service.find_one(id).await?;
+    let note = state.note_service.find_one(block.note_id).await?;
+    let workspace = state.workspace_service.get(note.workspace_id).await?;
+    if workspace.user_id != user.id {
+        return Err(CoreError::AccessDenied.into());
+    }
+
+    let block = state
+        .block_service
+        .update_table_cell(id, row_id, column_id, data.value)
+        .await?;
+    publish_block(&state, id).await?;
+    Ok(Json(block.into()))
+}
+
+async fn export_table_csv(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserDTO>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse> {
+    let block = state.block_service.find_one(id).await?;
+    let note = state.note_service.find_one(block.note_id).await?;
+    let workspace = state.workspace_service.get(note.workspace_id).await?;
+    if workspace.user_id != user.id {
+        return Err(CoreError::AccessDenied.into());
+    }
+
+    let BlockContent::Table(table) = &block.content else {
+        return Err(CoreError::InvalidBlockContent("Block is not a table".to_string()).into());
+    };
+    Ok((
+        [(header::CONTENT_TYPE, "text/csv; charset=utf-8")],
+        render_table_csv(table),
+    ))
+}
+
+async fn refresh_preview(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserDTO>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<BlockSchema>> {
+    let block = state.block_service.find_one(id).await?;
+    let note = state.note_service.find_one(block.note_id).await?;
+    let workspace = state.workspace_service.get(note.workspace_id).await?;
+    if workspace.user_id != user.id {
+        return Err(CoreError::AccessDenied.into());
+    }
+
+    let block = state.block_service.refresh_preview(id).await?;
+    publish_block(&state, id).await?;
+    Ok(Json(block.into()))
+}
+
+async fn get_text_state(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserDTO>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<TextStateSchema>> {
+    let block = state.block_service.find_one(id).await?;
+    let note = state.note_service.find_one(block.note_id).await?;
+    let workspace = state.workspace_service.get(note.workspace_id).await?;
+    if workspace.user_id != user.id {
+        return Err(CoreError::AccessDenied.into());
+    }
+
+    let text = state.block_service.get_text_state(id).await?;
+    Ok(Json(TextStateSchema::new(id, text)))
+}
+
+async fn edit_text(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserDTO>,
+    Path(id): Path<Uuid>,
+    Json(data): Json<EditTextSchema>,
+) -> Result<Json<TextEditedSchema>> {
+    let block = state.block_service.find_one(id).await?;
+    let note = state.note_service.find_one(block.note_id).await?;
+    let workspace = state.workspace_service.get(note.workspace_id).await?;
+    if workspace.user_id != user.id {
+        return Err(CoreError::AccessDenied.into());
+    }
+
+    let (block, ops) = state.block_service.merge_text(id, data.ops).await?;
+    state.collab.publish(
+        note.id,
+        NoteEvent::TextEdited {
+            block: block.clone(),
+            ops: ops.clone(),
+        },
+    );
+    Ok(Json(TextEditedSchema {
+        block: block.into(),
+        ops,
+    }))
+}
@@ -0,0 +1,300 @@
+use crate::errors::{ApiError, Result};
+use crate::schemas::collab::{NoteEditSchema, NoteEventSchema};
+use crate::state::AppState;
+use crate::utils::audit::RequestContext;
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Path, State};
+use axum::http::{HeaderMap, header};
+use axum::response::Response;
+use remind_core::collab::{Collaborator, NoteEvent};
+use remind_core::errors::{AuthError, CoreError};
+use remind_core::{AuditContext, BlockCreateDTO, BlockDTO, BlockUpdateDTO, NoteDTO, UserDTO};
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+use uuid::Uuid;
+
+/// Subprotocol of the note socket. Browsers can't set the Authorization
+/// header on sockets, so they offer it along with `bearer.<JWT>`.
+pub(crate) const SOCKET_PROTOCOL: &str = "remind";
+const BEARER_PROTOCOL_PREFIX: &str = "bearer.";
+/// How often an open socket checks that its user still owns the workspace
+const ACCESS_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// `GET /notes/{id}/ws`: live block events of the note and presence of its
+/// viewers. Clients send edits as JSON text messages, the resulting events
+/// come back to every viewer, the sender included. The JWT is read from the
+/// Authorization header or the `Sec-WebSocket-Protocol` header, never from
+/// the URL where it would end up in logs. Access is checked again with every
+/// edit and every few seconds, and the socket is closed once it is lost.
+pub(crate) async fn note_socket(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    headers: HeaderMap,
+    RequestContext(context): RequestContext,
+    upgrade: WebSocketUpgrade,
+) -> Result<Response> {
+    let bearer = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.split_whitespace().nth(1));
+    let offered = headers
+        .get_all(header::SEC_WEBSOCKET_PROTOCOL)
+        .iter()
+        .filter_map(|h| h.to_str().ok())
+        .flat_map(|h| h.split(','))
+        .map(str::trim)
+        .find_map(|p| p.strip_prefix(BEARER_PROTOCOL_PREFIX));
+    let token = bearer
+        .or(offered)
+        .ok_or(CoreError::from(AuthError::InvalidToken))?;
+    let (_, user) = super::authenticate(&state, token).await?;
+
+    let note = state.note_service.find_one(id).await?;
+    check_access(&state, &note, &user).await?;
+
+    let context = context.with_actor(user.id);
+    Ok(upgrade
+        .protocols([SOCKET_PROTOCOL])
+        .on_upgrade(move |socket| run_socket(state, socket, note, user, context)))
+}
+
+/// Whether the user still owns the workspace of the note
+async fn check_access(state: &AppState, note: &NoteDTO, user: &UserDTO) -> Result<()> {
+    let workspace = state.workspace_service.get(note.workspace_id).await?;
+    if workspace.user_id != user.id {
+        return Err(CoreError::AccessDenied.into());
+    }
+    Ok(())
+}
+
+fn error_event(error: ApiError) -> NoteEventSchema {
+    let (status, message) = error.status_and_message();
+    NoteEventSchema::Error {
+        status_code: status.as_u16(),
+        message,
+    }
+}
+
+async fn run_socket(
+    state: AppState,
+    mut socket: WebSocket,
+    note: NoteDTO,
+    user: UserDTO,
+    context: AuditContext,
+) {
+    let mut session = state.collab.join(
+        note.id,
+        Collaborator {
+            user_id: user.id,
+            username: user.username.clone(),
+        },
+    );
+    let mut access_check = tokio::time::interval(ACCESS_CHECK_INTERVAL);
+    access_check.reset();
+
+    loop {
+        // Set when access is lost, the socket closes after telling why
+        let mut closing = false;
+        let reply = tokio::select! {
+            message = socket.recv() => match message {
+                Some(Ok(Message::Text(text))) => match check_access(&state, &note, &user).await {
+                    Err(e) => {
+                        closing = true;
+                        error_event(e)
+                    }
+                    Ok(()) => match apply_edit(&state, &note, &context, &text).await {
+                        Ok(()) => continue,
+                        Err(e) => error_event(e),
+                    },
+                },
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                // Pings are answered by axum
+                Some(Ok(_)) => continue,
+            },
+            event = session.recv() => match event {
+                Ok(event) => NoteEventSchema::from(event),
+                Err(RecvError::Lagged(_)) => NoteEventSchema::Resync,
+                Err(RecvError::Closed) => break,
+            },
+            _ = access_check.tick() => match check_access(&state, &note, &user).await {
+                Ok(()) => continue,
+                Err(e) => {
+                    closing = true;
+                    error_event(e)
+                }
+            },
+        };
+
+        let Ok(text) = serde_json::to_string(&reply) else {
+            continue;
+        };
+        if socket.send(Message::Text(text.into())).await.is_err() || closing {
+            break;
+        }
+    }
+}
+
+/// Applies an edit of the note like the block routes do and tells its viewers
+async fn apply_edit(
+    state: &AppState,
+    note: &NoteDTO,
+    context: &AuditContext,
+    text: &str,
+) -> Result<()> {
+    let edit: NoteEditSchema = serde_json::from_str(text)
+        .map_err(|e| ApiError::BadRequest(format!("Invalid edit: {e}")))?;
+
+    match edit {
+        NoteEditSchema::CreateBlock {
+            block_type,
+            content,
+            parent_block,
+        } => {
+            let block = state
+                .block_service
+                .create(BlockCreateDTO {
+                    block_type,
+                    content,
+                    note_id: note.id,
+                    parent_block,
+                })
+                .await?;
+            state
+                .collab
+                .publish(note.id, NoteEvent::BlockCreated(block));
+        }
+        NoteEditSchema::UpdateBlock {
+            id,
+            block_type,
+            content,
+            version,
+        } => {
+            find_note_block(state, note, id).await?;
+            state
+                .block_service
+                .update(BlockUpdateDTO {
+                    id,
+                    block_type,
+                    content,
+                    version,
+                })
+                .await?;
+            publish_block(state, id).await?;
+        }
+        NoteEditSchema::EditText { id, ops } => {
+            find_note_block(state, note, id).await?;
+            let (block, ops) = state.block_service.merge_text(id, ops).await?;
+            state
+                .collab
+                .publish(note.id, NoteEvent::TextEdited { block, ops });
+        }
+        NoteEditSchema::DeleteBlock {
+            id,
+            promote_children,
+        } => {
+            let block = find_note_block(state, note, id).await?;
+            state
+                .block_service
+                .delete(id, promote_children, context)
+                .await?;
+            publish_deleted(
+                state,
+                note.id,
+                id,
+                promote_children.then_some(block.parent_block),
+            )
+            .await?;
+        }
+        NoteEditSchema::MoveBlock { id, parent_block } => {
+            find_note_block(state, note, id).await?;
+            let block = state.block_service.move_block(id, parent_block).await?;
+            state
+                .collab
+                .publish(note.id, NoteEvent::BlockUpdated(block));
+        }
+        NoteEditSchema::ReorderBlocks {
+            parent_block,
+            blocks,
+            version,
+        } => {
+            state
+                .note_service
+                .reorder_blocks(note.id, parent_block, blocks, version)
+                .await?;
+            publish_order(state, note.id, parent_block).await?;
+        }
+    }
+    Ok(())
+}
+
+async fn find_note_block(state: &AppState, note: &NoteDTO, id: Uuid) -> Result<BlockDTO> {
+    let block = state.block_service.find_one(id).await?;
+    if block.note_id != note.id {
+        return Err(CoreError::NotFound.into());
+    }
+    Ok(block)
+}
+
+/// Sends the current state of a changed block to the viewers of its note
+pub(crate) async fn publish_block(state: &AppState, id: Uuid) -> Result<BlockDTO> {
+    let block = state.block_service.find_one(id).await?;
+    state
+        .collab
+        .publish(block.note_id, NoteEvent::BlockUpdated(block.clone()));
+    Ok(block)
+}
+
+/// Sends the order of the blocks directly under `parent_block`
+pub(crate) async fn publish_order(
+    state: &AppState,
+    note_id: Uuid,
+    parent_block: Option<Uuid>,
+) -> Result<()> {
+    let note_blocks = state.block_service.get_all_in_note(note_id).await?;
+    let blocks = children(&note_blocks, parent_block)
+        .iter()
+        .map(|b| b.id)
+        .collect();
+    state.collab.publish(
+        note_id,
+        NoteEvent::BlocksReordered {
+            parent_block,
+            blocks,
+        },
+    );
+    Ok(())
+}
+
+/// Sends a deletion. Promoted children moved to `promoted_to`,
+/// so the new order of that level follows.
+pub(crate) async fn publish_deleted(
+    state: &AppState,
+    note_id: Uuid,
+    id: Uuid,
+    promoted_to: Option<Option<Uuid>>,
+) -> Result<()> {
+    state
+        .collab
+        .publish(note_id, NoteEvent::BlockDeleted { id });
+    if let Some(parent_block) = promoted_to {
+        publish_order(state, note_id, parent_block).await?;
+    }
+    Ok(())
+}
+
+/// Blocks directly under `parent_block` in a note tree
+fn children(blocks: &[BlockDTO], parent_block: Option<Uuid>) -> &[BlockDTO] {
+    let Some(parent) = parent_block else {
+        return blocks;
+    };
+    for block in blocks {
+        if block.id == parent {
+            return &block.children;
+        }
+        let found = children(&block.children, parent_block);
+        if !found.is_empty() {
+            return found;
+        }
+    }
+    &[]
+}
@@ -0,0 +1,272 @@
+use crate::errors::Result;
+use crate::schemas::collection::{
+    CreatePropertySchema, PropertySchema, SaveViewSchema, SetPropertyValueSchema,
+    UpdatePropertySchema, ViewResultSchema, ViewSchema,
+};
+use crate::schemas::{DataResponseSchema, OkResponseSchema};
+use crate::state::AppState;
+use axum::extract::{Path, State};
+use axum::routing::{delete, get, post, put};
+use axum::{Extension, Json, Router};
+use remind_core::errors::CoreError;
+use remind_core::{PropertyCreateDTO, UserDTO, ViewCreateDTO};
+use uuid::Uuid;
+
+/// Properties and views of collection notes. `{id}` is the collection note,
+/// its child notes are the rows.
+pub(crate) fn router(state: AppState) -> Router<AppState> {
+    Router::new()
+        .route("/{id}/properties", post(create_property))
+        .route("/{id}/properties", get(get_properties))
+        .route("/{id}/properties/{property_id}", put(update_property))
+        .route("/{id}/properties/{property_id}", delete(delete_property))
+        .route(
+            "/{id}/rows/{note_id}/values/{property_id}",
+            put(set_property_value),
+        )
+        .route("/{id}/views", post(create_view))
+        .route("/{id}/views", get(get_views))
+        .route("/{id}/views/{view_id}", get(get_view))
+        .route("/{id}/views/{view_id}", put(update_view))
+        .route("/{id}/views/{view_id}", delete(delete_view))
+        .route("/{id}/views/{view_id}/rows", get(query_view))
+        .layer(axum::middleware::from_fn_with_state(
+            state,
+            super::auth_middleware,
+        ))
+}
+
+async fn create_property(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserDTO>,
+    Path(id): Path<Uuid>,
+    Json(data): Json<CreatePropertySchema>,
+) -> Result<Json<PropertySchema>> {
+    let note = state.note_service.find_one(id).await?;
+    let workspace = state.workspace_service.get(note.workspace_id).await?;
+    if workspace.user_id != user.id {
+        return Err(CoreError::AccessDenied.into());
+    }
+
+    let dto = PropertyCreateDTO {
+        note_id: id,
+        name: data.name,
+        property_type: data.property_type,
+        options: data.options,
+    };
+    let property = state.collection_service.create_property(dto).await?;
+    Ok(Json(property.into()))
+}
+
+async fn get_properties(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserDTO>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<DataResponseSchema<Vec<PropertySchema>>>> {
+    let note = state.note_service.find_one(id).await?;
+    let workspace = state.workspace_service.get(note.workspace_id).await?;
+    if workspace.user_id != user.id {
+        return Err(CoreError::AccessDenied.into());
+    }
+
+    let properties = state
+        .collection_service
+        .get_properties(id)
+        .await?
+        .into_iter()
+        .map(PropertySchema::from)
+        .collect();
+    Ok(Json(DataResponseSchema(properties)))
+}
+
+async fn update_property(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserDTO>,
+    Path((id, property_id)): Path<(Uuid, Uuid)>,
+    Json(data): Json<UpdatePropertySchema>,
+) -> Result<Json<PropertySchema>> {
+    let note = state.note_service.find_one(id).await?;
+    let workspace = state.workspace_service.get(note.workspace_id).await?;
+    if workspace.user_id != user.id {
+        return Err(CoreError::AccessDenied.into());
+    }
+    let property = state.collection_service.find_property(property_id).await?;
+    if property.note_id != id {
+        return Err(CoreError::NotFound.into());
+    }
+
+    let property = state
+        .collection_service
+        .update_property(property_id, data.into())
+        .await?;
+    Ok(Json(property.into()))
+}
+
+async fn delete_property(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserDTO>,
+    Path((id, property_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<OkResponseSchema>> {
+    let note = state.note_service.find_one(id).await?;
+    let workspace = state.workspace_service.get(note.workspace_id).await?;
+    if workspace.user_id != user.id {
+        return Err(CoreError::AccessDenied.into());
+    }
+    let property = state.collection_service.find_property(property_id).await?;
+    if property.note_id != id {
+        return Err(CoreError::NotFound.into());
+    }
+
+    state
+        .collection_service
+        .delete_property(property_id)
+        .await?;
+    Ok(Json(OkResponseSchema::new(true)))
+}
+
+async fn set_property_value(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserDTO>,
+    Path((id, note_id, property_id)): Path<(Uuid, Uuid, Uuid)>,
+    Json(data): Json<SetPropertyValueSchema>,
+) -> Result<Json<OkResponseSchema>> {
+    let note = state.note_service.find_one(id).await?;
+    let workspace = state.workspace_service.get(note.workspace_id).await?;
+    if workspace.user_id != user.id {
+        return Err(CoreError::AccessDenied.into());
+    }
+    let property = state.collection_service.find_property(property_id).await?;
+    if property.note_id != id {
+        return Err(CoreError::NotFound.into());
+    }
+
+    state
+        .collection_service
+        .set_value(note_id, property_id, data.value)
+        .await?;
+    Ok(Json(OkResponseSchema::new(true)))
+}
+
+async fn create_view(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserDTO>,
+    Path(id): Path<Uuid>,
+    Json(data): Json<SaveViewSchema>,
+) -> Result<Json<ViewSchema>> {
+    let note = state.note_service.find_one(id).await?;
+    let workspace = state.workspace_service.get(note.workspace_id).await?;
+    if workspace.user_id != user.id {
+        return Err(CoreError::AccessDenied.into());
+    }
+
+    let dto = ViewCreateDTO {
+        note_id: id,
+        name: data.name,
+        view_type: data.view_type,
+        filters: data.filters,
+        sorts: data.sorts,
+        group_by: data.group_by,
+    };
+    let view = state.collection_service.create_view(dto).await?;
+    Ok(Json(view.into()))
+}
+
+async fn get_views(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserDTO>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<DataResponseSchema<Vec<ViewSchema>>>> {
+    let note = state.note_service.find_one(id).await?;
+    let workspace = state.workspace_service.get(note.workspace_id).await?;
+    if workspace.user_id != user.id {
+        return Err(CoreError::AccessDenied.into());
+    }
+
+    let views = state
+        .collection_service
+        .get_views(id)
+        .await?
+        .into_iter()
+        .map(ViewSchema::from)
+        .collect();
+    Ok(Json(DataResponseSchema(views)))
+}
+
+async fn get_view(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserDTO>,
+    Path((id, view_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<ViewSchema>> {
+    let note = state.note_service.find_one(id).await?;
+    let workspace = state.workspace_service.get(note.workspace_id).await?;
+    if workspace.user_id != user.id {
+        return Err(CoreError::AccessDenied.into());
+    }
+    let view = state.collection_service.find_view(view_id).await?;
+    if view.note_id != id {
+        return Err(CoreError::NotFound.into());
+    }
+
+    Ok(Json(view.into()))
+}
+
+async fn update_view(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserDTO>,
+    Path((id, view_id)): Path<(Uuid, Uuid)>,
+    Json(data): Json<SaveViewSchema>,
+) -> Result<Json<ViewSchema>> {
+    let note = state.note_service.find_one(id).await?;
+    let workspace = state.workspace_service.get(note.workspace_id).await?;
+    if workspace.user_id != user.id {
+        return Err(CoreError::AccessDenied.into());
+    }
+    let view = state.collection_service.find_view(view_id).await?;
+    if view.note_id != id {
+        return Err(CoreError::NotFound.into());
+    }
+
+    let view = state
+        .collection_service
+        .update_view(view_id, data.into())
+        .await?;
+    Ok(Json(view.into()))
+}
+
+async fn delete_view(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserDTO>,
+    Path((id, view_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<OkResponseSchema>> {
+    let note = state.note_service.find_one(id).await?;
+    let workspace = state.workspace_service.get(note.workspace_id).await?;
+    if workspace.user_id != user.id {
+        return Err(CoreError::AccessDenied.into());
+    }
+    let view = state.collection_service.find_view(view_id).await?;
+    if view.note_id != id {
+        return Err(CoreError::NotFound.into());
+    }
+
+    state.collection_service.delete_view(view_id).await?;
+    Ok(Json(OkResponseSchema::new(true)))
+}
+
+async fn query_view(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserDTO>,
+    Path((id, view_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<ViewResultSchema>> {
+    let note = state.note_service.find_one(id).await?;
+    let workspace = state.workspace_service.get(note.workspace_id).await?;
+    if workspace.user_id != user.id {
+        return Err(CoreError::AccessDenied.into());
+    }
+    let view = state.collection_service.find_view(view_id).await?;
+    if view.note_id != id {
+        return Err(CoreError::NotFound.into());
+    }
+
+    let result = state.collection_service.query_view(view_id).await?;
+    Ok(Json(result.into()))
+}
@@ -0,0 +1,119 @@
+use crate::errors::Result;
+use crate::schemas::OkResponseSchema;
+use crate::schemas::comment::{
+    CommentBodySchema, CommentSchema, CommentThreadSchema, CreateCommentSchema,
+};
+use crate::state::AppState;
+use axum::extract::{Path, State};
+use axum::routing::{delete, post, put};
+use axum::{Extension, Json, Router};
+use remind_core::errors::CoreError;
+use remind_core::{CommentCreateDTO, CommentDTO, UserDTO};
+use uuid::Uuid;
+
+pub(crate) fn router(state: AppState) -> Router<AppState> {
+    Router::new()
+        .route("/", post(create_comment))
+        .route("/{id}", put(edit_comment))
+        .route("/{id}", delete(delete_comment))
+        .route("/{id}/replies", post(reply_to_comment))
+        .route("/{id}/resolve", post(resolve_thread))
+        .route("/{id}/resolve", delete(reopen_thread))
+        .layer(axum::middleware::from_fn_with_state(
+            state,
+            super::auth_middleware,
+        ))
+}
+
+/// Starts a thread on a note, or on a block of it with `block_id`
+async fn create_comment(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserDTO>,
+    Json(data): Json<CreateCommentSchema>,
+) -> Result<Json<CommentThreadSchema>> {
+    let note = state.note_service.find_one(data.note_id).await?;
+    let workspace = state.workspace_service.get(note.workspace_id).await?;
+    if workspace.user_id != user.id {
+        return Err(CoreError::AccessDenied.into());
+    }
+
+    let dto = CommentCreateDTO {
+        note_id: data.note_id,
+        block_id: data.block_id,
+        author_id: user.id,
+        body: data.body,
+    };
+    let comment = state.comment_service.create(dto).await?;
+    Ok(Json(comment.into()))
+}
+
+async fn reply_to_comment(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserDTO>,
+    Path(id): Path<Uuid>,
+    Json(data): Json<CommentBodySchema>,
+) -> Result<Json<CommentSchema>> {
+    find_visible_comment(&state, &user, id).await?;
+    let reply = state.comment_service.reply(id, user.id, &data.body).await?;
+    Ok(Json(reply.into()))
+}
+
+/// Changes the body of a comment of the user
+async fn edit_comment(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserDTO>,
+    Path(id): Path<Uuid>,
+    Json(data): Json<CommentBodySchema>,
+) -> Result<Json<CommentSchema>> {
+    find_visible_comment(&state, &user, id).await?;
+    let comment = state.comment_service.edit(id, user.id, &data.body).await?;
+    Ok(Json(comment.into()))
+}
+
+/// Deletes a comment of the user, a thread goes with its replies
+async fn delete_comment(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserDTO>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<OkResponseSchema>> {
+    find_visible_comment(&state, &user, id).await?;
+    state.comment_service.delete(id, user.id).await?;
+    Ok(Json(OkResponseSchema::new(true)))
+}
+
+async fn resolve_thread(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserDTO>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<CommentThreadSchema>> {
+    find_visible_comment(&state, &user, id).await?;
+    let comment = state
+        .comment_service
+        .set_resolved(id, user.id, true)
+        .await?;
+    Ok(Json(comment.into()))
+}
+
+async fn reopen_thread(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserDTO>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<CommentThreadSchema>> {
+    find_visible_comment(&state, &user, id).await?;
+    let comment = state
+        .comment_service
+        .set_resolved(id, user.id, false)
+        .await?;
+    Ok(Json(comment.into()))
+}
+
+/// Finds the comment and checks the user can see its note
+async fn find_visible_comment(state: &AppState, user: &UserDTO, id: Uuid) -> Result<CommentDTO> {
+    let comment = state.comment_service.find_one(id).await?;
+    let note = state.note_service.find_one(comment.note_id).await?;
+    let workspace = state.workspace_service.get(note.workspace_id).await?;
+    if workspace.user_id != user.id {
+        return Err(CoreError::AccessDenied.into());
+    }
+    Ok(comment)
+}
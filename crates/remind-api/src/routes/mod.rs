@@ -3,16 +3,27 @@ use crate::state::AppState;
 use axum::Json;
 use axum::body::Body;
 use axum::extract::{Request, State};
-use axum::http::Response;
+use axum::http::{HeaderMap, Response, header};
 use axum::middleware::Next;
 use axum::response::IntoResponse;
 use remind_core::UserDTO;
 use remind_core::errors::{AuthError, CoreError};
+use remind_core::remind_auth::Claims;
 use serde_json::json;
 
+pub(crate) mod attachment;
 pub(crate) mod auth;
 pub(crate) mod block;
+pub(crate) mod collab;
+pub(crate) mod collection;
+pub(crate) mod comment;
 pub(crate) mod note;
+pub(crate) mod notification;
+pub(crate) mod public;
+pub(crate) mod sync;
+pub(crate) mod tag;
+pub(crate) mod template;
+pub(crate) mod webhook;
 pub(crate) mod workspace;
 
 pub async fn handler_404() -> impl IntoResponse {
@@ -27,7 +38,7 @@ pub async fn auth_middleware(
     mut request: Request,
     next: Next,
 ) -> Result<Response<Body>, ApiError> {
-    let auth_header = match request.headers_mut().get(axum::http::header::AUTHORIZATION) {
+    let auth_header = match request.headers_mut().get(header::AUTHORIZATION) {
         None => return Err(CoreError::from(AuthError::InvalidToken))?,
         Some(header) => header
             .to_str()
@@ -42,18 +53,53 @@ pub async fn auth_middleware(
             .ok_or(CoreError::from(AuthError::InvalidToken))?,
     );
 
+    let (claims, user) = authenticate(&state, token).await?;
+    request.extensions_mut().insert(claims);
+    request.extensions_mut().insert(user);
+
+    Ok(next.run(request).await)
+}
+
+/// Checks a JWT and finds its user
+pub(crate) async fn authenticate(
+    state: &AppState,
+    token: &str,
+) -> Result<(Claims, UserDTO), ApiError> {
     let claims = state
         .jwt_processor
         .decode_token(token)
         .map_err(|_| CoreError::from(AuthError::InvalidToken))?;
-    request.extensions_mut().insert(claims.clone());
 
     let user: UserDTO = state
         .user_service
         .find_one_by_username(claims.sub.to_string())
         .await
         .map_err(|_| CoreError::from(AuthError::InvalidToken))?;
-    request.extensions_mut().insert(user);
+    Ok((claims, user))
+}
 
-    Ok(next.run(request).await)
+/// ETag of a note or block at `version`
+pub(crate) fn etag(version: i64) -> String {
+    format!("\"{version}\"")
+}
+
+/// Version the client edited, from the required `If-Match` header.
+/// `None` for `*`, which matches any version.
+pub(crate) fn if_match(headers: &HeaderMap) -> Result<Option<i64>, ApiError> {
+    let value = headers
+        .get(header::IF_MATCH)
+        .ok_or(ApiError::PreconditionRequired)?
+        .to_str()
+        .unwrap_or_default()
+        .trim();
+    if value == "*" {
+        return Ok(None);
+    }
+
+    let version = value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .and_then(|v| v.parse().ok())
+        .ok_or_else(|| ApiError::BadRequest(format!("Invalid If-Match header {value}")))?;
+    Ok(Some(version))
 }
@@ -1,14 +1,25 @@
-use crate::errors::Result;
-use crate::schemas::OkResponseSchema;
+use crate::errors::{ApiError, Result};
+use crate::routes::collab::publish_order;
+use crate::routes::{etag, if_match};
+use crate::schemas::comment::CommentThreadSchema;
 use crate::schemas::note::{
-    CreateNoteSchema, NoteSchema, ReorderNoteBlocksSchema, UpdateNoteSchema,
+    BacklinkSchema, CreateNoteSchema, ExportNoteQuery, NoteSchema, ReorderNoteBlocksSchema,
+    UpdateNoteSchema,
 };
+use crate::schemas::share_link::{CreateShareLinkSchema, ShareLinkSchema};
+use crate::schemas::template::{CreateTemplateSchema, NoteTemplateSchema};
+use crate::schemas::{DataResponseSchema, OkResponseSchema};
 use crate::state::AppState;
-use axum::extract::{Path, State};
+use crate::utils::audit::RequestContext;
+use axum::extract::{Path, Query, State};
+use axum::http::{HeaderMap, header};
+use axum::response::IntoResponse;
 use axum::routing::{delete, get, post, put};
 use axum::{Extension, Json, Router};
-use remind_core::UserDTO;
 use remind_core::errors::CoreError;
+use remind_core::{
+    NoteCreateDTO, NoteTemplateCreateDTO, NoteUpdateDTO, ShareLinkCreateDTO, UserDTO,
+};
 use uuid::Uuid;
 
 pub(crate) fn router(state: AppState) -> Router<AppState> {
@@ -17,27 +28,44 @@ pub(crate) fn router(state: AppState) -> Router<AppState> {
         .route("/{id}", delete(delete_note))
         .route("/{id}", get(get_note))
         .route("/{id}", put(update_note))
+        .route("/{id}/backlinks", get(get_backlinks))
+        .route("/{id}/comments", get(get_comments))
         .route("/{id}/blocks/reorder", post(reorder_blocks))
+        .route("/{id}/export", get(export_note))
+        .route("/{id}/share", post(create_share_link))
+        .route("/{id}/template", post(save_as_template))
+        .route("/{id}/share", get(get_share_links))
+        .route("/{id}/share/{link_id}", delete(revoke_share_link))
+        .route("/{id}/tags/{tag_id}", put(tag_note))
+        .route("/{id}/tags/{tag_id}", delete(untag_note))
         .layer(axum::middleware::from_fn_with_state(
             state,
             super::auth_middleware,
         ))
+        // Authenticates itself, browsers can't send headers with WebSockets
+        .route("/{id}/ws", get(super::collab::note_socket))
 }
 
 async fn create_note(
     State(state): State<AppState>,
+    Extension(user): Extension<UserDTO>,
     Json(data): Json<CreateNoteSchema>,
 ) -> Result<Json<NoteSchema>> {
     state.workspace_service.get(data.workspace_id).await?;
-    let note = state.note_service.create(data.into()).await?;
+    let dto = NoteCreateDTO {
+        author: Some(user.username),
+        ..data.into()
+    };
+    let note = state.note_service.create(dto).await?;
     Ok(Json(note.into()))
 }
 
 async fn delete_note(
     State(state): State<AppState>,
     Path(id): Path<Uuid>,
+    RequestContext(context): RequestContext,
 ) -> Result<Json<OkResponseSchema>> {
-    state.note_service.delete(id).await?;
+    state.note_service.delete(id, &context).await?;
     Ok(Json(OkResponseSchema::new(true)))
 }
 
@@ -45,38 +73,242 @@ async fn get_note(
     State(state): State<AppState>,
     Path(id): Path<Uuid>,
     Extension(user): Extension<UserDTO>,
-) -> Result<Json<NoteSchema>> {
+) -> Result<impl IntoResponse> {
     let note = state.note_service.find_one(id).await?;
     let workspace = state.workspace_service.get(note.workspace_id).await?;
     if workspace.user_id != user.id {
         return Err(CoreError::AccessDenied.into());
     }
 
-    Ok(Json(note.into()))
+    Ok((
+        [(header::ETAG, etag(note.version))],
+        Json(NoteSchema::from(note)),
+    ))
 }
 
 async fn update_note(
     State(state): State<AppState>,
     Path(id): Path<Uuid>,
     Extension(user): Extension<UserDTO>,
+    headers: HeaderMap,
     Json(data): Json<UpdateNoteSchema>,
-) -> Result<Json<OkResponseSchema>> {
+) -> Result<impl IntoResponse> {
     let note = state.note_service.find_one(id).await?;
     let workspace = state.workspace_service.get(note.workspace_id).await?;
     if workspace.user_id != user.id {
         return Err(CoreError::AccessDenied.into());
     }
 
-    state.note_service.update(id, data.into()).await?;
+    let mut dto: NoteUpdateDTO = data.into();
+    dto.version = if_match(&headers)?;
+    match state.note_service.update(id, dto).await {
+        Err(CoreError::VersionMismatch) => {
+            let current = state.note_service.find_one(id).await?;
+            return Err(ApiError::stale(current.version, NoteSchema::from(current)));
+        }
+        result => result?,
+    }
+    let note = state.note_service.find_one(id).await?;
 
-    Ok(Json(OkResponseSchema::new(true)))
+    Ok((
+        [(header::ETAG, etag(note.version))],
+        Json(OkResponseSchema::new(true)),
+    ))
+}
+
+async fn get_backlinks(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserDTO>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<DataResponseSchema<Vec<BacklinkSchema>>>> {
+    let note = state.note_service.find_one(id).await?;
+    let workspace = state.workspace_service.get(note.workspace_id).await?;
+    if workspace.user_id != user.id {
+        return Err(CoreError::AccessDenied.into());
+    }
+
+    let backlinks = state
+        .note_service
+        .get_backlinks(id)
+        .await?
+        .into_iter()
+        .map(BacklinkSchema::from)
+        .collect();
+    Ok(Json(DataResponseSchema(backlinks)))
+}
+
+/// Comment threads of the note with their replies, including the resolved
+/// ones and those whose block was deleted
+async fn get_comments(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserDTO>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<DataResponseSchema<Vec<CommentThreadSchema>>>> {
+    let note = state.note_service.find_one(id).await?;
+    let workspace = state.workspace_service.get(note.workspace_id).await?;
+    if workspace.user_id != user.id {
+        return Err(CoreError::AccessDenied.into());
+    }
+
+    let threads = state
+        .comment_service
+        .get_all_in_note(id)
+        .await?
+        .into_iter()
+        .map(CommentThreadSchema::from)
+        .collect();
+    Ok(Json(DataResponseSchema(threads)))
 }
 
 async fn reorder_blocks(
     State(state): State<AppState>,
     Extension(user): Extension<UserDTO>,
     Path(id): Path<Uuid>,
+    headers: HeaderMap,
     Json(data): Json<ReorderNoteBlocksSchema>,
+) -> Result<impl IntoResponse> {
+    let note = state.note_service.find_one(id).await?;
+    let workspace = state.workspace_service.get(note.workspace_id).await?;
+    if workspace.user_id != user.id {
+        return Err(CoreError::AccessDenied.into());
+    }
+
+    let version = if_match(&headers)?;
+    let result = state
+        .note_service
+        .reorder_blocks(id, data.parent_block, data.blocks, version)
+        .await;
+    match result {
+        Err(CoreError::VersionMismatch) => {
+            let current = state.note_service.find_one(id).await?;
+            return Err(ApiError::stale(current.version, NoteSchema::from(current)));
+        }
+        result => result?,
+    }
+    publish_order(&state, id, data.parent_block).await?;
+    let note = state.note_service.find_one(id).await?;
+
+    Ok((
+        [(header::ETAG, etag(note.version))],
+        Json(NoteSchema::from(note)),
+    ))
+}
+
+async fn export_note(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserDTO>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<ExportNoteQuery>,
+    RequestContext(context): RequestContext,
+) -> Result<impl IntoResponse> {
+    let note = state.note_service.find_one(id).await?;
+    let workspace = state.workspace_service.get(note.workspace_id).await?;
+    if workspace.user_id != user.id {
+        return Err(CoreError::AccessDenied.into());
+    }
+    let export = state
+        .note_service
+        .export(id, query.format, &context)
+        .await?;
+
+    Ok((
+        [(header::CONTENT_TYPE, query.format.content_type())],
+        export,
+    ))
+}
+
+async fn create_share_link(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserDTO>,
+    Path(id): Path<Uuid>,
+    RequestContext(context): RequestContext,
+    Json(data): Json<CreateShareLinkSchema>,
+) -> Result<Json<ShareLinkSchema>> {
+    let note = state.note_service.find_one(id).await?;
+    let workspace = state.workspace_service.get(note.workspace_id).await?;
+    if workspace.user_id != user.id {
+        return Err(CoreError::AccessDenied.into());
+    }
+
+    let dto = ShareLinkCreateDTO {
+        note_id: id,
+        created_by: user.id,
+        password: data.password,
+        include_subpages: data.include_subpages,
+        expires_at: data.expires_at,
+    };
+    let link = state.share_link_service.create(dto, &context).await?;
+    Ok(Json(link.into()))
+}
+
+/// `POST /notes/{id}/template`: saves the note as a template of its workspace
+async fn save_as_template(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserDTO>,
+    Path(id): Path<Uuid>,
+    Json(data): Json<CreateTemplateSchema>,
+) -> Result<Json<NoteTemplateSchema>> {
+    let note = state.note_service.find_one(id).await?;
+    let workspace = state.workspace_service.get(note.workspace_id).await?;
+    if workspace.user_id != user.id {
+        return Err(CoreError::AccessDenied.into());
+    }
+
+    let dto = NoteTemplateCreateDTO {
+        note_id: id,
+        name: data.name,
+        title: data.title,
+        created_by: user.id,
+    };
+    let template = state.note_service.save_as_template(dto).await?;
+    Ok(Json(template.into()))
+}
+
+async fn get_share_links(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserDTO>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<DataResponseSchema<Vec<ShareLinkSchema>>>> {
+    let note = state.note_service.find_one(id).await?;
+    let workspace = state.workspace_service.get(note.workspace_id).await?;
+    if workspace.user_id != user.id {
+        return Err(CoreError::AccessDenied.into());
+    }
+
+    let links = state
+        .share_link_service
+        .get_all_in_note(id)
+        .await?
+        .into_iter()
+        .map(ShareLinkSchema::from)
+        .collect();
+    Ok(Json(DataResponseSchema(links)))
+}
+
+async fn revoke_share_link(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserDTO>,
+    Path((id, link_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<OkResponseSchema>> {
+    let note = state.note_service.find_one(id).await?;
+    let workspace = state.workspace_service.get(note.workspace_id).await?;
+    if workspace.user_id != user.id {
+        return Err(CoreError::AccessDenied.into());
+    }
+
+    let link = state.share_link_service.find_one(link_id).await?;
+    if link.note_id != id {
+        return Err(CoreError::NotFound.into());
+    }
+
+    state.share_link_service.revoke(link_id).await?;
+    Ok(Json(OkResponseSchema::new(true)))
+}
+
+async fn tag_note(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserDTO>,
+    Path((id, tag_id)): Path<(Uuid, Uuid)>,
 ) -> Result<Json<NoteSchema>> {
     let note = state.note_service.find_one(id).await?;
     let workspace = state.workspace_service.get(note.workspace_id).await?;
@@ -84,8 +316,23 @@ async fn reorder_blocks(
         return Err(CoreError::AccessDenied.into());
     }
 
-    state.note_service.reorder_blocks(id, data.blocks).await?;
+    state.tag_service.tag_note(id, tag_id).await?;
+    let note = state.note_service.find_one(id).await?;
+    Ok(Json(note.into()))
+}
+
+async fn untag_note(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserDTO>,
+    Path((id, tag_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<NoteSchema>> {
     let note = state.note_service.find_one(id).await?;
+    let workspace = state.workspace_service.get(note.workspace_id).await?;
+    if workspace.user_id != user.id {
+        return Err(CoreError::AccessDenied.into());
+    }
 
+    state.tag_service.untag_note(id, tag_id).await?;
+    let note = state.note_service.find_one(id).await?;
     Ok(Json(note.into()))
 }
@@ -0,0 +1,91 @@
+use crate::errors::Result;
+use crate::schemas::notification::{
+    MarkedReadSchema, NotificationPageSchema, NotificationPreferenceSchema, NotificationQuery,
+};
+use crate::schemas::{DataResponseSchema, OkResponseSchema};
+use crate::state::AppState;
+use axum::extract::{Path, Query, State};
+use axum::routing::{get, post};
+use axum::{Extension, Json, Router};
+use remind_core::UserDTO;
+use uuid::Uuid;
+
+/// Notifications listed without `limit`
+const DEFAULT_NOTIFICATION_LIMIT: i64 = 20;
+
+pub(crate) fn router(state: AppState) -> Router<AppState> {
+    Router::new()
+        .route("/", get(get_notifications))
+        .route("/read", post(mark_all_read))
+        .route("/{id}/read", post(mark_read))
+        .route("/preferences", get(get_preferences).put(set_preferences))
+        .layer(axum::middleware::from_fn_with_state(
+            state,
+            super::auth_middleware,
+        ))
+}
+
+/// `GET /notifications?before=<cursor>&limit=<n>`: inbox of the user,
+/// newest first, with the number of unread notifications
+async fn get_notifications(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserDTO>,
+    Query(query): Query<NotificationQuery>,
+) -> Result<Json<NotificationPageSchema>> {
+    let page = state
+        .notification_service
+        .get_page(
+            user.id,
+            query.before,
+            query.limit.unwrap_or(DEFAULT_NOTIFICATION_LIMIT),
+        )
+        .await?;
+    Ok(Json(page.into()))
+}
+
+async fn mark_read(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserDTO>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<OkResponseSchema>> {
+    state.notification_service.mark_read(id, user.id).await?;
+    Ok(Json(OkResponseSchema::new(true)))
+}
+
+async fn mark_all_read(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserDTO>,
+) -> Result<Json<MarkedReadSchema>> {
+    let marked = state.notification_service.mark_all_read(user.id).await?;
+    Ok(Json(MarkedReadSchema { marked }))
+}
+
+async fn get_preferences(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserDTO>,
+) -> Result<Json<DataResponseSchema<Vec<NotificationPreferenceSchema>>>> {
+    let preferences = state
+        .notification_service
+        .get_preferences(user.id)
+        .await?
+        .into_iter()
+        .map(NotificationPreferenceSchema::from)
+        .collect();
+    Ok(Json(DataResponseSchema(preferences)))
+}
+
+/// Turns kinds of notifications on or off, the kinds left out stay as they are
+async fn set_preferences(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserDTO>,
+    Json(data): Json<Vec<NotificationPreferenceSchema>>,
+) -> Result<Json<DataResponseSchema<Vec<NotificationPreferenceSchema>>>> {
+    let preferences = state
+        .notification_service
+        .set_preferences(user.id, data.into_iter().map(Into::into).collect())
+        .await?
+        .into_iter()
+        .map(NotificationPreferenceSchema::from)
+        .collect();
+    Ok(Json(DataResponseSchema(preferences)))
+}
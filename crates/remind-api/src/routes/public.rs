@@ -0,0 +1,55 @@
+use crate::errors::Result;
+use crate::routes::attachment::content_response;
+use crate::schemas::attachment::AttachmentContentQuery;
+use crate::schemas::share_link::PublicNoteSchema;
+use crate::state::AppState;
+use axum::extract::{Path, Query, State};
+use axum::http::HeaderMap;
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::{Json, Router};
+use uuid::Uuid;
+
+/// Header carrying the password of a protected share link
+pub const SHARE_PASSWORD_HEADER: &str = "x-share-password";
+
+pub(crate) fn router() -> Router<AppState> {
+    Router::new().route("/{slug}", get(get_shared_note)).route(
+        "/{slug}/attachments/{id}/content",
+        get(get_shared_attachment_content),
+    )
+}
+
+async fn get_shared_note(
+    State(state): State<AppState>,
+    Path(slug): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<PublicNoteSchema>> {
+    let note = state
+        .share_link_service
+        .resolve(slug, share_password(&headers))
+        .await?;
+    Ok(Json(note.into()))
+}
+
+/// Reads an upload shown by the shared note
+async fn get_shared_attachment_content(
+    State(state): State<AppState>,
+    Path((slug, id)): Path<(String, Uuid)>,
+    Query(query): Query<AttachmentContentQuery>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse> {
+    state
+        .share_link_service
+        .check_shared_attachment(slug, share_password(&headers), id)
+        .await?;
+    let (attachment, data) = state.attachment_service.get_content(id, query.w).await?;
+    Ok(content_response(attachment, data))
+}
+
+fn share_password(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(SHARE_PASSWORD_HEADER)
+        .and_then(|h| h.to_str().ok())
+        .map(|p| p.to_string())
+}
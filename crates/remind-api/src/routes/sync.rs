@@ -0,0 +1,347 @@
+use crate::errors::{ApiError, Result};
+use crate::routes::collab::{publish_block, publish_deleted, publish_order};
+use crate::schemas::DataResponseSchema;
+use crate::schemas::block::BlockSchema;
+use crate::schemas::change::ChangeSchema;
+use crate::schemas::note::NoteSchema;
+use crate::schemas::sync::{
+    SyncOperationKind, SyncOperationSchema, SyncPullSchema, SyncPushSchema, SyncQuery,
+    SyncResultSchema, SyncStatus,
+};
+use crate::state::AppState;
+use crate::utils::audit::RequestContext;
+use axum::extract::{Query, State};
+use axum::routing::get;
+use axum::{Extension, Json, Router};
+use remind_core::collab::NoteEvent;
+use remind_core::errors::CoreError;
+use remind_core::{
+    AuditContext, BlockCreateDTO, BlockDTO, BlockUpdateDTO, MAX_CHANGES_PER_PAGE, NoteCreateDTO,
+    NoteDTO, NoteUpdateDTO, UserDTO,
+};
+use serde_json::Value;
+use uuid::Uuid;
+
+/// Changes sent by a pull without `limit`
+const DEFAULT_PULL_LIMIT: i64 = 100;
+/// Most operations accepted by one push
+const MAX_OPERATIONS_PER_PUSH: usize = 500;
+
+pub(crate) fn router(state: AppState) -> Router<AppState> {
+    Router::new()
+        .route("/", get(pull_changes).post(push_operations))
+        .layer(axum::middleware::from_fn_with_state(
+            state,
+            super::auth_middleware,
+        ))
+}
+
+/// `GET /sync?since=<cursor>`: what changed in the workspaces of the user
+/// since the last pull. Changed notes are sent whole with their blocks,
+/// deleted ones by id. The first pull, without `since`, sends every note
+/// and the cursor to continue from.
+async fn pull_changes(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserDTO>,
+    Query(query): Query<SyncQuery>,
+) -> Result<Json<SyncPullSchema>> {
+    let workspaces: Vec<Uuid> = state
+        .workspace_service
+        .get_all_by_user(user.id)
+        .await?
+        .iter()
+        .map(|w| w.id)
+        .collect();
+
+    let Some(since) = query.since else {
+        // The cursor is taken first, changes made while the notes are read
+        // come again with the next pull
+        let cursor = state.change_service.get_last_id(&workspaces).await?;
+        let mut notes = Vec::new();
+        for workspace_id in &workspaces {
+            let workspace_notes = state
+                .note_service
+                .get_all_in_workspace(*workspace_id)
+                .await?;
+            notes.extend(workspace_notes.into_iter().map(NoteSchema::from));
+        }
+        return Ok(Json(SyncPullSchema {
+            cursor,
+            has_more: false,
+            changes: Vec::new(),
+            notes,
+            deleted_notes: Vec::new(),
+        }));
+    };
+
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_PULL_LIMIT)
+        .clamp(1, MAX_CHANGES_PER_PAGE);
+    let changes = state
+        .change_service
+        .get_after(&workspaces, since, limit)
+        .await?;
+
+    let mut note_ids: Vec<Uuid> = Vec::new();
+    for change in &changes {
+        if !note_ids.contains(&change.note_id) {
+            note_ids.push(change.note_id);
+        }
+    }
+    let mut notes = Vec::new();
+    let mut deleted_notes = Vec::new();
+    for id in note_ids {
+        match state.note_service.find_one(id).await {
+            Ok(note) => notes.push(NoteSchema::from(note)),
+            Err(CoreError::NotFound) => deleted_notes.push(id),
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    Ok(Json(SyncPullSchema {
+        cursor: changes.last().map_or(since, |c| c.id),
+        has_more: changes.len() as i64 == limit,
+        changes: changes.into_iter().map(ChangeSchema::from).collect(),
+        notes,
+        deleted_notes,
+    }))
+}
+
+/// `POST /sync`: applies the operations an offline client queued, in order.
+/// An operation sent again, say after a lost response, isn't applied twice
+/// and gets its first result back. Operations based on an outdated version
+/// come back as conflicts with the current state, the rest still go through.
+async fn push_operations(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserDTO>,
+    RequestContext(context): RequestContext,
+    Json(data): Json<SyncPushSchema>,
+) -> Result<Json<DataResponseSchema<Vec<SyncResultSchema>>>> {
+    if data.operations.len() > MAX_OPERATIONS_PER_PUSH {
+        return Err(ApiError::BadRequest(format!(
+            "At most {MAX_OPERATIONS_PER_PUSH} operations can be pushed at once"
+        )));
+    }
+
+    let mut results = Vec::new();
+    for SyncOperationSchema { id, operation } in data.operations {
+        if let Some(result) = state.sync_service.get_result(user.id, id).await? {
+            results.push(stored_result(result)?);
+            continue;
+        }
+
+        let result = match apply_operation(&state, &user, &context, operation.clone()).await {
+            Ok(version) => SyncResultSchema {
+                version,
+                ..SyncResultSchema::new(id, SyncStatus::Applied)
+            },
+            Err(ApiError::CoreError(CoreError::VersionMismatch)) => SyncResultSchema {
+                current: current_state(&state, &operation).await?,
+                ..SyncResultSchema::new(id, SyncStatus::Conflict)
+            },
+            Err(e) => {
+                let (status, message) = e.status_and_message();
+                let result = SyncResultSchema {
+                    status_code: Some(status.as_u16()),
+                    message: Some(message),
+                    ..SyncResultSchema::new(id, SyncStatus::Rejected)
+                };
+                // Failures of the server aren't kept, the operation can be retried
+                if status.is_server_error() {
+                    results.push(result);
+                    continue;
+                }
+                result
+            }
+        };
+        let result = serde_json::to_value(&result).map_err(|_| CoreError::ServerError)?;
+        let saved = state.sync_service.save_result(user.id, id, result).await?;
+        results.push(stored_result(saved)?);
+    }
+
+    Ok(Json(DataResponseSchema(results)))
+}
+
+/// Applies an operation like the note and block routes do and tells the
+/// viewers of the note. Returns the version of the note or block after it.
+async fn apply_operation(
+    state: &AppState,
+    user: &UserDTO,
+    context: &AuditContext,
+    operation: SyncOperationKind,
+) -> Result<Option<i64>> {
+    match operation {
+        SyncOperationKind::CreateNote {
+            note_id,
+            workspace_id,
+            title,
+            parent_note,
+        } => {
+            let workspace = state.workspace_service.get(workspace_id).await?;
+            if workspace.user_id != user.id {
+                return Err(CoreError::AccessDenied.into());
+            }
+            if let Some(parent) = parent_note
+                && find_own_note(state, user, parent).await?.workspace_id != workspace_id
+            {
+                return Err(CoreError::NotFound.into());
+            }
+            let note = state
+                .note_service
+                .create_with_id(
+                    note_id,
+                    NoteCreateDTO {
+                        title,
+                        workspace_id,
+                        parent_note,
+                        template_id: None,
+                        author: None,
+                    },
+                )
+                .await?;
+            Ok(Some(note.version))
+        }
+        SyncOperationKind::UpdateNote {
+            note_id,
+            title,
+            version,
+        } => {
+            find_own_note(state, user, note_id).await?;
+            state
+                .note_service
+                .update(note_id, NoteUpdateDTO { title, version })
+                .await?;
+            Ok(Some(state.note_service.find_one(note_id).await?.version))
+        }
+        SyncOperationKind::DeleteNote { note_id } => {
+            // Deleting what is already gone is done
+            match find_own_note(state, user, note_id).await {
+                Err(ApiError::CoreError(CoreError::NotFound)) => return Ok(None),
+                result => result?,
+            };
+            state.note_service.delete(note_id, context).await?;
+            Ok(None)
+        }
+        SyncOperationKind::CreateBlock {
+            block_id,
+            note_id,
+            block_type,
+            content,
+            parent_block,
+        } => {
+            find_own_note(state, user, note_id).await?;
+            let block = state
+                .block_service
+                .create_with_id(
+                    block_id,
+                    BlockCreateDTO {
+                        block_type,
+                        content,
+                        note_id,
+                        parent_block,
+                    },
+                )
+                .await?;
+            let version = block.version;
+            state
+                .collab
+                .publish(note_id, NoteEvent::BlockCreated(block));
+            Ok(Some(version))
+        }
+        SyncOperationKind::UpdateBlock {
+            block_id,
+            block_type,
+            content,
+            version,
+        } => {
+            find_own_block(state, user, block_id).await?;
+            state
+                .block_service
+                .update(BlockUpdateDTO {
+                    id: block_id,
+                    block_type,
+                    content,
+                    version,
+                })
+                .await?;
+            let block = publish_block(state, block_id).await?;
+            Ok(Some(block.version))
+        }
+        SyncOperationKind::EditText { block_id, ops } => {
+            let (note, _) = find_own_block(state, user, block_id).await?;
+            let (block, ops) = state.block_service.merge_text(block_id, ops).await?;
+            let version = block.version;
+            state
+                .collab
+                .publish(note.id, NoteEvent::TextEdited { block, ops });
+            Ok(Some(version))
+        }
+        SyncOperationKind::DeleteBlock {
+            block_id,
+            promote_children,
+        } => {
+            let (note, block) = match find_own_block(state, user, block_id).await {
+                Err(ApiError::CoreError(CoreError::NotFound)) => return Ok(None),
+                result => result?,
+            };
+            state
+                .block_service
+                .delete(block_id, promote_children, context)
+                .await?;
+            let promoted_to = promote_children.then_some(block.parent_block);
+            publish_deleted(state, note.id, block_id, promoted_to).await?;
+            Ok(None)
+        }
+        SyncOperationKind::ReorderBlocks {
+            note_id,
+            parent_block,
+            blocks,
+            version,
+        } => {
+            find_own_note(state, user, note_id).await?;
+            state
+                .note_service
+                .reorder_blocks(note_id, parent_block, blocks, version)
+                .await?;
+            publish_order(state, note_id, parent_block).await?;
+            Ok(Some(state.note_service.find_one(note_id).await?.version))
+        }
+    }
+}
+
+/// State the client has to merge its conflicting operation with
+async fn current_state(state: &AppState, operation: &SyncOperationKind) -> Result<Option<Value>> {
+    let current = match operation {
+        SyncOperationKind::UpdateNote { note_id, .. }
+        | SyncOperationKind::ReorderBlocks { note_id, .. } => {
+            let note = state.note_service.find_one(*note_id).await?;
+            serde_json::to_value(NoteSchema::from(note))
+        }
+        SyncOperationKind::UpdateBlock { block_id, .. } => {
+            let block = state.block_service.find_one(*block_id).await?;
+            serde_json::to_value(BlockSchema::from(block))
+        }
+        _ => return Ok(None),
+    };
+    Ok(current.ok())
+}
+
+fn stored_result(result: Value) -> Result<SyncResultSchema> {
+    Ok(serde_json::from_value(result).map_err(|_| CoreError::ServerError)?)
+}
+
+async fn find_own_note(state: &AppState, user: &UserDTO, id: Uuid) -> Result<NoteDTO> {
+    let note = state.note_service.find_one(id).await?;
+    let workspace = state.workspace_service.get(note.workspace_id).await?;
+    if workspace.user_id != user.id {
+        return Err(CoreError::AccessDenied.into());
+    }
+    Ok(note)
+}
+
+async fn find_own_block(state: &AppState, user: &UserDTO, id: Uuid) -> Result<(NoteDTO, BlockDTO)> {
+    let block = state.block_service.find_one(id).await?;
+    let note = find_own_note(state, user, block.note_id).await?;
+    Ok((note, block))
+}
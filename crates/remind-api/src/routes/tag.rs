@@ -0,0 +1,71 @@
+use crate::errors::Result;
+use crate::schemas::OkResponseSchema;
+use crate::schemas::tag::{CreateTagSchema, TagSchema, UpdateTagSchema};
+use crate::state::AppState;
+use axum::extract::{Path, State};
+use axum::routing::{delete, post, put};
+use axum::{Extension, Json, Router};
+use remind_core::errors::CoreError;
+use remind_core::{TagCreateDTO, UserDTO};
+use uuid::Uuid;
+
+pub(crate) fn router(state: AppState) -> Router<AppState> {
+    Router::new()
+        .route("/", post(create_tag))
+        .route("/{id}", put(update_tag))
+        .route("/{id}", delete(delete_tag))
+        .layer(axum::middleware::from_fn_with_state(
+            state,
+            super::auth_middleware,
+        ))
+}
+
+async fn create_tag(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserDTO>,
+    Json(data): Json<CreateTagSchema>,
+) -> Result<Json<TagSchema>> {
+    let workspace = state.workspace_service.get(data.workspace_id).await?;
+    if workspace.user_id != user.id {
+        return Err(CoreError::AccessDenied.into());
+    }
+
+    let dto = TagCreateDTO {
+        workspace_id: data.workspace_id,
+        name: data.name,
+        color: data.color,
+    };
+    let tag = state.tag_service.create(dto).await?;
+    Ok(Json(tag.into()))
+}
+
+async fn update_tag(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserDTO>,
+    Path(id): Path<Uuid>,
+    Json(data): Json<UpdateTagSchema>,
+) -> Result<Json<TagSchema>> {
+    let tag = state.tag_service.find_one(id).await?;
+    let workspace = state.workspace_service.get(tag.workspace_id).await?;
+    if workspace.user_id != user.id {
+        return Err(CoreError::AccessDenied.into());
+    }
+
+    let tag = state.tag_service.update(id, data.into()).await?;
+    Ok(Json(tag.into()))
+}
+
+async fn delete_tag(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserDTO>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<OkResponseSchema>> {
+    let tag = state.tag_service.find_one(id).await?;
+    let workspace = state.workspace_service.get(tag.workspace_id).await?;
+    if workspace.user_id != user.id {
+        return Err(CoreError::AccessDenied.into());
+    }
+
+    state.tag_service.delete(id).await?;
+    Ok(Json(OkResponseSchema::new(true)))
+}
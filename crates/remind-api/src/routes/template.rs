@@ -0,0 +1,49 @@
+use crate::errors::Result;
+use crate::schemas::OkResponseSchema;
+use crate::schemas::template::NoteTemplateSchema;
+use crate::state::AppState;
+use axum::extract::{Path, State};
+use axum::routing::{delete, get};
+use axum::{Extension, Json, Router};
+use remind_core::errors::CoreError;
+use remind_core::{NoteTemplateDTO, UserDTO};
+use uuid::Uuid;
+
+pub(crate) fn router(state: AppState) -> Router<AppState> {
+    Router::new()
+        .route("/{id}", get(get_template))
+        .route("/{id}", delete(delete_template))
+        .layer(axum::middleware::from_fn_with_state(
+            state,
+            super::auth_middleware,
+        ))
+}
+
+/// Template of a workspace the user owns
+async fn find_own_template(state: &AppState, user: &UserDTO, id: Uuid) -> Result<NoteTemplateDTO> {
+    let template = state.note_service.find_template(id).await?;
+    let workspace = state.workspace_service.get(template.workspace_id).await?;
+    if workspace.user_id != user.id {
+        return Err(CoreError::AccessDenied.into());
+    }
+    Ok(template)
+}
+
+async fn get_template(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserDTO>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<NoteTemplateSchema>> {
+    let template = find_own_template(&state, &user, id).await?;
+    Ok(Json(template.into()))
+}
+
+async fn delete_template(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserDTO>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<OkResponseSchema>> {
+    find_own_template(&state, &user, id).await?;
+    state.note_service.delete_template(id).await?;
+    Ok(Json(OkResponseSchema::new(true)))
+}
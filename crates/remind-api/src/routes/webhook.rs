@@ -0,0 +1,105 @@
+use crate::errors::Result;
+use crate::schemas::webhook::{
+    CreateWebhookSchema, WebhookDeliveriesQuery, WebhookDeliverySchema, WebhookSchema,
+};
+use crate::schemas::{DataResponseSchema, OkResponseSchema};
+use crate::state::AppState;
+use axum::extract::{Path, Query, State};
+use axum::routing::{delete, get, post};
+use axum::{Extension, Json, Router};
+use remind_core::errors::CoreError;
+use remind_core::{MAX_DELIVERIES_PER_PAGE, UserDTO, WebhookCreateDTO, WebhookDTO};
+use uuid::Uuid;
+
+pub(crate) fn router(state: AppState) -> Router<AppState> {
+    Router::new()
+        .route("/", post(create_webhook))
+        .route("/{id}", get(get_webhook))
+        .route("/{id}", delete(delete_webhook))
+        .route("/{id}/test", post(send_test_event))
+        .route("/{id}/deliveries", get(get_deliveries))
+        .layer(axum::middleware::from_fn_with_state(
+            state,
+            super::auth_middleware,
+        ))
+}
+
+async fn create_webhook(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserDTO>,
+    Json(data): Json<CreateWebhookSchema>,
+) -> Result<Json<WebhookSchema>> {
+    let workspace = state.workspace_service.get(data.workspace_id).await?;
+    if workspace.user_id != user.id {
+        return Err(CoreError::AccessDenied.into());
+    }
+
+    let webhook = state
+        .webhook_service
+        .create(WebhookCreateDTO {
+            workspace_id: data.workspace_id,
+            url: data.url,
+            secret: data.secret,
+            event_types: data.event_types,
+        })
+        .await?;
+    Ok(Json(webhook.into()))
+}
+
+async fn get_webhook(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserDTO>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<WebhookSchema>> {
+    let webhook = find_own_webhook(&state, &user, id).await?;
+    Ok(Json(webhook.into()))
+}
+
+async fn delete_webhook(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserDTO>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<OkResponseSchema>> {
+    find_own_webhook(&state, &user, id).await?;
+    state.webhook_service.delete(id).await?;
+    Ok(Json(OkResponseSchema::new(true)))
+}
+
+/// Sends a `ping` event to the endpoint right away, the response tells
+/// whether it got through
+async fn send_test_event(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserDTO>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<WebhookDeliverySchema>> {
+    find_own_webhook(&state, &user, id).await?;
+    let delivery = state.webhook_service.send_test(id).await?;
+    Ok(Json(delivery.into()))
+}
+
+/// Latest deliveries of the webhook with the outcome of their last attempt
+async fn get_deliveries(
+    State(state): State<AppState>,
+    Extension(user): Extension<UserDTO>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<WebhookDeliveriesQuery>,
+) -> Result<Json<DataResponseSchema<Vec<WebhookDeliverySchema>>>> {
+    find_own_webhook(&state, &user, id).await?;
+    let deliveries = state
+        .webhook_service
+        .get_deliveries(id, query.limit.unwrap_or(MAX_DELIVERIES_PER_PAGE))
+        .await?
+        .into_iter()
+        .map(WebhookDeliverySchema::from)
+        .collect();
+    Ok(Json(DataResponseSchema(deliveries)))
+}
+
+async fn find_own_webhook(state: &AppState, user: &UserDTO, id: Uuid) -> Result<WebhookDTO> {
+    let webhook = state.webhook_service.find_one(id).await?;
+    let workspace = state.workspace_service.get(webhook.workspace_id).await?;
+    if workspace.user_id != user.id {
+        return Err(CoreError::AccessDenied.into());
+    }
+    Ok(webhook)
+}
@@ -1,21 +1,40 @@
+use crate::errors::ApiError;
 use crate::errors::Result;
 use crate::schemas::DataResponseSchema;
+use crate::schemas::audit::{AuditPageSchema, AuditQuery, DEFAULT_AUDIT_LIMIT};
+use crate::schemas::change::{ChangeFeedQuery, ChangeSchema};
 use crate::schemas::note::NoteSchema;
+use crate::schemas::tag::{TagFilterQuery, TagSchema};
+use crate::schemas::template::NoteTemplateSchema;
+use crate::schemas::webhook::WebhookSchema;
 use crate::schemas::workspace::{CreateWorkspaceSchema, WorkspaceSchema};
 use crate::state::AppState;
-use axum::extract::{Path, State};
+use axum::extract::{Path, Query, State};
+use axum::http::HeaderMap;
+use axum::response::sse::{Event, KeepAlive, Sse};
 use axum::routing::{get, post};
 use axum::{Extension, Json, Router};
+use futures_util::Stream;
 use remind_core::errors::CoreError;
-use remind_core::{UserDTO, WorkspaceCreateDTO};
+use remind_core::{ChangeDTO, MAX_CHANGES_PER_PAGE, UserDTO, WorkspaceCreateDTO};
+use std::collections::VecDeque;
+use std::time::Duration;
 use uuid::Uuid;
 
+/// How often the change feed looks for new changes
+const CHANGE_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
 pub(crate) fn router(state: AppState) -> Router<AppState> {
     Router::new()
         .route("/", post(create_workspace))
         .route("/my", get(get_my_workspaces))
         .route("/my/{id}", get(get_my_workspace))
         .route("/my/{id}/notes", get(get_my_workspace_notes))
+        .route("/my/{id}/tags", get(get_my_workspace_tags))
+        .route("/my/{id}/templates", get(get_my_workspace_templates))
+        .route("/my/{id}/events", get(get_my_workspace_events))
+        .route("/my/{id}/webhooks", get(get_my_workspace_webhooks))
+        .route("/my/{id}/audit", get(get_my_workspace_audit))
         .layer(axum::middleware::from_fn_with_state(
             state,
             super::auth_middleware,
@@ -69,15 +88,19 @@ async fn get_my_workspace_notes(
     State(state): State<AppState>,
     Path(id): Path<Uuid>,
     Extension(user): Extension<UserDTO>,
+    Query(query): Query<TagFilterQuery>,
 ) -> Result<Json<DataResponseSchema<Vec<NoteSchema>>>> {
     let workspace = state.workspace_service.get(id).await?;
     if workspace.user_id != user.id {
         return Err(CoreError::AccessDenied.into());
     }
 
+    let tags = query
+        .tag_ids()
+        .map_err(|e| ApiError::BadRequest(format!("Invalid tag id: {e}")))?;
     let notes = state
         .note_service
-        .get_all_in_workspace(workspace.id)
+        .get_all_in_workspace_by_tags(workspace.id, tags, query.matching)
         .await?
         .iter()
         .map(|n| NoteSchema::from(n.clone()))
@@ -85,3 +108,149 @@ async fn get_my_workspace_notes(
 
     Ok(Json(DataResponseSchema(notes)))
 }
+
+async fn get_my_workspace_templates(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Extension(user): Extension<UserDTO>,
+) -> Result<Json<DataResponseSchema<Vec<NoteTemplateSchema>>>> {
+    let workspace = state.workspace_service.get(id).await?;
+    if workspace.user_id != user.id {
+        return Err(CoreError::AccessDenied.into());
+    }
+
+    let templates = state
+        .note_service
+        .get_templates_in_workspace(workspace.id)
+        .await?
+        .into_iter()
+        .map(NoteTemplateSchema::from)
+        .collect();
+    Ok(Json(DataResponseSchema(templates)))
+}
+
+async fn get_my_workspace_tags(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Extension(user): Extension<UserDTO>,
+) -> Result<Json<DataResponseSchema<Vec<TagSchema>>>> {
+    let workspace = state.workspace_service.get(id).await?;
+    if workspace.user_id != user.id {
+        return Err(CoreError::AccessDenied.into());
+    }
+
+    let tags = state
+        .tag_service
+        .get_all_in_workspace(workspace.id)
+        .await?
+        .into_iter()
+        .map(TagSchema::from)
+        .collect();
+
+    Ok(Json(DataResponseSchema(tags)))
+}
+
+async fn get_my_workspace_webhooks(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Extension(user): Extension<UserDTO>,
+) -> Result<Json<DataResponseSchema<Vec<WebhookSchema>>>> {
+    let workspace = state.workspace_service.get(id).await?;
+    if workspace.user_id != user.id {
+        return Err(CoreError::AccessDenied.into());
+    }
+
+    let webhooks = state
+        .webhook_service
+        .get_all_in_workspace(workspace.id)
+        .await?
+        .into_iter()
+        .map(WebhookSchema::from)
+        .collect();
+
+    Ok(Json(DataResponseSchema(webhooks)))
+}
+
+/// `GET /workspaces/my/{id}/audit`: audit log of the workspace, newest
+/// first, for its owner. Filters by `action` (comma separated), `actor_id`,
+/// `note_id`, `target_id` and `from`/`to` times, pages with `before`.
+async fn get_my_workspace_audit(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Extension(user): Extension<UserDTO>,
+    Query(query): Query<AuditQuery>,
+) -> Result<Json<AuditPageSchema>> {
+    let workspace = state.workspace_service.get(id).await?;
+    if workspace.user_id != user.id {
+        return Err(CoreError::AccessDenied.into());
+    }
+
+    let filter = query.filter().map_err(ApiError::BadRequest)?;
+    let limit = query.limit.unwrap_or(DEFAULT_AUDIT_LIMIT);
+    let entries = state
+        .audit_service
+        .get_in_workspace(workspace.id, filter, limit)
+        .await?;
+    Ok(Json(AuditPageSchema::new(entries, limit)))
+}
+
+/// `GET /workspaces/my/{id}/events`: changes of the workspace as Server-Sent
+/// Events, each with the change id as event id. Reconnecting clients resume
+/// after `Last-Event-ID` (or the `after` query), others start from now. The
+/// change log is polled, so changes made through any instance show up.
+async fn get_my_workspace_events(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Extension(user): Extension<UserDTO>,
+    Query(query): Query<ChangeFeedQuery>,
+    headers: HeaderMap,
+) -> Result<Sse<impl Stream<Item = std::result::Result<Event, axum::Error>>>> {
+    let workspace = state.workspace_service.get(id).await?;
+    if workspace.user_id != user.id {
+        return Err(CoreError::AccessDenied.into());
+    }
+
+    let last_event_id = match headers.get("last-event-id") {
+        Some(header) => Some(
+            header
+                .to_str()
+                .ok()
+                .and_then(|h| h.trim().parse::<i64>().ok())
+                .ok_or_else(|| ApiError::BadRequest("Invalid Last-Event-ID".to_string()))?,
+        ),
+        None => query.after,
+    };
+    let after = match last_event_id {
+        Some(after) => after,
+        None => state.change_service.get_last_id(&[workspace.id]).await?,
+    };
+
+    let feed = (state, after, VecDeque::<ChangeDTO>::new());
+    let stream =
+        futures_util::stream::unfold(feed, move |(state, after, mut pending)| async move {
+            loop {
+                if let Some(change) = pending.pop_front() {
+                    let after = change.id;
+                    let event = Event::default()
+                        .id(change.id.to_string())
+                        .json_data(ChangeSchema::from(change));
+                    return Some((event, (state, after, pending)));
+                }
+                match state
+                    .change_service
+                    .get_after(&[workspace.id], after, MAX_CHANGES_PER_PAGE)
+                    .await
+                {
+                    Ok(changes) if !changes.is_empty() => pending.extend(changes),
+                    Ok(_) => tokio::time::sleep(CHANGE_POLL_INTERVAL).await,
+                    // The client reconnects and resumes from its last event
+                    Err(e) => {
+                        tracing::error!("Failed to read changes: {e}");
+                        return None;
+                    }
+                }
+            }
+        });
+
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
@@ -0,0 +1,37 @@
+use chrono::{DateTime, Utc};
+use remind_core::AttachmentDTO;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AttachmentSchema {
+    pub id: Uuid,
+    pub workspace_id: Uuid,
+    pub file_name: String,
+    pub content_type: String,
+    pub size: i64,
+    pub created_at: DateTime<Utc>,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+}
+
+impl From<AttachmentDTO> for AttachmentSchema {
+    fn from(value: AttachmentDTO) -> Self {
+        Self {
+            id: value.id,
+            workspace_id: value.workspace_id,
+            file_name: value.file_name,
+            content_type: value.content_type,
+            size: value.size,
+            created_at: value.created_at,
+            width: value.width,
+            height: value.height,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct AttachmentContentQuery {
+    /// Preferred image width, picks the closest thumbnail
+    pub w: Option<u32>,
+}
@@ -0,0 +1,97 @@
+use chrono::{DateTime, Utc};
+use remind_core::{AuditAction, AuditEntryDTO, AuditFilter, MAX_AUDIT_ENTRIES_PER_PAGE};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct AuditEntrySchema {
+    pub id: i64,
+    pub action: AuditAction,
+    pub actor_id: Option<Uuid>,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub note_id: Option<Uuid>,
+    pub target_id: Option<Uuid>,
+    pub details: Value,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<AuditEntryDTO> for AuditEntrySchema {
+    fn from(value: AuditEntryDTO) -> Self {
+        Self {
+            id: value.id,
+            action: value.action,
+            actor_id: value.actor_id,
+            ip: value.ip,
+            user_agent: value.user_agent,
+            note_id: value.note_id,
+            target_id: value.target_id,
+            details: value.details,
+            created_at: value.created_at,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct AuditPageSchema {
+    pub data: Vec<AuditEntrySchema>,
+    /// Send it back as `before` for the following page
+    pub next_cursor: Option<i64>,
+}
+
+impl AuditPageSchema {
+    /// Page of entries read with `limit`, a full page may have a next one
+    pub fn new(entries: Vec<AuditEntryDTO>, limit: i64) -> Self {
+        let next_cursor = match entries.len() as i64 == limit.clamp(1, MAX_AUDIT_ENTRIES_PER_PAGE) {
+            true => entries.last().map(|e| e.id),
+            false => None,
+        };
+        Self {
+            data: entries.into_iter().map(AuditEntrySchema::from).collect(),
+            next_cursor,
+        }
+    }
+}
+
+/// `?action=note_deleted,block_deleted&actor_id=<id>&from=<time>&before=<cursor>`
+/// Entries listed without `limit`
+pub const DEFAULT_AUDIT_LIMIT: i64 = 50;
+
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct AuditQuery {
+    /// Comma separated actions
+    pub action: Option<String>,
+    pub actor_id: Option<Uuid>,
+    pub note_id: Option<Uuid>,
+    pub target_id: Option<Uuid>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub before: Option<i64>,
+    pub limit: Option<i64>,
+}
+
+impl AuditQuery {
+    pub fn filter(&self) -> Result<AuditFilter, String> {
+        let actions = self
+            .action
+            .iter()
+            .flat_map(|a| a.split(','))
+            .map(str::trim)
+            .filter(|a| !a.is_empty())
+            .map(|a| {
+                serde_json::from_value(Value::String(a.to_string()))
+                    .map_err(|_| format!("Unknown action {a}"))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(AuditFilter {
+            actions,
+            actor_id: self.actor_id,
+            note_id: self.note_id,
+            target_id: self.target_id,
+            from: self.from,
+            to: self.to,
+            before: self.before,
+        })
+    }
+}
@@ -29,30 +29,30 @@ pub struct AuthTokenSchema {
     pub token_type: String,
 }
 
-impl Into<UserLoginUsernameDTO> for LoginByUsernameSchema {
-    fn into(self) -> UserLoginUsernameDTO {
+impl From<LoginByUsernameSchema> for UserLoginUsernameDTO {
+    fn from(value: LoginByUsernameSchema) -> Self {
         UserLoginUsernameDTO {
-            username: self.username,
-            password: self.password,
+            username: value.username,
+            password: value.password,
         }
     }
 }
 
-impl Into<UserLoginEmailDTO> for LoginByEmailSchema {
-    fn into(self) -> UserLoginEmailDTO {
+impl From<LoginByEmailSchema> for UserLoginEmailDTO {
+    fn from(value: LoginByEmailSchema) -> Self {
         UserLoginEmailDTO {
-            email: self.email,
-            password: self.password,
+            email: value.email,
+            password: value.password,
         }
     }
 }
 
-impl Into<UserCreateDTO> for RegisterUserSchema {
-    fn into(self) -> UserCreateDTO {
+impl From<RegisterUserSchema> for UserCreateDTO {
+    fn from(value: RegisterUserSchema) -> Self {
         UserCreateDTO {
-            username: self.username,
-            email: self.email,
-            password: self.password,
+            username: value.username,
+            email: value.email,
+            password: value.password,
         }
     }
 }
@@ -1,5 +1,8 @@
-use remind_core::{BlockContent, BlockCreateDTO, BlockDTO, BlockType};
+use remind_core::{
+    BlockContent, BlockCreateDTO, BlockDTO, BlockType, CellValue, TextCrdt, TextItem, TextOp,
+};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use uuid::Uuid;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -9,6 +12,20 @@ pub struct BlockSchema {
     pub content: BlockContent,
     pub position: i32,
     pub note_id: Uuid,
+    /// Also sent as ETag, send it back as `If-Match` to update the block
+    pub version: i64,
+    /// Toggle containing the block
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_block: Option<Uuid>,
+    /// Number of a NumberedList item within its list
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub number: Option<u32>,
+    /// Body of a toggle
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<BlockSchema>,
+    /// Current content of the source of a Synced block
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub synced_source: Option<Box<BlockSchema>>,
 }
 
 impl From<BlockDTO> for BlockSchema {
@@ -18,7 +35,14 @@ impl From<BlockDTO> for BlockSchema {
             block_type: value.block_type,
             content: value.content,
             position: value.position,
+            number: value.number,
             note_id: value.note_id,
+            version: value.version,
+            parent_block: value.parent_block,
+            children: value.children.into_iter().map(BlockSchema::from).collect(),
+            synced_source: value
+                .synced_source
+                .map(|source| Box::new(BlockSchema::from(*source))),
         }
     }
 }
@@ -28,6 +52,9 @@ pub struct CreateBlockSchema {
     pub block_type: BlockType,
     pub content: BlockContent,
     pub note_id: Uuid,
+    /// Toggle to add the block to
+    #[serde(default)]
+    pub parent_block: Option<Uuid>,
 }
 
 impl From<CreateBlockSchema> for BlockCreateDTO {
@@ -36,6 +63,7 @@ impl From<CreateBlockSchema> for BlockCreateDTO {
             block_type: value.block_type,
             content: value.content,
             note_id: value.note_id,
+            parent_block: value.parent_block,
         }
     }
 }
@@ -45,3 +73,60 @@ pub struct UpdateBlockSchema {
     pub block_type: Option<BlockType>,
     pub content: Option<BlockContent>,
 }
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct UpdateTableCellSchema {
+    /// `null` clears the cell
+    pub value: Option<CellValue>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AddTableRowSchema {
+    #[serde(default)]
+    pub cells: BTreeMap<Uuid, CellValue>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MoveBlockSchema {
+    /// Toggle to move the block into, `null` moves it to the top level
+    pub parent_block: Option<Uuid>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct DeleteBlockQuery {
+    /// Keep the children of a toggle in its place instead of deleting them
+    #[serde(default)]
+    pub promote_children: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TextStateSchema {
+    pub block_id: Uuid,
+    pub text: String,
+    pub clock: u64,
+    /// Characters in order, deleted ones included as anchors
+    pub items: Vec<TextItem>,
+}
+
+impl TextStateSchema {
+    pub fn new(block_id: Uuid, state: TextCrdt) -> Self {
+        Self {
+            block_id,
+            text: state.text(),
+            clock: state.clock(),
+            items: state.items().to_vec(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EditTextSchema {
+    pub ops: Vec<TextOp>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TextEditedSchema {
+    pub block: BlockSchema,
+    /// Operations that changed the text, to apply on other replicas
+    pub ops: Vec<TextOp>,
+}
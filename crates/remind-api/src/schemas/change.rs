@@ -0,0 +1,33 @@
+use chrono::{DateTime, Utc};
+use remind_core::{ChangeDTO, ChangeType};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ChangeSchema {
+    pub id: i64,
+    #[serde(rename = "type")]
+    pub change_type: ChangeType,
+    pub note_id: Uuid,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<ChangeDTO> for ChangeSchema {
+    fn from(value: ChangeDTO) -> Self {
+        Self {
+            id: value.id,
+            change_type: value.change_type,
+            note_id: value.note_id,
+            block_id: value.block_id,
+            created_at: value.created_at,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ChangeFeedQuery {
+    /// Id of the last change seen, for clients that can't send `Last-Event-ID`
+    pub after: Option<i64>,
+}
@@ -0,0 +1,122 @@
+use crate::schemas::block::BlockSchema;
+use remind_core::collab::{Collaborator, NoteEvent};
+use remind_core::{BlockContent, BlockType, TextOp};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CollaboratorSchema {
+    pub user_id: Uuid,
+    pub username: String,
+}
+
+impl From<Collaborator> for CollaboratorSchema {
+    fn from(value: Collaborator) -> Self {
+        Self {
+            user_id: value.user_id,
+            username: value.username,
+        }
+    }
+}
+
+/// Message sent to the clients of `/notes/{id}/ws`
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum NoteEventSchema {
+    BlockCreated {
+        block: BlockSchema,
+    },
+    BlockUpdated {
+        block: BlockSchema,
+    },
+    BlockDeleted {
+        id: Uuid,
+    },
+    /// `ops` turn the text of the block before into the one of `block`
+    TextEdited {
+        block: BlockSchema,
+        ops: Vec<TextOp>,
+    },
+    /// `blocks` are now directly under `parent_block`, in this order
+    BlocksReordered {
+        parent_block: Option<Uuid>,
+        blocks: Vec<Uuid>,
+    },
+    Presence {
+        users: Vec<CollaboratorSchema>,
+    },
+    /// Events were missed, the note has to be loaded again
+    Resync,
+    /// An edit of this client was rejected
+    Error {
+        status_code: u16,
+        message: String,
+    },
+}
+
+impl From<NoteEvent> for NoteEventSchema {
+    fn from(value: NoteEvent) -> Self {
+        match value {
+            NoteEvent::BlockCreated(block) => Self::BlockCreated {
+                block: block.into(),
+            },
+            NoteEvent::BlockUpdated(block) => Self::BlockUpdated {
+                block: block.into(),
+            },
+            NoteEvent::BlockDeleted { id } => Self::BlockDeleted { id },
+            NoteEvent::TextEdited { block, ops } => Self::TextEdited {
+                block: block.into(),
+                ops,
+            },
+            NoteEvent::BlocksReordered {
+                parent_block,
+                blocks,
+            } => Self::BlocksReordered {
+                parent_block,
+                blocks,
+            },
+            NoteEvent::Presence(users) => Self::Presence {
+                users: users.into_iter().map(CollaboratorSchema::from).collect(),
+            },
+        }
+    }
+}
+
+/// Edit sent by a client of `/notes/{id}/ws`, the same as the block routes
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum NoteEditSchema {
+    CreateBlock {
+        block_type: BlockType,
+        content: BlockContent,
+        #[serde(default)]
+        parent_block: Option<Uuid>,
+    },
+    UpdateBlock {
+        id: Uuid,
+        block_type: Option<BlockType>,
+        content: Option<BlockContent>,
+        /// Version the edit is based on, like `If-Match` of the block routes
+        #[serde(default)]
+        version: Option<i64>,
+    },
+    /// Text operations on a PlainText or Code block, merged with concurrent ones
+    EditText { id: Uuid, ops: Vec<TextOp> },
+    DeleteBlock {
+        id: Uuid,
+        #[serde(default)]
+        promote_children: bool,
+    },
+    MoveBlock {
+        id: Uuid,
+        parent_block: Option<Uuid>,
+    },
+    ReorderBlocks {
+        #[serde(default)]
+        parent_block: Option<Uuid>,
+        blocks: Vec<Uuid>,
+        /// Version of the note the order is based on
+        #[serde(default)]
+        version: Option<i64>,
+    },
+}
@@ -0,0 +1,184 @@
+use crate::schemas::note::NoteIconSchema;
+use remind_core::{
+    CollectionGroupDTO, CollectionPropertyDTO, CollectionRowDTO, CollectionViewDTO, PropertyType,
+    PropertyUpdateDTO, PropertyValue, ViewFilter, ViewResultDTO, ViewSort, ViewType, ViewUpdateDTO,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PropertySchema {
+    pub id: Uuid,
+    pub note_id: Uuid,
+    pub name: String,
+    pub property_type: PropertyType,
+    pub options: Vec<String>,
+    pub position: i32,
+}
+
+impl From<CollectionPropertyDTO> for PropertySchema {
+    fn from(value: CollectionPropertyDTO) -> Self {
+        Self {
+            id: value.id,
+            note_id: value.note_id,
+            name: value.name,
+            property_type: value.property_type,
+            options: value.options,
+            position: value.position,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CreatePropertySchema {
+    pub name: String,
+    pub property_type: PropertyType,
+    #[serde(default)]
+    pub options: Vec<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct UpdatePropertySchema {
+    pub name: Option<String>,
+    pub options: Option<Vec<String>>,
+}
+
+impl From<UpdatePropertySchema> for PropertyUpdateDTO {
+    fn from(value: UpdatePropertySchema) -> Self {
+        Self {
+            name: value.name,
+            options: value.options,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SetPropertyValueSchema {
+    /// `null` clears the value
+    pub value: Option<PropertyValue>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ViewSchema {
+    pub id: Uuid,
+    pub note_id: Uuid,
+    pub name: String,
+    pub view_type: ViewType,
+    pub filters: Vec<ViewFilter>,
+    pub sorts: Vec<ViewSort>,
+    pub group_by: Option<Uuid>,
+    pub position: i32,
+}
+
+impl From<CollectionViewDTO> for ViewSchema {
+    fn from(value: CollectionViewDTO) -> Self {
+        Self {
+            id: value.id,
+            note_id: value.note_id,
+            name: value.name,
+            view_type: value.view_type,
+            filters: value.filters,
+            sorts: value.sorts,
+            group_by: value.group_by,
+            position: value.position,
+        }
+    }
+}
+
+/// Used both to create a view and to replace its definition
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SaveViewSchema {
+    pub name: String,
+    pub view_type: ViewType,
+    #[serde(default)]
+    pub filters: Vec<ViewFilter>,
+    #[serde(default)]
+    pub sorts: Vec<ViewSort>,
+    pub group_by: Option<Uuid>,
+}
+
+impl From<SaveViewSchema> for ViewUpdateDTO {
+    fn from(value: SaveViewSchema) -> Self {
+        Self {
+            name: value.name,
+            view_type: value.view_type,
+            filters: value.filters,
+            sorts: value.sorts,
+            group_by: value.group_by,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CollectionRowSchema {
+    pub note_id: Uuid,
+    pub title: String,
+    pub icon: NoteIconSchema,
+    pub values: HashMap<Uuid, PropertyValue>,
+}
+
+impl From<CollectionRowDTO> for CollectionRowSchema {
+    fn from(value: CollectionRowDTO) -> Self {
+        Self {
+            note_id: value.note_id,
+            title: value.title,
+            icon: NoteIconSchema {
+                icon_type: value.icon_type,
+                data: value.icon_data,
+            },
+            values: value.values,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CollectionGroupSchema {
+    pub value: Option<String>,
+    pub rows: Vec<CollectionRowSchema>,
+}
+
+impl From<CollectionGroupDTO> for CollectionGroupSchema {
+    fn from(value: CollectionGroupDTO) -> Self {
+        Self {
+            value: value.value,
+            rows: value
+                .rows
+                .into_iter()
+                .map(CollectionRowSchema::from)
+                .collect(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ViewResultSchema {
+    pub view: ViewSchema,
+    pub properties: Vec<PropertySchema>,
+    pub rows: Vec<CollectionRowSchema>,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub groups: Vec<CollectionGroupSchema>,
+}
+
+impl From<ViewResultDTO> for ViewResultSchema {
+    fn from(value: ViewResultDTO) -> Self {
+        Self {
+            view: value.view.into(),
+            properties: value
+                .properties
+                .into_iter()
+                .map(PropertySchema::from)
+                .collect(),
+            rows: value
+                .rows
+                .into_iter()
+                .map(CollectionRowSchema::from)
+                .collect(),
+            groups: value
+                .groups
+                .into_iter()
+                .map(CollectionGroupSchema::from)
+                .collect(),
+        }
+    }
+}
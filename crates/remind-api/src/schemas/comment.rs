@@ -0,0 +1,82 @@
+use chrono::{DateTime, Utc};
+use remind_core::{CommentDTO, CommentThreadDTO};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CommentSchema {
+    pub id: Uuid,
+    pub note_id: Uuid,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_id: Option<Uuid>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thread_id: Option<Uuid>,
+    pub author_id: Uuid,
+    pub body: String,
+    pub created_at: DateTime<Utc>,
+    pub edited_at: Option<DateTime<Utc>>,
+}
+
+impl From<CommentDTO> for CommentSchema {
+    fn from(value: CommentDTO) -> Self {
+        Self {
+            id: value.id,
+            note_id: value.note_id,
+            block_id: value.block_id,
+            thread_id: value.thread_id,
+            author_id: value.author_id,
+            body: value.body,
+            created_at: value.created_at,
+            edited_at: value.edited_at,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CommentThreadSchema {
+    #[serde(flatten)]
+    pub comment: CommentSchema,
+    pub resolved: bool,
+    pub resolved_at: Option<DateTime<Utc>>,
+    pub resolved_by: Option<Uuid>,
+    /// The block of the thread was deleted
+    pub orphaned: bool,
+    pub replies: Vec<CommentSchema>,
+}
+
+impl From<CommentThreadDTO> for CommentThreadSchema {
+    fn from(value: CommentThreadDTO) -> Self {
+        let CommentThreadDTO { comment, replies } = value;
+        Self {
+            resolved: comment.resolved_at.is_some(),
+            resolved_at: comment.resolved_at,
+            resolved_by: comment.resolved_by,
+            orphaned: comment.orphaned,
+            comment: comment.into(),
+            replies: replies.into_iter().map(CommentSchema::from).collect(),
+        }
+    }
+}
+
+impl From<CommentDTO> for CommentThreadSchema {
+    fn from(value: CommentDTO) -> Self {
+        CommentThreadDTO {
+            comment: value,
+            replies: Vec::new(),
+        }
+        .into()
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CreateCommentSchema {
+    pub note_id: Uuid,
+    /// Block of the note the thread is about, leave out for the whole note
+    pub block_id: Option<Uuid>,
+    pub body: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CommentBodySchema {
+    pub body: String,
+}
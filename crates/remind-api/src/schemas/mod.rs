@@ -1,10 +1,22 @@
 use serde::ser::SerializeStruct;
 use serde::{Serialize, Serializer};
 
+pub mod attachment;
+pub mod audit;
 pub mod auth;
 pub mod block;
+pub mod change;
+pub mod collab;
+pub mod collection;
+pub mod comment;
 pub mod note;
+pub mod notification;
+pub mod share_link;
+pub mod sync;
+pub mod tag;
+pub mod template;
 pub mod user;
+pub mod webhook;
 pub mod workspace;
 
 pub struct DataResponseSchema<T: Serialize>(pub T);
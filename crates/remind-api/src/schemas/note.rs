@@ -1,5 +1,7 @@
 use crate::schemas::block::BlockSchema;
-use remind_core::{NoteCreateDTO, NoteDTO, NoteIconType, NoteUpdateDTO};
+use crate::schemas::tag::TagSchema;
+use remind_core::render::RenderFormat;
+use remind_core::{BacklinkDTO, NoteCreateDTO, NoteDTO, NoteIconType, NoteUpdateDTO};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -18,6 +20,10 @@ pub struct NoteSchema {
     pub workspace_id: Uuid,
     pub blocks: Vec<BlockSchema>,
     pub parent: Option<Uuid>,
+    pub tags: Vec<TagSchema>,
+    /// Also sent as ETag, send it back as `If-Match` to update the note
+    /// or reorder its blocks
+    pub version: i64,
 }
 
 impl From<NoteDTO> for NoteSchema {
@@ -36,15 +42,38 @@ impl From<NoteDTO> for NoteSchema {
                 .map(|b| BlockSchema::from(b.clone()))
                 .collect(),
             parent: value.parent_note,
+            tags: value.tags.into_iter().map(TagSchema::from).collect(),
+            version: value.version,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BacklinkSchema {
+    pub note_id: Uuid,
+    pub title: String,
+    pub snippets: Vec<String>,
+}
+
+impl From<BacklinkDTO> for BacklinkSchema {
+    fn from(value: BacklinkDTO) -> Self {
+        Self {
+            note_id: value.note_id,
+            title: value.title,
+            snippets: value.snippets,
         }
     }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct CreateNoteSchema {
+    /// May be left out with a template, which then names the note
+    #[serde(default)]
     pub title: String,
     pub workspace_id: Uuid,
     pub parent: Option<Uuid>,
+    #[serde(default)]
+    pub template_id: Option<Uuid>,
 }
 
 impl From<CreateNoteSchema> for NoteCreateDTO {
@@ -53,6 +82,8 @@ impl From<CreateNoteSchema> for NoteCreateDTO {
             title: value.title,
             workspace_id: value.workspace_id,
             parent_note: value.parent,
+            template_id: value.template_id,
+            author: None,
         }
     }
 }
@@ -64,11 +95,23 @@ pub struct UpdateNoteSchema {
 
 impl From<UpdateNoteSchema> for NoteUpdateDTO {
     fn from(value: UpdateNoteSchema) -> Self {
-        Self { title: value.title }
+        Self {
+            title: value.title,
+            version: None,
+        }
     }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ReorderNoteBlocksSchema {
     pub blocks: Vec<Uuid>,
+    /// Toggle whose children are reordered, the top level when missing
+    #[serde(default)]
+    pub parent_block: Option<Uuid>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ExportNoteQuery {
+    #[serde(default)]
+    pub format: RenderFormat,
 }
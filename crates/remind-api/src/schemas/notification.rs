@@ -0,0 +1,92 @@
+use chrono::{DateTime, Utc};
+use remind_core::{
+    NotificationDTO, NotificationKind, NotificationPageDTO, NotificationPreferenceDTO,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct NotificationSchema {
+    pub id: Uuid,
+    pub kind: NotificationKind,
+    pub actor_id: Option<Uuid>,
+    pub note_id: Option<Uuid>,
+    pub block_id: Option<Uuid>,
+    pub comment_id: Option<Uuid>,
+    pub read: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<NotificationDTO> for NotificationSchema {
+    fn from(value: NotificationDTO) -> Self {
+        Self {
+            id: value.id,
+            kind: value.kind,
+            actor_id: value.actor_id,
+            note_id: value.note_id,
+            block_id: value.block_id,
+            comment_id: value.comment_id,
+            read: value.read_at.is_some(),
+            created_at: value.created_at,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct NotificationPageSchema {
+    pub data: Vec<NotificationSchema>,
+    pub unread_count: i64,
+    /// Send it back as `before` for the following page
+    pub next_cursor: Option<Uuid>,
+}
+
+impl From<NotificationPageDTO> for NotificationPageSchema {
+    fn from(value: NotificationPageDTO) -> Self {
+        Self {
+            data: value
+                .notifications
+                .into_iter()
+                .map(NotificationSchema::from)
+                .collect(),
+            unread_count: value.unread_count,
+            next_cursor: value.next_cursor,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct NotificationQuery {
+    /// Cursor of the previous page
+    pub before: Option<Uuid>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct MarkedReadSchema {
+    /// Notifications that were unread
+    pub marked: u64,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct NotificationPreferenceSchema {
+    pub kind: NotificationKind,
+    pub enabled: bool,
+}
+
+impl From<NotificationPreferenceDTO> for NotificationPreferenceSchema {
+    fn from(value: NotificationPreferenceDTO) -> Self {
+        Self {
+            kind: value.kind,
+            enabled: value.enabled,
+        }
+    }
+}
+
+impl From<NotificationPreferenceSchema> for NotificationPreferenceDTO {
+    fn from(value: NotificationPreferenceSchema) -> Self {
+        Self {
+            kind: value.kind,
+            enabled: value.enabled,
+        }
+    }
+}
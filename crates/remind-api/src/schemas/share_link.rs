@@ -0,0 +1,102 @@
+use crate::schemas::note::NoteIconSchema;
+use chrono::{DateTime, Utc};
+use remind_core::{BlockType, PublicBlockContent, PublicBlockDTO, PublicNoteDTO, ShareLinkDTO};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ShareLinkSchema {
+    pub id: Uuid,
+    pub slug: String,
+    pub note_id: Uuid,
+    pub has_password: bool,
+    pub include_subpages: bool,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<ShareLinkDTO> for ShareLinkSchema {
+    fn from(value: ShareLinkDTO) -> Self {
+        Self {
+            id: value.id,
+            slug: value.slug,
+            note_id: value.note_id,
+            has_password: value.has_password,
+            include_subpages: value.include_subpages,
+            expires_at: value.expires_at,
+            created_at: value.created_at,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CreateShareLinkSchema {
+    pub password: Option<String>,
+    #[serde(default)]
+    pub include_subpages: bool,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PublicBlockSchema {
+    pub id: Uuid,
+    pub block_type: BlockType,
+    pub content: PublicBlockContent,
+    pub position: i32,
+    /// Number of a NumberedList item within its list
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub number: Option<u32>,
+    /// Body of a toggle
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<PublicBlockSchema>,
+}
+
+impl From<PublicBlockDTO> for PublicBlockSchema {
+    fn from(value: PublicBlockDTO) -> Self {
+        Self {
+            id: value.id,
+            block_type: value.block_type,
+            content: value.content,
+            position: value.position,
+            number: value.number,
+            children: value
+                .children
+                .into_iter()
+                .map(PublicBlockSchema::from)
+                .collect(),
+        }
+    }
+}
+
+/// Sanitized `NoteSchema` for unauthenticated readers
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PublicNoteSchema {
+    pub id: Uuid,
+    pub title: String,
+    pub icon: NoteIconSchema,
+    pub blocks: Vec<PublicBlockSchema>,
+    pub subpages: Vec<PublicNoteSchema>,
+}
+
+impl From<PublicNoteDTO> for PublicNoteSchema {
+    fn from(value: PublicNoteDTO) -> Self {
+        Self {
+            id: value.id,
+            title: value.title,
+            icon: NoteIconSchema {
+                icon_type: value.icon_type,
+                data: value.icon_data,
+            },
+            blocks: value
+                .blocks
+                .into_iter()
+                .map(PublicBlockSchema::from)
+                .collect(),
+            subpages: value
+                .subpages
+                .into_iter()
+                .map(PublicNoteSchema::from)
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use crate::schemas::change::ChangeSchema;
+use crate::schemas::note::NoteSchema;
+use remind_core::{BlockContent, BlockType, TextOp};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct SyncQuery {
+    /// Cursor of the last pull, everything is sent without it
+    pub since: Option<i64>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SyncPullSchema {
+    /// Send it back as `since` on the next pull
+    pub cursor: i64,
+    /// More changes are waiting after `cursor`
+    pub has_more: bool,
+    pub changes: Vec<ChangeSchema>,
+    /// Current state of the changed notes, with all their blocks
+    pub notes: Vec<NoteSchema>,
+    pub deleted_notes: Vec<Uuid>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct SyncPushSchema {
+    pub operations: Vec<SyncOperationSchema>,
+}
+
+/// Operation made by a client while offline. `id` is generated by the client
+/// and stays the same when the operation is sent again.
+#[derive(Clone, Debug, Deserialize)]
+pub struct SyncOperationSchema {
+    pub id: Uuid,
+    #[serde(flatten)]
+    pub operation: SyncOperationKind,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SyncOperationKind {
+    /// `note_id` is generated by the client
+    CreateNote {
+        note_id: Uuid,
+        workspace_id: Uuid,
+        title: String,
+        #[serde(default)]
+        parent_note: Option<Uuid>,
+    },
+    UpdateNote {
+        note_id: Uuid,
+        title: Option<String>,
+        /// Version the edit is based on, `None` overwrites
+        #[serde(default)]
+        version: Option<i64>,
+    },
+    DeleteNote {
+        note_id: Uuid,
+    },
+    /// `block_id` is generated by the client
+    CreateBlock {
+        block_id: Uuid,
+        note_id: Uuid,
+        block_type: BlockType,
+        content: BlockContent,
+        #[serde(default)]
+        parent_block: Option<Uuid>,
+    },
+    UpdateBlock {
+        block_id: Uuid,
+        block_type: Option<BlockType>,
+        content: Option<BlockContent>,
+        #[serde(default)]
+        version: Option<i64>,
+    },
+    /// Text operations on a PlainText or Code block, merged with the edits
+    /// made in the meantime instead of conflicting with them
+    EditText {
+        block_id: Uuid,
+        ops: Vec<TextOp>,
+    },
+    DeleteBlock {
+        block_id: Uuid,
+        #[serde(default)]
+        promote_children: bool,
+    },
+    ReorderBlocks {
+        note_id: Uuid,
+        #[serde(default)]
+        parent_block: Option<Uuid>,
+        blocks: Vec<Uuid>,
+        #[serde(default)]
+        version: Option<i64>,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncStatus {
+    Applied,
+    /// Based on an outdated version, see `current`
+    Conflict,
+    Rejected,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SyncResultSchema {
+    pub id: Uuid,
+    pub status: SyncStatus,
+    /// Version of the note or block after the operation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status_code: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// Current state of the note or block in conflict
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub current: Option<Value>,
+}
+
+impl SyncResultSchema {
+    pub fn new(id: Uuid, status: SyncStatus) -> Self {
+        Self {
+            id,
+            status,
+            version: None,
+            status_code: None,
+            message: None,
+            current: None,
+        }
+    }
+}
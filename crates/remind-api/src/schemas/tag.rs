@@ -0,0 +1,65 @@
+use remind_core::{TagDTO, TagMatch, TagUpdateDTO};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TagSchema {
+    pub id: Uuid,
+    pub workspace_id: Uuid,
+    pub name: String,
+    pub color: String,
+}
+
+impl From<TagDTO> for TagSchema {
+    fn from(value: TagDTO) -> Self {
+        Self {
+            id: value.id,
+            workspace_id: value.workspace_id,
+            name: value.name,
+            color: value.color,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CreateTagSchema {
+    pub workspace_id: Uuid,
+    pub name: String,
+    pub color: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct UpdateTagSchema {
+    pub name: Option<String>,
+    pub color: Option<String>,
+}
+
+impl From<UpdateTagSchema> for TagUpdateDTO {
+    fn from(value: UpdateTagSchema) -> Self {
+        Self {
+            name: value.name,
+            color: value.color,
+        }
+    }
+}
+
+/// `?tags=<id>,<id>&match=all` on the notes of a workspace
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct TagFilterQuery {
+    /// Comma separated tag ids
+    pub tags: Option<String>,
+    #[serde(default, rename = "match")]
+    pub matching: TagMatch,
+}
+
+impl TagFilterQuery {
+    pub fn tag_ids(&self) -> Result<Vec<Uuid>, uuid::Error> {
+        self.tags
+            .iter()
+            .flat_map(|t| t.split(','))
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+            .map(Uuid::parse_str)
+            .collect()
+    }
+}
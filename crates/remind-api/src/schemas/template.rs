@@ -0,0 +1,40 @@
+use chrono::{DateTime, Utc};
+use remind_core::{NoteIconType, NoteTemplateDTO, TemplateBlock};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct NoteTemplateSchema {
+    pub id: Uuid,
+    pub workspace_id: Uuid,
+    pub name: String,
+    pub title: String,
+    pub icon_type: NoteIconType,
+    pub icon_data: String,
+    pub blocks: Vec<TemplateBlock>,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<NoteTemplateDTO> for NoteTemplateSchema {
+    fn from(value: NoteTemplateDTO) -> Self {
+        Self {
+            id: value.id,
+            workspace_id: value.workspace_id,
+            name: value.name,
+            title: value.title,
+            icon_type: value.icon_type,
+            icon_data: value.icon_data,
+            blocks: value.blocks,
+            created_by: value.created_by,
+            created_at: value.created_at,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct CreateTemplateSchema {
+    pub name: String,
+    /// Title pattern like `Standup {{date}}`, defaults to the title of the note
+    pub title: Option<String>,
+}
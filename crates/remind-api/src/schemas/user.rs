@@ -1,4 +1,4 @@
-use remind_core::UserDTO;
+use remind_core::{StorageUsageDTO, UserDTO};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -18,3 +18,39 @@ impl From<UserDTO> for UserSchema {
         }
     }
 }
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StorageUsageSchema {
+    pub used: i64,
+    pub quota: i64,
+}
+
+impl From<StorageUsageDTO> for StorageUsageSchema {
+    fn from(value: StorageUsageDTO) -> Self {
+        Self {
+            used: value.used,
+            quota: value.quota,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WorkspaceStorageUsageSchema {
+    pub workspace_id: Uuid,
+    #[serde(flatten)]
+    pub usage: StorageUsageSchema,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct UserStorageSchema {
+    #[serde(flatten)]
+    pub usage: StorageUsageSchema,
+    pub workspaces: Vec<WorkspaceStorageUsageSchema>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MeSchema {
+    #[serde(flatten)]
+    pub user: UserSchema,
+    pub storage: UserStorageSchema,
+}
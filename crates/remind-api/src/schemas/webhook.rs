@@ -0,0 +1,76 @@
+use chrono::{DateTime, Utc};
+use remind_core::{ChangeType, DeliveryStatus, WebhookDTO, WebhookDeliveryDTO};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WebhookSchema {
+    pub id: Uuid,
+    pub workspace_id: Uuid,
+    pub url: String,
+    pub event_types: Vec<ChangeType>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<WebhookDTO> for WebhookSchema {
+    fn from(value: WebhookDTO) -> Self {
+        Self {
+            id: value.id,
+            workspace_id: value.workspace_id,
+            url: value.url,
+            event_types: value.event_types,
+            created_at: value.created_at,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CreateWebhookSchema {
+    pub workspace_id: Uuid,
+    pub url: String,
+    /// Key of the `X-ReMind-Signature` HMAC-SHA256 of every payload and
+    /// its `X-ReMind-Timestamp`
+    pub secret: String,
+    /// Note and block changes, there are no reminder events yet
+    pub event_types: Vec<ChangeType>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WebhookDeliverySchema {
+    pub id: Uuid,
+    pub webhook_id: Uuid,
+    pub event: String,
+    pub payload: Value,
+    pub status: DeliveryStatus,
+    pub attempts: i32,
+    /// When a pending delivery is tried next
+    pub next_attempt_at: DateTime<Utc>,
+    pub last_status_code: Option<i32>,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+impl From<WebhookDeliveryDTO> for WebhookDeliverySchema {
+    fn from(value: WebhookDeliveryDTO) -> Self {
+        Self {
+            id: value.id,
+            webhook_id: value.webhook_id,
+            event: value.event,
+            payload: value.payload,
+            status: value.status,
+            attempts: value.attempts,
+            next_attempt_at: value.next_attempt_at,
+            last_status_code: value.last_status_code,
+            last_error: value.last_error,
+            created_at: value.created_at,
+            delivered_at: value.delivered_at,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct WebhookDeliveriesQuery {
+    pub limit: Option<i64>,
+}
@@ -1,16 +1,39 @@
-use crate::config::Config;
+use crate::config::{Config, StorageBackend};
+use remind_core::collab::CollabHub;
+use remind_core::diagrams::CommandSvgRenderer;
+use remind_core::previews::{HttpLinkPreviewer, PreviewLimits};
 use remind_core::remind_auth::{DecodingKey, EncodingKey, JwtProcessor};
+use remind_core::webhooks::{HttpWebhookSender, SenderLimits};
 use remind_core::{
-    BlockRepository, BlockService, NoteRepository, NoteService, PgPool, UserRepository,
-    UserService, WorkspaceRepository, WorkspaceService,
+    AttachmentRepository, AttachmentService, AuditRepository, AuditService, BlobStore,
+    BlockRepository, BlockService, ChangeRepository, ChangeService, CollectionRepository,
+    CollectionService, CommentRepository, CommentService, LocalBlobStore, NoteRepository,
+    NoteService, NotificationRepository, NotificationService, PgPool, RetryPolicy, S3BlobStore,
+    S3Config, ShareLinkRepository, ShareLinkService, SyncRepository, SyncService, TagRepository,
+    TagService, UploadLimits, UserRepository, UserService, WebhookRepository, WebhookService,
+    WorkspaceRepository, WorkspaceService,
 };
+use std::sync::Arc;
+use std::time::Duration;
 
 #[derive(Clone)]
 pub struct AppState {
     pub user_service: UserService<UserRepository>,
+    pub audit_service: AuditService<AuditRepository>,
     pub workspace_service: WorkspaceService<WorkspaceRepository>,
-    pub block_service: BlockService<BlockRepository>,
-    pub note_service: NoteService<NoteRepository, BlockRepository>,
+    pub block_service: BlockService<BlockRepository, ChangeRepository>,
+    pub note_service: NoteService<NoteRepository, BlockRepository, ChangeRepository>,
+    pub change_service: ChangeService<ChangeRepository>,
+    pub sync_service: SyncService<SyncRepository>,
+    pub share_link_service: ShareLinkService<ShareLinkRepository, NoteRepository, BlockRepository>,
+    pub attachment_service: AttachmentService<AttachmentRepository>,
+    pub collection_service: CollectionService<CollectionRepository, NoteRepository>,
+    pub tag_service: TagService<TagRepository, NoteRepository>,
+    pub comment_service: CommentService<CommentRepository, NoteRepository, BlockRepository>,
+    pub notification_service: NotificationService<NotificationRepository>,
+    pub webhook_service: WebhookService<WebhookRepository, ChangeRepository>,
+    /// Viewers of open notes and their live events
+    pub collab: CollabHub,
     pub config: Config,
     pub jwt_processor: JwtProcessor,
 }
@@ -22,21 +45,118 @@ impl AppState {
             EncodingKey::from_secret(config.jwt_secret.as_ref()),
             DecodingKey::from_secret(config.jwt_secret.as_ref()),
         );
-        let user_service = UserService::new(user_repo, jwt_processor.clone());
+        let audit_service = AuditService::new(AuditRepository::new(pg_pool.clone()));
+        let user_service = UserService::new(user_repo, jwt_processor.clone())
+            .with_audit_sink(Arc::new(audit_service.clone()));
         let workspace_repo = WorkspaceRepository::new(pg_pool.clone());
         let workspace_service = WorkspaceService::new(workspace_repo);
 
+        let attachment_repo = AttachmentRepository::new(pg_pool.clone());
+        let attachment_service = AttachmentService::new(
+            attachment_repo,
+            blob_store(&config),
+            UploadLimits {
+                max_file_size: config.max_upload_size,
+                user_quota: config.user_storage_quota,
+                workspace_quota: config.workspace_storage_quota,
+            },
+        );
+
         let block_repo = BlockRepository::new(pg_pool.clone());
-        let block_service = BlockService::new(block_repo.clone());
+        let change_repo = ChangeRepository::new(pg_pool.clone());
+        let change_service = ChangeService::new(change_repo.clone());
+        let webhook_service = WebhookService::new(
+            WebhookRepository::new(pg_pool.clone()),
+            change_repo.clone(),
+            Arc::new(HttpWebhookSender::new(SenderLimits {
+                timeout: Duration::from_secs(config.webhook_timeout),
+                allow_private_addresses: config.webhook_allow_private,
+            })),
+            RetryPolicy::default(),
+        );
+        let sync_service = SyncService::new(SyncRepository::new(pg_pool.clone()));
+        let notification_service =
+            NotificationService::new(NotificationRepository::new(pg_pool.clone()));
+        let mut block_service = BlockService::new(block_repo.clone(), change_repo.clone())
+            .with_image_pipeline(Arc::new(attachment_service.clone()))
+            .with_notifier(Arc::new(notification_service.clone()))
+            .with_audit_sink(Arc::new(audit_service.clone()))
+            .with_link_previewer(Arc::new(HttpLinkPreviewer::new(PreviewLimits {
+                timeout: Duration::from_secs(config.link_preview_timeout),
+                max_body_size: config.link_preview_max_size,
+                max_redirects: config.link_preview_max_redirects,
+                allow_private_addresses: config.link_preview_allow_private,
+            })));
+        let render_timeout = Duration::from_secs(config.render_timeout);
+        if let Some(math) = config
+            .math_render_command
+            .as_deref()
+            .and_then(|c| CommandSvgRenderer::from_command_line(c, render_timeout))
+        {
+            block_service = block_service.with_math_renderer(Arc::new(math));
+        }
+        if let Some(diagrams) = config
+            .diagram_render_command
+            .as_deref()
+            .and_then(|c| CommandSvgRenderer::from_command_line(c, render_timeout))
+        {
+            block_service = block_service.with_diagram_renderer(Arc::new(diagrams));
+        }
         let note_repo = NoteRepository::new(pg_pool.clone());
-        let note_service = NoteService::new(note_repo, block_repo);
+        let note_service = NoteService::new(note_repo.clone(), block_repo.clone(), change_repo)
+            .with_audit_sink(Arc::new(audit_service.clone()))
+            .with_template_writer(Arc::new(block_service.clone()));
+        let share_link_repo = ShareLinkRepository::new(pg_pool.clone());
+        let share_link_service =
+            ShareLinkService::new(share_link_repo, note_repo.clone(), block_repo.clone())
+                .with_audit_sink(Arc::new(audit_service.clone()));
+        let comment_service = CommentService::new(
+            CommentRepository::new(pg_pool.clone()),
+            note_repo.clone(),
+            block_repo,
+        )
+        .with_notifier(Arc::new(notification_service.clone()));
+        let collection_repo = CollectionRepository::new(pg_pool.clone());
+        let collection_service = CollectionService::new(collection_repo, note_repo.clone());
+        let tag_repo = TagRepository::new(pg_pool.clone());
+        let tag_service = TagService::new(tag_repo, note_repo);
         Self {
             user_service,
+            audit_service,
+            collab: CollabHub::new(),
             config,
             jwt_processor,
             workspace_service,
             block_service,
             note_service,
+            change_service,
+            sync_service,
+            share_link_service,
+            attachment_service,
+            collection_service,
+            tag_service,
+            comment_service,
+            notification_service,
+            webhook_service,
         }
     }
 }
+
+fn blob_store(config: &Config) -> Arc<dyn BlobStore> {
+    match config.storage_backend {
+        StorageBackend::Local => Arc::new(LocalBlobStore::new(config.storage_path.clone())),
+        StorageBackend::S3 => Arc::new(S3BlobStore::new(S3Config {
+            endpoint: config.s3_endpoint.clone().expect("S3_ENDPOINT is required"),
+            bucket: config.s3_bucket.clone().expect("S3_BUCKET is required"),
+            region: config.s3_region.clone(),
+            access_key: config
+                .s3_access_key
+                .clone()
+                .expect("S3_ACCESS_KEY is required"),
+            secret_key: config
+                .s3_secret_key
+                .clone()
+                .expect("S3_SECRET_KEY is required"),
+        })),
+    }
+}
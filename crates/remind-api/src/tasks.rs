@@ -0,0 +1,38 @@
+use crate::state::AppState;
+use std::time::Duration;
+
+/// Periodically deletes attachments no block references anymore
+pub fn spawn_attachment_gc(state: AppState) {
+    let interval = Duration::from_secs(state.config.attachment_gc_interval);
+    let grace_period = chrono::Duration::seconds(state.config.attachment_gc_grace_period);
+
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            match state.attachment_service.collect_garbage(grace_period).await {
+                Ok(0) => {}
+                Ok(count) => tracing::info!("Deleted {} orphaned attachments", count),
+                Err(e) => tracing::error!("Attachment garbage collection failed: {}", e),
+            }
+        }
+    });
+}
+
+/// Queues webhook deliveries for new changes and sends the ones that are due
+pub fn spawn_webhook_worker(state: AppState) {
+    let interval = Duration::from_secs(state.config.webhook_worker_interval);
+
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = state.webhook_service.enqueue_changes().await {
+                tracing::error!("Queueing webhook deliveries failed: {}", e);
+            }
+            if let Err(e) = state.webhook_service.deliver_due().await {
+                tracing::error!("Sending webhook deliveries failed: {}", e);
+            }
+        }
+    });
+}
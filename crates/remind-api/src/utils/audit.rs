@@ -0,0 +1,60 @@
+use crate::errors::ApiError;
+use crate::state::AppState;
+use axum::extract::{ConnectInfo, FromRequestParts};
+use axum::http::header::USER_AGENT;
+use axum::http::request::Parts;
+use remind_core::{AuditContext, UserDTO};
+use std::net::SocketAddr;
+
+/// Longest user agent kept in the audit log
+const MAX_USER_AGENT_LENGTH: usize = 512;
+
+/// Who made the request and from where. The user is known behind the
+/// auth middleware only.
+#[derive(Clone, Debug, Default)]
+pub struct RequestContext(pub AuditContext);
+
+impl FromRequestParts<AppState> for RequestContext {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let forwarded = parts
+            .headers
+            .get_all("x-forwarded-for")
+            .iter()
+            .filter_map(|h| h.to_str().ok())
+            .collect::<Vec<_>>()
+            .join(",");
+        let ip = forwarded_client(&forwarded, state.config.trusted_proxies).or_else(|| {
+            parts
+                .extensions
+                .get::<ConnectInfo<SocketAddr>>()
+                .map(|ConnectInfo(addr)| addr.ip().to_string())
+        });
+        let user_agent = parts
+            .headers
+            .get(USER_AGENT)
+            .and_then(|h| h.to_str().ok())
+            .map(|ua| ua.chars().take(MAX_USER_AGENT_LENGTH).collect());
+
+        Ok(Self(AuditContext {
+            actor_id: parts.extensions.get::<UserDTO>().map(|u| u.id),
+            ip,
+            user_agent,
+        }))
+    }
+}
+
+/// Client address in an `X-Forwarded-For` list passed through `proxies`
+/// trusted proxies. Each of them appends the address it got the request
+/// from, so entries further left were sent by the client and can be forged.
+pub(crate) fn forwarded_client(header: &str, proxies: usize) -> Option<String> {
+    let entries: Vec<&str> = header.split(',').map(str::trim).collect();
+    if proxies == 0 || entries.len() < proxies {
+        return None;
+    }
+    Some(entries[entries.len() - proxies].to_string()).filter(|ip| !ip.is_empty())
+}
@@ -0,0 +1,91 @@
+mod fixtures;
+
+use crate::fixtures::{TestApp, id_of, read_json, spawn_app};
+use reqwest::StatusCode;
+use reqwest::header::{CONTENT_DISPOSITION, CONTENT_TYPE, HeaderMap};
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+const BOUNDARY: &str = "remind-test-boundary";
+
+async fn upload(app: &TestApp, token: &str, workspace_id: Uuid, name: &str, data: &[u8]) -> Uuid {
+    let mut body = format!(
+        "--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"workspace_id\"\r\n\r\n\
+         {workspace_id}\r\n--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"file\"; \
+         filename=\"{name}\"\r\nContent-Type: application/octet-stream\r\n\r\n"
+    )
+    .into_bytes();
+    body.extend_from_slice(data);
+    body.extend_from_slice(format!("\r\n--{BOUNDARY}--\r\n").as_bytes());
+
+    let response = reqwest::Client::new()
+        .post(app.url("/attachments"))
+        .bearer_auth(token)
+        .header(
+            CONTENT_TYPE,
+            format!("multipart/form-data; boundary={BOUNDARY}"),
+        )
+        .body(body)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    id_of(&read_json(response).await)
+}
+
+fn assert_downloaded(headers: &HeaderMap) {
+    assert_eq!(headers[CONTENT_TYPE], "application/octet-stream");
+    assert_eq!(
+        headers[CONTENT_DISPOSITION],
+        "attachment; filename=\"page.html\""
+    );
+    assert_eq!(headers["x-content-type-options"], "nosniff");
+    assert_eq!(headers["content-security-policy"], "sandbox");
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_markup_is_never_served_inline(pool: PgPool) {
+    let app = spawn_app(pool).await;
+    let token = app.create_user("alice").await;
+    let workspace_id = app.create_workspace(&token).await;
+    let html = b"<html><body><script>alert(document.domain)</script></body></html>";
+    let attachment_id = upload(&app, &token, workspace_id, "page.html", html).await;
+
+    let response = app
+        .get(&format!("/attachments/{attachment_id}/content"), &token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_downloaded(response.headers());
+    assert_eq!(response.bytes().await.unwrap().as_ref(), html);
+
+    // The same goes for uploads read through a share link
+    let note = app.create_note(&token, workspace_id).await;
+    let block = json!({
+        "block_type": "File",
+        "note_id": id_of(&note),
+        "content": { "type": "File", "attachment_id": attachment_id, "name": "page.html" },
+    });
+    let response = app.post("/blocks", &token, block).send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = app
+        .post(&format!("/notes/{}/share", id_of(&note)), &token, json!({}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let slug = read_json(response).await["slug"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    let response = reqwest::get(app.url(&format!(
+        "/public/{slug}/attachments/{attachment_id}/content"
+    )))
+    .await
+    .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_downloaded(response.headers());
+}
@@ -0,0 +1,64 @@
+mod fixtures;
+
+use crate::fixtures::{TestApp, read_json, spawn_app_with_config, test_config};
+use remind_api::config::Config;
+use sqlx::PgPool;
+
+/// Address of the latest login of the user
+async fn login_ip(app: &TestApp, token: &str) -> Option<String> {
+    let response = app
+        .get("/auth/me/audit?action=login_succeeded", token)
+        .send()
+        .await
+        .unwrap();
+    let audit = read_json(response).await;
+    assert_eq!(audit["data"][0]["action"], "login_succeeded");
+    audit["data"][0]["ip"].as_str().map(str::to_string)
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_login_ip_behind_proxy(pool: PgPool) {
+    let config = Config {
+        trusted_proxies: 1,
+        ..test_config()
+    };
+    let app = spawn_app_with_config(pool, config).await;
+    let token = app.create_user("alice").await;
+    let login = app.url("/auth/login/username");
+
+    // The proxy appends the address it got the request from, the entry
+    // before it was sent by the client
+    let request = reqwest::Client::new()
+        .post(&login)
+        .header("X-Forwarded-For", "203.0.113.9, 198.51.100.7");
+    app.login("alice", request).await;
+    assert_eq!(
+        login_ip(&app, &token).await.as_deref(),
+        Some("198.51.100.7")
+    );
+
+    // Headers split by the proxy are read as one list
+    let request = reqwest::Client::new()
+        .post(&login)
+        .header("X-Forwarded-For", "203.0.113.9")
+        .header("X-Forwarded-For", "192.0.2.44");
+    app.login("alice", request).await;
+    assert_eq!(login_ip(&app, &token).await.as_deref(), Some("192.0.2.44"));
+
+    // Without the header the connection address is kept
+    app.login("alice", reqwest::Client::new().post(&login))
+        .await;
+    assert_eq!(login_ip(&app, &token).await.as_deref(), Some("127.0.0.1"));
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_login_ip_without_proxy(pool: PgPool) {
+    let app = spawn_app_with_config(pool, test_config()).await;
+    let token = app.create_user("alice").await;
+
+    let request = reqwest::Client::new()
+        .post(app.url("/auth/login/username"))
+        .header("X-Forwarded-For", "203.0.113.9");
+    app.login("alice", request).await;
+    assert_eq!(login_ip(&app, &token).await.as_deref(), Some("127.0.0.1"));
+}
@@ -0,0 +1,70 @@
+mod fixtures;
+
+use crate::fixtures::{id_of, spawn_app};
+use futures_util::StreamExt;
+use reqwest::StatusCode;
+use serde_json::Value;
+use sqlx::PgPool;
+use std::time::Duration;
+use tokio_tungstenite::connect_async;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::header::SEC_WEBSOCKET_PROTOCOL;
+use tokio_tungstenite::tungstenite::{Error, Message};
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_socket_authorization(pool: PgPool) {
+    let app = spawn_app(pool).await;
+    let token = app.create_user("alice").await;
+    let other = app.create_user("bob").await;
+    let workspace_id = app.create_workspace(&token).await;
+    let note_id = id_of(&app.create_note(&token, workspace_id).await);
+    let url = format!("ws://{}/notes/{note_id}/ws", app.addr);
+    let request = |protocols: Option<String>| {
+        let mut request = url.as_str().into_client_request().unwrap();
+        if let Some(protocols) = protocols {
+            let headers = request.headers_mut();
+            headers.insert(SEC_WEBSOCKET_PROTOCOL, protocols.parse().unwrap());
+        }
+        request
+    };
+
+    // Browsers offer the token as a subprotocol, only `remind` is accepted back
+    let (mut socket, response) = connect_async(request(Some(format!("remind, bearer.{token}"))))
+        .await
+        .unwrap();
+    assert_eq!(response.headers()[SEC_WEBSOCKET_PROTOCOL], "remind");
+
+    app.create_block(&token, note_id, "Hello").await;
+    let event = tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            let Some(Ok(Message::Text(text))) = socket.next().await else {
+                panic!("socket closed");
+            };
+            let event: Value = serde_json::from_str(&text).unwrap();
+            if event["type"] == "block_created" {
+                return event;
+            }
+        }
+    })
+    .await
+    .unwrap();
+    assert_eq!(event["block"]["content"]["spans"][0]["text"], "Hello");
+
+    let rejected = |result: Result<_, Error>| match result {
+        Err(Error::Http(response)) => response.status(),
+        Err(e) => panic!("unexpected error {e}"),
+        Ok(_) => panic!("socket opened"),
+    };
+    assert_eq!(
+        rejected(connect_async(request(None)).await),
+        StatusCode::BAD_REQUEST
+    );
+    assert_eq!(
+        rejected(connect_async(request(Some("remind, bearer.forged".to_string()))).await),
+        StatusCode::BAD_REQUEST
+    );
+    assert_eq!(
+        rejected(connect_async(request(Some(format!("remind, bearer.{other}")))).await),
+        StatusCode::FORBIDDEN
+    );
+}
@@ -0,0 +1,83 @@
+mod fixtures;
+
+use crate::fixtures::{TestApp, id_of, spawn_app};
+use reqwest::{Response, StatusCode};
+use serde_json::Value;
+use sqlx::PgPool;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Reads the next `count` events of a Server-Sent Events response as
+/// `(id, data)`, skipping keep-alive comments
+async fn read_events(response: &mut Response, count: usize) -> Vec<(i64, Value)> {
+    let mut events = Vec::new();
+    let mut buffer = String::new();
+    let read = async {
+        while events.len() < count {
+            let chunk = response.chunk().await.unwrap().expect("stream ended");
+            buffer.push_str(std::str::from_utf8(&chunk).unwrap());
+            while let Some(end) = buffer.find("\n\n") {
+                let event: String = buffer.drain(..end + 2).collect();
+                let field = |name: &str| {
+                    event
+                        .lines()
+                        .find_map(|l| l.strip_prefix(name))
+                        .map(|v| v.trim().to_string())
+                };
+                if let (Some(id), Some(data)) = (field("id:"), field("data:")) {
+                    events.push((id.parse().unwrap(), serde_json::from_str(&data).unwrap()));
+                }
+            }
+        }
+    };
+    tokio::time::timeout(Duration::from_secs(10), read)
+        .await
+        .expect("no event in time");
+    events
+}
+
+async fn open_feed(
+    app: &TestApp,
+    token: &str,
+    workspace_id: Uuid,
+    last_event_id: Option<&str>,
+) -> Response {
+    let mut request = app.get(&format!("/workspaces/my/{workspace_id}/events"), token);
+    if let Some(id) = last_event_id {
+        request = request.header("Last-Event-ID", id);
+    }
+    request.send().await.unwrap()
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_event_feed_resumes_after_last_event_id(pool: PgPool) {
+    let app = spawn_app(pool).await;
+    let token = app.create_user("alice").await;
+    let workspace_id = app.create_workspace(&token).await;
+    let note_id = id_of(&app.create_note(&token, workspace_id).await);
+    let first = id_of(&app.create_block(&token, note_id, "First").await);
+    let second = id_of(&app.create_block(&token, note_id, "Second").await);
+
+    let mut feed = open_feed(&app, &token, workspace_id, Some("0")).await;
+    assert_eq!(feed.status(), StatusCode::OK);
+    let events = read_events(&mut feed, 3).await;
+    assert_eq!(events[0].1["note_id"], note_id.to_string());
+    assert_eq!(events[1].1["block_id"], first.to_string());
+    assert_eq!(events[2].1["block_id"], second.to_string());
+    assert!(events.windows(2).all(|w| w[0].0 < w[1].0));
+
+    // A reconnecting client gets what it missed, and only that
+    let last_seen = events[1].0.to_string();
+    let mut feed = open_feed(&app, &token, workspace_id, Some(&last_seen)).await;
+    let events = read_events(&mut feed, 1).await;
+    assert_eq!(events[0].1["block_id"], second.to_string());
+
+    // New clients start from now
+    let mut feed = open_feed(&app, &token, workspace_id, None).await;
+    let third = id_of(&app.create_block(&token, note_id, "Third").await);
+    let events = read_events(&mut feed, 1).await;
+    assert_eq!(events[0].1["block_id"], third.to_string());
+
+    let feed = open_feed(&app, &token, workspace_id, Some("latest")).await;
+    assert_eq!(feed.status(), StatusCode::BAD_REQUEST);
+}
@@ -0,0 +1,143 @@
+#![allow(dead_code)]
+
+use remind_api::app;
+use remind_api::config::Config;
+use remind_api::state::AppState;
+use reqwest::header::CONTENT_TYPE;
+use reqwest::{Client, RequestBuilder, Response, StatusCode};
+use serde_json::{Value, json};
+use sqlx::PgPool;
+use std::net::SocketAddr;
+use uuid::Uuid;
+
+/// The API served on a free local port
+pub struct TestApp {
+    pub addr: SocketAddr,
+    pub state: AppState,
+    client: Client,
+}
+
+pub fn test_config() -> Config {
+    let storage = std::env::temp_dir().join(format!("remind-api-test-{}", Uuid::new_v4()));
+    Config {
+        jwt_secret: "test-secret".to_string(),
+        storage_path: storage.to_string_lossy().into_owned(),
+        max_upload_size: 1024 * 1024,
+        user_storage_quota: 16 * 1024 * 1024,
+        workspace_storage_quota: 16 * 1024 * 1024,
+        ..Config::default()
+    }
+}
+
+pub async fn spawn_app(pool: PgPool) -> TestApp {
+    spawn_app_with_config(pool, test_config()).await
+}
+
+pub async fn spawn_app_with_config(pool: PgPool, config: Config) -> TestApp {
+    let state = AppState::new(pool, config);
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let router = app(state.clone());
+    tokio::spawn(async move {
+        axum::serve(
+            listener,
+            router.into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .await
+        .unwrap();
+    });
+    TestApp {
+        addr,
+        state,
+        client: Client::new(),
+    }
+}
+
+impl TestApp {
+    pub fn url(&self, path: &str) -> String {
+        format!("http://{}{path}", self.addr)
+    }
+
+    pub fn get(&self, path: &str, token: &str) -> RequestBuilder {
+        self.client.get(self.url(path)).bearer_auth(token)
+    }
+
+    pub fn post(&self, path: &str, token: &str, body: Value) -> RequestBuilder {
+        with_json(self.client.post(self.url(path)).bearer_auth(token), body)
+    }
+
+    pub fn put(&self, path: &str, token: &str, body: Value) -> RequestBuilder {
+        with_json(self.client.put(self.url(path)).bearer_auth(token), body)
+    }
+
+    /// Registers a user, returns their access token
+    pub async fn create_user(&self, username: &str) -> String {
+        let register = json!({
+            "username": username,
+            "email": format!("{username}@example.com"),
+            "password": "password",
+        });
+        let response = with_json(self.client.post(self.url("/auth/register")), register)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = self
+            .login(username, self.client.post(self.url("/auth/login/username")))
+            .await;
+        read_json(response).await["access_token"]
+            .as_str()
+            .unwrap()
+            .to_string()
+    }
+
+    /// Logs the user in through the request, which may carry more headers
+    pub async fn login(&self, username: &str, request: RequestBuilder) -> Response {
+        let login = json!({ "username": username, "password": "password" });
+        let response = with_json(request, login).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        response
+    }
+
+    pub async fn create_workspace(&self, token: &str) -> Uuid {
+        let response = self
+            .post("/workspaces", token, json!({ "title": "Workspace" }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        id_of(&read_json(response).await)
+    }
+
+    pub async fn create_note(&self, token: &str, workspace_id: Uuid) -> Value {
+        let note = json!({ "title": "Note", "workspace_id": workspace_id, "parent": null });
+        let response = self.post("/notes", token, note).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        read_json(response).await
+    }
+
+    pub async fn create_block(&self, token: &str, note_id: Uuid, text: &str) -> Value {
+        let block = json!({
+            "block_type": "PlainText",
+            "note_id": note_id,
+            "content": { "type": "PlainText", "spans": [{ "text": text }] },
+        });
+        let response = self.post("/blocks", token, block).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        read_json(response).await
+    }
+}
+
+fn with_json(request: RequestBuilder, body: Value) -> RequestBuilder {
+    request
+        .header(CONTENT_TYPE, "application/json")
+        .body(body.to_string())
+}
+
+pub async fn read_json(response: Response) -> Value {
+    serde_json::from_slice(&response.bytes().await.unwrap()).unwrap()
+}
+
+pub fn id_of(value: &Value) -> Uuid {
+    value["id"].as_str().unwrap().parse().unwrap()
+}
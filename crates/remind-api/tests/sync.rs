@@ -0,0 +1,105 @@
+mod fixtures;
+
+use crate::fixtures::{TestApp, read_json, spawn_app};
+use reqwest::StatusCode;
+use serde_json::{Value, json};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+async fn push(app: &TestApp, token: &str, operations: Vec<Value>) -> Vec<Value> {
+    let response = app
+        .post("/sync", token, json!({ "operations": operations }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = read_json(response).await;
+    body["data"].as_array().unwrap().clone()
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_push_is_idempotent(pool: PgPool) {
+    let app = spawn_app(pool).await;
+    let token = app.create_user("alice").await;
+    let workspace_id = app.create_workspace(&token).await;
+    let note_id = Uuid::new_v4();
+    let create_note = |title: &str| {
+        json!({
+            "id": "7f6c9d2e-0a43-4d1b-9a55-3f0c1b2e4d01",
+            "type": "create_note",
+            "note_id": note_id,
+            "workspace_id": workspace_id,
+            "title": title,
+        })
+    };
+    let create_block = json!({
+        "id": Uuid::new_v4(),
+        "type": "create_block",
+        "block_id": Uuid::new_v4(),
+        "note_id": note_id,
+        "block_type": "PlainText",
+        "content": { "type": "PlainText", "spans": [{ "text": "Offline" }] },
+    });
+    let stale_rename = json!({
+        "id": Uuid::new_v4(),
+        "type": "update_note",
+        "note_id": note_id,
+        "title": "Stale",
+        "version": 0,
+    });
+    let missing_note = json!({
+        "id": Uuid::new_v4(),
+        "type": "update_note",
+        "note_id": Uuid::new_v4(),
+        "title": "Gone",
+    });
+    let operations = vec![
+        create_note("Offline"),
+        create_block.clone(),
+        stale_rename.clone(),
+        missing_note.clone(),
+    ];
+
+    let first = push(&app, &token, operations).await;
+    let statuses: Vec<&str> = first
+        .iter()
+        .map(|r| r["status"].as_str().unwrap())
+        .collect();
+    assert_eq!(statuses, ["applied", "applied", "conflict", "rejected"]);
+    assert_eq!(first[2]["current"]["title"], "Offline");
+    assert_eq!(first[3]["status_code"], 404);
+
+    // A push sent again after a lost response gets the same results, even
+    // when the operations were changed in between
+    let again = push(
+        &app,
+        &token,
+        vec![
+            create_note("Changed"),
+            create_block,
+            stale_rename,
+            missing_note,
+        ],
+    )
+    .await;
+    assert_eq!(again, first);
+
+    let response = app
+        .get(&format!("/workspaces/my/{workspace_id}/notes"), &token)
+        .send()
+        .await
+        .unwrap();
+    let notes = read_json(response).await["data"]
+        .as_array()
+        .unwrap()
+        .clone();
+    assert_eq!(notes.len(), 1);
+    assert_eq!(notes[0]["title"], "Offline");
+    assert_eq!(notes[0]["blocks"].as_array().unwrap().len(), 1);
+
+    // Ids of operations are kept per user
+    let other = app.create_user("bob").await;
+    let rejected = push(&app, &other, vec![create_note("Bob")]).await;
+    assert_eq!(rejected[0]["status"], "rejected");
+    assert_eq!(rejected[0]["status_code"], 403);
+}
@@ -0,0 +1,99 @@
+mod fixtures;
+
+use crate::fixtures::{id_of, read_json, spawn_app};
+use reqwest::StatusCode;
+use reqwest::header::{ETAG, IF_MATCH};
+use serde_json::json;
+use sqlx::PgPool;
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_note_if_match(pool: PgPool) {
+    let app = spawn_app(pool).await;
+    let token = app.create_user("alice").await;
+    let workspace_id = app.create_workspace(&token).await;
+    let note_id = id_of(&app.create_note(&token, workspace_id).await);
+    let path = format!("/notes/{note_id}");
+
+    let response = app.get(&path, &token).send().await.unwrap();
+    assert_eq!(response.headers()[ETAG], "\"1\"");
+    assert_eq!(read_json(response).await["version"], 1);
+
+    let rename = |title: &str| json!({ "title": title });
+    let response = app.put(&path, &token, rename("New")).send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::PRECONDITION_REQUIRED);
+
+    let response = app
+        .put(&path, &token, rename("First"))
+        .header(IF_MATCH, "\"1\"")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[ETAG], "\"2\"");
+
+    // A stale edit gets the current note back to redo it on
+    let response = app
+        .put(&path, &token, rename("Second"))
+        .header(IF_MATCH, "\"1\"")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::PRECONDITION_FAILED);
+    assert_eq!(response.headers()[ETAG], "\"2\"");
+    let body = read_json(response).await;
+    assert_eq!(body["current"]["title"], "First");
+    assert_eq!(body["current"]["version"], 2);
+
+    let response = app
+        .put(&path, &token, rename("Any"))
+        .header(IF_MATCH, "*")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[ETAG], "\"3\"");
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_block_if_match(pool: PgPool) {
+    let app = spawn_app(pool).await;
+    let token = app.create_user("alice").await;
+    let workspace_id = app.create_workspace(&token).await;
+    let note_id = id_of(&app.create_note(&token, workspace_id).await);
+    let block = app.create_block(&token, note_id, "Hello").await;
+    let path = format!("/blocks/{}", id_of(&block));
+    let edit =
+        |text: &str| json!({ "content": { "type": "PlainText", "spans": [{ "text": text }] } });
+
+    let response = app.put(&path, &token, edit("Hi")).send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::PRECONDITION_REQUIRED);
+
+    let version = block["version"].as_i64().unwrap();
+    let response = app
+        .put(&path, &token, edit("Hi"))
+        .header(IF_MATCH, format!("\"{version}\""))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[ETAG], format!("\"{}\"", version + 1));
+
+    let response = app
+        .put(&path, &token, edit("Hey"))
+        .header(IF_MATCH, format!("\"{version}\""))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::PRECONDITION_FAILED);
+    assert_eq!(response.headers()[ETAG], format!("\"{}\"", version + 1));
+    let body = read_json(response).await;
+    assert_eq!(body["current"]["content"]["spans"][0]["text"], "Hi");
+
+    let response = app
+        .put(&path, &token, edit("Hey"))
+        .header(IF_MATCH, "1")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
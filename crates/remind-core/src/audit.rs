@@ -0,0 +1,21 @@
+use crate::AuditEvent;
+use crate::errors::Result;
+use async_trait::async_trait;
+
+/// Where services record security and content events. An event that can't
+/// be recorded fails the operation that emitted it.
+#[async_trait]
+pub trait AuditSink: Send + Sync {
+    async fn record(&self, event: AuditEvent) -> Result<()>;
+}
+
+/// Records the event when auditing is enabled
+pub(crate) async fn record(
+    sink: &Option<std::sync::Arc<dyn AuditSink>>,
+    event: AuditEvent,
+) -> Result<()> {
+    match sink {
+        None => Ok(()),
+        Some(sink) => sink.record(event).await,
+    }
+}
@@ -0,0 +1,143 @@
+use crate::{BlockDTO, TextOp};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+/// Events kept for a slow connection before it has to resync
+const ROOM_CAPACITY: usize = 256;
+
+/// Change to a note, sent to everyone who has it open
+#[derive(Clone, Debug)]
+pub enum NoteEvent {
+    BlockCreated(BlockDTO),
+    BlockUpdated(BlockDTO),
+    BlockDeleted {
+        id: Uuid,
+    },
+    /// Text operations merged into a PlainText or Code block
+    TextEdited {
+        block: BlockDTO,
+        ops: Vec<TextOp>,
+    },
+    BlocksReordered {
+        parent_block: Option<Uuid>,
+        blocks: Vec<Uuid>,
+    },
+    /// Users currently viewing the note
+    Presence(Vec<Collaborator>),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Collaborator {
+    pub user_id: Uuid,
+    pub username: String,
+}
+
+struct Room {
+    sender: broadcast::Sender<NoteEvent>,
+    /// Viewers by connection, a user can have the note open more than once
+    viewers: HashMap<Uuid, Collaborator>,
+}
+
+impl Room {
+    /// Viewers by name, each user once
+    fn presence(&self) -> Vec<Collaborator> {
+        let mut users: Vec<Collaborator> = Vec::new();
+        for viewer in self.viewers.values() {
+            if !users.iter().any(|u| u.user_id == viewer.user_id) {
+                users.push(viewer.clone());
+            }
+        }
+        users.sort_by(|a, b| a.username.cmp(&b.username));
+        users
+    }
+}
+
+/// Fans out note events to the connections that have the note open.
+/// Rooms only exist while someone is connected.
+#[derive(Clone, Default)]
+pub struct CollabHub {
+    rooms: Arc<Mutex<HashMap<Uuid, Room>>>,
+}
+
+impl CollabHub {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Opens the note for the user. Everyone in the note, the new connection
+    /// included, is sent the updated presence.
+    pub fn join(&self, note_id: Uuid, user: Collaborator) -> NoteSession {
+        let connection_id = Uuid::new_v4();
+        let mut rooms = self.rooms.lock().unwrap();
+        let room = rooms.entry(note_id).or_insert_with(|| Room {
+            sender: broadcast::channel(ROOM_CAPACITY).0,
+            viewers: HashMap::new(),
+        });
+        let receiver = room.sender.subscribe();
+        room.viewers.insert(connection_id, user);
+        let _ = room.sender.send(NoteEvent::Presence(room.presence()));
+
+        NoteSession {
+            hub: self.clone(),
+            note_id,
+            connection_id,
+            receiver,
+        }
+    }
+
+    /// Sends the event to everyone viewing the note
+    pub fn publish(&self, note_id: Uuid, event: NoteEvent) {
+        let rooms = self.rooms.lock().unwrap();
+        if let Some(room) = rooms.get(&note_id) {
+            // Fails only without receivers, then nobody is left to tell
+            let _ = room.sender.send(event);
+        }
+    }
+
+    /// Users viewing the note
+    pub fn presence(&self, note_id: Uuid) -> Vec<Collaborator> {
+        let rooms = self.rooms.lock().unwrap();
+        rooms.get(&note_id).map(Room::presence).unwrap_or_default()
+    }
+
+    fn leave(&self, note_id: Uuid, connection_id: Uuid) {
+        let mut rooms = self.rooms.lock().unwrap();
+        let Some(room) = rooms.get_mut(&note_id) else {
+            return;
+        };
+        room.viewers.remove(&connection_id);
+        if room.viewers.is_empty() {
+            rooms.remove(&note_id);
+        } else {
+            let _ = room.sender.send(NoteEvent::Presence(room.presence()));
+        }
+    }
+}
+
+/// Connection to a note, leaves the note when dropped
+pub struct NoteSession {
+    hub: CollabHub,
+    note_id: Uuid,
+    connection_id: Uuid,
+    receiver: broadcast::Receiver<NoteEvent>,
+}
+
+impl NoteSession {
+    pub fn note_id(&self) -> Uuid {
+        self.note_id
+    }
+
+    /// Next event of the note. `Err(Lagged)` means events were missed
+    /// and the note has to be loaded again.
+    pub async fn recv(&mut self) -> Result<NoteEvent, broadcast::error::RecvError> {
+        self.receiver.recv().await
+    }
+}
+
+impl Drop for NoteSession {
+    fn drop(&mut self) {
+        self.hub.leave(self.note_id, self.connection_id);
+    }
+}
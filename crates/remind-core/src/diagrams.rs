@@ -0,0 +1,588 @@
+use crate::errors::{CoreError, Result};
+use async_trait::async_trait;
+use quick_xml::Reader;
+use quick_xml::escape::escape;
+use quick_xml::events::{BytesStart, Event};
+use std::fmt::Write;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::process::Command;
+
+/// Longest accepted source of a Math or Diagram block
+pub const MAX_SOURCE_LENGTH: usize = 20_000;
+
+/// TeX primitives that read or write files or redefine the language.
+/// They have no place in an equation and are dangerous for TeX based renderers.
+const FORBIDDEN_LATEX_COMMANDS: [&str; 14] = [
+    "input",
+    "include",
+    "write",
+    "immediate",
+    "openin",
+    "openout",
+    "read",
+    "def",
+    "edef",
+    "gdef",
+    "catcode",
+    "csname",
+    "usepackage",
+    "special",
+];
+
+const MERMAID_DIAGRAMS: [&str; 19] = [
+    "graph",
+    "flowchart",
+    "sequenceDiagram",
+    "classDiagram",
+    "stateDiagram",
+    "stateDiagram-v2",
+    "erDiagram",
+    "journey",
+    "gantt",
+    "pie",
+    "quadrantChart",
+    "requirementDiagram",
+    "gitGraph",
+    "mindmap",
+    "timeline",
+    "sankey-beta",
+    "xychart-beta",
+    "block-beta",
+    "C4Context",
+];
+
+const FLOWCHART_DIRECTIONS: [&str; 5] = ["TB", "TD", "BT", "RL", "LR"];
+
+/// Renders the source of Math or Diagram blocks to SVG
+#[async_trait]
+pub trait SvgRenderer: Send + Sync {
+    async fn render(&self, code: &str) -> Result<String>;
+}
+
+/// Renders with an external program, e.g. a MathJax or Mermaid CLI.
+/// The source is written to its stdin and the SVG is read from its stdout.
+#[derive(Clone, Debug)]
+pub struct CommandSvgRenderer {
+    program: String,
+    args: Vec<String>,
+    timeout: Duration,
+    max_output_size: usize,
+}
+
+impl CommandSvgRenderer {
+    pub fn new(program: String, args: Vec<String>, timeout: Duration) -> Self {
+        Self {
+            program,
+            args,
+            timeout,
+            max_output_size: 1024 * 1024,
+        }
+    }
+
+    /// Splits a command line on whitespace, `None` when it is empty
+    pub fn from_command_line(command: &str, timeout: Duration) -> Option<Self> {
+        let mut parts = command.split_whitespace().map(str::to_string);
+        let program = parts.next()?;
+        Some(Self::new(program, parts.collect(), timeout))
+    }
+
+    async fn run(&self, code: &str) -> Result<String> {
+        let mut child = Command::new(&self.program)
+            .args(&self.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| CoreError::Render(e.to_string()))?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin
+                .write_all(code.as_bytes())
+                .await
+                .map_err(|e| CoreError::Render(e.to_string()))?;
+        }
+        let mut output = Vec::new();
+        if let Some(stdout) = child.stdout.take() {
+            // One byte more than allowed to notice oversized output
+            stdout
+                .take(self.max_output_size as u64 + 1)
+                .read_to_end(&mut output)
+                .await
+                .map_err(|e| CoreError::Render(e.to_string()))?;
+        }
+        let status = child
+            .wait()
+            .await
+            .map_err(|e| CoreError::Render(e.to_string()))?;
+
+        if !status.success() {
+            return Err(CoreError::Render(format!("Renderer exited with {status}")));
+        }
+        if output.len() > self.max_output_size {
+            return Err(CoreError::Render("Rendered SVG is too large".to_string()));
+        }
+        let svg = String::from_utf8(output)
+            .map_err(|_| CoreError::Render("Renderer output is not UTF-8".to_string()))?;
+        sanitize_svg(&svg).map_err(CoreError::Render)
+    }
+}
+
+#[async_trait]
+impl SvgRenderer for CommandSvgRenderer {
+    async fn render(&self, code: &str) -> Result<String> {
+        tokio::time::timeout(self.timeout, self.run(code))
+            .await
+            .map_err(|_| CoreError::Render("Renderer timed out".to_string()))?
+    }
+}
+
+/// Elements kept by [`sanitize_svg`], lowercase like an HTML parser sees them
+const SVG_ELEMENTS: [&str; 36] = [
+    "svg",
+    "g",
+    "defs",
+    "symbol",
+    "use",
+    "title",
+    "desc",
+    "style",
+    "path",
+    "rect",
+    "circle",
+    "ellipse",
+    "line",
+    "polyline",
+    "polygon",
+    "text",
+    "tspan",
+    "textpath",
+    "marker",
+    "lineargradient",
+    "radialgradient",
+    "stop",
+    "clippath",
+    "mask",
+    "pattern",
+    "filter",
+    "feblend",
+    "fecolormatrix",
+    "fecomposite",
+    "fedropshadow",
+    "feflood",
+    "fegaussianblur",
+    "femerge",
+    "femergenode",
+    "femorphology",
+    "feoffset",
+];
+
+/// Attributes kept by [`sanitize_svg`], besides `data-*` and `aria-*` ones
+const SVG_ATTRIBUTES: [&str; 97] = [
+    "id",
+    "class",
+    "style",
+    "role",
+    "xmlns",
+    "xmlns:xlink",
+    "version",
+    "href",
+    "xlink:href",
+    "viewbox",
+    "preserveaspectratio",
+    "width",
+    "height",
+    "x",
+    "y",
+    "x1",
+    "y1",
+    "x2",
+    "y2",
+    "cx",
+    "cy",
+    "r",
+    "rx",
+    "ry",
+    "fx",
+    "fy",
+    "d",
+    "points",
+    "pathlength",
+    "transform",
+    "fill",
+    "fill-opacity",
+    "fill-rule",
+    "stroke",
+    "stroke-width",
+    "stroke-opacity",
+    "stroke-linecap",
+    "stroke-linejoin",
+    "stroke-dasharray",
+    "stroke-dashoffset",
+    "stroke-miterlimit",
+    "opacity",
+    "color",
+    "display",
+    "visibility",
+    "overflow",
+    "vector-effect",
+    "shape-rendering",
+    "text-rendering",
+    "font-family",
+    "font-size",
+    "font-style",
+    "font-weight",
+    "text-anchor",
+    "text-decoration",
+    "dominant-baseline",
+    "alignment-baseline",
+    "letter-spacing",
+    "word-spacing",
+    "writing-mode",
+    "direction",
+    "dx",
+    "dy",
+    "rotate",
+    "textlength",
+    "lengthadjust",
+    "startoffset",
+    "clip-path",
+    "clip-rule",
+    "clippathunits",
+    "mask",
+    "maskunits",
+    "maskcontentunits",
+    "marker-start",
+    "marker-mid",
+    "marker-end",
+    "markerwidth",
+    "markerheight",
+    "markerunits",
+    "refx",
+    "refy",
+    "orient",
+    "offset",
+    "stop-color",
+    "stop-opacity",
+    "gradientunits",
+    "gradienttransform",
+    "spreadmethod",
+    "patternunits",
+    "patterncontentunits",
+    "patterntransform",
+    "filter",
+    "in",
+    "in2",
+    "result",
+    "stddeviation",
+    "mode",
+];
+
+/// Rebuilds an SVG from its allowed elements and attributes so it can be
+/// embedded into exported pages. Scripts, `foreignObject` and other unknown
+/// elements are dropped with their content, and so are event handlers,
+/// links other than to fragments of the SVG and CSS loading anything.
+/// Fails when the SVG doesn't parse or has no `<svg>` root.
+pub fn sanitize_svg(svg: &str) -> std::result::Result<String, String> {
+    let mut reader = Reader::from_str(svg);
+    let mut out = String::with_capacity(svg.len());
+    // Open allowed elements, and the depth within a dropped one
+    let mut open: Vec<String> = Vec::new();
+    let mut dropped = 0usize;
+    let mut done = false;
+    loop {
+        let event = reader
+            .read_event()
+            .map_err(|e| format!("SVG doesn't parse: {e}"))?;
+        match event {
+            Event::Eof => break,
+            _ if done => {}
+            Event::Start(_) if dropped > 0 => dropped += 1,
+            Event::End(_) if dropped > 0 => dropped -= 1,
+            _ if dropped > 0 => {}
+            Event::Start(e) => {
+                let name = element_name(&e, &open)?;
+                match name {
+                    Some(name) => {
+                        write_start(&mut out, &name, &e)?;
+                        out.push('>');
+                        open.push(name);
+                    }
+                    None => dropped = 1,
+                }
+            }
+            Event::Empty(e) => {
+                if let Some(name) = element_name(&e, &open)? {
+                    write_start(&mut out, &name, &e)?;
+                    out.push_str("/>");
+                    done = open.is_empty();
+                }
+            }
+            Event::End(_) => {
+                let name = open.pop().ok_or("Unexpected end of element")?;
+                let _ = write!(out, "</{name}>");
+                done = open.is_empty();
+            }
+            Event::Text(e) if !open.is_empty() => {
+                match e.unescape() {
+                    Ok(text) => push_text(&mut out, &open, text.as_ref()),
+                    // Entities HTML knows but XML doesn't, like `&nbsp;`. CSS
+                    // can't be checked without decoding them, so it's dropped.
+                    Err(_) if in_style(&open) => {}
+                    Err(_) => out.push_str(&String::from_utf8_lossy(&e)),
+                }
+            }
+            Event::CData(e) if !open.is_empty() => {
+                push_text(&mut out, &open, &String::from_utf8_lossy(&e));
+            }
+            // Declarations, comments, doctypes and whitespace around the root
+            _ => {}
+        }
+    }
+    if !done {
+        return Err("SVG has no <svg> root".to_string());
+    }
+    Ok(out)
+}
+
+/// Name of an allowed element, `None` to drop it. The root has to be `<svg>`.
+fn element_name(
+    element: &BytesStart,
+    open: &[String],
+) -> std::result::Result<Option<String>, String> {
+    let name = String::from_utf8_lossy(element.name().as_ref()).to_ascii_lowercase();
+    if open.is_empty() && name != "svg" {
+        return Err("SVG has no <svg> root".to_string());
+    }
+    if !SVG_ELEMENTS.contains(&name.as_str()) {
+        return Ok(None);
+    }
+    // Inside `<style>` an element would be CSS text
+    if in_style(open) {
+        return Ok(None);
+    }
+    Ok(Some(name))
+}
+
+fn write_start(
+    out: &mut String,
+    name: &str,
+    element: &BytesStart,
+) -> std::result::Result<(), String> {
+    let _ = write!(out, "<{name}");
+    for attribute in element.attributes() {
+        let attribute = attribute.map_err(|e| e.to_string())?;
+        let key = String::from_utf8_lossy(attribute.key.as_ref()).to_ascii_lowercase();
+        let value = attribute.unescape_value().map_err(|e| e.to_string())?;
+        if is_allowed_attribute(&key, &value) {
+            let _ = write!(out, " {key}=\"{}\"", escape(value.as_ref()));
+        }
+    }
+    Ok(())
+}
+
+fn is_allowed_attribute(key: &str, value: &str) -> bool {
+    let known =
+        SVG_ATTRIBUTES.contains(&key) || key.starts_with("data-") || key.starts_with("aria-");
+    match key {
+        _ if !known => false,
+        "href" | "xlink:href" => value.trim_start().starts_with('#'),
+        // Presentation attributes like `fill` take `url(...)` as well
+        _ => is_safe_css(value),
+    }
+}
+
+/// CSS that loads nothing outside the SVG. Escapes could spell out
+/// `url(` or `@import` past the checks, so CSS with any is refused.
+fn is_safe_css(css: &str) -> bool {
+    if css.contains('\\') {
+        return false;
+    }
+    let css: String = css
+        .to_ascii_lowercase()
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect();
+    if css.contains("@import") || css.contains("expression(") || css.contains("javascript:") {
+        return false;
+    }
+    css.match_indices("url(").all(|(i, _)| {
+        css[i + 4..]
+            .trim_start_matches(['"', '\''])
+            .starts_with('#')
+    })
+}
+
+fn in_style(open: &[String]) -> bool {
+    open.last().is_some_and(|parent| parent == "style")
+}
+
+/// Adds the text escaped, the CSS of `<style>` only when it is safe
+fn push_text(out: &mut String, open: &[String], text: &str) {
+    if !in_style(open) || is_safe_css(text) {
+        out.push_str(&escape(text));
+    }
+}
+
+/// Checks the LaTeX of a Math block: balanced groups and environments and
+/// no commands touching files. The source is math mode, so `$` is not allowed.
+pub fn validate_latex(code: &str) -> std::result::Result<(), String> {
+    check_source(code)?;
+
+    let mut groups: Vec<String> = Vec::new();
+    let mut chars = code.char_indices().peekable();
+    while let Some((i, ch)) = chars.next() {
+        match ch {
+            '\\' => {
+                let rest = &code[i + 1..];
+                let name: String = rest
+                    .chars()
+                    .take_while(|c| c.is_ascii_alphabetic())
+                    .collect();
+                if name.is_empty() {
+                    // Escaped symbol like `\{` or `\\`
+                    chars.next();
+                    continue;
+                }
+                for _ in 0..name.chars().count() {
+                    chars.next();
+                }
+                if FORBIDDEN_LATEX_COMMANDS.contains(&name.as_str()) {
+                    return Err(format!("\\{name} is not allowed"));
+                }
+                match name.as_str() {
+                    "begin" | "end" => {
+                        let after = &code[i + 1 + name.len()..];
+                        let env = after
+                            .trim_start()
+                            .strip_prefix('{')
+                            .and_then(|a| a.split_once('}'))
+                            .map(|(env, _)| env.trim().to_string())
+                            .filter(|env| !env.is_empty())
+                            .ok_or_else(|| format!("\\{name} needs an environment name"))?;
+                        if name == "begin" {
+                            groups.push(format!("\\begin{{{env}}}"));
+                        } else if groups.pop().as_deref() != Some(&format!("\\begin{{{env}}}")) {
+                            return Err(format!("Unexpected \\end{{{env}}}"));
+                        }
+                        // Skip over the environment name
+                        let skip = after.find('}').unwrap_or(0) + 1;
+                        for _ in 0..after[..skip].chars().count() {
+                            chars.next();
+                        }
+                    }
+                    "left" => groups.push("\\left".to_string()),
+                    "right" => close_group(&mut groups, "\\left", "\\right without \\left")?,
+                    _ => {}
+                }
+            }
+            '{' => groups.push("{".to_string()),
+            '}' => close_group(&mut groups, "{", "Unbalanced }")?,
+            '$' => return Err("Math is already in math mode, remove the $".to_string()),
+            '%' => {
+                // Comment until the end of the line
+                while chars.next_if(|(_, c)| *c != '\n').is_some() {}
+            }
+            _ => {}
+        }
+    }
+    match groups.pop() {
+        None => Ok(()),
+        Some(open) => Err(format!("{open} is never closed")),
+    }
+}
+
+/// Checks the Mermaid source of a Diagram block: a known diagram type first
+/// and, for flowcharts, balanced node shapes. Init directives are rejected
+/// because they can turn off Mermaid's security settings.
+pub fn validate_mermaid(code: &str) -> std::result::Result<(), String> {
+    check_source(code)?;
+
+    let lines: Vec<&str> = code
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .collect();
+    if lines.iter().any(|l| l.starts_with("%%{")) {
+        return Err("Mermaid directives are not supported".to_string());
+    }
+    let mut lines = lines.into_iter().filter(|l| !l.starts_with("%%"));
+    let header = lines.next().ok_or("Diagram is empty")?;
+    let mut words = header.split_whitespace();
+    let kind = words.next().unwrap_or_default();
+    if !MERMAID_DIAGRAMS.contains(&kind) {
+        return Err(format!("Unknown diagram type {kind}"));
+    }
+    if !matches!(kind, "graph" | "flowchart") {
+        return Ok(());
+    }
+
+    if let Some(direction) = words.next()
+        && !FLOWCHART_DIRECTIONS.contains(&direction)
+    {
+        return Err(format!("Unknown flowchart direction {direction}"));
+    }
+    for (number, line) in lines.enumerate() {
+        check_brackets(line).map_err(|e| format!("Line {}: {e}", number + 2))?;
+    }
+    Ok(())
+}
+
+fn close_group(
+    groups: &mut Vec<String>,
+    open: &str,
+    error: &str,
+) -> std::result::Result<(), String> {
+    match groups.pop() {
+        Some(group) if group == open => Ok(()),
+        _ => Err(error.to_string()),
+    }
+}
+
+fn check_source(code: &str) -> std::result::Result<(), String> {
+    if code.trim().is_empty() {
+        return Err("Source must not be empty".to_string());
+    }
+    if code.len() > MAX_SOURCE_LENGTH {
+        return Err(format!("Source is longer than {MAX_SOURCE_LENGTH} bytes"));
+    }
+    Ok(())
+}
+
+/// Brackets of node shapes have to match, quoted labels are skipped.
+/// `id>text]` is the asymmetric shape, so `>` right after a node id opens it.
+fn check_brackets(line: &str) -> std::result::Result<(), String> {
+    let mut open = Vec::new();
+    let mut quoted = false;
+    let mut previous = ' ';
+    for ch in line.chars() {
+        match ch {
+            '"' => quoted = !quoted,
+            _ if quoted => {}
+            '(' | '[' | '{' => open.push(ch),
+            '>' if previous.is_alphanumeric() || previous == '_' => open.push(ch),
+            ')' | ']' | '}' => {
+                let matches = match open.pop() {
+                    Some('(') => ch == ')',
+                    Some('[') | Some('>') => ch == ']',
+                    Some('{') => ch == '}',
+                    _ => false,
+                };
+                if !matches {
+                    return Err(format!("Unbalanced {ch}"));
+                }
+            }
+            _ => {}
+        }
+        previous = ch;
+    }
+    if quoted {
+        return Err("Unclosed quote".to_string());
+    }
+    match open.pop() {
+        None => Ok(()),
+        Some(ch) => Err(format!("{ch} is never closed")),
+    }
+}
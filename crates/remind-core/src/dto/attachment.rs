@@ -0,0 +1,62 @@
+use crate::Attachment;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+#[derive(Clone, Debug)]
+pub struct AttachmentDTO {
+    pub id: Uuid,
+    pub workspace_id: Uuid,
+    pub user_id: Uuid,
+    pub file_name: String,
+    pub content_type: String,
+    pub size: i64,
+    pub created_at: DateTime<Utc>,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+}
+
+impl From<Attachment> for AttachmentDTO {
+    fn from(value: Attachment) -> Self {
+        Self {
+            id: value.id,
+            workspace_id: value.workspace_id,
+            user_id: value.user_id,
+            file_name: value.file_name,
+            content_type: value.content_type,
+            size: value.size,
+            created_at: value.created_at,
+            width: value.width,
+            height: value.height,
+        }
+    }
+}
+
+impl AttachmentDTO {
+    pub fn is_image(&self) -> bool {
+        self.content_type.starts_with("image/")
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct AttachmentCreateDTO {
+    pub workspace_id: Uuid,
+    pub user_id: Uuid,
+    pub file_name: String,
+    pub data: Vec<u8>,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct UploadLimits {
+    /// Max size of a single file in bytes
+    pub max_file_size: usize,
+    /// Max total size of the files a user uploads
+    pub user_quota: i64,
+    /// Max total size of the files stored in a workspace
+    pub workspace_quota: i64,
+}
+
+#[derive(Clone, Debug)]
+pub struct StorageUsageDTO {
+    pub used: i64,
+    pub quota: i64,
+}
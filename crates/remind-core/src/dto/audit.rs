@@ -0,0 +1,91 @@
+use crate::{AuditAction, AuditEntry};
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use uuid::Uuid;
+
+/// Who made a request and from where, for the audit log
+#[derive(Clone, Debug, Default)]
+pub struct AuditContext {
+    pub actor_id: Option<Uuid>,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+impl AuditContext {
+    /// Context of the same request once the user is known
+    pub fn with_actor(&self, actor_id: Uuid) -> Self {
+        Self {
+            actor_id: Some(actor_id),
+            ..self.clone()
+        }
+    }
+}
+
+/// Event emitted by a service
+#[derive(Clone, Debug)]
+pub struct AuditEvent {
+    pub action: AuditAction,
+    pub context: AuditContext,
+    /// Found from the note when left out
+    pub workspace_id: Option<Uuid>,
+    pub note_id: Option<Uuid>,
+    pub target_id: Option<Uuid>,
+    pub details: Value,
+}
+
+impl AuditEvent {
+    pub fn new(action: AuditAction, context: &AuditContext) -> Self {
+        Self {
+            action,
+            context: context.clone(),
+            workspace_id: None,
+            note_id: None,
+            target_id: None,
+            details: Value::Object(Default::default()),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct AuditEntryDTO {
+    pub id: i64,
+    pub action: AuditAction,
+    pub actor_id: Option<Uuid>,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub workspace_id: Option<Uuid>,
+    pub note_id: Option<Uuid>,
+    pub target_id: Option<Uuid>,
+    pub details: Value,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<AuditEntry> for AuditEntryDTO {
+    fn from(value: AuditEntry) -> Self {
+        Self {
+            id: value.id,
+            action: value.action,
+            actor_id: value.actor_id,
+            ip: value.ip,
+            user_agent: value.user_agent,
+            workspace_id: value.workspace_id,
+            note_id: value.note_id,
+            target_id: value.target_id,
+            details: value.details,
+            created_at: value.created_at,
+        }
+    }
+}
+
+/// Entries of a workspace matching all the set fields, newest first
+#[derive(Clone, Debug, Default)]
+pub struct AuditFilter {
+    pub actions: Vec<AuditAction>,
+    pub actor_id: Option<Uuid>,
+    pub note_id: Option<Uuid>,
+    pub target_id: Option<Uuid>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    /// Cursor, entries older than this one
+    pub before: Option<i64>,
+}
@@ -1,4 +1,5 @@
-use crate::{Block, BlockContent, BlockType};
+use crate::{Block, BlockContent, BlockType, clamp_list_indents};
+use std::collections::HashMap;
 use uuid::Uuid;
 
 #[derive(Clone, Debug)]
@@ -8,6 +9,15 @@ pub struct BlockDTO {
     pub content: BlockContent,
     pub position: i32,
     pub note_id: Uuid,
+    pub parent_block: Option<Uuid>,
+    pub version: i64,
+    /// Number of a NumberedList item within its list, computed on read
+    pub number: Option<u32>,
+    /// Body of a toggle, only filled when the whole note is read
+    pub children: Vec<BlockDTO>,
+    /// Live source of a Synced block, only filled when the whole note is read.
+    /// `None` when the source was deleted.
+    pub synced_source: Option<Box<BlockDTO>>,
 }
 
 impl From<Block> for BlockDTO {
@@ -18,15 +28,80 @@ impl From<Block> for BlockDTO {
             content: value.content,
             position: value.position,
             note_id: value.note_id,
+            parent_block: value.parent_block,
+            version: value.version,
+            number: None,
+            children: Vec::new(),
+            synced_source: None,
         }
     }
 }
 
+/// Converts the blocks of a note, as returned by `BlockRepo::find_all_in_note`,
+/// into a tree of its top level blocks and lays out their lists
+pub(crate) fn blocks_to_dtos(blocks: Vec<Block>) -> Vec<BlockDTO> {
+    let mut children: HashMap<Option<Uuid>, Vec<BlockDTO>> = HashMap::new();
+    for block in blocks {
+        children
+            .entry(block.parent_block)
+            .or_default()
+            .push(block.into());
+    }
+    build_level(&mut children, None)
+}
+
+/// Finds a block anywhere in a note tree
+pub(crate) fn find_block(blocks: &[BlockDTO], id: Uuid) -> Option<&BlockDTO> {
+    blocks.iter().find_map(|b| {
+        if b.id == id {
+            Some(b)
+        } else {
+            find_block(&b.children, id)
+        }
+    })
+}
+
+fn build_level(
+    children: &mut HashMap<Option<Uuid>, Vec<BlockDTO>>,
+    parent: Option<Uuid>,
+) -> Vec<BlockDTO> {
+    let mut dtos = children.remove(&parent).unwrap_or_default();
+    layout_lists(&mut dtos);
+    for dto in dtos.iter_mut() {
+        dto.children = build_level(children, Some(dto.id));
+    }
+    dtos
+}
+
+/// Clamps the indents and numbers the items of lists among sibling blocks
+fn layout_lists(dtos: &mut [BlockDTO]) {
+    clamp_list_indents(dtos.iter_mut().map(|b| &mut b.content));
+
+    // Running number per indent level, `None` where the list is bulleted
+    let mut counters: Vec<Option<u32>> = Vec::new();
+    for dto in dtos.iter_mut() {
+        let Some(indent) = dto.content.list_indent() else {
+            counters.clear();
+            continue;
+        };
+        let depth = indent as usize;
+        counters.truncate(depth + 1);
+        counters.resize(depth + 1, None);
+        counters[depth] = match dto.content {
+            BlockContent::NumberedList(_) => Some(counters[depth].unwrap_or(0) + 1),
+            _ => None,
+        };
+        dto.number = counters[depth];
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct BlockCreateDTO {
     pub block_type: BlockType,
     pub content: BlockContent,
     pub note_id: Uuid,
+    /// Toggle to add the block to, `None` adds it to the top level
+    pub parent_block: Option<Uuid>,
 }
 
 #[derive(Clone, Debug)]
@@ -34,4 +109,6 @@ pub struct BlockUpdateDTO {
     pub id: Uuid,
     pub block_type: Option<BlockType>,
     pub content: Option<BlockContent>,
+    /// Version the update is based on, `None` skips the check
+    pub version: Option<i64>,
 }
@@ -0,0 +1,26 @@
+use crate::{Change, ChangeType};
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+#[derive(Clone, Debug)]
+pub struct ChangeDTO {
+    pub id: i64,
+    pub workspace_id: Uuid,
+    pub change_type: ChangeType,
+    pub note_id: Uuid,
+    pub block_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<Change> for ChangeDTO {
+    fn from(value: Change) -> Self {
+        Self {
+            id: value.id,
+            workspace_id: value.workspace_id,
+            change_type: value.change_type,
+            note_id: value.note_id,
+            block_id: value.block_id,
+            created_at: value.created_at,
+        }
+    }
+}
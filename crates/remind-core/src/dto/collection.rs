@@ -0,0 +1,117 @@
+use crate::entities::note::NoteIconType;
+use crate::{
+    CollectionProperty, CollectionView, PropertyType, PropertyValue, ViewFilter, ViewSort, ViewType,
+};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+#[derive(Clone, Debug)]
+pub struct CollectionPropertyDTO {
+    pub id: Uuid,
+    pub note_id: Uuid,
+    pub name: String,
+    pub property_type: PropertyType,
+    pub options: Vec<String>,
+    pub position: i32,
+}
+
+impl From<CollectionProperty> for CollectionPropertyDTO {
+    fn from(value: CollectionProperty) -> Self {
+        Self {
+            id: value.id,
+            note_id: value.note_id,
+            name: value.name,
+            property_type: value.property_type,
+            options: value.options,
+            position: value.position,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct PropertyCreateDTO {
+    pub note_id: Uuid,
+    pub name: String,
+    pub property_type: PropertyType,
+    pub options: Vec<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct PropertyUpdateDTO {
+    pub name: Option<String>,
+    pub options: Option<Vec<String>>,
+}
+
+#[derive(Clone, Debug)]
+pub struct CollectionViewDTO {
+    pub id: Uuid,
+    pub note_id: Uuid,
+    pub name: String,
+    pub view_type: ViewType,
+    pub filters: Vec<ViewFilter>,
+    pub sorts: Vec<ViewSort>,
+    pub group_by: Option<Uuid>,
+    pub position: i32,
+}
+
+impl From<CollectionView> for CollectionViewDTO {
+    fn from(value: CollectionView) -> Self {
+        Self {
+            id: value.id,
+            note_id: value.note_id,
+            name: value.name,
+            view_type: value.view_type,
+            filters: value.filters.0,
+            sorts: value.sorts.0,
+            group_by: value.group_by,
+            position: value.position,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ViewCreateDTO {
+    pub note_id: Uuid,
+    pub name: String,
+    pub view_type: ViewType,
+    pub filters: Vec<ViewFilter>,
+    pub sorts: Vec<ViewSort>,
+    pub group_by: Option<Uuid>,
+}
+
+/// Replaces the whole view definition
+#[derive(Clone, Debug)]
+pub struct ViewUpdateDTO {
+    pub name: String,
+    pub view_type: ViewType,
+    pub filters: Vec<ViewFilter>,
+    pub sorts: Vec<ViewSort>,
+    pub group_by: Option<Uuid>,
+}
+
+/// Child note of a collection with its property values
+#[derive(Clone, Debug)]
+pub struct CollectionRowDTO {
+    pub note_id: Uuid,
+    pub title: String,
+    pub icon_type: NoteIconType,
+    pub icon_data: String,
+    pub values: HashMap<Uuid, PropertyValue>,
+}
+
+/// Board column, `value` is `None` for rows without a valid option
+#[derive(Clone, Debug)]
+pub struct CollectionGroupDTO {
+    pub value: Option<String>,
+    pub rows: Vec<CollectionRowDTO>,
+}
+
+#[derive(Clone, Debug)]
+pub struct ViewResultDTO {
+    pub view: CollectionViewDTO,
+    pub properties: Vec<CollectionPropertyDTO>,
+    /// Filtered and sorted rows
+    pub rows: Vec<CollectionRowDTO>,
+    /// Rows split into columns, only for board views
+    pub groups: Vec<CollectionGroupDTO>,
+}
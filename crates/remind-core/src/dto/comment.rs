@@ -0,0 +1,52 @@
+use crate::Comment;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+#[derive(Clone, Debug)]
+pub struct CommentDTO {
+    pub id: Uuid,
+    pub note_id: Uuid,
+    pub block_id: Option<Uuid>,
+    pub thread_id: Option<Uuid>,
+    pub author_id: Uuid,
+    pub body: String,
+    pub orphaned: bool,
+    pub resolved_at: Option<DateTime<Utc>>,
+    pub resolved_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub edited_at: Option<DateTime<Utc>>,
+}
+
+impl From<Comment> for CommentDTO {
+    fn from(value: Comment) -> Self {
+        Self {
+            id: value.id,
+            note_id: value.note_id,
+            block_id: value.block_id,
+            thread_id: value.thread_id,
+            author_id: value.author_id,
+            body: value.body,
+            orphaned: value.orphaned,
+            resolved_at: value.resolved_at,
+            resolved_by: value.resolved_by,
+            created_at: value.created_at,
+            edited_at: value.edited_at,
+        }
+    }
+}
+
+/// First comment of a thread with its replies, oldest first
+#[derive(Clone, Debug)]
+pub struct CommentThreadDTO {
+    pub comment: CommentDTO,
+    pub replies: Vec<CommentDTO>,
+}
+
+#[derive(Clone, Debug)]
+pub struct CommentCreateDTO {
+    pub note_id: Uuid,
+    /// Block of the note the thread is about, `None` for the whole note
+    pub block_id: Option<Uuid>,
+    pub author_id: Uuid,
+    pub body: String,
+}
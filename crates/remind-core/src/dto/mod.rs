@@ -1,4 +1,14 @@
+pub(crate) mod attachment;
+pub(crate) mod audit;
 pub(crate) mod block;
+pub(crate) mod change;
+pub(crate) mod collection;
+pub(crate) mod comment;
 pub(crate) mod note;
+pub(crate) mod notification;
+pub(crate) mod share_link;
+pub(crate) mod tag;
+pub(crate) mod template;
 pub(crate) mod user;
+pub(crate) mod webhook;
 pub(crate) mod workspace;
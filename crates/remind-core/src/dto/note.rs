@@ -1,4 +1,5 @@
 use crate::dto::block::BlockDTO;
+use crate::dto::tag::TagDTO;
 use crate::entities::note::NoteIconType;
 use uuid::Uuid;
 
@@ -11,17 +12,34 @@ pub struct NoteDTO {
     pub workspace_id: Uuid,
     pub blocks: Vec<BlockDTO>,
     pub parent_note: Option<Uuid>,
+    pub tags: Vec<TagDTO>,
+    pub version: i64,
 }
 
 #[derive(Clone, Debug)]
 pub struct NoteCreateDTO {
+    /// Left empty to use the title of the template
     pub title: String,
     pub workspace_id: Uuid,
     pub parent_note: Option<Uuid>,
+    /// Template of the same workspace to copy the icon and blocks from
+    pub template_id: Option<Uuid>,
+    /// Username filled into `{{user}}` of the template
+    pub author: Option<String>,
 }
 
 #[derive(Clone, Debug)]
 pub struct NoteUpdateDTO {
     pub title: Option<String>,
     // pub parent_note: Option<Uuid>
+    /// Version the update is based on, `None` skips the check
+    pub version: Option<i64>,
+}
+
+/// Note mentioning another note, with the text of the mentioning blocks
+#[derive(Clone, Debug)]
+pub struct BacklinkDTO {
+    pub note_id: Uuid,
+    pub title: String,
+    pub snippets: Vec<String>,
 }
@@ -0,0 +1,59 @@
+use crate::{Notification, NotificationKind};
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+#[derive(Clone, Debug)]
+pub struct NotificationDTO {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub kind: NotificationKind,
+    pub actor_id: Option<Uuid>,
+    pub note_id: Option<Uuid>,
+    pub block_id: Option<Uuid>,
+    pub comment_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub read_at: Option<DateTime<Utc>>,
+}
+
+impl From<Notification> for NotificationDTO {
+    fn from(value: Notification) -> Self {
+        Self {
+            id: value.id,
+            user_id: value.user_id,
+            kind: value.kind,
+            actor_id: value.actor_id,
+            note_id: value.note_id,
+            block_id: value.block_id,
+            comment_id: value.comment_id,
+            created_at: value.created_at,
+            read_at: value.read_at,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct NotificationCreateDTO {
+    /// User notified
+    pub user_id: Uuid,
+    pub kind: NotificationKind,
+    /// User who caused it, if known. Users aren't notified of what they did.
+    pub actor_id: Option<Uuid>,
+    pub note_id: Option<Uuid>,
+    pub block_id: Option<Uuid>,
+    pub comment_id: Option<Uuid>,
+}
+
+/// Notifications of a user, newest first
+#[derive(Clone, Debug)]
+pub struct NotificationPageDTO {
+    pub notifications: Vec<NotificationDTO>,
+    pub unread_count: i64,
+    /// Pass as `before` for the following page, `None` on the last one
+    pub next_cursor: Option<Uuid>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NotificationPreferenceDTO {
+    pub kind: NotificationKind,
+    pub enabled: bool,
+}
@@ -0,0 +1,84 @@
+use crate::dto::block::BlockDTO;
+use crate::entities::note::NoteIconType;
+use crate::{BlockType, PublicBlockContent, ShareLink};
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+#[derive(Clone, Debug)]
+pub struct ShareLinkDTO {
+    pub id: Uuid,
+    pub slug: String,
+    pub note_id: Uuid,
+    pub has_password: bool,
+    pub include_subpages: bool,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<ShareLink> for ShareLinkDTO {
+    fn from(value: ShareLink) -> Self {
+        Self {
+            id: value.id,
+            slug: value.slug,
+            note_id: value.note_id,
+            has_password: value.password.is_some(),
+            include_subpages: value.include_subpages,
+            expires_at: value.expires_at,
+            created_at: value.created_at,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ShareLinkCreateDTO {
+    pub note_id: Uuid,
+    pub created_by: Uuid,
+    pub password: Option<String>,
+    pub include_subpages: bool,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// Note as seen through a share link: no workspace or user identifiers
+#[derive(Clone, Debug)]
+pub struct PublicNoteDTO {
+    pub id: Uuid,
+    pub title: String,
+    pub icon_type: NoteIconType,
+    pub icon_data: String,
+    pub blocks: Vec<PublicBlockDTO>,
+    pub subpages: Vec<PublicNoteDTO>,
+}
+
+/// Block as seen through a share link, see [`PublicBlockContent`]
+#[derive(Clone, Debug)]
+pub struct PublicBlockDTO {
+    pub id: Uuid,
+    pub block_type: BlockType,
+    pub content: PublicBlockContent,
+    pub position: i32,
+    pub number: Option<u32>,
+    pub children: Vec<PublicBlockDTO>,
+}
+
+/// Public view of the blocks of a note shared under `slug`. Synced blocks
+/// take the place of their source, those without one are left out.
+pub(crate) fn to_public_blocks(blocks: Vec<BlockDTO>, slug: &str) -> Vec<PublicBlockDTO> {
+    blocks
+        .into_iter()
+        .filter_map(|block| {
+            let (id, position, number) = (block.id, block.position, block.number);
+            let shown = match block.synced_source {
+                Some(source) => *source,
+                None => block,
+            };
+            Some(PublicBlockDTO {
+                id,
+                content: PublicBlockContent::new(shown.content, slug)?,
+                block_type: shown.block_type,
+                position,
+                number,
+                children: to_public_blocks(shown.children, slug),
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,46 @@
+use crate::Tag;
+use serde::Deserialize;
+use uuid::Uuid;
+
+#[derive(Clone, Debug)]
+pub struct TagDTO {
+    pub id: Uuid,
+    pub workspace_id: Uuid,
+    pub name: String,
+    pub color: String,
+}
+
+impl From<Tag> for TagDTO {
+    fn from(value: Tag) -> Self {
+        Self {
+            id: value.id,
+            workspace_id: value.workspace_id,
+            name: value.name,
+            color: value.color,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct TagCreateDTO {
+    pub workspace_id: Uuid,
+    pub name: String,
+    pub color: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct TagUpdateDTO {
+    pub name: Option<String>,
+    pub color: Option<String>,
+}
+
+/// How notes are filtered by several tags
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TagMatch {
+    /// Notes with at least one of the tags
+    #[default]
+    Any,
+    /// Notes with all of the tags
+    All,
+}
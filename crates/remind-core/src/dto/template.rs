@@ -0,0 +1,43 @@
+use crate::entities::note::NoteIconType;
+use crate::entities::template::{NoteTemplate, TemplateBlock};
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+#[derive(Clone, Debug)]
+pub struct NoteTemplateDTO {
+    pub id: Uuid,
+    pub workspace_id: Uuid,
+    pub name: String,
+    pub title: String,
+    pub icon_type: NoteIconType,
+    pub icon_data: String,
+    pub blocks: Vec<TemplateBlock>,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<NoteTemplate> for NoteTemplateDTO {
+    fn from(value: NoteTemplate) -> Self {
+        Self {
+            id: value.id,
+            workspace_id: value.workspace_id,
+            name: value.name,
+            title: value.title,
+            icon_type: value.icon_type,
+            icon_data: value.icon_data,
+            blocks: value.blocks.0,
+            created_by: value.created_by,
+            created_at: value.created_at,
+        }
+    }
+}
+
+/// Saves a note, with its icon and blocks, as a template of its workspace
+#[derive(Clone, Debug)]
+pub struct NoteTemplateCreateDTO {
+    pub note_id: Uuid,
+    pub name: String,
+    /// Title pattern like `Standup {{date}}`, the title of the note when unset
+    pub title: Option<String>,
+    pub created_by: Uuid,
+}
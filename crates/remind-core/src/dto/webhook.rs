@@ -0,0 +1,67 @@
+use crate::{ChangeType, DeliveryStatus, Webhook, WebhookDelivery};
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use uuid::Uuid;
+
+/// Webhook without its secret, which is only known to the owner
+#[derive(Clone, Debug)]
+pub struct WebhookDTO {
+    pub id: Uuid,
+    pub workspace_id: Uuid,
+    pub url: String,
+    pub event_types: Vec<ChangeType>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<Webhook> for WebhookDTO {
+    fn from(value: Webhook) -> Self {
+        Self {
+            id: value.id,
+            workspace_id: value.workspace_id,
+            url: value.url,
+            event_types: value.event_types,
+            created_at: value.created_at,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct WebhookCreateDTO {
+    pub workspace_id: Uuid,
+    pub url: String,
+    pub secret: String,
+    pub event_types: Vec<ChangeType>,
+}
+
+#[derive(Clone, Debug)]
+pub struct WebhookDeliveryDTO {
+    pub id: Uuid,
+    pub webhook_id: Uuid,
+    pub event: String,
+    pub payload: Value,
+    pub status: DeliveryStatus,
+    pub attempts: i32,
+    pub next_attempt_at: DateTime<Utc>,
+    pub last_status_code: Option<i32>,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+impl From<WebhookDelivery> for WebhookDeliveryDTO {
+    fn from(value: WebhookDelivery) -> Self {
+        Self {
+            id: value.id,
+            webhook_id: value.webhook_id,
+            event: value.event,
+            payload: value.payload,
+            status: value.status,
+            attempts: value.attempts,
+            next_attempt_at: value.next_attempt_at,
+            last_status_code: value.last_status_code,
+            last_error: value.last_error,
+            created_at: value.created_at,
+            delivered_at: value.delivered_at,
+        }
+    }
+}
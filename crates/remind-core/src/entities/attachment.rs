@@ -0,0 +1,35 @@
+use chrono::{DateTime, Utc};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, FromRow)]
+pub struct Attachment {
+    pub id: Uuid,
+    pub workspace_id: Uuid,
+    /// Uploader id
+    pub user_id: Uuid,
+    /// Key of the contents in the blob store
+    pub storage_key: String,
+    /// Original file name
+    pub file_name: String,
+    /// Sniffed content type
+    pub content_type: String,
+    /// Size in bytes
+    pub size: i64,
+    pub created_at: DateTime<Utc>,
+    /// Pixel dimensions, set once an image has been processed
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    /// Widths of the generated thumbnails
+    pub variants: Vec<i32>,
+    /// Total size of the thumbnails in bytes
+    pub variants_size: i64,
+    /// When garbage collection first found no block referencing it
+    pub orphaned_at: Option<DateTime<Utc>>,
+}
+
+impl Attachment {
+    pub fn variant_key(&self, width: i32) -> String {
+        format!("{}_w{}", self.storage_key, width)
+    }
+}
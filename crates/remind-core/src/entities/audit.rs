@@ -0,0 +1,38 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// Most audit entries listed at once
+pub const MAX_AUDIT_ENTRIES_PER_PAGE: i64 = 200;
+
+#[derive(Clone, Copy, Debug, sqlx::Type, PartialEq, Eq, Deserialize, Serialize)]
+#[sqlx(type_name = "audit_action", rename_all = "PascalCase")]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    LoginSucceeded,
+    /// Unknown user or wrong password, the attempted login is in the details
+    LoginFailed,
+    TokenCreated,
+    NoteDeleted,
+    BlockDeleted,
+    NoteExported,
+    ShareLinkCreated,
+}
+
+/// Entry of the append-only audit log
+#[derive(Clone, Debug, FromRow)]
+pub struct AuditEntry {
+    pub id: i64,
+    pub action: AuditAction,
+    pub actor_id: Option<Uuid>,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub workspace_id: Option<Uuid>,
+    pub note_id: Option<Uuid>,
+    /// Note, block or share link acted on
+    pub target_id: Option<Uuid>,
+    pub details: Value,
+    pub created_at: DateTime<Utc>,
+}
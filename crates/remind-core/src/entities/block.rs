@@ -1,3 +1,6 @@
+use crate::entities::rich_text::{Mention, RICH_TEXT_VERSION, TextSpan, plain_text};
+use crate::entities::table::TableContent;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::Row;
 use sqlx::types::Json;
@@ -10,6 +13,19 @@ pub enum BlockType {
     Checkbox,
     Image,
     Code,
+    File,
+    Heading,
+    Quote,
+    Callout,
+    Divider,
+    BulletedList,
+    NumberedList,
+    Table,
+    Bookmark,
+    Math,
+    Diagram,
+    Toggle,
+    Synced,
 }
 
 impl BlockType {
@@ -19,6 +35,19 @@ impl BlockType {
             BlockType::Checkbox => "Checkbox",
             BlockType::Image => "Image",
             BlockType::Code => "Code",
+            BlockType::File => "File",
+            BlockType::Heading => "Heading",
+            BlockType::Quote => "Quote",
+            BlockType::Callout => "Callout",
+            BlockType::Divider => "Divider",
+            BlockType::BulletedList => "BulletedList",
+            BlockType::NumberedList => "NumberedList",
+            BlockType::Table => "Table",
+            BlockType::Bookmark => "Bookmark",
+            BlockType::Math => "Math",
+            BlockType::Diagram => "Diagram",
+            BlockType::Toggle => "Toggle",
+            BlockType::Synced => "Synced",
         }
     }
 
@@ -33,8 +62,63 @@ impl BlockType {
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(try_from = "PlainTextContentRepr", into = "PlainTextContentRepr")]
 pub struct PlainTextContent {
-    pub text: String,
+    pub spans: Vec<TextSpan>,
+}
+
+impl PlainTextContent {
+    pub fn plain(text: impl Into<String>) -> Self {
+        Self {
+            spans: vec![TextSpan::plain(text)],
+        }
+    }
+
+    pub fn text(&self) -> String {
+        plain_text(&self.spans)
+    }
+}
+
+/// Stored form of [`PlainTextContent`].
+/// Rows written before rich text only have `text`; newer ones keep `text`
+/// next to the spans so plain readers and search keep working.
+#[derive(Deserialize, Serialize)]
+struct PlainTextContentRepr {
+    #[serde(default = "legacy_version")]
+    version: u8,
+    #[serde(default)]
+    spans: Option<Vec<TextSpan>>,
+    #[serde(default)]
+    text: Option<String>,
+}
+
+fn legacy_version() -> u8 {
+    1
+}
+
+impl TryFrom<PlainTextContentRepr> for PlainTextContent {
+    type Error = String;
+
+    fn try_from(value: PlainTextContentRepr) -> Result<Self, Self::Error> {
+        if value.version > RICH_TEXT_VERSION {
+            return Err(format!("Unsupported rich text version {}", value.version));
+        }
+        match (value.spans, value.text) {
+            (Some(spans), _) => Ok(Self { spans }),
+            (None, Some(text)) => Ok(Self::plain(text)),
+            (None, None) => Ok(Self { spans: Vec::new() }),
+        }
+    }
+}
+
+impl From<PlainTextContent> for PlainTextContentRepr {
+    fn from(value: PlainTextContent) -> Self {
+        Self {
+            version: RICH_TEXT_VERSION,
+            text: Some(value.text()),
+            spans: Some(value.spans),
+        }
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -47,6 +131,14 @@ pub struct CheckboxContent {
 pub struct ImageContent {
     pub url: String,
     pub alt: Option<String>,
+    /// Set when the image was uploaded instead of linked
+    #[serde(default)]
+    pub attachment_id: Option<Uuid>,
+    /// Pixel dimensions of uploaded images, filled in by the server
+    #[serde(default)]
+    pub width: Option<u32>,
+    #[serde(default)]
+    pub height: Option<u32>,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -55,6 +147,137 @@ pub struct CodeContent {
     pub language: String,
 }
 
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct FileContent {
+    pub attachment_id: Uuid,
+    /// Display name, defaults to the uploaded file name
+    pub name: String,
+}
+
+/// Deepest supported heading level
+pub const MAX_HEADING_LEVEL: u8 = 3;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct HeadingContent {
+    /// 1 to [`MAX_HEADING_LEVEL`]
+    pub level: u8,
+    pub spans: Vec<TextSpan>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct QuoteContent {
+    pub spans: Vec<TextSpan>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CalloutColor {
+    #[default]
+    Default,
+    Gray,
+    Brown,
+    Orange,
+    Yellow,
+    Green,
+    Blue,
+    Purple,
+    Pink,
+    Red,
+}
+
+impl CalloutColor {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CalloutColor::Default => "default",
+            CalloutColor::Gray => "gray",
+            CalloutColor::Brown => "brown",
+            CalloutColor::Orange => "orange",
+            CalloutColor::Yellow => "yellow",
+            CalloutColor::Green => "green",
+            CalloutColor::Blue => "blue",
+            CalloutColor::Purple => "purple",
+            CalloutColor::Pink => "pink",
+            CalloutColor::Red => "red",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct CalloutContent {
+    /// Emoji shown next to the text
+    pub icon: String,
+    #[serde(default)]
+    pub color: CalloutColor,
+    pub spans: Vec<TextSpan>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct DividerContent {}
+
+/// Metadata of a bookmarked page, fetched by the server
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct LinkPreview {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    /// Absolute URL of the page icon
+    pub favicon: Option<String>,
+    pub fetched_at: DateTime<Utc>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct BookmarkContent {
+    pub url: String,
+    /// Cached preview, kept until the URL changes or it is refreshed.
+    /// Previews sent by clients are ignored.
+    #[serde(default)]
+    pub preview: Option<LinkPreview>,
+}
+
+/// LaTeX equation, written without `$` delimiters
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct MathContent {
+    pub code: String,
+    /// Rendered by the server and kept until the code changes.
+    /// SVGs sent by clients are ignored.
+    #[serde(default)]
+    pub svg: Option<String>,
+}
+
+/// Mermaid diagram
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct DiagramContent {
+    pub code: String,
+    /// Rendered by the server and kept until the code changes.
+    /// SVGs sent by clients are ignored.
+    #[serde(default)]
+    pub svg: Option<String>,
+}
+
+/// Collapsible block, its body are the blocks whose `parent_block` it is
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ToggleContent {
+    /// Text shown while the toggle is collapsed
+    pub spans: Vec<TextSpan>,
+}
+
+/// Mirror of a block of another note in the same workspace, showing the
+/// current content of the source
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SyncedContent {
+    pub source_block: Uuid,
+}
+
+/// Deepest supported list nesting
+pub const MAX_LIST_INDENT: u8 = 6;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ListItemContent {
+    pub spans: Vec<TextSpan>,
+    /// Nesting level, an item is a child of the closest preceding item with a smaller indent
+    #[serde(default)]
+    pub indent: u8,
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(tag = "type")]
 pub enum BlockContent {
@@ -62,6 +285,19 @@ pub enum BlockContent {
     Checkbox(CheckboxContent),
     Image(ImageContent),
     Code(CodeContent),
+    File(FileContent),
+    Heading(HeadingContent),
+    Quote(QuoteContent),
+    Callout(CalloutContent),
+    Divider(DividerContent),
+    BulletedList(ListItemContent),
+    NumberedList(ListItemContent),
+    Table(TableContent),
+    Bookmark(BookmarkContent),
+    Math(MathContent),
+    Diagram(DiagramContent),
+    Toggle(ToggleContent),
+    Synced(SyncedContent),
 }
 
 impl BlockContent {
@@ -71,6 +307,116 @@ impl BlockContent {
             BlockContent::Checkbox(_) => "Checkbox",
             BlockContent::Image(_) => "Image",
             BlockContent::Code(_) => "Code",
+            BlockContent::File(_) => "File",
+            BlockContent::Heading(_) => "Heading",
+            BlockContent::Quote(_) => "Quote",
+            BlockContent::Callout(_) => "Callout",
+            BlockContent::Divider(_) => "Divider",
+            BlockContent::BulletedList(_) => "BulletedList",
+            BlockContent::NumberedList(_) => "NumberedList",
+            BlockContent::Table(_) => "Table",
+            BlockContent::Bookmark(_) => "Bookmark",
+            BlockContent::Math(_) => "Math",
+            BlockContent::Diagram(_) => "Diagram",
+            BlockContent::Toggle(_) => "Toggle",
+            BlockContent::Synced(_) => "Synced",
+        }
+    }
+
+    /// Unformatted text of the block, used for search
+    pub fn plain_text(&self) -> String {
+        match self {
+            BlockContent::PlainText(c) => c.text(),
+            BlockContent::Checkbox(c) => c.text.clone(),
+            BlockContent::Image(c) => c.alt.clone().unwrap_or_default(),
+            BlockContent::Code(c) => c.code.clone(),
+            BlockContent::Math(c) => c.code.clone(),
+            BlockContent::Diagram(c) => c.code.clone(),
+            BlockContent::File(c) => c.name.clone(),
+            BlockContent::Divider(_) => String::new(),
+            // Found through the source block
+            BlockContent::Synced(_) => String::new(),
+            BlockContent::Bookmark(c) => match c.preview.as_ref().and_then(|p| p.title.as_ref()) {
+                Some(title) => format!("{title} {}", c.url),
+                None => c.url.clone(),
+            },
+            BlockContent::Table(c) => c
+                .text_rows()
+                .iter()
+                .flatten()
+                .filter(|t| !t.is_empty())
+                .cloned()
+                .collect::<Vec<_>>()
+                .join(" "),
+            content => content.spans().map(plain_text).unwrap_or_default(),
+        }
+    }
+
+    /// Text edited through text operations, `None` for blocks other than
+    /// PlainText and Code
+    pub fn mergeable_text(&self) -> Option<String> {
+        match self {
+            BlockContent::PlainText(c) => Some(c.text()),
+            BlockContent::Code(c) => Some(c.code.clone()),
+            _ => None,
+        }
+    }
+
+    /// Rich text of the block, if it has any
+    pub fn spans(&self) -> Option<&[TextSpan]> {
+        match self {
+            BlockContent::PlainText(c) => Some(&c.spans),
+            BlockContent::Heading(c) => Some(&c.spans),
+            BlockContent::Quote(c) => Some(&c.spans),
+            BlockContent::Callout(c) => Some(&c.spans),
+            BlockContent::Toggle(c) => Some(&c.spans),
+            BlockContent::BulletedList(c) | BlockContent::NumberedList(c) => Some(&c.spans),
+            _ => None,
+        }
+    }
+
+    pub fn spans_mut(&mut self) -> Option<&mut Vec<TextSpan>> {
+        match self {
+            BlockContent::PlainText(c) => Some(&mut c.spans),
+            BlockContent::Heading(c) => Some(&mut c.spans),
+            BlockContent::Quote(c) => Some(&mut c.spans),
+            BlockContent::Callout(c) => Some(&mut c.spans),
+            BlockContent::Toggle(c) => Some(&mut c.spans),
+            BlockContent::BulletedList(c) | BlockContent::NumberedList(c) => Some(&mut c.spans),
+            _ => None,
+        }
+    }
+
+    /// Nesting level of list items, `None` for other blocks
+    pub fn list_indent(&self) -> Option<u8> {
+        match self {
+            BlockContent::BulletedList(c) | BlockContent::NumberedList(c) => Some(c.indent),
+            _ => None,
+        }
+    }
+
+    /// Notes and users mentioned in the text, each once
+    pub fn mentions(&self) -> Vec<Mention> {
+        let mut mentions = Vec::new();
+        for mention in self
+            .spans()
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|s| s.mention)
+        {
+            if !mentions.contains(&mention) {
+                mentions.push(mention);
+            }
+        }
+        mentions
+    }
+
+    /// Attachment referenced by this content, if any
+    pub fn attachment_id(&self) -> Option<Uuid> {
+        match self {
+            BlockContent::Image(c) => c.attachment_id,
+            BlockContent::File(c) => Some(c.attachment_id),
+            _ => None,
         }
     }
 }
@@ -81,7 +427,12 @@ pub struct Block {
     pub block_type: BlockType,
     pub content: BlockContent,
     pub note_id: Uuid,
+    /// Toggle containing this block, `None` on the top level of the note
+    pub parent_block: Option<Uuid>,
+    /// Position among the blocks with the same parent
     pub position: i32,
+    /// Bumped by every save
+    pub version: i64,
 }
 
 impl<'r> sqlx::FromRow<'r, sqlx::postgres::PgRow> for Block {
@@ -89,7 +440,9 @@ impl<'r> sqlx::FromRow<'r, sqlx::postgres::PgRow> for Block {
         let id: Uuid = row.try_get("id")?;
         let note_id: Uuid = row.try_get("note_id")?;
         let block_type: BlockType = row.try_get("block_type")?;
+        let parent_block: Option<Uuid> = row.try_get("parent_block")?;
         let position: i32 = row.try_get("position")?;
+        let version: i64 = row.try_get("version")?;
         let content: Json<BlockContent> = row.try_get("content")?;
 
         match (block_type.clone(), &content.0) {
@@ -98,30 +451,170 @@ impl<'r> sqlx::FromRow<'r, sqlx::postgres::PgRow> for Block {
                 note_id,
                 block_type,
                 content: content.0,
+                parent_block,
                 position,
+                version,
             }),
             (BlockType::Image, BlockContent::Image(_)) => Ok(Block {
                 id,
                 note_id,
                 block_type,
                 content: content.0,
+                parent_block,
                 position,
+                version,
             }),
             (BlockType::Code, BlockContent::Code(_)) => Ok(Block {
                 id,
                 note_id,
                 block_type,
                 content: content.0,
+                parent_block,
                 position,
+                version,
             }),
             (BlockType::Checkbox, BlockContent::Checkbox(_)) => Ok(Block {
                 id,
                 note_id,
                 block_type,
                 content: content.0,
+                parent_block,
+                position,
+                version,
+            }),
+            (BlockType::File, BlockContent::File(_)) => Ok(Block {
+                id,
+                note_id,
+                block_type,
+                content: content.0,
+                parent_block,
+                position,
+                version,
+            }),
+            (BlockType::Heading, BlockContent::Heading(_)) => Ok(Block {
+                id,
+                note_id,
+                block_type,
+                content: content.0,
+                parent_block,
+                position,
+                version,
+            }),
+            (BlockType::Quote, BlockContent::Quote(_)) => Ok(Block {
+                id,
+                note_id,
+                block_type,
+                content: content.0,
+                parent_block,
+                position,
+                version,
+            }),
+            (BlockType::Callout, BlockContent::Callout(_)) => Ok(Block {
+                id,
+                note_id,
+                block_type,
+                content: content.0,
+                parent_block,
+                position,
+                version,
+            }),
+            (BlockType::Divider, BlockContent::Divider(_)) => Ok(Block {
+                id,
+                note_id,
+                block_type,
+                content: content.0,
+                parent_block,
                 position,
+                version,
+            }),
+            (BlockType::BulletedList, BlockContent::BulletedList(_)) => Ok(Block {
+                id,
+                note_id,
+                block_type,
+                content: content.0,
+                parent_block,
+                position,
+                version,
+            }),
+            (BlockType::NumberedList, BlockContent::NumberedList(_)) => Ok(Block {
+                id,
+                note_id,
+                block_type,
+                content: content.0,
+                parent_block,
+                position,
+                version,
+            }),
+            (BlockType::Table, BlockContent::Table(_)) => Ok(Block {
+                id,
+                note_id,
+                block_type,
+                content: content.0,
+                parent_block,
+                position,
+                version,
+            }),
+            (BlockType::Bookmark, BlockContent::Bookmark(_)) => Ok(Block {
+                id,
+                note_id,
+                block_type,
+                content: content.0,
+                parent_block,
+                position,
+                version,
+            }),
+            (BlockType::Math, BlockContent::Math(_)) => Ok(Block {
+                id,
+                note_id,
+                block_type,
+                content: content.0,
+                parent_block,
+                position,
+                version,
+            }),
+            (BlockType::Diagram, BlockContent::Diagram(_)) => Ok(Block {
+                id,
+                note_id,
+                block_type,
+                content: content.0,
+                parent_block,
+                position,
+                version,
+            }),
+            (BlockType::Toggle, BlockContent::Toggle(_)) => Ok(Block {
+                id,
+                note_id,
+                block_type,
+                content: content.0,
+                parent_block,
+                position,
+                version,
+            }),
+            (BlockType::Synced, BlockContent::Synced(_)) => Ok(Block {
+                id,
+                note_id,
+                block_type,
+                content: content.0,
+                parent_block,
+                position,
+                version,
             }),
             _ => Err(sqlx::Error::RowNotFound),
         }
     }
 }
+
+/// Clamps list indents so every item is at most one level deeper than the
+/// list item before it. Blocks are expected in note order.
+pub fn clamp_list_indents<'a>(contents: impl IntoIterator<Item = &'a mut BlockContent>) {
+    let mut previous: Option<u8> = None;
+    for content in contents {
+        previous = match content {
+            BlockContent::BulletedList(c) | BlockContent::NumberedList(c) => {
+                c.indent = c.indent.min(previous.map_or(0, |p| p + 1));
+                Some(c.indent)
+            }
+            _ => None,
+        };
+    }
+}
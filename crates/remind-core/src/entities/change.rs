@@ -0,0 +1,46 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+#[derive(Clone, Copy, Debug, sqlx::Type, PartialEq, Eq, Deserialize, Serialize)]
+#[sqlx(type_name = "change_type", rename_all = "PascalCase")]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeType {
+    NoteCreated,
+    NoteRenamed,
+    NoteDeleted,
+    BlockCreated,
+    BlockUpdated,
+    BlockDeleted,
+    /// Blocks of the note, or of one of its toggles, got a new order
+    BlocksReordered,
+}
+
+impl ChangeType {
+    /// Name of the change in events and payloads
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChangeType::NoteCreated => "note_created",
+            ChangeType::NoteRenamed => "note_renamed",
+            ChangeType::NoteDeleted => "note_deleted",
+            ChangeType::BlockCreated => "block_created",
+            ChangeType::BlockUpdated => "block_updated",
+            ChangeType::BlockDeleted => "block_deleted",
+            ChangeType::BlocksReordered => "blocks_reordered",
+        }
+    }
+}
+
+/// Entry of the change log of a workspace
+#[derive(Clone, Debug, FromRow)]
+pub struct Change {
+    /// Increases with every change, clients resume after the last one they saw
+    pub id: i64,
+    pub workspace_id: Uuid,
+    pub change_type: ChangeType,
+    pub note_id: Uuid,
+    /// Changed block, or the toggle of reordered blocks
+    pub block_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
@@ -0,0 +1,200 @@
+use crate::entities::table::DATE_FORMAT;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use sqlx::types::Json;
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use uuid::Uuid;
+
+#[derive(Clone, Copy, Debug, sqlx::Type, PartialEq, Deserialize, Serialize)]
+#[sqlx(type_name = "property_type", rename_all = "PascalCase")]
+pub enum PropertyType {
+    Text,
+    Number,
+    Checkbox,
+    /// `YYYY-MM-DD`, e.g. a due date
+    Date,
+    /// One of the property options, e.g. a status
+    Select,
+    /// Any of the property options, e.g. tags
+    MultiSelect,
+    /// User id, e.g. an assignee
+    Person,
+}
+
+impl PropertyType {
+    pub fn has_options(&self) -> bool {
+        matches!(self, PropertyType::Select | PropertyType::MultiSelect)
+    }
+}
+
+/// Property of the child notes of a collection note
+#[derive(Clone, Debug, FromRow)]
+pub struct CollectionProperty {
+    pub id: Uuid,
+    /// Collection note
+    pub note_id: Uuid,
+    pub name: String,
+    pub property_type: PropertyType,
+    /// Choices of Select and MultiSelect properties
+    pub options: Vec<String>,
+    pub position: i32,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum PropertyValue {
+    Checkbox(bool),
+    Number(f64),
+    Text(String),
+    List(Vec<String>),
+}
+
+impl PropertyValue {
+    pub fn validate(&self, property: &CollectionProperty) -> Result<(), String> {
+        let valid = match (property.property_type, self) {
+            (PropertyType::Text, PropertyValue::Text(_)) => true,
+            (PropertyType::Number, PropertyValue::Number(n)) => n.is_finite(),
+            (PropertyType::Checkbox, PropertyValue::Checkbox(_)) => true,
+            (PropertyType::Date, PropertyValue::Text(s)) => {
+                NaiveDate::parse_from_str(s, DATE_FORMAT).is_ok()
+            }
+            (PropertyType::Select, PropertyValue::Text(s)) => property.options.contains(s),
+            (PropertyType::MultiSelect, PropertyValue::List(items)) => {
+                let mut seen = HashSet::new();
+                items
+                    .iter()
+                    .all(|i| property.options.contains(i) && seen.insert(i))
+            }
+            (PropertyType::Person, PropertyValue::Text(s)) => Uuid::parse_str(s).is_ok(),
+            _ => false,
+        };
+        if !valid {
+            return Err(format!(
+                "Invalid value for {:?} property {}",
+                property.property_type, property.name
+            ));
+        }
+        Ok(())
+    }
+
+    fn is_empty(&self) -> bool {
+        match self {
+            PropertyValue::Text(s) => s.is_empty(),
+            PropertyValue::List(items) => items.is_empty(),
+            _ => false,
+        }
+    }
+}
+
+/// Compares values of the same property, empty values go last
+pub fn compare_values(a: Option<&PropertyValue>, b: Option<&PropertyValue>) -> Ordering {
+    match (a, b) {
+        (Some(PropertyValue::Number(a)), Some(PropertyValue::Number(b))) => a.total_cmp(b),
+        (Some(PropertyValue::Checkbox(a)), Some(PropertyValue::Checkbox(b))) => a.cmp(b),
+        // Dates are ISO formatted, so they sort as text
+        (Some(PropertyValue::Text(a)), Some(PropertyValue::Text(b))) => {
+            a.to_lowercase().cmp(&b.to_lowercase())
+        }
+        (Some(PropertyValue::List(a)), Some(PropertyValue::List(b))) => a.cmp(b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        _ => Ordering::Equal,
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterOp {
+    Equals,
+    NotEquals,
+    /// Text containing the value, or lists containing the item
+    Contains,
+    LessThan,
+    GreaterThan,
+    IsEmpty,
+    IsNotEmpty,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ViewFilter {
+    pub property_id: Uuid,
+    pub op: FilterOp,
+    #[serde(default)]
+    pub value: Option<PropertyValue>,
+}
+
+impl ViewFilter {
+    pub fn matches(&self, value: Option<&PropertyValue>) -> bool {
+        let value = value.filter(|v| !v.is_empty());
+        match self.op {
+            FilterOp::IsEmpty => value.is_none(),
+            FilterOp::IsNotEmpty => value.is_some(),
+            FilterOp::Equals => value == self.value.as_ref(),
+            FilterOp::NotEquals => value != self.value.as_ref(),
+            FilterOp::Contains => match (value, &self.value) {
+                (Some(PropertyValue::Text(text)), Some(PropertyValue::Text(needle))) => {
+                    text.to_lowercase().contains(&needle.to_lowercase())
+                }
+                (Some(PropertyValue::List(items)), Some(PropertyValue::Text(item))) => {
+                    items.contains(item)
+                }
+                _ => false,
+            },
+            FilterOp::LessThan | FilterOp::GreaterThan => {
+                let expected = match self.op {
+                    FilterOp::LessThan => Ordering::Less,
+                    _ => Ordering::Greater,
+                };
+                value.is_some()
+                    && self.value.is_some()
+                    && compare_values(value, self.value.as_ref()) == expected
+            }
+        }
+    }
+
+    pub fn needs_value(&self) -> bool {
+        !matches!(self.op, FilterOp::IsEmpty | FilterOp::IsNotEmpty)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ViewSort {
+    pub property_id: Uuid,
+    #[serde(default)]
+    pub descending: bool,
+}
+
+#[derive(Clone, Copy, Debug, sqlx::Type, PartialEq, Deserialize, Serialize)]
+#[sqlx(type_name = "view_type", rename_all = "PascalCase")]
+pub enum ViewType {
+    Table,
+    /// Columns grouped by a Select property
+    Board,
+    List,
+}
+
+/// Saved way of looking at the child notes of a collection
+#[derive(Clone, Debug, FromRow)]
+pub struct CollectionView {
+    pub id: Uuid,
+    /// Collection note
+    pub note_id: Uuid,
+    pub name: String,
+    pub view_type: ViewType,
+    /// All filters have to match
+    pub filters: Json<Vec<ViewFilter>>,
+    /// Applied in order, the first one is the primary sort
+    pub sorts: Json<Vec<ViewSort>>,
+    /// Select property of board columns
+    pub group_by: Option<Uuid>,
+    pub position: i32,
+}
+
+#[derive(Clone, Debug, FromRow)]
+pub struct NotePropertyValue {
+    pub note_id: Uuid,
+    pub property_id: Uuid,
+    pub value: Json<PropertyValue>,
+}
@@ -0,0 +1,25 @@
+use chrono::{DateTime, Utc};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// Longest comment body
+pub const MAX_COMMENT_LENGTH: usize = 10_000;
+
+/// Comment on a note or one of its blocks. A comment without `thread_id`
+/// starts a thread, the others reply to it.
+#[derive(Clone, Debug, FromRow)]
+pub struct Comment {
+    pub id: Uuid,
+    pub note_id: Uuid,
+    /// Block the thread is about, `None` for the whole note
+    pub block_id: Option<Uuid>,
+    pub thread_id: Option<Uuid>,
+    pub author_id: Uuid,
+    pub body: String,
+    /// The block of the thread was deleted
+    pub orphaned: bool,
+    pub resolved_at: Option<DateTime<Utc>>,
+    pub resolved_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub edited_at: Option<DateTime<Utc>>,
+}
@@ -18,4 +18,6 @@ pub struct Note {
     pub icon_data: String,
     pub workspace_id: Uuid,
     pub parent_note: Option<Uuid>,
+    /// Bumped by every save
+    pub version: i64,
 }
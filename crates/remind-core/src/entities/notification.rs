@@ -0,0 +1,35 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// Most notifications listed at once
+pub const MAX_NOTIFICATIONS_PER_PAGE: i64 = 100;
+
+#[derive(Clone, Copy, Debug, sqlx::Type, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[sqlx(type_name = "notification_kind", rename_all = "PascalCase")]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    /// The user was mentioned in a block
+    Mention,
+    /// Someone replied to a thread the user took part in
+    CommentReply,
+}
+
+impl NotificationKind {
+    pub const ALL: [NotificationKind; 2] =
+        [NotificationKind::Mention, NotificationKind::CommentReply];
+}
+
+#[derive(Clone, Debug, FromRow)]
+pub struct Notification {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub kind: NotificationKind,
+    pub actor_id: Option<Uuid>,
+    pub note_id: Option<Uuid>,
+    pub block_id: Option<Uuid>,
+    pub comment_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub read_at: Option<DateTime<Utc>>,
+}
@@ -0,0 +1,97 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Current version of the rich text representation.
+/// Version 1 was a bare `{ "text": ... }` without formatting.
+pub const RICH_TEXT_VERSION: u8 = 2;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TextMark {
+    Bold,
+    Italic,
+    Underline,
+    Strikethrough,
+    Code,
+}
+
+/// Note or user referenced from rich text. Targets are kept by id,
+/// so mentions of a note survive renaming it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(tag = "type", content = "id", rename_all = "snake_case")]
+pub enum Mention {
+    Note(Uuid),
+    User(Uuid),
+}
+
+impl Mention {
+    pub fn id(&self) -> Uuid {
+        match self {
+            Mention::Note(id) | Mention::User(id) => *id,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct TextSpan {
+    pub text: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub marks: Vec<TextMark>,
+    /// Link target
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub href: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mention: Option<Mention>,
+    /// Set on read when the mentioned note no longer exists
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub broken: bool,
+}
+
+impl TextSpan {
+    pub fn plain(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            marks: Vec::new(),
+            href: None,
+            mention: None,
+            broken: false,
+        }
+    }
+
+    pub fn mention(text: impl Into<String>, mention: Mention) -> Self {
+        Self {
+            mention: Some(mention),
+            ..Self::plain(text)
+        }
+    }
+
+    fn same_format(&self, other: &TextSpan) -> bool {
+        self.href == other.href
+            && self.mention == other.mention
+            && self.broken == other.broken
+            && self.marks.len() == other.marks.len()
+            && self.marks.iter().all(|m| other.marks.contains(m))
+    }
+}
+
+/// Extracts the unformatted text
+pub fn plain_text(spans: &[TextSpan]) -> String {
+    spans.iter().map(|s| s.text.as_str()).collect()
+}
+
+/// Drops empty spans and merges neighbours with the same formatting
+pub fn normalize_spans(spans: Vec<TextSpan>) -> Vec<TextSpan> {
+    let mut normalized: Vec<TextSpan> = Vec::with_capacity(spans.len());
+    for mut span in spans {
+        if span.text.is_empty() {
+            continue;
+        }
+        // Broken mentions are only known on read
+        span.broken = false;
+        match normalized.last_mut() {
+            Some(last) if last.same_format(&span) => last.text.push_str(&span.text),
+            _ => normalized.push(span),
+        }
+    }
+    normalized
+}
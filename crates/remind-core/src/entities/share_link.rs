@@ -0,0 +1,157 @@
+use crate::{
+    BlockContent, BookmarkContent, CalloutContent, CellValue, CheckboxContent, CodeContent,
+    ColumnType, DiagramContent, DividerContent, HeadingContent, ListItemContent, MathContent,
+    PlainTextContent, QuoteContent, TableContent, ToggleContent,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, FromRow)]
+pub struct ShareLink {
+    pub id: Uuid,
+    /// Public unguessable identifier used in `/public/{slug}`
+    pub slug: String,
+    pub note_id: Uuid,
+    /// User who created the link
+    pub created_by: Uuid,
+    /// Hashed password, if the link is protected
+    pub password: Option<String>,
+    /// Whether child notes are shared too
+    pub include_subpages: bool,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl ShareLink {
+    pub fn is_expired(&self) -> bool {
+        self.expires_at.is_some_and(|e| e <= Utc::now())
+    }
+}
+
+/// Content of a block as seen through a share link. Mentions keep only their
+/// label, uploads are linked through the share link and Synced blocks show
+/// the content of their source, so no ids of the workspace are given away.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "type")]
+pub enum PublicBlockContent {
+    PlainText(PlainTextContent),
+    Checkbox(CheckboxContent),
+    Image(PublicImageContent),
+    Code(CodeContent),
+    File(PublicFileContent),
+    Heading(HeadingContent),
+    Quote(QuoteContent),
+    Callout(CalloutContent),
+    Divider(DividerContent),
+    BulletedList(ListItemContent),
+    NumberedList(ListItemContent),
+    Table(PublicTableContent),
+    Bookmark(BookmarkContent),
+    Math(MathContent),
+    Diagram(DiagramContent),
+    Toggle(ToggleContent),
+}
+
+impl PublicBlockContent {
+    /// Public view of the content of a block shared under `slug`. `None` for
+    /// Synced blocks, they are replaced by their source.
+    pub fn new(mut content: BlockContent, slug: &str) -> Option<Self> {
+        for span in content.spans_mut().into_iter().flatten() {
+            span.mention = None;
+        }
+        let content = match content {
+            BlockContent::PlainText(c) => PublicBlockContent::PlainText(c),
+            BlockContent::Checkbox(c) => PublicBlockContent::Checkbox(c),
+            BlockContent::Image(c) => PublicBlockContent::Image(PublicImageContent {
+                url: match c.attachment_id {
+                    Some(id) => shared_attachment_url(slug, id),
+                    None => c.url,
+                },
+                alt: c.alt,
+                width: c.width,
+                height: c.height,
+            }),
+            BlockContent::Code(c) => PublicBlockContent::Code(c),
+            BlockContent::File(c) => PublicBlockContent::File(PublicFileContent {
+                url: shared_attachment_url(slug, c.attachment_id),
+                name: c.name,
+            }),
+            BlockContent::Heading(c) => PublicBlockContent::Heading(c),
+            BlockContent::Quote(c) => PublicBlockContent::Quote(c),
+            BlockContent::Callout(c) => PublicBlockContent::Callout(c),
+            BlockContent::Divider(c) => PublicBlockContent::Divider(c),
+            BlockContent::BulletedList(c) => PublicBlockContent::BulletedList(c),
+            BlockContent::NumberedList(c) => PublicBlockContent::NumberedList(c),
+            BlockContent::Table(c) => PublicBlockContent::Table(c.into()),
+            BlockContent::Bookmark(c) => PublicBlockContent::Bookmark(c),
+            BlockContent::Math(c) => PublicBlockContent::Math(c),
+            BlockContent::Diagram(c) => PublicBlockContent::Diagram(c),
+            BlockContent::Toggle(c) => PublicBlockContent::Toggle(c),
+            BlockContent::Synced(_) => return None,
+        };
+        Some(content)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PublicImageContent {
+    /// Linked image, or the upload read through the share link
+    pub url: String,
+    pub alt: Option<String>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PublicFileContent {
+    /// Upload read through the share link
+    pub url: String,
+    pub name: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PublicTableColumn {
+    pub name: String,
+    pub column_type: ColumnType,
+}
+
+/// Table with its cells in column order, empty cells are `null`
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PublicTableContent {
+    pub columns: Vec<PublicTableColumn>,
+    pub rows: Vec<Vec<Option<CellValue>>>,
+}
+
+impl From<TableContent> for PublicTableContent {
+    fn from(mut value: TableContent) -> Self {
+        let rows = value
+            .rows
+            .iter_mut()
+            .map(|row| {
+                value
+                    .columns
+                    .iter()
+                    .map(|column| row.cells.remove(&column.id))
+                    .collect()
+            })
+            .collect();
+        Self {
+            columns: value
+                .columns
+                .into_iter()
+                .map(|c| PublicTableColumn {
+                    name: c.name,
+                    column_type: c.column_type,
+                })
+                .collect(),
+            rows,
+        }
+    }
+}
+
+/// Link to the content of an upload of a note shared under `slug`
+pub fn shared_attachment_url(slug: &str, id: Uuid) -> String {
+    format!("/public/{slug}/attachments/{id}/content")
+}
@@ -0,0 +1,135 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use uuid::Uuid;
+
+/// Format of Date cells
+pub const DATE_FORMAT: &str = "%Y-%m-%d";
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColumnType {
+    Text,
+    Number,
+    Checkbox,
+    Date,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct TableColumn {
+    pub id: Uuid,
+    pub name: String,
+    pub column_type: ColumnType,
+}
+
+/// Value of a single cell. Dates are stored as `YYYY-MM-DD` text.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum CellValue {
+    Checkbox(bool),
+    Number(f64),
+    Text(String),
+}
+
+impl CellValue {
+    pub fn matches(&self, column_type: ColumnType) -> bool {
+        match (self, column_type) {
+            (CellValue::Text(_), ColumnType::Text) => true,
+            (CellValue::Number(n), ColumnType::Number) => n.is_finite(),
+            (CellValue::Checkbox(_), ColumnType::Checkbox) => true,
+            (CellValue::Text(s), ColumnType::Date) => {
+                NaiveDate::parse_from_str(s, DATE_FORMAT).is_ok()
+            }
+            _ => false,
+        }
+    }
+
+    /// Cell as plain text, used for search and export
+    pub fn text(&self) -> String {
+        match self {
+            CellValue::Checkbox(b) => b.to_string(),
+            CellValue::Number(n) => n.to_string(),
+            CellValue::Text(s) => s.clone(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct TableRow {
+    pub id: Uuid,
+    /// Cells by column id, empty cells are left out
+    #[serde(default)]
+    pub cells: BTreeMap<Uuid, CellValue>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct TableContent {
+    pub columns: Vec<TableColumn>,
+    #[serde(default)]
+    pub rows: Vec<TableRow>,
+}
+
+impl TableContent {
+    pub fn column(&self, id: Uuid) -> Option<&TableColumn> {
+        self.columns.iter().find(|c| c.id == id)
+    }
+
+    pub fn row_mut(&mut self, id: Uuid) -> Option<&mut TableRow> {
+        self.rows.iter_mut().find(|r| r.id == id)
+    }
+
+    /// Checks that ids are unique and every cell fits its column
+    pub fn validate(&self) -> Result<(), String> {
+        let mut column_ids = HashSet::new();
+        for column in &self.columns {
+            if !column_ids.insert(column.id) {
+                return Err(format!("Duplicate column {}", column.id));
+            }
+            if column.name.trim().is_empty() {
+                return Err("Column name must not be empty".to_string());
+            }
+        }
+
+        let mut row_ids = HashSet::new();
+        for row in &self.rows {
+            if !row_ids.insert(row.id) {
+                return Err(format!("Duplicate row {}", row.id));
+            }
+            for (column_id, value) in &row.cells {
+                self.validate_cell(*column_id, value)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn validate_cell(&self, column_id: Uuid, value: &CellValue) -> Result<(), String> {
+        let column = self
+            .column(column_id)
+            .ok_or_else(|| format!("Unknown column {column_id}"))?;
+        if !value.matches(column.column_type) {
+            return Err(format!(
+                "Value of column {} must be a {:?} value",
+                column.name, column.column_type
+            ));
+        }
+        Ok(())
+    }
+
+    /// Cell texts row by row, in column order
+    pub fn text_rows(&self) -> Vec<Vec<String>> {
+        self.rows
+            .iter()
+            .map(|row| {
+                self.columns
+                    .iter()
+                    .map(|c| {
+                        row.cells
+                            .get(&c.id)
+                            .map(CellValue::text)
+                            .unwrap_or_default()
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
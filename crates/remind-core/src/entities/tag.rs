@@ -0,0 +1,18 @@
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// Longest tag name
+pub const MAX_TAG_NAME_LENGTH: usize = 64;
+
+/// Color of tags created without one
+pub const DEFAULT_TAG_COLOR: &str = "#9e9e9e";
+
+#[derive(Clone, Debug, FromRow)]
+pub struct Tag {
+    pub id: Uuid,
+    pub workspace_id: Uuid,
+    /// Unique in the workspace, ignoring case
+    pub name: String,
+    /// Hex color like `#ff8800`
+    pub color: String,
+}
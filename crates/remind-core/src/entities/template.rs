@@ -0,0 +1,87 @@
+use crate::entities::block::{BlockContent, BlockType};
+use crate::entities::note::NoteIconType;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use sqlx::types::Json;
+use uuid::Uuid;
+
+/// Longest template name
+pub const MAX_TEMPLATE_NAME_LENGTH: usize = 200;
+
+/// Block of a template, a toggle keeps its body as children
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct TemplateBlock {
+    pub block_type: BlockType,
+    pub content: BlockContent,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<TemplateBlock>,
+}
+
+/// Note saved to start new notes of a workspace from
+#[derive(Clone, Debug, FromRow)]
+pub struct NoteTemplate {
+    pub id: Uuid,
+    pub workspace_id: Uuid,
+    pub name: String,
+    /// Title of the notes made from it, variables are filled in
+    pub title: String,
+    pub icon_type: NoteIconType,
+    pub icon_data: String,
+    pub blocks: Json<Vec<TemplateBlock>>,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Values of the `{{date}}`, `{{time}}` and `{{user}}` variables of a
+/// template. Other variables, and `{{user}}` without a user, are kept as
+/// written.
+#[derive(Clone, Debug)]
+pub struct TemplateVariables {
+    pub now: DateTime<Utc>,
+    pub user: Option<String>,
+}
+
+impl TemplateVariables {
+    fn value(&self, name: &str) -> Option<String> {
+        match name {
+            "date" => Some(self.now.format("%Y-%m-%d").to_string()),
+            "time" => Some(self.now.format("%H:%M").to_string()),
+            "user" => self.user.clone(),
+            _ => None,
+        }
+    }
+
+    pub fn fill(&self, text: &str) -> String {
+        let mut filled = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(start) = rest.find("{{") {
+            let Some(len) = rest[start + 2..].find("}}") else {
+                break;
+            };
+            let end = start + 2 + len + 2;
+            filled.push_str(&rest[..start]);
+            match self.value(rest[start + 2..end - 2].trim()) {
+                Some(value) => filled.push_str(&value),
+                None => filled.push_str(&rest[start..end]),
+            }
+            rest = &rest[end..];
+        }
+        filled.push_str(rest);
+        filled
+    }
+
+    /// Fills the variables in the text of the content, mentions and other
+    /// fields like links and code are kept as written
+    pub fn fill_content(&self, mut content: BlockContent) -> BlockContent {
+        if let BlockContent::Checkbox(c) = &mut content {
+            c.text = self.fill(&c.text);
+        }
+        for span in content.spans_mut().into_iter().flatten() {
+            if span.mention.is_none() {
+                span.text = self.fill(&span.text);
+            }
+        }
+        content
+    }
+}
@@ -0,0 +1,194 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use uuid::Uuid;
+
+/// Replica of the edits made by the server itself, like seeding the text of
+/// an existing block or catching up with a plain content update
+pub const SERVER_REPLICA: Uuid = Uuid::nil();
+
+/// Identity of a character across replicas. `counter` is a Lamport clock,
+/// ids are ordered by it first and by replica for concurrent inserts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Deserialize, Serialize)]
+pub struct CharId {
+    pub counter: u64,
+    pub replica: Uuid,
+}
+
+impl fmt::Display for CharId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}@{}", self.counter, self.replica)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TextOp {
+    /// Inserts `ch` right after the character `after`, at the start when `None`
+    Insert {
+        id: CharId,
+        after: Option<CharId>,
+        ch: char,
+    },
+    /// Hides a character. It stays as anchor of concurrent inserts.
+    Delete { id: CharId },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct TextItem {
+    pub id: CharId,
+    pub ch: char,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub deleted: bool,
+}
+
+/// Text of a PlainText or Code block as a sequence CRDT (RGA). Replicas
+/// applying the same operations end up with the same text, whatever order
+/// concurrent operations arrive in. Deleted characters are kept as tombstones.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct TextCrdt {
+    items: Vec<TextItem>,
+    /// Highest counter seen
+    clock: u64,
+}
+
+impl TextCrdt {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// State of a text written by the server, the same on every replica
+    pub fn from_text(text: &str) -> Self {
+        let mut crdt = Self::new();
+        crdt.insert(SERVER_REPLICA, 0, text);
+        crdt
+    }
+
+    pub fn text(&self) -> String {
+        self.items
+            .iter()
+            .filter(|i| !i.deleted)
+            .map(|i| i.ch)
+            .collect()
+    }
+
+    /// All characters in order, tombstones included
+    pub fn items(&self) -> &[TextItem] {
+        &self.items
+    }
+
+    pub fn clock(&self) -> u64 {
+        self.clock
+    }
+
+    /// Ids of the visible characters in order
+    pub fn visible_ids(&self) -> Vec<CharId> {
+        self.items
+            .iter()
+            .filter(|i| !i.deleted)
+            .map(|i| i.id)
+            .collect()
+    }
+
+    /// Inserts `text` at the visible character index, returns the operations
+    /// to send to the other replicas
+    pub fn insert(&mut self, replica: Uuid, index: usize, text: &str) -> Vec<TextOp> {
+        let visible = self.visible_ids();
+        let mut after = match index.min(visible.len()) {
+            0 => None,
+            index => Some(visible[index - 1]),
+        };
+
+        let mut ops = Vec::new();
+        for ch in text.chars() {
+            let id = CharId {
+                counter: self.clock + 1,
+                replica,
+            };
+            let op = TextOp::Insert { id, after, ch };
+            self.apply(&op)
+                .expect("local inserts follow known characters");
+            ops.push(op);
+            after = Some(id);
+        }
+        ops
+    }
+
+    /// Deletes `len` visible characters starting at `index`
+    pub fn delete(&mut self, index: usize, len: usize) -> Vec<TextOp> {
+        let ops: Vec<TextOp> = self
+            .visible_ids()
+            .into_iter()
+            .skip(index)
+            .take(len)
+            .map(|id| TextOp::Delete { id })
+            .collect();
+        for op in &ops {
+            self.apply(op).expect("local deletes hit known characters");
+        }
+        ops
+    }
+
+    /// Turns the text into `text`, keeping the unchanged start and end
+    pub fn replace(&mut self, replica: Uuid, text: &str) -> Vec<TextOp> {
+        let old: Vec<char> = self.text().chars().collect();
+        let new: Vec<char> = text.chars().collect();
+        let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+        let suffix = old[prefix..]
+            .iter()
+            .rev()
+            .zip(new[prefix..].iter().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+
+        let mut ops = self.delete(prefix, old.len() - prefix - suffix);
+        let inserted: String = new[prefix..new.len() - suffix].iter().collect();
+        ops.extend(self.insert(replica, prefix, &inserted));
+        ops
+    }
+
+    /// Applies an operation of any replica. Returns whether it changed
+    /// anything, operations already applied are skipped. Fails when the
+    /// operation refers to a character this replica hasn't seen yet.
+    pub fn apply(&mut self, op: &TextOp) -> Result<bool, String> {
+        match *op {
+            TextOp::Insert { id, after, ch } => {
+                if self.position(id).is_some() {
+                    return Ok(false);
+                }
+                let mut index = match after {
+                    None => 0,
+                    Some(after) => {
+                        self.position(after)
+                            .ok_or_else(|| format!("Unknown character {after}"))?
+                            + 1
+                    }
+                };
+                // Later concurrent inserts at the same place go first, and
+                // everything inserted after them has an even higher counter
+                while self.items.get(index).is_some_and(|i| i.id > id) {
+                    index += 1;
+                }
+                self.items.insert(
+                    index,
+                    TextItem {
+                        id,
+                        ch,
+                        deleted: false,
+                    },
+                );
+                self.clock = self.clock.max(id.counter);
+                Ok(true)
+            }
+            TextOp::Delete { id } => {
+                let index = self
+                    .position(id)
+                    .ok_or_else(|| format!("Unknown character {id}"))?;
+                Ok(!std::mem::replace(&mut self.items[index].deleted, true))
+            }
+        }
+    }
+
+    fn position(&self, id: CharId) -> Option<usize> {
+        self.items.iter().position(|i| i.id == id)
+    }
+}
@@ -0,0 +1,53 @@
+use crate::ChangeType;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// Shortest secret a webhook is signed with
+pub const MIN_WEBHOOK_SECRET_LENGTH: usize = 16;
+
+/// Event sent by `send test event`, never caused by a change
+pub const WEBHOOK_TEST_EVENT: &str = "ping";
+
+/// Endpoint of a workspace notified about its changes
+#[derive(Clone, Debug, FromRow)]
+pub struct Webhook {
+    pub id: Uuid,
+    pub workspace_id: Uuid,
+    pub url: String,
+    /// Key of the HMAC-SHA256 signature of every payload
+    pub secret: String,
+    pub event_types: Vec<ChangeType>,
+    /// Changes up to this one are queued for delivery
+    pub last_change_id: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Clone, Copy, Debug, sqlx::Type, PartialEq, Eq, Deserialize, Serialize)]
+#[sqlx(type_name = "webhook_delivery_status", rename_all = "PascalCase")]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryStatus {
+    /// Waiting for its first attempt or a retry
+    Pending,
+    Delivered,
+    /// Gave up after the last retry
+    Failed,
+}
+
+/// Payload queued for a webhook, with the outcome of its attempts
+#[derive(Clone, Debug, FromRow)]
+pub struct WebhookDelivery {
+    pub id: Uuid,
+    pub webhook_id: Uuid,
+    pub event: String,
+    pub payload: Value,
+    pub status: DeliveryStatus,
+    pub attempts: i32,
+    pub next_attempt_at: DateTime<Utc>,
+    pub last_status_code: Option<i32>,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+}
@@ -11,12 +11,62 @@ pub enum CoreError {
     ServerError,
     #[error("Not found")]
     NotFound,
+    #[error("Already exists")]
+    AlreadyExists,
     #[error("User have reached the limit of create workspaces (3)")]
     TooManyWorkspaces,
     #[error("You don't have access to do it")]
     AccessDenied,
     #[error("Block type must be matching block content type")]
     BlockTypeNotMatches,
+    #[error("Share link password is missing or wrong")]
+    SharePasswordInvalid,
+    #[error("Storage error: {0}")]
+    Storage(String),
+    #[error("File is too large (max {0} bytes)")]
+    FileTooLarge(usize),
+    #[error("Attachment must be an image")]
+    NotAnImage,
+    #[error("Storage quota exceeded")]
+    StorageQuotaExceeded,
+    #[error("Invalid rich text: {0}")]
+    InvalidRichText(String),
+    #[error("Invalid block content: {0}")]
+    InvalidBlockContent(String),
+    #[error("Invalid parent block: {0}")]
+    InvalidBlockParent(String),
+    #[error("Invalid block order: {0}")]
+    InvalidBlockOrder(String),
+    #[error("Invalid property: {0}")]
+    InvalidProperty(String),
+    #[error("Invalid view: {0}")]
+    InvalidView(String),
+    #[error("Invalid tag: {0}")]
+    InvalidTag(String),
+    #[error("Invalid webhook: {0}")]
+    InvalidWebhook(String),
+    #[error("Invalid comment: {0}")]
+    InvalidComment(String),
+    #[error("Invalid template: {0}")]
+    InvalidTemplate(String),
+    #[error("Changed by someone else in the meantime")]
+    VersionMismatch,
+    #[error("Text is out of sync: {0}")]
+    TextOutOfSync(String),
+    #[error("Too many concurrent edits, try again")]
+    ConcurrentEdits,
+    #[error("Link points to an address that is not allowed")]
+    LinkPreviewBlocked,
+    #[error("Could not fetch link preview: {0}")]
+    LinkPreview(String),
+    #[error("Could not render SVG: {0}")]
+    Render(String),
+}
+
+impl From<std::io::Error> for CoreError {
+    fn from(value: std::io::Error) -> Self {
+        CoreError::Storage(value.to_string())
+    }
 }
 
 pub type Result<T> = std::result::Result<T, CoreError>;
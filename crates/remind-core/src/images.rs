@@ -0,0 +1,97 @@
+use crate::errors::{CoreError, Result};
+use async_trait::async_trait;
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use image::metadata::Orientation;
+use image::{DynamicImage, ImageDecoder, ImageFormat, ImageReader, Limits};
+use std::io::Cursor;
+use uuid::Uuid;
+
+/// Widths of the generated thumbnails, only those narrower than the original are kept
+pub const THUMBNAIL_WIDTHS: [u32; 3] = [320, 640, 1280];
+
+const MAX_DIMENSION: u32 = 12_000;
+const JPEG_QUALITY: u8 = 85;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ImageDimensions {
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Result of [`process_image`]
+pub struct ProcessedImage {
+    /// Re-encoded original without metadata, `None` when it is kept as uploaded
+    pub stripped: Option<Vec<u8>>,
+    pub dimensions: ImageDimensions,
+    /// Thumbnails as `(width, data)`, encoded in the original format
+    pub variants: Vec<(u32, Vec<u8>)>,
+}
+
+/// Processes uploaded images referenced by blocks
+#[async_trait]
+pub trait ImagePipeline: Send + Sync {
+    /// Processes the attachment once and returns its dimensions.
+    /// Returns `None` for image formats the server can't decode.
+    async fn process(&self, attachment_id: Uuid) -> Result<Option<ImageDimensions>>;
+}
+
+/// Whether [`process_image`] supports this content type
+pub fn is_processable(content_type: &str) -> bool {
+    matches!(
+        content_type,
+        "image/png" | "image/jpeg" | "image/gif" | "image/webp"
+    )
+}
+
+/// Decodes the image, applies and drops its EXIF orientation and metadata,
+/// and generates thumbnails.
+/// GIFs are not re-encoded to keep their animation; they carry no EXIF data.
+pub fn process_image(data: &[u8], content_type: &str) -> Result<ProcessedImage> {
+    let format = ImageFormat::from_mime_type(content_type).ok_or(CoreError::NotAnImage)?;
+    let mut reader = ImageReader::with_format(Cursor::new(data), format);
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_DIMENSION);
+    limits.max_image_height = Some(MAX_DIMENSION);
+    reader.limits(limits);
+
+    let mut decoder = reader.into_decoder().map_err(|_| CoreError::NotAnImage)?;
+    let orientation = decoder.orientation().unwrap_or(Orientation::NoTransforms);
+    let mut image = DynamicImage::from_decoder(decoder).map_err(|_| CoreError::NotAnImage)?;
+    image.apply_orientation(orientation);
+
+    let stripped = match format {
+        ImageFormat::Gif => None,
+        _ => Some(encode(&image, format)?),
+    };
+
+    let mut variants = Vec::new();
+    for width in THUMBNAIL_WIDTHS {
+        if width >= image.width() {
+            break;
+        }
+        let thumbnail = image.resize(width, image.height(), FilterType::Triangle);
+        variants.push((width, encode(&thumbnail, format)?));
+    }
+
+    Ok(ProcessedImage {
+        stripped,
+        dimensions: ImageDimensions {
+            width: image.width(),
+            height: image.height(),
+        },
+        variants,
+    })
+}
+
+fn encode(image: &DynamicImage, format: ImageFormat) -> Result<Vec<u8>> {
+    let mut buf = Cursor::new(Vec::new());
+    let result = match format {
+        ImageFormat::Jpeg => DynamicImage::ImageRgb8(image.to_rgb8())
+            .write_with_encoder(JpegEncoder::new_with_quality(&mut buf, JPEG_QUALITY)),
+        ImageFormat::WebP => DynamicImage::ImageRgba8(image.to_rgba8()).write_to(&mut buf, format),
+        _ => image.write_to(&mut buf, format),
+    };
+    result.map_err(|_| CoreError::ServerError)?;
+    Ok(buf.into_inner())
+}
@@ -1,14 +1,48 @@
+pub mod audit;
+pub mod collab;
+pub mod diagrams;
 pub(crate) mod dto;
 mod entities;
 pub mod errors;
+pub mod images;
+pub mod notifications;
+pub mod previews;
+pub mod render;
 pub(crate) mod repositories;
 pub(crate) mod services;
+pub mod storage;
+pub mod templates;
+pub mod webhooks;
 
-pub use dto::{block::*, note::*, user::*, workspace::*};
-pub use entities::{block::*, note::*, user::User, workspace::Workspace};
+pub use dto::{
+    attachment::*, audit::*, block::*, change::*, collection::*, comment::*, note::*,
+    notification::*, share_link::*, tag::*, template::*, user::*, webhook::*, workspace::*,
+};
+pub use entities::{
+    attachment::Attachment, audit::*, block::*, change::*, collection::*, comment::*, note::*,
+    notification::*, rich_text::*, share_link::*, table::*, tag::*, template::*, text_crdt::*,
+    user::User, webhook::*, workspace::Workspace,
+};
 pub use remind_auth;
-pub use repositories::{block::*, note::*, user::*, workspace::*};
+pub use repositories::{
+    attachment::*, audit::*, block::*, change::*, collection::*, comment::*, note::*,
+    notification::*, share_link::*, sync::*, tag::*, user::*, webhook::*, workspace::*,
+};
 pub use services::{
-    block::BlockService, note::NoteService, user::UserService, workspace::WorkspaceService,
+    attachment::AttachmentService,
+    audit::AuditService,
+    block::BlockService,
+    change::{ChangeService, MAX_CHANGES_PER_PAGE},
+    collection::CollectionService,
+    comment::CommentService,
+    note::NoteService,
+    notification::NotificationService,
+    share_link::ShareLinkService,
+    sync::SyncService,
+    tag::TagService,
+    user::UserService,
+    webhook::{MAX_DELIVERIES_PER_PAGE, RetryPolicy, WebhookService},
+    workspace::WorkspaceService,
 };
 pub use sqlx::{PgPool, postgres::PgPoolOptions};
+pub use storage::{BlobStore, local::LocalBlobStore, s3::S3BlobStore, s3::S3Config};
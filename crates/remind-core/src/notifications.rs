@@ -0,0 +1,23 @@
+use crate::errors::Result;
+use crate::{Mention, NotificationCreateDTO};
+use async_trait::async_trait;
+use uuid::Uuid;
+
+/// Notifies users of what happens to notes they are involved in
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    /// Notifies the user, unless they caused it or turned its kind off
+    async fn notify(&self, data: NotificationCreateDTO) -> Result<()>;
+}
+
+/// Users mentioned in `after` but not in `before`
+pub fn new_user_mentions(before: &[Mention], after: &[Mention]) -> Vec<Uuid> {
+    after
+        .iter()
+        .filter(|m| !before.contains(m))
+        .filter_map(|m| match m {
+            Mention::User(id) => Some(*id),
+            Mention::Note(_) => None,
+        })
+        .collect()
+}
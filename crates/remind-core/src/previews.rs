@@ -0,0 +1,391 @@
+use crate::LinkPreview;
+use crate::errors::{CoreError, Result};
+use async_trait::async_trait;
+use chrono::Utc;
+use reqwest::header::{ACCEPT, CONTENT_TYPE, LOCATION, USER_AGENT};
+use reqwest::redirect::Policy;
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+use url::{Host, Url};
+
+const MAX_TITLE_LENGTH: usize = 300;
+const MAX_DESCRIPTION_LENGTH: usize = 1000;
+
+/// Fetches previews of links in Bookmark blocks
+#[async_trait]
+pub trait LinkPreviewer: Send + Sync {
+    async fn preview(&self, url: &str) -> Result<LinkPreview>;
+}
+
+#[derive(Clone, Debug)]
+pub struct PreviewLimits {
+    /// Time limit of the whole fetch, redirects included
+    pub timeout: Duration,
+    /// Bytes of the page that are read, the rest is ignored
+    pub max_body_size: usize,
+    pub max_redirects: usize,
+    /// Allows private, loopback and link-local addresses.
+    /// Only meant for tests and trusted networks.
+    pub allow_private_addresses: bool,
+}
+
+impl Default for PreviewLimits {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(5),
+            max_body_size: 512 * 1024,
+            max_redirects: 3,
+            allow_private_addresses: false,
+        }
+    }
+}
+
+/// Fetches pages over HTTP and reads their title, description and favicon.
+/// Every hop is resolved and checked before connecting, and the connection
+/// is pinned to the checked addresses so DNS can't be swapped in between.
+#[derive(Clone)]
+pub struct HttpLinkPreviewer {
+    limits: PreviewLimits,
+}
+
+impl HttpLinkPreviewer {
+    pub fn new(limits: PreviewLimits) -> Self {
+        Self { limits }
+    }
+
+    async fn fetch(&self, url: Url) -> Result<(Url, String)> {
+        let mut url = url;
+        for _ in 0..=self.limits.max_redirects {
+            if !matches!(url.scheme(), "http" | "https") {
+                return Err(CoreError::LinkPreviewBlocked);
+            }
+            let addrs = self.resolve(&url).await?;
+
+            // A proxy would connect on its own, past the address checks
+            let mut client = reqwest::Client::builder()
+                .redirect(Policy::none())
+                .no_proxy();
+            if let Some(Host::Domain(domain)) = url.host() {
+                client = client.resolve_to_addrs(domain, &addrs);
+            }
+            let client = client
+                .build()
+                .map_err(|e| CoreError::LinkPreview(e.to_string()))?;
+            let mut response = client
+                .get(url.clone())
+                .header(USER_AGENT, "ReMind link preview")
+                .header(ACCEPT, "text/html")
+                .send()
+                .await
+                .map_err(|e| CoreError::LinkPreview(e.to_string()))?;
+
+            if response.status().is_redirection() {
+                let location = response
+                    .headers()
+                    .get(LOCATION)
+                    .and_then(|l| l.to_str().ok())
+                    .ok_or_else(|| CoreError::LinkPreview("Redirect without location".into()))?;
+                url = url
+                    .join(location)
+                    .map_err(|_| CoreError::LinkPreview("Invalid redirect".into()))?;
+                continue;
+            }
+            if !response.status().is_success() {
+                return Err(CoreError::LinkPreview(format!(
+                    "Page responded with {}",
+                    response.status()
+                )));
+            }
+            let is_html = response
+                .headers()
+                .get(CONTENT_TYPE)
+                .and_then(|c| c.to_str().ok())
+                .is_some_and(|c| c.contains("text/html") || c.contains("application/xhtml"));
+            if !is_html {
+                return Err(CoreError::LinkPreview("Not an HTML page".into()));
+            }
+
+            let mut body = Vec::new();
+            while let Some(chunk) = response
+                .chunk()
+                .await
+                .map_err(|e| CoreError::LinkPreview(e.to_string()))?
+            {
+                let remaining = self.limits.max_body_size - body.len();
+                body.extend_from_slice(&chunk[..chunk.len().min(remaining)]);
+                if body.len() >= self.limits.max_body_size {
+                    break;
+                }
+            }
+            return Ok((url, String::from_utf8_lossy(&body).into_owned()));
+        }
+        Err(CoreError::LinkPreview("Too many redirects".into()))
+    }
+
+    /// Resolves the host of the url, rejecting non-public addresses
+    async fn resolve(&self, url: &Url) -> Result<Vec<SocketAddr>> {
+        let port = url
+            .port_or_known_default()
+            .ok_or(CoreError::LinkPreviewBlocked)?;
+        let addrs: Vec<SocketAddr> = match url.host() {
+            Some(Host::Domain(domain)) => tokio::net::lookup_host((domain, port))
+                .await
+                .map_err(|e| CoreError::LinkPreview(e.to_string()))?
+                .collect(),
+            Some(Host::Ipv4(ip)) => vec![SocketAddr::new(ip.into(), port)],
+            Some(Host::Ipv6(ip)) => vec![SocketAddr::new(ip.into(), port)],
+            None => Vec::new(),
+        };
+
+        if addrs.is_empty() {
+            return Err(CoreError::LinkPreview("Host not found".into()));
+        }
+        if !self.limits.allow_private_addresses && !addrs.iter().all(|a| is_public(a.ip())) {
+            return Err(CoreError::LinkPreviewBlocked);
+        }
+        Ok(addrs)
+    }
+}
+
+#[async_trait]
+impl LinkPreviewer for HttpLinkPreviewer {
+    async fn preview(&self, url: &str) -> Result<LinkPreview> {
+        let url = Url::parse(url).map_err(|_| CoreError::LinkPreviewBlocked)?;
+        let (url, html) = tokio::time::timeout(self.limits.timeout, self.fetch(url))
+            .await
+            .map_err(|_| CoreError::LinkPreview("Timed out".into()))??;
+        Ok(parse_preview(&html, &url))
+    }
+}
+
+/// Whether the address is reachable on the public internet
+pub fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || ip.is_unspecified()
+                || ip.is_multicast()
+                || a == 0
+                || a >= 240
+                // Carrier-grade NAT, 100.64.0.0/10
+                || (a == 100 && (b & 0xc0) == 64)
+                // Benchmarking, 198.18.0.0/15
+                || (a == 198 && (b & 0xfe) == 18))
+        }
+        IpAddr::V6(ip) => {
+            if let Some(ip) = ip.to_ipv4_mapped() {
+                return is_public(ip.into());
+            }
+            let [a, b, c, d, e, f, ..] = ip.segments();
+            !(ip.is_loopback()
+                || ip.is_unspecified()
+                || ip.is_multicast()
+                // Unique local, fc00::/7
+                || (a & 0xfe00) == 0xfc00
+                // Link local, fe80::/10
+                || (a & 0xffc0) == 0xfe80
+                // Documentation, 2001:db8::/32
+                || (a == 0x2001 && b == 0x0db8)
+                // Prefixes that embed an IPv4 address, which may be private:
+                // NAT64 64:ff9b::/96 and 64:ff9b:1::/48, 6to4 2002::/16,
+                // Teredo 2001::/32 and IPv4-compatible ::/96
+                || (a == 0x64 && b == 0xff9b)
+                || a == 0x2002
+                || (a == 0x2001 && b == 0)
+                || [a, b, c, d, e, f] == [0; 6])
+        }
+    }
+}
+
+/// Reads the preview from the `<head>` of a page.
+/// Open Graph tags win over `<title>` and the description meta tag.
+pub fn parse_preview(html: &str, base: &Url) -> LinkPreview {
+    let lower = html.to_ascii_lowercase();
+    let mut title = None;
+    let mut og_title = None;
+    let mut description = None;
+    let mut og_description = None;
+    let mut favicon = None;
+
+    let mut pos = 0;
+    while let Some(offset) = lower[pos..].find('<') {
+        let start = pos + offset + 1;
+        // The first character may be the slash of a closing tag
+        let name_end = lower[start..]
+            .char_indices()
+            .skip(1)
+            .find(|(_, c)| c.is_ascii_whitespace() || *c == '>' || *c == '/')
+            .map_or(lower.len(), |(e, _)| start + e);
+        let name = &lower[start..name_end];
+        let tag_end = lower[name_end..]
+            .find('>')
+            .map_or(lower.len(), |e| name_end + e);
+        pos = (tag_end + 1).min(lower.len());
+
+        match name {
+            "title" | "script" | "style" => {
+                let close = format!("</{name}");
+                let text_end = lower[pos..].find(&close).map_or(lower.len(), |e| pos + e);
+                if name == "title" && title.is_none() {
+                    title = clean_text(&html[pos..text_end], MAX_TITLE_LENGTH);
+                }
+                pos = text_end;
+            }
+            "meta" => {
+                let attrs = parse_attributes(&html[name_end..tag_end]);
+                let key = attrs.get("property").or_else(|| attrs.get("name"));
+                let content = attrs.get("content").map(String::as_str).unwrap_or_default();
+                match key.map(|k| k.to_ascii_lowercase()).as_deref() {
+                    Some("og:title") => og_title = clean_text(content, MAX_TITLE_LENGTH),
+                    Some("og:description") => {
+                        og_description = clean_text(content, MAX_DESCRIPTION_LENGTH)
+                    }
+                    Some("description") if description.is_none() => {
+                        description = clean_text(content, MAX_DESCRIPTION_LENGTH)
+                    }
+                    _ => {}
+                }
+            }
+            "link" if favicon.is_none() => {
+                let attrs = parse_attributes(&html[name_end..tag_end]);
+                let is_icon = attrs.get("rel").is_some_and(|rel| {
+                    rel.to_ascii_lowercase()
+                        .split_ascii_whitespace()
+                        .any(|r| r == "icon")
+                });
+                if is_icon {
+                    favicon = attrs.get("href").and_then(|href| web_url(base, href));
+                }
+            }
+            "body" | "/head" => break,
+            _ => {}
+        }
+    }
+
+    LinkPreview {
+        title: og_title.or(title),
+        description: og_description.or(description),
+        favicon: favicon.or_else(|| web_url(base, "/favicon.ico")),
+        fetched_at: Utc::now(),
+    }
+}
+
+/// Resolves a link of the page, keeping only http(s) targets
+fn web_url(base: &Url, href: &str) -> Option<String> {
+    let url = base.join(href.trim()).ok()?;
+    matches!(url.scheme(), "http" | "https").then(|| url.to_string())
+}
+
+fn parse_attributes(tag: &str) -> HashMap<String, String> {
+    let bytes = tag.as_bytes();
+    let mut attrs = HashMap::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        while i < bytes.len() && (bytes[i].is_ascii_whitespace() || bytes[i] == b'/') {
+            i += 1;
+        }
+        let name_start = i;
+        while i < bytes.len() && !bytes[i].is_ascii_whitespace() && !matches!(bytes[i], b'=' | b'/')
+        {
+            i += 1;
+        }
+        let name = tag[name_start..i].to_ascii_lowercase();
+        while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+            i += 1;
+        }
+
+        let mut value = "";
+        if i < bytes.len() && bytes[i] == b'=' {
+            i += 1;
+            while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+                i += 1;
+            }
+            if i < bytes.len() && matches!(bytes[i], b'"' | b'\'') {
+                let quote = bytes[i];
+                let value_start = i + 1;
+                i = value_start;
+                while i < bytes.len() && bytes[i] != quote {
+                    i += 1;
+                }
+                value = &tag[value_start..i];
+                i += 1;
+            } else {
+                let value_start = i;
+                while i < bytes.len() && !bytes[i].is_ascii_whitespace() {
+                    i += 1;
+                }
+                value = &tag[value_start..i];
+            }
+        } else if name.is_empty() {
+            i += 1;
+            continue;
+        }
+        attrs.entry(name).or_insert_with(|| decode_entities(value));
+    }
+    attrs
+}
+
+/// Decodes entities, collapses whitespace and cuts the text to `max` characters
+fn clean_text(text: &str, max: usize) -> Option<String> {
+    let text = decode_entities(text);
+    let text: String = text
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .take(max)
+        .collect();
+    (!text.is_empty()).then_some(text)
+}
+
+fn decode_entities(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let decoded = rest[1..]
+            .find(';')
+            .filter(|&end| end <= 10)
+            .and_then(|end| Some((decode_entity(&rest[1..end + 1])?, end + 2)));
+        match decoded {
+            Some((ch, len)) => {
+                out.push(ch);
+                rest = &rest[len..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn decode_entity(entity: &str) -> Option<char> {
+    match entity {
+        "amp" => Some('&'),
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        "nbsp" => Some(' '),
+        _ => {
+            let code = match entity
+                .strip_prefix("#x")
+                .or_else(|| entity.strip_prefix("#X"))
+            {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => entity.strip_prefix('#')?.parse().ok()?,
+            };
+            char::from_u32(code)
+        }
+    }
+}
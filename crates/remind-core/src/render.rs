@@ -0,0 +1,474 @@
+use crate::diagrams::sanitize_svg;
+use crate::{
+    BlockContent, BlockDTO, ColumnType, Mention, NoteDTO, TableContent, TextMark, TextSpan,
+};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+use uuid::Uuid;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RenderFormat {
+    #[default]
+    Markdown,
+    Html,
+}
+
+impl RenderFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            RenderFormat::Markdown => "text/markdown; charset=utf-8",
+            RenderFormat::Html => "text/html; charset=utf-8",
+        }
+    }
+
+    pub fn render(&self, note: &NoteDTO) -> String {
+        match self {
+            RenderFormat::Markdown => render_markdown(note),
+            RenderFormat::Html => render_html(note),
+        }
+    }
+}
+
+/// Link to the content of an uploaded file
+fn attachment_url(id: Uuid) -> String {
+    format!("/attachments/{id}/content")
+}
+
+pub fn render_markdown(note: &NoteDTO) -> String {
+    let mut out = format!("# {}\n", escape_markdown(&note.title));
+    out.push_str(&markdown_blocks(&note.blocks));
+    out
+}
+
+/// Sibling blocks, each starting on a new paragraph
+fn markdown_blocks(blocks: &[BlockDTO]) -> String {
+    let mut out = String::new();
+    let mut in_list = false;
+    for block in blocks {
+        let is_list_item = block.content.list_indent().is_some();
+        // Items of the same list are not separated by blank lines
+        if !(in_list && is_list_item) {
+            out.push('\n');
+        }
+        out.push_str(&markdown_block(block));
+        out.push('\n');
+        in_list = is_list_item;
+    }
+    out
+}
+
+fn markdown_block(block: &BlockDTO) -> String {
+    match &block.content {
+        BlockContent::PlainText(c) => markdown_spans(&c.spans),
+        BlockContent::Checkbox(c) => format!(
+            "- [{}] {}",
+            if c.status { "x" } else { " " },
+            escape_markdown(&c.text)
+        ),
+        BlockContent::Image(c) => format!(
+            "![{}]({})",
+            escape_markdown(c.alt.as_deref().unwrap_or_default()),
+            link_destination(&c.url)
+        ),
+        BlockContent::Code(c) => code_fence(&c.language, &c.code),
+        BlockContent::Math(c) => format!("$$\n{}\n$$", c.code.trim()),
+        BlockContent::Diagram(c) => code_fence("mermaid", &c.code),
+        BlockContent::File(c) => format!(
+            "[{}]({})",
+            escape_markdown(&c.name),
+            attachment_url(c.attachment_id)
+        ),
+        // The note title is the only top level heading
+        BlockContent::Heading(c) => format!(
+            "{} {}",
+            "#".repeat(c.level as usize + 1),
+            markdown_spans(&c.spans)
+        ),
+        BlockContent::Quote(c) => quote_lines(&markdown_spans(&c.spans)),
+        BlockContent::Callout(c) => {
+            quote_lines(&format!("{} {}", c.icon, markdown_spans(&c.spans)))
+        }
+        BlockContent::Divider(_) => "---".to_string(),
+        BlockContent::BulletedList(c) => format!(
+            "{}- {}",
+            "    ".repeat(c.indent as usize),
+            markdown_spans(&c.spans)
+        ),
+        BlockContent::NumberedList(c) => format!(
+            "{}{}. {}",
+            "    ".repeat(c.indent as usize),
+            block.number.unwrap_or(1),
+            markdown_spans(&c.spans)
+        ),
+        BlockContent::Table(c) => markdown_table(c),
+        // Markdown has no toggles, GitHub and most editors render `<details>`
+        BlockContent::Toggle(c) => format!(
+            "<details>\n<summary>{}</summary>\n{}\n</details>",
+            html_spans(&c.spans),
+            markdown_blocks(&block.children)
+        ),
+        BlockContent::Synced(_) => block
+            .synced_source
+            .as_deref()
+            .map(markdown_block)
+            .unwrap_or_default(),
+        BlockContent::Bookmark(c) => {
+            let title = c.preview.as_ref().and_then(|p| p.title.as_deref());
+            format!(
+                "[{}]({})",
+                escape_markdown(title.unwrap_or(&c.url)),
+                link_destination(&c.url)
+            )
+        }
+    }
+}
+
+fn code_fence(language: &str, code: &str) -> String {
+    // The fence has to be longer than any backtick run in the code
+    let longest = code.split(|ch| ch != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat(longest.max(2) + 1);
+    format!("{fence}{language}\n{code}\n{fence}")
+}
+
+fn code_span(code: &str) -> String {
+    // Like fences, the delimiter is longer than any backtick run in the code.
+    // Padding keeps backticks at the edges apart from it, and is stripped
+    // again when read.
+    let longest = code.split(|ch| ch != '`').map(str::len).max().unwrap_or(0);
+    let delimiter = "`".repeat(longest + 1);
+    if longest > 0 || code.starts_with(' ') || code.ends_with(' ') {
+        format!("{delimiter} {code} {delimiter}")
+    } else {
+        format!("{delimiter}{code}{delimiter}")
+    }
+}
+
+/// URL as a link destination. URLs with spaces, parentheses or angle
+/// brackets are put in `<...>`, percent-encoded so they can't end it early.
+fn link_destination(url: &str) -> String {
+    let unsafe_char = |ch: char| ch.is_whitespace() || matches!(ch, '<' | '>' | '\\');
+    if !url.contains(|ch: char| unsafe_char(ch) || matches!(ch, '(' | ')')) {
+        return url.to_string();
+    }
+    let mut out = String::from("<");
+    for ch in url.chars() {
+        if unsafe_char(ch) {
+            let mut buf = [0; 4];
+            for byte in ch.encode_utf8(&mut buf).bytes() {
+                out.push_str(&format!("%{byte:02X}"));
+            }
+        } else {
+            out.push(ch);
+        }
+    }
+    out.push('>');
+    out
+}
+
+fn markdown_table(table: &TableContent) -> String {
+    let row = |cells: Vec<String>| {
+        let cells: Vec<String> = cells
+            .iter()
+            .map(|c| escape_markdown(&c.replace('\n', " ")))
+            .collect();
+        format!("| {} |", cells.join(" | "))
+    };
+
+    let mut lines = vec![row(table.columns.iter().map(|c| c.name.clone()).collect())];
+    let alignments: Vec<&str> = table
+        .columns
+        .iter()
+        .map(|c| match c.column_type {
+            ColumnType::Number => "---:",
+            _ => "---",
+        })
+        .collect();
+    lines.push(format!("| {} |", alignments.join(" | ")));
+    lines.extend(table.text_rows().into_iter().map(row));
+    lines.join("\n")
+}
+
+fn quote_lines(text: &str) -> String {
+    text.lines()
+        .map(|l| format!("> {l}"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn markdown_spans(spans: &[TextSpan]) -> String {
+    let mut out = String::new();
+    for span in spans {
+        let mut text = if span.marks.contains(&TextMark::Code) {
+            code_span(&span.text)
+        } else {
+            escape_markdown(&span.text)
+        };
+        for mark in &span.marks {
+            text = match mark {
+                TextMark::Bold => format!("**{text}**"),
+                TextMark::Italic => format!("_{text}_"),
+                TextMark::Strikethrough => format!("~~{text}~~"),
+                TextMark::Underline => format!("<u>{text}</u>"),
+                TextMark::Code => text,
+            };
+        }
+        if let Some(href) = &span.href {
+            text = format!("[{text}]({})", link_destination(href));
+        }
+        // Mentions are app links, exports keep their text
+        if span.mention.is_some() && span.broken {
+            text = format!("~~{text}~~");
+        }
+        out.push_str(&text);
+    }
+    out
+}
+
+fn escape_markdown(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for ch in text.chars() {
+        if matches!(
+            ch,
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '~' | '|'
+        ) {
+            out.push('\\');
+        }
+        out.push(ch);
+    }
+    out
+}
+
+pub fn render_html(note: &NoteDTO) -> String {
+    let title = escape_html(&note.title);
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n</head>\n<body>\n<article>\n<h1>{title}</h1>\n"
+    );
+    out.push_str(&html_blocks(&note.blocks));
+    out.push_str("</article>\n</body>\n</html>\n");
+    out
+}
+
+/// Sibling blocks, with consecutive list items grouped into lists
+fn html_blocks(blocks: &[BlockDTO]) -> String {
+    let mut out = String::new();
+    // Tags of the lists currently open, one per indent level
+    let mut lists: Vec<&str> = Vec::new();
+    for block in blocks {
+        let list_item = match &block.content {
+            BlockContent::BulletedList(c) => Some(("ul", c)),
+            BlockContent::NumberedList(c) => Some(("ol", c)),
+            _ => None,
+        };
+        let Some((tag, item)) = list_item else {
+            close_lists(&mut out, &mut lists, 0);
+            out.push_str(&html_block(block));
+            out.push('\n');
+            continue;
+        };
+
+        let depth = item.indent as usize;
+        close_lists(&mut out, &mut lists, depth + 1);
+        if lists.len() == depth + 1 {
+            if lists[depth] == tag {
+                out.push_str("</li>\n");
+            } else {
+                close_lists(&mut out, &mut lists, depth);
+            }
+        }
+        if lists.len() == depth {
+            let _ = writeln!(out, "<{tag}>");
+            lists.push(tag);
+        }
+        let _ = write!(out, "<li>{}", html_spans(&item.spans));
+    }
+    close_lists(&mut out, &mut lists, 0);
+    out
+}
+
+/// Closes open lists until `depth` remain
+fn close_lists(out: &mut String, lists: &mut Vec<&str>, depth: usize) {
+    while lists.len() > depth {
+        if let Some(tag) = lists.pop() {
+            let _ = writeln!(out, "</li></{tag}>");
+        }
+    }
+}
+
+fn html_block(block: &BlockDTO) -> String {
+    match &block.content {
+        BlockContent::PlainText(c) => format!("<p>{}</p>", html_spans(&c.spans)),
+        BlockContent::Checkbox(c) => format!(
+            "<p><input type=\"checkbox\" disabled{}> {}</p>",
+            if c.status { " checked" } else { "" },
+            escape_html(&c.text)
+        ),
+        BlockContent::Image(c) => {
+            let mut img = format!(
+                "<img src=\"{}\" alt=\"{}\"",
+                escape_html(&c.url),
+                escape_html(c.alt.as_deref().unwrap_or_default())
+            );
+            if let (Some(width), Some(height)) = (c.width, c.height) {
+                let _ = write!(img, " width=\"{width}\" height=\"{height}\"");
+            }
+            img.push('>');
+            img
+        }
+        BlockContent::Code(c) => format!(
+            "<pre><code class=\"language-{}\">{}</code></pre>",
+            escape_html(&c.language),
+            escape_html(&c.code)
+        ),
+        BlockContent::File(c) => format!(
+            "<p><a href=\"{}\">{}</a></p>",
+            attachment_url(c.attachment_id),
+            escape_html(&c.name)
+        ),
+        BlockContent::Heading(c) => {
+            // h1 is taken by the note title
+            let level = c.level + 1;
+            format!("<h{level}>{}</h{level}>", html_spans(&c.spans))
+        }
+        BlockContent::Quote(c) => format!("<blockquote>{}</blockquote>", html_spans(&c.spans)),
+        BlockContent::Callout(c) => format!(
+            "<aside class=\"callout callout-{}\"><span class=\"callout-icon\">{}</span> {}</aside>",
+            c.color.as_str(),
+            escape_html(&c.icon),
+            html_spans(&c.spans)
+        ),
+        BlockContent::Divider(_) => "<hr>".to_string(),
+        // Lists are opened and closed by `render_html`
+        BlockContent::BulletedList(c) | BlockContent::NumberedList(c) => {
+            format!("<li>{}</li>", html_spans(&c.spans))
+        }
+        BlockContent::Table(c) => html_table(c),
+        BlockContent::Toggle(c) => format!(
+            "<details>\n<summary>{}</summary>\n{}</details>",
+            html_spans(&c.spans),
+            html_blocks(&block.children)
+        ),
+        // List items come with their own list
+        BlockContent::Synced(_) => format!(
+            "<div class=\"synced\">\n{}</div>",
+            block
+                .synced_source
+                .as_deref()
+                .map(|source| html_blocks(std::slice::from_ref(source)))
+                .unwrap_or_default()
+        ),
+        // Without a safe pre-rendered SVG the source is left for MathJax or KaTeX
+        BlockContent::Math(c) => match c.svg.as_deref().map(sanitize_svg) {
+            Some(Ok(svg)) => format!("<figure class=\"math\">{svg}</figure>"),
+            _ => format!("<div class=\"math\">\\[{}\\]</div>", escape_html(&c.code)),
+        },
+        // and for mermaid.js
+        BlockContent::Diagram(c) => match c.svg.as_deref().map(sanitize_svg) {
+            Some(Ok(svg)) => format!("<figure class=\"diagram\">{svg}</figure>"),
+            _ => format!("<pre class=\"mermaid\">{}</pre>", escape_html(&c.code)),
+        },
+        BlockContent::Bookmark(c) => {
+            let preview = c.preview.as_ref();
+            let title = preview.and_then(|p| p.title.as_deref());
+            let mut out = format!(
+                "<p class=\"bookmark\"><a href=\"{}\">{}</a>",
+                escape_html(&c.url),
+                escape_html(title.unwrap_or(&c.url))
+            );
+            if let Some(description) = preview.and_then(|p| p.description.as_deref()) {
+                let _ = write!(out, "<br>{}", escape_html(description));
+            }
+            out.push_str("</p>");
+            out
+        }
+    }
+}
+
+fn html_table(table: &TableContent) -> String {
+    let mut out = String::from("<table>\n<thead><tr>");
+    for column in &table.columns {
+        let _ = write!(out, "<th>{}</th>", escape_html(&column.name));
+    }
+    out.push_str("</tr></thead>\n<tbody>\n");
+    for row in table.text_rows() {
+        out.push_str("<tr>");
+        for cell in row {
+            let _ = write!(out, "<td>{}</td>", escape_html(&cell));
+        }
+        out.push_str("</tr>\n");
+    }
+    out.push_str("</tbody>\n</table>");
+    out
+}
+
+fn html_spans(spans: &[TextSpan]) -> String {
+    let mut out = String::new();
+    for span in spans {
+        let mut text = escape_html(&span.text);
+        for mark in &span.marks {
+            let tag = match mark {
+                TextMark::Bold => "strong",
+                TextMark::Italic => "em",
+                TextMark::Underline => "u",
+                TextMark::Strikethrough => "s",
+                TextMark::Code => "code",
+            };
+            text = format!("<{tag}>{text}</{tag}>");
+        }
+        if let Some(href) = &span.href {
+            text = format!("<a href=\"{}\">{text}</a>", escape_html(href));
+        }
+        if let Some(mention) = &span.mention {
+            let (kind, id) = match mention {
+                Mention::Note(id) => ("note", id),
+                Mention::User(id) => ("user", id),
+            };
+            let class = if span.broken {
+                "mention broken"
+            } else {
+                "mention"
+            };
+            text = format!("<span class=\"{class}\" data-{kind}=\"{id}\">{text}</span>");
+        }
+        out.push_str(&text);
+    }
+    out
+}
+
+fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            ch => out.push(ch),
+        }
+    }
+    out
+}
+
+/// Renders a table as CSV with a header row of column names
+pub fn render_table_csv(table: &TableContent) -> String {
+    let mut out = String::new();
+    let header = table.columns.iter().map(|c| c.name.clone()).collect();
+    for row in std::iter::once(header).chain(table.text_rows()) {
+        let cells: Vec<String> = row.iter().map(|c| escape_csv(c)).collect();
+        out.push_str(&cells.join(","));
+        out.push_str("\r\n");
+    }
+    out
+}
+
+fn escape_csv(cell: &str) -> String {
+    // Spreadsheets run cells starting with these as formulas
+    if cell.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("\"'{}\"", cell.replace('"', "\"\""))
+    } else if cell.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell.to_string()
+    }
+}
@@ -0,0 +1,201 @@
+use crate::Attachment;
+use crate::errors::CoreError;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+/// Whether a block, or a block of a template, references the attachment `a`
+/// Space taken by the attachment `a`, thumbnails included
+const STORED_SIZE: &str = "COALESCE(SUM(a.size + a.variants_size), 0)::BIGINT";
+
+const REFERENCED: &str = r#"(EXISTS (SELECT 1 FROM blocks b WHERE b.attachment_id = a.id)
+    OR EXISTS (SELECT 1 FROM note_templates t WHERE t.workspace_id = a.workspace_id
+        AND jsonb_path_exists(t.blocks, '$.**.attachment_id ? (@ == $id)', jsonb_build_object('id', a.id))))"#;
+
+#[async_trait]
+pub trait AttachmentRepo {
+    /// Inserts the attachment unless it takes the uploader or the workspace
+    /// over its quota, `StorageQuotaExceeded` otherwise
+    async fn create_within_quota(
+        &self,
+        data: Attachment,
+        user_quota: i64,
+        workspace_quota: i64,
+    ) -> crate::errors::Result<()>;
+    async fn find_one(&self, id: Uuid) -> crate::errors::Result<Option<Attachment>>;
+    async fn delete(&self, id: Uuid) -> crate::errors::Result<()>;
+    /// Deletes the attachment unless something references it by now.
+    /// Returns the deleted attachment, its files are left to the caller.
+    async fn delete_if_unreferenced(&self, id: Uuid) -> crate::errors::Result<Option<Attachment>>;
+    async fn save(&self, data: Attachment) -> crate::errors::Result<()>;
+    /// Total size of the files uploaded by the user
+    async fn usage_by_user(&self, user_id: Uuid) -> crate::errors::Result<i64>;
+    /// Total size of the files stored in the workspace
+    async fn usage_by_workspace(&self, workspace_id: Uuid) -> crate::errors::Result<i64>;
+    /// Sets `orphaned_at` on attachments no block or template references
+    /// anymore, and clears it on those referenced again
+    async fn mark_orphans(&self) -> crate::errors::Result<()>;
+    async fn find_orphaned_before(
+        &self,
+        before: DateTime<Utc>,
+    ) -> crate::errors::Result<Vec<Attachment>>;
+}
+
+#[derive(Clone)]
+pub struct AttachmentRepository {
+    pool: sqlx::PgPool,
+}
+
+impl AttachmentRepository {
+    pub fn new(pool: sqlx::PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl AttachmentRepo for AttachmentRepository {
+    async fn create_within_quota(
+        &self,
+        data: Attachment,
+        user_quota: i64,
+        workspace_quota: i64,
+    ) -> crate::errors::Result<()> {
+        let mut tx = self.pool.begin().await?;
+        // Concurrent uploads of the user or into the workspace wait here, so
+        // each one sees the usage including the others
+        sqlx::query(r#"SELECT 1 FROM users WHERE id = $1 FOR UPDATE"#)
+            .bind(data.user_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(r#"SELECT 1 FROM workspaces WHERE id = $1 FOR UPDATE"#)
+            .bind(data.workspace_id)
+            .execute(&mut *tx)
+            .await?;
+        let (user_usage, workspace_usage): (i64, i64) = sqlx::query_as(&format!(
+            r#"SELECT
+            (SELECT {STORED_SIZE} FROM attachments a WHERE a.user_id = $1),
+            (SELECT {STORED_SIZE} FROM attachments a WHERE a.workspace_id = $2)"#
+        ))
+        .bind(data.user_id)
+        .bind(data.workspace_id)
+        .fetch_one(&mut *tx)
+        .await?;
+        if user_usage + data.size > user_quota || workspace_usage + data.size > workspace_quota {
+            return Err(CoreError::StorageQuotaExceeded);
+        }
+
+        sqlx::query(
+            r#"INSERT INTO attachments (id, workspace_id, user_id, storage_key, file_name, content_type, size, created_at, width, height, variants)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)"#,
+        )
+        .bind(data.id)
+        .bind(data.workspace_id)
+        .bind(data.user_id)
+        .bind(data.storage_key)
+        .bind(data.file_name)
+        .bind(data.content_type)
+        .bind(data.size)
+        .bind(data.created_at)
+        .bind(data.width)
+        .bind(data.height)
+        .bind(data.variants)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn find_one(&self, id: Uuid) -> crate::errors::Result<Option<Attachment>> {
+        let attachment =
+            sqlx::query_as::<_, Attachment>(r#"SELECT * FROM attachments WHERE id = $1"#)
+                .bind(id)
+                .fetch_optional(&self.pool)
+                .await?;
+        Ok(attachment)
+    }
+
+    async fn delete(&self, id: Uuid) -> crate::errors::Result<()> {
+        sqlx::query(r#"DELETE FROM attachments WHERE id = $1"#)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn delete_if_unreferenced(&self, id: Uuid) -> crate::errors::Result<Option<Attachment>> {
+        let attachment = sqlx::query_as::<_, Attachment>(&format!(
+            r#"DELETE FROM attachments a WHERE a.id = $1 AND NOT {REFERENCED} RETURNING *"#
+        ))
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(attachment)
+    }
+
+    async fn save(&self, data: Attachment) -> crate::errors::Result<()> {
+        sqlx::query(
+            r#"UPDATE attachments SET file_name = $2, content_type = $3, size = $4, width = $5, height = $6, variants = $7, variants_size = $8 WHERE id = $1"#,
+        )
+        .bind(data.id)
+        .bind(data.file_name)
+        .bind(data.content_type)
+        .bind(data.size)
+        .bind(data.width)
+        .bind(data.height)
+        .bind(data.variants)
+        .bind(data.variants_size)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn usage_by_user(&self, user_id: Uuid) -> crate::errors::Result<i64> {
+        let usage: i64 = sqlx::query_scalar(&format!(
+            r#"SELECT {STORED_SIZE} FROM attachments a WHERE a.user_id = $1"#
+        ))
+        .bind(user_id)
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(usage)
+    }
+
+    async fn usage_by_workspace(&self, workspace_id: Uuid) -> crate::errors::Result<i64> {
+        let usage: i64 = sqlx::query_scalar(&format!(
+            r#"SELECT {STORED_SIZE} FROM attachments a WHERE a.workspace_id = $1"#
+        ))
+        .bind(workspace_id)
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(usage)
+    }
+
+    async fn mark_orphans(&self) -> crate::errors::Result<()> {
+        sqlx::query(&format!(
+            r#"UPDATE attachments a SET orphaned_at = now()
+        WHERE a.orphaned_at IS NULL AND NOT {REFERENCED}"#
+        ))
+        .execute(&self.pool)
+        .await?;
+        sqlx::query(&format!(
+            r#"UPDATE attachments a SET orphaned_at = NULL
+        WHERE a.orphaned_at IS NOT NULL AND {REFERENCED}"#
+        ))
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn find_orphaned_before(
+        &self,
+        before: DateTime<Utc>,
+    ) -> crate::errors::Result<Vec<Attachment>> {
+        let attachments = sqlx::query_as::<_, Attachment>(
+            r#"SELECT * FROM attachments WHERE orphaned_at IS NOT NULL AND orphaned_at < $1"#,
+        )
+        .bind(before)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(attachments)
+    }
+}
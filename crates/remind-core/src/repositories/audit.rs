@@ -0,0 +1,117 @@
+use crate::{AuditEntry, AuditEvent, AuditFilter};
+use async_trait::async_trait;
+use uuid::Uuid;
+
+/// The audit log is only ever appended to
+#[async_trait]
+pub trait AuditRepo {
+    async fn create(&self, data: AuditEvent) -> crate::errors::Result<()>;
+    async fn find_in_workspace(
+        &self,
+        workspace_id: Uuid,
+        filter: AuditFilter,
+        limit: i64,
+    ) -> crate::errors::Result<Vec<AuditEntry>>;
+    /// Entries about the account of the user, like its logins
+    async fn find_for_user(
+        &self,
+        user_id: Uuid,
+        filter: AuditFilter,
+        limit: i64,
+    ) -> crate::errors::Result<Vec<AuditEntry>>;
+}
+
+#[derive(Clone)]
+pub struct AuditRepository {
+    pool: sqlx::PgPool,
+}
+
+impl AuditRepository {
+    pub fn new(pool: sqlx::PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl AuditRepo for AuditRepository {
+    async fn create(&self, data: AuditEvent) -> crate::errors::Result<()> {
+        sqlx::query(
+            r#"INSERT INTO audit_log (action, actor_id, ip, user_agent, workspace_id, note_id, target_id, details)
+        VALUES ($1, $2, $3, $4, coalesce($5, (SELECT workspace_id FROM notes WHERE id = $6)), $6, $7, $8)"#,
+        )
+        .bind(data.action)
+        .bind(data.context.actor_id)
+        .bind(data.context.ip)
+        .bind(data.context.user_agent)
+        .bind(data.workspace_id)
+        .bind(data.note_id)
+        .bind(data.target_id)
+        .bind(data.details)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn find_in_workspace(
+        &self,
+        workspace_id: Uuid,
+        filter: AuditFilter,
+        limit: i64,
+    ) -> crate::errors::Result<Vec<AuditEntry>> {
+        self.find("workspace_id = $1", workspace_id, filter, limit)
+            .await
+    }
+
+    async fn find_for_user(
+        &self,
+        user_id: Uuid,
+        filter: AuditFilter,
+        limit: i64,
+    ) -> crate::errors::Result<Vec<AuditEntry>> {
+        self.find(
+            "workspace_id IS NULL AND target_id = $1",
+            user_id,
+            filter,
+            limit,
+        )
+        .await
+    }
+}
+
+impl AuditRepository {
+    /// Entries in the scope matching the filter, `scope` compares to `$1`
+    async fn find(
+        &self,
+        scope: &str,
+        scope_id: Uuid,
+        filter: AuditFilter,
+        limit: i64,
+    ) -> crate::errors::Result<Vec<AuditEntry>> {
+        let query = format!(
+            r#"SELECT * FROM audit_log
+        WHERE {scope}
+        AND (cardinality($2::audit_action[]) = 0 OR action = ANY($2))
+        AND ($3::uuid IS NULL OR actor_id = $3)
+        AND ($4::uuid IS NULL OR note_id = $4)
+        AND ($5::uuid IS NULL OR target_id = $5)
+        AND ($6::timestamptz IS NULL OR created_at >= $6)
+        AND ($7::timestamptz IS NULL OR created_at < $7)
+        AND ($8::bigint IS NULL OR id < $8)
+        ORDER BY id DESC
+        LIMIT $9"#
+        );
+        let entries = sqlx::query_as::<_, AuditEntry>(&query)
+            .bind(scope_id)
+            .bind(filter.actions)
+            .bind(filter.actor_id)
+            .bind(filter.note_id)
+            .bind(filter.target_id)
+            .bind(filter.from)
+            .bind(filter.to)
+            .bind(filter.before)
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(entries)
+    }
+}
@@ -1,15 +1,64 @@
-use crate::Block;
+use crate::{Attachment, Block, Mention, TextCrdt};
 use async_trait::async_trait;
+use sqlx::PgConnection;
 use sqlx::types::Json;
 use uuid::Uuid;
 
 #[async_trait]
 pub trait BlockRepo {
-    async fn create(&self, data: Block) -> crate::errors::Result<()>;
+    async fn create(&self, conn: &mut PgConnection, data: Block) -> crate::errors::Result<()>;
     async fn find_one(&self, id: Uuid) -> crate::errors::Result<Option<Block>>;
+    /// Blocks of the note as a flattened tree: top level blocks by position,
+    /// each toggle followed by its children
     async fn find_all_in_note(&self, note_id: Uuid) -> crate::errors::Result<Vec<Block>>;
-    async fn delete(&self, id: Uuid) -> crate::errors::Result<()>;
-    async fn save(&self, data: Block) -> crate::errors::Result<()>;
+    /// Deletes the block with its children. Comment threads on them are
+    /// kept, marked orphaned and resolved.
+    async fn delete(&self, conn: &mut PgConnection, id: Uuid) -> crate::errors::Result<()>;
+    async fn save(&self, conn: &mut PgConnection, data: Block) -> crate::errors::Result<()>;
+    /// Moves the block to another position under its parent. The order is
+    /// part of the note, the version of the block stays.
+    async fn save_position(
+        &self,
+        conn: &mut PgConnection,
+        id: Uuid,
+        position: i32,
+    ) -> crate::errors::Result<()>;
+    /// Saves the block only if it is still at `version`, returns whether it did
+    async fn save_if_version(
+        &self,
+        conn: &mut PgConnection,
+        data: Block,
+        version: i64,
+    ) -> crate::errors::Result<bool>;
+    /// Replaces the mentions of a block in the link index
+    async fn save_links(
+        &self,
+        conn: &mut PgConnection,
+        block_id: Uuid,
+        links: Vec<Mention>,
+    ) -> crate::errors::Result<()>;
+    /// Blocks mentioning the target, grouped by note
+    async fn find_all_linking_to(&self, target: Mention) -> crate::errors::Result<Vec<Block>>;
+    /// Workspace of the note, `None` when there is no such note
+    async fn find_note_workspace(&self, note_id: Uuid) -> crate::errors::Result<Option<Uuid>>;
+    /// Attachment a block references
+    async fn find_attachment(&self, id: Uuid) -> crate::errors::Result<Option<Attachment>>;
+    /// Text CRDT of the block with the version it was saved at
+    async fn find_text_state(
+        &self,
+        block_id: Uuid,
+    ) -> crate::errors::Result<Option<(TextCrdt, i64)>>;
+    /// Saves the block together with its text CRDT. `version` is the one the
+    /// state was read at, `None` if there was none, the block is checked
+    /// against its own version. Returns `false` when either was saved by
+    /// someone else in between, the transaction must then be rolled back.
+    async fn save_text(
+        &self,
+        conn: &mut PgConnection,
+        data: Block,
+        state: &TextCrdt,
+        version: Option<i64>,
+    ) -> crate::errors::Result<bool>;
 }
 
 #[derive(Clone)]
@@ -25,17 +74,19 @@ impl BlockRepository {
 
 #[async_trait]
 impl BlockRepo for BlockRepository {
-    async fn create(&self, data: Block) -> crate::errors::Result<()> {
+    async fn create(&self, conn: &mut PgConnection, data: Block) -> crate::errors::Result<()> {
         sqlx::query(
-            r#"INSERT INTO blocks (id, block_type, content, note_id, position)
-        VALUES ($1, $2, $3, $4, $5)"#,
+            r#"INSERT INTO blocks (id, block_type, content, note_id, position, attachment_id, parent_block)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)"#,
         )
         .bind(data.id)
         .bind(data.block_type)
         .bind(Json(&data.content))
         .bind(data.note_id)
         .bind(data.position)
-        .execute(&self.pool)
+        .bind(data.content.attachment_id())
+        .bind(data.parent_block)
+        .execute(conn)
         .await?;
 
         Ok(())
@@ -51,7 +102,14 @@ impl BlockRepo for BlockRepository {
 
     async fn find_all_in_note(&self, note_id: Uuid) -> crate::errors::Result<Vec<Block>> {
         let blocks = sqlx::query_as::<_, Block>(
-            r#"SELECT * FROM blocks WHERE note_id = $1 ORDER BY position"#,
+            r#"WITH RECURSIVE tree AS (
+            SELECT b.*, ARRAY[b.position] AS path FROM blocks b
+            WHERE b.note_id = $1 AND b.parent_block IS NULL
+            UNION ALL
+            SELECT b.*, tree.path || b.position FROM blocks b
+            JOIN tree ON b.parent_block = tree.id
+        )
+        SELECT * FROM tree ORDER BY path"#,
         )
         .bind(note_id)
         .fetch_all(&self.pool)
@@ -59,25 +117,193 @@ impl BlockRepo for BlockRepository {
         Ok(blocks)
     }
 
-    async fn delete(&self, id: Uuid) -> crate::errors::Result<()> {
+    async fn delete(&self, conn: &mut PgConnection, id: Uuid) -> crate::errors::Result<()> {
+        sqlx::query(
+            r#"WITH RECURSIVE deleted AS (
+            SELECT id FROM blocks WHERE id = $1
+            UNION ALL
+            SELECT b.id FROM blocks b JOIN deleted d ON b.parent_block = d.id
+        )
+        UPDATE comments
+        SET orphaned = true, resolved_at = coalesce(resolved_at, now())
+        WHERE thread_id IS NULL AND block_id IN (SELECT id FROM deleted)"#,
+        )
+        .bind(id)
+        .execute(&mut *conn)
+        .await?;
         sqlx::query(r#"DELETE FROM blocks WHERE id = $1"#)
             .bind(id)
-            .execute(&self.pool)
+            .execute(conn)
             .await?;
         Ok(())
     }
 
-    async fn save(&self, data: Block) -> crate::errors::Result<()> {
+    async fn save(&self, conn: &mut PgConnection, data: Block) -> crate::errors::Result<()> {
         sqlx::query(
-            r#"UPDATE blocks SET block_type = $2, content = $3, note_id = $4, position = $5 WHERE id = $1"#,
+            r#"UPDATE blocks SET block_type = $2, content = $3, note_id = $4, position = $5, attachment_id = $6, parent_block = $7, version = version + 1 WHERE id = $1"#,
         )
             .bind(data.id)
             .bind(data.block_type)
             .bind(Json(&data.content))
             .bind(data.note_id)
             .bind(data.position)
-            .execute(&self.pool)
+            .bind(data.content.attachment_id())
+            .bind(data.parent_block)
+            .execute(conn)
+            .await?;
+        Ok(())
+    }
+
+    async fn save_position(
+        &self,
+        conn: &mut PgConnection,
+        id: Uuid,
+        position: i32,
+    ) -> crate::errors::Result<()> {
+        sqlx::query(r#"UPDATE blocks SET position = $2 WHERE id = $1"#)
+            .bind(id)
+            .bind(position)
+            .execute(conn)
+            .await?;
+        Ok(())
+    }
+
+    async fn save_if_version(
+        &self,
+        conn: &mut PgConnection,
+        data: Block,
+        version: i64,
+    ) -> crate::errors::Result<bool> {
+        let result = sqlx::query(
+            r#"UPDATE blocks SET block_type = $2, content = $3, note_id = $4, position = $5, attachment_id = $6, parent_block = $7, version = version + 1
+        WHERE id = $1 AND version = $8"#,
+        )
+        .bind(data.id)
+        .bind(data.block_type)
+        .bind(Json(&data.content))
+        .bind(data.note_id)
+        .bind(data.position)
+        .bind(data.content.attachment_id())
+        .bind(data.parent_block)
+        .bind(version)
+        .execute(conn)
+        .await?;
+        Ok(result.rows_affected() == 1)
+    }
+
+    async fn save_links(
+        &self,
+        conn: &mut PgConnection,
+        block_id: Uuid,
+        links: Vec<Mention>,
+    ) -> crate::errors::Result<()> {
+        sqlx::query(r#"DELETE FROM block_links WHERE block_id = $1"#)
+            .bind(block_id)
+            .execute(&mut *conn)
             .await?;
+        for link in links {
+            sqlx::query(
+                r#"INSERT INTO block_links (block_id, target_type, target_id)
+            VALUES ($1, $2::mention_type, $3)"#,
+            )
+            .bind(block_id)
+            .bind(mention_type(&link))
+            .bind(link.id())
+            .execute(&mut *conn)
+            .await?;
+        }
         Ok(())
     }
+
+    async fn find_all_linking_to(&self, target: Mention) -> crate::errors::Result<Vec<Block>> {
+        let blocks = sqlx::query_as::<_, Block>(
+            r#"SELECT b.* FROM blocks b
+        JOIN block_links l ON l.block_id = b.id
+        WHERE l.target_type = $1::mention_type AND l.target_id = $2
+        ORDER BY b.note_id, b.position"#,
+        )
+        .bind(mention_type(&target))
+        .bind(target.id())
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(blocks)
+    }
+
+    async fn find_note_workspace(&self, note_id: Uuid) -> crate::errors::Result<Option<Uuid>> {
+        let workspace_id =
+            sqlx::query_scalar::<_, Uuid>(r#"SELECT workspace_id FROM notes WHERE id = $1"#)
+                .bind(note_id)
+                .fetch_optional(&self.pool)
+                .await?;
+        Ok(workspace_id)
+    }
+
+    async fn find_attachment(&self, id: Uuid) -> crate::errors::Result<Option<Attachment>> {
+        let attachment =
+            sqlx::query_as::<_, Attachment>(r#"SELECT * FROM attachments WHERE id = $1"#)
+                .bind(id)
+                .fetch_optional(&self.pool)
+                .await?;
+        Ok(attachment)
+    }
+
+    async fn find_text_state(
+        &self,
+        block_id: Uuid,
+    ) -> crate::errors::Result<Option<(TextCrdt, i64)>> {
+        let state = sqlx::query_as::<_, (Json<TextCrdt>, i64)>(
+            r#"SELECT state, version FROM block_text_states WHERE block_id = $1"#,
+        )
+        .bind(block_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(state.map(|(state, version)| (state.0, version)))
+    }
+
+    async fn save_text(
+        &self,
+        conn: &mut PgConnection,
+        data: Block,
+        state: &TextCrdt,
+        version: Option<i64>,
+    ) -> crate::errors::Result<bool> {
+        let query = match version {
+            None => sqlx::query(
+                r#"INSERT INTO block_text_states (block_id, state) VALUES ($1, $2)
+            ON CONFLICT DO NOTHING"#,
+            )
+            .bind(data.id)
+            .bind(Json(state)),
+            Some(version) => sqlx::query(
+                r#"UPDATE block_text_states SET state = $2, version = version + 1
+            WHERE block_id = $1 AND version = $3"#,
+            )
+            .bind(data.id)
+            .bind(Json(state))
+            .bind(version),
+        };
+        let saved = query.execute(&mut *conn).await?.rows_affected();
+        if saved == 0 {
+            return Ok(false);
+        }
+
+        let result = sqlx::query(
+            r#"UPDATE blocks SET block_type = $2, content = $3, version = version + 1
+        WHERE id = $1 AND version = $4"#,
+        )
+        .bind(data.id)
+        .bind(data.block_type)
+        .bind(Json(&data.content))
+        .bind(data.version)
+        .execute(conn)
+        .await?;
+        Ok(result.rows_affected() == 1)
+    }
+}
+
+fn mention_type(mention: &Mention) -> &'static str {
+    match mention {
+        Mention::Note(_) => "Note",
+        Mention::User(_) => "User",
+    }
 }
@@ -0,0 +1,166 @@
+use crate::{Change, ChangeType};
+use async_trait::async_trait;
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+/// Transaction a change is written in, together with its entry in the log
+pub type ChangeTransaction = sqlx::Transaction<'static, sqlx::Postgres>;
+
+/// Held by the one reader giving changes their place in the log
+const SEQUENCE_LOCK: i64 = 0x6368_616e_6765;
+
+#[async_trait]
+pub trait ChangeRepo {
+    /// Starts the transaction of a change. Writes of notes and blocks take
+    /// it, so they are logged if and only if they are committed.
+    async fn begin(&self) -> crate::errors::Result<ChangeTransaction>;
+    async fn create(
+        &self,
+        conn: &mut PgConnection,
+        workspace_id: Uuid,
+        change_type: ChangeType,
+        note_id: Uuid,
+        block_id: Option<Uuid>,
+    ) -> crate::errors::Result<()>;
+    /// Logs a change in a note, in the workspace of the note
+    async fn create_in_note(
+        &self,
+        conn: &mut PgConnection,
+        change_type: ChangeType,
+        note_id: Uuid,
+        block_id: Option<Uuid>,
+    ) -> crate::errors::Result<()>;
+    /// Changes of the workspaces after the given one, oldest first
+    async fn find_all_after(
+        &self,
+        workspace_ids: &[Uuid],
+        after: i64,
+        limit: i64,
+    ) -> crate::errors::Result<Vec<Change>>;
+    /// Id of the latest change of the workspaces, 0 without any
+    async fn find_last_id(&self, workspace_ids: &[Uuid]) -> crate::errors::Result<i64>;
+}
+
+#[derive(Clone)]
+pub struct ChangeRepository {
+    pool: sqlx::PgPool,
+}
+
+impl ChangeRepository {
+    pub fn new(pool: sqlx::PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Places the committed changes without a place yet after every other
+    /// one. A change is only read once placed, and places only grow in the
+    /// order they are given, so changes committing out of the order of their
+    /// ids are still never skipped by a cursor.
+    ///
+    /// Readers only write when there is something to place, and don't wait
+    /// for one already placing: what it misses is placed on a later read.
+    async fn sequence(&self) -> crate::errors::Result<()> {
+        let unsequenced: bool =
+            sqlx::query_scalar(r#"SELECT EXISTS (SELECT 1 FROM change_log WHERE seq IS NULL)"#)
+                .fetch_one(&self.pool)
+                .await?;
+        if !unsequenced {
+            return Ok(());
+        }
+
+        let mut tx = self.pool.begin().await?;
+        let locked: bool = sqlx::query_scalar("SELECT pg_try_advisory_xact_lock($1)")
+            .bind(SEQUENCE_LOCK)
+            .fetch_one(&mut *tx)
+            .await?;
+        if !locked {
+            return Ok(());
+        }
+        sqlx::query(
+            r#"WITH committed AS (
+            SELECT id, row_number() OVER (ORDER BY id) AS n FROM change_log WHERE seq IS NULL
+        )
+        UPDATE change_log c
+        SET seq = (SELECT coalesce(max(seq), 0) FROM change_log) + committed.n
+        FROM committed WHERE c.id = committed.id"#,
+        )
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ChangeRepo for ChangeRepository {
+    async fn begin(&self) -> crate::errors::Result<ChangeTransaction> {
+        Ok(self.pool.begin().await?)
+    }
+
+    async fn create(
+        &self,
+        conn: &mut PgConnection,
+        workspace_id: Uuid,
+        change_type: ChangeType,
+        note_id: Uuid,
+        block_id: Option<Uuid>,
+    ) -> crate::errors::Result<()> {
+        let query = sqlx::query(
+            r#"INSERT INTO change_log (workspace_id, change_type, note_id, block_id)
+        VALUES ($1, $2, $3, $4)"#,
+        )
+        .bind(workspace_id)
+        .bind(change_type)
+        .bind(note_id)
+        .bind(block_id);
+        query.execute(conn).await?;
+        Ok(())
+    }
+
+    async fn create_in_note(
+        &self,
+        conn: &mut PgConnection,
+        change_type: ChangeType,
+        note_id: Uuid,
+        block_id: Option<Uuid>,
+    ) -> crate::errors::Result<()> {
+        let query = sqlx::query(
+            r#"INSERT INTO change_log (workspace_id, change_type, note_id, block_id)
+        SELECT workspace_id, $1, id, $3 FROM notes WHERE id = $2"#,
+        )
+        .bind(change_type)
+        .bind(note_id)
+        .bind(block_id);
+        query.execute(conn).await?;
+        Ok(())
+    }
+
+    async fn find_all_after(
+        &self,
+        workspace_ids: &[Uuid],
+        after: i64,
+        limit: i64,
+    ) -> crate::errors::Result<Vec<Change>> {
+        self.sequence().await?;
+        let changes = sqlx::query_as::<_, Change>(
+            r#"SELECT seq AS id, workspace_id, change_type, note_id, block_id, created_at
+        FROM change_log WHERE workspace_id = ANY($1) AND seq > $2
+        ORDER BY seq LIMIT $3"#,
+        )
+        .bind(workspace_ids)
+        .bind(after)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(changes)
+    }
+
+    async fn find_last_id(&self, workspace_ids: &[Uuid]) -> crate::errors::Result<i64> {
+        self.sequence().await?;
+        let id: Option<i64> =
+            sqlx::query_scalar(r#"SELECT max(seq) FROM change_log WHERE workspace_id = ANY($1)"#)
+                .bind(workspace_ids)
+                .fetch_one(&self.pool)
+                .await?;
+        Ok(id.unwrap_or(0))
+    }
+}
@@ -0,0 +1,237 @@
+use crate::{CollectionProperty, CollectionView, NotePropertyValue, PropertyValue};
+use async_trait::async_trait;
+use sqlx::types::Json;
+use uuid::Uuid;
+
+#[async_trait]
+pub trait CollectionRepo {
+    async fn create_property(&self, data: CollectionProperty) -> crate::errors::Result<()>;
+    async fn find_property(&self, id: Uuid) -> crate::errors::Result<Option<CollectionProperty>>;
+    async fn find_properties(
+        &self,
+        note_id: Uuid,
+    ) -> crate::errors::Result<Vec<CollectionProperty>>;
+    /// Saves the property. Values no longer among the options of a Select or
+    /// MultiSelect property are cleared with it.
+    async fn save_property(&self, data: CollectionProperty) -> crate::errors::Result<()>;
+    async fn delete_property(&self, id: Uuid) -> crate::errors::Result<()>;
+
+    async fn set_value(
+        &self,
+        note_id: Uuid,
+        property_id: Uuid,
+        value: PropertyValue,
+    ) -> crate::errors::Result<()>;
+    async fn delete_value(&self, note_id: Uuid, property_id: Uuid) -> crate::errors::Result<()>;
+    async fn find_values(&self, note_ids: &[Uuid])
+    -> crate::errors::Result<Vec<NotePropertyValue>>;
+
+    async fn create_view(&self, data: CollectionView) -> crate::errors::Result<()>;
+    async fn find_view(&self, id: Uuid) -> crate::errors::Result<Option<CollectionView>>;
+    async fn find_views(&self, note_id: Uuid) -> crate::errors::Result<Vec<CollectionView>>;
+    async fn save_view(&self, data: CollectionView) -> crate::errors::Result<()>;
+    async fn delete_view(&self, id: Uuid) -> crate::errors::Result<()>;
+}
+
+#[derive(Clone)]
+pub struct CollectionRepository {
+    pool: sqlx::PgPool,
+}
+
+impl CollectionRepository {
+    pub fn new(pool: sqlx::PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl CollectionRepo for CollectionRepository {
+    async fn create_property(&self, data: CollectionProperty) -> crate::errors::Result<()> {
+        sqlx::query(
+            r#"INSERT INTO collection_properties (id, note_id, name, property_type, options, position)
+        VALUES ($1, $2, $3, $4, $5, $6)"#,
+        )
+        .bind(data.id)
+        .bind(data.note_id)
+        .bind(data.name)
+        .bind(data.property_type)
+        .bind(data.options)
+        .bind(data.position)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn find_property(&self, id: Uuid) -> crate::errors::Result<Option<CollectionProperty>> {
+        let property = sqlx::query_as::<_, CollectionProperty>(
+            r#"SELECT * FROM collection_properties WHERE id = $1"#,
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(property)
+    }
+
+    async fn find_properties(
+        &self,
+        note_id: Uuid,
+    ) -> crate::errors::Result<Vec<CollectionProperty>> {
+        let properties = sqlx::query_as::<_, CollectionProperty>(
+            r#"SELECT * FROM collection_properties WHERE note_id = $1 ORDER BY position"#,
+        )
+        .bind(note_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(properties)
+    }
+
+    async fn save_property(&self, data: CollectionProperty) -> crate::errors::Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            r#"UPDATE collection_properties SET name = $1, options = $2, position = $3 WHERE id = $4"#,
+        )
+        .bind(data.name)
+        .bind(&data.options)
+        .bind(data.position)
+        .bind(data.id)
+        .execute(&mut *tx)
+        .await?;
+
+        if data.property_type.has_options() {
+            // MultiSelect values keep their remaining options in order
+            sqlx::query(
+                r#"UPDATE note_property_values SET value = (
+            SELECT COALESCE(jsonb_agg(item ORDER BY n), '[]'::jsonb)
+            FROM jsonb_array_elements_text(value) WITH ORDINALITY AS items(item, n)
+            WHERE item = ANY($2))
+        WHERE property_id = $1 AND jsonb_typeof(value) = 'array'"#,
+            )
+            .bind(data.id)
+            .bind(&data.options)
+            .execute(&mut *tx)
+            .await?;
+            sqlx::query(
+                r#"DELETE FROM note_property_values WHERE property_id = $1
+        AND ((jsonb_typeof(value) = 'string' AND NOT (value #>> '{}') = ANY($2))
+            OR value = '[]'::jsonb)"#,
+            )
+            .bind(data.id)
+            .bind(&data.options)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn delete_property(&self, id: Uuid) -> crate::errors::Result<()> {
+        sqlx::query(r#"DELETE FROM collection_properties WHERE id = $1"#)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn set_value(
+        &self,
+        note_id: Uuid,
+        property_id: Uuid,
+        value: PropertyValue,
+    ) -> crate::errors::Result<()> {
+        sqlx::query(
+            r#"INSERT INTO note_property_values (note_id, property_id, value) VALUES ($1, $2, $3)
+        ON CONFLICT (note_id, property_id) DO UPDATE SET value = EXCLUDED.value"#,
+        )
+        .bind(note_id)
+        .bind(property_id)
+        .bind(Json(value))
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn delete_value(&self, note_id: Uuid, property_id: Uuid) -> crate::errors::Result<()> {
+        sqlx::query(r#"DELETE FROM note_property_values WHERE note_id = $1 AND property_id = $2"#)
+            .bind(note_id)
+            .bind(property_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn find_values(
+        &self,
+        note_ids: &[Uuid],
+    ) -> crate::errors::Result<Vec<NotePropertyValue>> {
+        let values = sqlx::query_as::<_, NotePropertyValue>(
+            r#"SELECT * FROM note_property_values WHERE note_id = ANY($1)"#,
+        )
+        .bind(note_ids)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(values)
+    }
+
+    async fn create_view(&self, data: CollectionView) -> crate::errors::Result<()> {
+        sqlx::query(
+            r#"INSERT INTO collection_views (id, note_id, name, view_type, filters, sorts, group_by, position)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)"#,
+        )
+        .bind(data.id)
+        .bind(data.note_id)
+        .bind(data.name)
+        .bind(data.view_type)
+        .bind(data.filters)
+        .bind(data.sorts)
+        .bind(data.group_by)
+        .bind(data.position)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn find_view(&self, id: Uuid) -> crate::errors::Result<Option<CollectionView>> {
+        let view =
+            sqlx::query_as::<_, CollectionView>(r#"SELECT * FROM collection_views WHERE id = $1"#)
+                .bind(id)
+                .fetch_optional(&self.pool)
+                .await?;
+        Ok(view)
+    }
+
+    async fn find_views(&self, note_id: Uuid) -> crate::errors::Result<Vec<CollectionView>> {
+        let views = sqlx::query_as::<_, CollectionView>(
+            r#"SELECT * FROM collection_views WHERE note_id = $1 ORDER BY position"#,
+        )
+        .bind(note_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(views)
+    }
+
+    async fn save_view(&self, data: CollectionView) -> crate::errors::Result<()> {
+        sqlx::query(
+            r#"UPDATE collection_views SET name = $1, view_type = $2, filters = $3, sorts = $4, group_by = $5, position = $6
+        WHERE id = $7"#,
+        )
+        .bind(data.name)
+        .bind(data.view_type)
+        .bind(data.filters)
+        .bind(data.sorts)
+        .bind(data.group_by)
+        .bind(data.position)
+        .bind(data.id)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn delete_view(&self, id: Uuid) -> crate::errors::Result<()> {
+        sqlx::query(r#"DELETE FROM collection_views WHERE id = $1"#)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+}
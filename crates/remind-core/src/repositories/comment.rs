@@ -0,0 +1,86 @@
+use crate::Comment;
+use async_trait::async_trait;
+use uuid::Uuid;
+
+#[async_trait]
+pub trait CommentRepo {
+    async fn create(&self, data: Comment) -> crate::errors::Result<()>;
+    async fn find_one(&self, id: Uuid) -> crate::errors::Result<Option<Comment>>;
+    /// Comments of the note, oldest first
+    async fn find_all_in_note(&self, note_id: Uuid) -> crate::errors::Result<Vec<Comment>>;
+    async fn save(&self, data: Comment) -> crate::errors::Result<()>;
+    /// Deletes the comment, and its replies if it starts a thread
+    async fn delete(&self, id: Uuid) -> crate::errors::Result<()>;
+}
+
+#[derive(Clone)]
+pub struct CommentRepository {
+    pool: sqlx::PgPool,
+}
+
+impl CommentRepository {
+    pub fn new(pool: sqlx::PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl CommentRepo for CommentRepository {
+    async fn create(&self, data: Comment) -> crate::errors::Result<()> {
+        sqlx::query(
+            r#"INSERT INTO comments (id, note_id, block_id, thread_id, author_id, body, created_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)"#,
+        )
+        .bind(data.id)
+        .bind(data.note_id)
+        .bind(data.block_id)
+        .bind(data.thread_id)
+        .bind(data.author_id)
+        .bind(data.body)
+        .bind(data.created_at)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn find_one(&self, id: Uuid) -> crate::errors::Result<Option<Comment>> {
+        let comment = sqlx::query_as::<_, Comment>(r#"SELECT * FROM comments WHERE id = $1"#)
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(comment)
+    }
+
+    async fn find_all_in_note(&self, note_id: Uuid) -> crate::errors::Result<Vec<Comment>> {
+        let comments = sqlx::query_as::<_, Comment>(
+            r#"SELECT * FROM comments WHERE note_id = $1 ORDER BY created_at, id"#,
+        )
+        .bind(note_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(comments)
+    }
+
+    async fn save(&self, data: Comment) -> crate::errors::Result<()> {
+        sqlx::query(
+            r#"UPDATE comments SET body = $2, resolved_at = $3, resolved_by = $4, edited_at = $5
+        WHERE id = $1"#,
+        )
+        .bind(data.id)
+        .bind(data.body)
+        .bind(data.resolved_at)
+        .bind(data.resolved_by)
+        .bind(data.edited_at)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn delete(&self, id: Uuid) -> crate::errors::Result<()> {
+        sqlx::query(r#"DELETE FROM comments WHERE id = $1"#)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+}
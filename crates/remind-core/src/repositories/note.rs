@@ -1,14 +1,49 @@
-use crate::Note;
+use crate::{Note, NoteTemplate, Tag, TagMatch};
 use async_trait::async_trait;
+use sqlx::PgConnection;
+use sqlx::types::Json;
 use uuid::Uuid;
 
 #[async_trait]
 pub trait NoteRepo {
-    async fn create(&self, data: Note) -> crate::errors::Result<()>;
+    async fn create(&self, conn: &mut PgConnection, data: Note) -> crate::errors::Result<()>;
     async fn find_one(&self, id: Uuid) -> crate::errors::Result<Option<Note>>;
     async fn find_all_in_workspace(&self, workspace_id: Uuid) -> crate::errors::Result<Vec<Note>>;
-    async fn delete(&self, id: Uuid) -> crate::errors::Result<()>;
-    async fn save(&self, data: Note) -> crate::errors::Result<()>;
+    async fn find_all_children(&self, parent_note: Uuid) -> crate::errors::Result<Vec<Note>>;
+    async fn delete(&self, conn: &mut PgConnection, id: Uuid) -> crate::errors::Result<()>;
+    async fn save(&self, conn: &mut PgConnection, data: Note) -> crate::errors::Result<()>;
+    /// Saves the note only if it is still at `version`, returns whether it did
+    async fn save_if_version(
+        &self,
+        conn: &mut PgConnection,
+        data: Note,
+        version: i64,
+    ) -> crate::errors::Result<bool>;
+    /// Bumps the version of the note for changes stored outside the note row.
+    /// Only if it is still at `version` when given, returns whether it did.
+    async fn bump_version(
+        &self,
+        conn: &mut PgConnection,
+        id: Uuid,
+        version: Option<i64>,
+    ) -> crate::errors::Result<bool>;
+    /// Tags of the note by name
+    async fn find_tags(&self, id: Uuid) -> crate::errors::Result<Vec<Tag>>;
+    /// Notes of the workspace with any or all of the tags
+    async fn find_all_with_tags(
+        &self,
+        workspace_id: Uuid,
+        tags: Vec<Uuid>,
+        matching: TagMatch,
+    ) -> crate::errors::Result<Vec<Note>>;
+    async fn create_template(&self, data: NoteTemplate) -> crate::errors::Result<()>;
+    async fn find_template(&self, id: Uuid) -> crate::errors::Result<Option<NoteTemplate>>;
+    /// Templates of the workspace by name
+    async fn find_templates_in_workspace(
+        &self,
+        workspace_id: Uuid,
+    ) -> crate::errors::Result<Vec<NoteTemplate>>;
+    async fn delete_template(&self, id: Uuid) -> crate::errors::Result<()>;
 }
 
 #[derive(Clone)]
@@ -24,12 +59,12 @@ impl NoteRepository {
 
 #[async_trait]
 impl NoteRepo for NoteRepository {
-    async fn create(&self, data: Note) -> crate::errors::Result<()> {
+    async fn create(&self, conn: &mut PgConnection, data: Note) -> crate::errors::Result<()> {
         sqlx::query(
             "INSERT INTO notes(id, title, icon_type, icon_data, workspace_id, parent_note)  VALUES ($1, $2, $3, $4, $5, $6)",
         )
             .bind(data.id).bind(data.title).bind(data.icon_type).bind(data.icon_data).bind(data.workspace_id).bind(data.parent_note)
-        .execute(&self.pool)
+        .execute(conn)
         .await?;
         Ok(())
     }
@@ -51,16 +86,25 @@ impl NoteRepo for NoteRepository {
         Ok(notes)
     }
 
-    async fn delete(&self, id: Uuid) -> crate::errors::Result<()> {
+    async fn find_all_children(&self, parent_note: Uuid) -> crate::errors::Result<Vec<Note>> {
+        let notes = sqlx::query_as::<_, Note>(r#"SELECT * FROM notes WHERE parent_note = $1"#)
+            .bind(parent_note)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(notes)
+    }
+
+    async fn delete(&self, conn: &mut PgConnection, id: Uuid) -> crate::errors::Result<()> {
         sqlx::query!(r#"DELETE FROM notes WHERE id = $1"#, id)
-            .execute(&self.pool)
+            .execute(conn)
             .await?;
         Ok(())
     }
 
-    async fn save(&self, data: Note) -> crate::errors::Result<()> {
+    async fn save(&self, conn: &mut PgConnection, data: Note) -> crate::errors::Result<()> {
         sqlx::query(
-            r#"UPDATE notes SET title = $2, icon_type = $3, icon_data = $4, workspace_id = $5, parent_note = $6 WHERE id = $1"#,
+            r#"UPDATE notes SET title = $2, icon_type = $3, icon_data = $4, workspace_id = $5, parent_note = $6, version = version + 1 WHERE id = $1"#,
         )
             .bind(data.id)
             .bind(data.title)
@@ -68,8 +112,132 @@ impl NoteRepo for NoteRepository {
             .bind(data.icon_data)
             .bind(data.workspace_id)
             .bind(data.parent_note)
+        .execute(conn)
+        .await?;
+        Ok(())
+    }
+
+    async fn save_if_version(
+        &self,
+        conn: &mut PgConnection,
+        data: Note,
+        version: i64,
+    ) -> crate::errors::Result<bool> {
+        let result = sqlx::query(
+            r#"UPDATE notes SET title = $2, icon_type = $3, icon_data = $4, workspace_id = $5, parent_note = $6, version = version + 1
+        WHERE id = $1 AND version = $7"#,
+        )
+        .bind(data.id)
+        .bind(data.title)
+        .bind(data.icon_type)
+        .bind(data.icon_data)
+        .bind(data.workspace_id)
+        .bind(data.parent_note)
+        .bind(version)
+        .execute(conn)
+        .await?;
+        Ok(result.rows_affected() == 1)
+    }
+
+    async fn bump_version(
+        &self,
+        conn: &mut PgConnection,
+        id: Uuid,
+        version: Option<i64>,
+    ) -> crate::errors::Result<bool> {
+        let result = sqlx::query(
+            r#"UPDATE notes SET version = version + 1
+        WHERE id = $1 AND ($2::BIGINT IS NULL OR version = $2)"#,
+        )
+        .bind(id)
+        .bind(version)
+        .execute(conn)
+        .await?;
+        Ok(result.rows_affected() == 1)
+    }
+
+    async fn find_tags(&self, id: Uuid) -> crate::errors::Result<Vec<Tag>> {
+        let tags = sqlx::query_as::<_, Tag>(
+            r#"SELECT t.* FROM tags t
+        JOIN note_tags nt ON nt.tag_id = t.id
+        WHERE nt.note_id = $1 ORDER BY lower(t.name)"#,
+        )
+        .bind(id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(tags)
+    }
+
+    async fn find_all_with_tags(
+        &self,
+        workspace_id: Uuid,
+        tags: Vec<Uuid>,
+        matching: TagMatch,
+    ) -> crate::errors::Result<Vec<Note>> {
+        // Any tag is one match, all tags are as many matches as tags
+        let required = match matching {
+            TagMatch::Any => 1,
+            TagMatch::All => tags.len() as i64,
+        };
+        let notes = sqlx::query_as::<_, Note>(
+            r#"SELECT * FROM notes n WHERE n.workspace_id = $1
+        AND (SELECT count(*) FROM note_tags nt WHERE nt.note_id = n.id AND nt.tag_id = ANY($2)) >= $3"#,
+        )
+        .bind(workspace_id)
+        .bind(tags)
+        .bind(required)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(notes)
+    }
+
+    async fn create_template(&self, data: NoteTemplate) -> crate::errors::Result<()> {
+        sqlx::query(
+            r#"INSERT INTO note_templates (id, workspace_id, name, title, icon_type, icon_data, blocks, created_by, created_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)"#,
+        )
+        .bind(data.id)
+        .bind(data.workspace_id)
+        .bind(data.name)
+        .bind(data.title)
+        .bind(data.icon_type)
+        .bind(data.icon_data)
+        .bind(Json(&data.blocks.0))
+        .bind(data.created_by)
+        .bind(data.created_at)
         .execute(&self.pool)
         .await?;
         Ok(())
     }
+
+    async fn find_template(&self, id: Uuid) -> crate::errors::Result<Option<NoteTemplate>> {
+        let template =
+            sqlx::query_as::<_, NoteTemplate>(r#"SELECT * FROM note_templates WHERE id = $1"#)
+                .bind(id)
+                .fetch_optional(&self.pool)
+                .await?;
+        Ok(template)
+    }
+
+    async fn find_templates_in_workspace(
+        &self,
+        workspace_id: Uuid,
+    ) -> crate::errors::Result<Vec<NoteTemplate>> {
+        let templates = sqlx::query_as::<_, NoteTemplate>(
+            r#"SELECT * FROM note_templates WHERE workspace_id = $1 ORDER BY lower(name), created_at"#,
+        )
+        .bind(workspace_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(templates)
+    }
+
+    async fn delete_template(&self, id: Uuid) -> crate::errors::Result<()> {
+        sqlx::query(r#"DELETE FROM note_templates WHERE id = $1"#)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
 }
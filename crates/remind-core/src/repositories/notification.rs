@@ -0,0 +1,156 @@
+use crate::{Notification, NotificationKind};
+use async_trait::async_trait;
+use uuid::Uuid;
+
+#[async_trait]
+pub trait NotificationRepo {
+    /// Stores the notification unless its user doesn't exist or turned its
+    /// kind off. Returns whether it was stored.
+    async fn create(&self, data: Notification) -> crate::errors::Result<bool>;
+    /// Up to `limit` notifications of the user older than `before`, newest first
+    async fn find_page(
+        &self,
+        user_id: Uuid,
+        before: Option<Uuid>,
+        limit: i64,
+    ) -> crate::errors::Result<Vec<Notification>>;
+    async fn count_unread(&self, user_id: Uuid) -> crate::errors::Result<i64>;
+    /// Returns false when the user has no such notification
+    async fn mark_read(&self, id: Uuid, user_id: Uuid) -> crate::errors::Result<bool>;
+    /// Returns the number of notifications marked
+    async fn mark_all_read(&self, user_id: Uuid) -> crate::errors::Result<u64>;
+    /// Kinds the user chose about, with whether they notify
+    async fn find_preferences(
+        &self,
+        user_id: Uuid,
+    ) -> crate::errors::Result<Vec<(NotificationKind, bool)>>;
+    async fn save_preference(
+        &self,
+        user_id: Uuid,
+        kind: NotificationKind,
+        enabled: bool,
+    ) -> crate::errors::Result<()>;
+}
+
+#[derive(Clone)]
+pub struct NotificationRepository {
+    pool: sqlx::PgPool,
+}
+
+impl NotificationRepository {
+    pub fn new(pool: sqlx::PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl NotificationRepo for NotificationRepository {
+    async fn create(&self, data: Notification) -> crate::errors::Result<bool> {
+        let created = sqlx::query(
+            r#"INSERT INTO notifications (id, user_id, kind, actor_id, note_id, block_id, comment_id, created_at)
+        SELECT $1, $2, $3, $4, $5, $6, $7, $8
+        WHERE EXISTS (SELECT 1 FROM users WHERE id = $2)
+        AND NOT EXISTS (
+            SELECT 1 FROM notification_preferences
+            WHERE user_id = $2 AND kind = $3 AND NOT enabled
+        )"#,
+        )
+        .bind(data.id)
+        .bind(data.user_id)
+        .bind(data.kind)
+        .bind(data.actor_id)
+        .bind(data.note_id)
+        .bind(data.block_id)
+        .bind(data.comment_id)
+        .bind(data.created_at)
+        .execute(&self.pool)
+        .await?;
+        Ok(created.rows_affected() > 0)
+    }
+
+    async fn find_page(
+        &self,
+        user_id: Uuid,
+        before: Option<Uuid>,
+        limit: i64,
+    ) -> crate::errors::Result<Vec<Notification>> {
+        let notifications = sqlx::query_as::<_, Notification>(
+            r#"SELECT * FROM notifications n
+        WHERE n.user_id = $1
+        AND ($2::uuid IS NULL OR (n.created_at, n.id) < (
+            SELECT created_at, id FROM notifications WHERE id = $2 AND user_id = $1
+        ))
+        ORDER BY n.created_at DESC, n.id DESC
+        LIMIT $3"#,
+        )
+        .bind(user_id)
+        .bind(before)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(notifications)
+    }
+
+    async fn count_unread(&self, user_id: Uuid) -> crate::errors::Result<i64> {
+        let count = sqlx::query_scalar::<_, i64>(
+            r#"SELECT count(*) FROM notifications WHERE user_id = $1 AND read_at IS NULL"#,
+        )
+        .bind(user_id)
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(count)
+    }
+
+    async fn mark_read(&self, id: Uuid, user_id: Uuid) -> crate::errors::Result<bool> {
+        let marked = sqlx::query(
+            r#"UPDATE notifications SET read_at = coalesce(read_at, now())
+        WHERE id = $1 AND user_id = $2"#,
+        )
+        .bind(id)
+        .bind(user_id)
+        .execute(&self.pool)
+        .await?;
+        Ok(marked.rows_affected() > 0)
+    }
+
+    async fn mark_all_read(&self, user_id: Uuid) -> crate::errors::Result<u64> {
+        let marked = sqlx::query(
+            r#"UPDATE notifications SET read_at = now() WHERE user_id = $1 AND read_at IS NULL"#,
+        )
+        .bind(user_id)
+        .execute(&self.pool)
+        .await?;
+        Ok(marked.rows_affected())
+    }
+
+    async fn find_preferences(
+        &self,
+        user_id: Uuid,
+    ) -> crate::errors::Result<Vec<(NotificationKind, bool)>> {
+        let preferences = sqlx::query_as::<_, (NotificationKind, bool)>(
+            r#"SELECT kind, enabled FROM notification_preferences WHERE user_id = $1"#,
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(preferences)
+    }
+
+    async fn save_preference(
+        &self,
+        user_id: Uuid,
+        kind: NotificationKind,
+        enabled: bool,
+    ) -> crate::errors::Result<()> {
+        sqlx::query(
+            r#"INSERT INTO notification_preferences (user_id, kind, enabled) VALUES ($1, $2, $3)
+        ON CONFLICT (user_id, kind) DO UPDATE SET enabled = excluded.enabled"#,
+        )
+        .bind(user_id)
+        .bind(kind)
+        .bind(enabled)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,79 @@
+use crate::ShareLink;
+use async_trait::async_trait;
+use uuid::Uuid;
+
+#[async_trait]
+pub trait ShareLinkRepo {
+    async fn create(&self, data: ShareLink) -> crate::errors::Result<()>;
+    async fn find_one(&self, id: Uuid) -> crate::errors::Result<Option<ShareLink>>;
+    async fn find_one_by_slug(&self, slug: String) -> crate::errors::Result<Option<ShareLink>>;
+    async fn find_all_by_note(&self, note_id: Uuid) -> crate::errors::Result<Vec<ShareLink>>;
+    async fn delete(&self, id: Uuid) -> crate::errors::Result<()>;
+}
+
+#[derive(Clone)]
+pub struct ShareLinkRepository {
+    pool: sqlx::PgPool,
+}
+
+impl ShareLinkRepository {
+    pub fn new(pool: sqlx::PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl ShareLinkRepo for ShareLinkRepository {
+    async fn create(&self, data: ShareLink) -> crate::errors::Result<()> {
+        sqlx::query(
+            r#"INSERT INTO share_links (id, slug, note_id, created_by, password, include_subpages, expires_at, created_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)"#,
+        )
+        .bind(data.id)
+        .bind(data.slug)
+        .bind(data.note_id)
+        .bind(data.created_by)
+        .bind(data.password)
+        .bind(data.include_subpages)
+        .bind(data.expires_at)
+        .bind(data.created_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn find_one(&self, id: Uuid) -> crate::errors::Result<Option<ShareLink>> {
+        let link = sqlx::query_as::<_, ShareLink>(r#"SELECT * FROM share_links WHERE id = $1"#)
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(link)
+    }
+
+    async fn find_one_by_slug(&self, slug: String) -> crate::errors::Result<Option<ShareLink>> {
+        let link = sqlx::query_as::<_, ShareLink>(r#"SELECT * FROM share_links WHERE slug = $1"#)
+            .bind(slug)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(link)
+    }
+
+    async fn find_all_by_note(&self, note_id: Uuid) -> crate::errors::Result<Vec<ShareLink>> {
+        let links = sqlx::query_as::<_, ShareLink>(
+            r#"SELECT * FROM share_links WHERE note_id = $1 ORDER BY created_at"#,
+        )
+        .bind(note_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(links)
+    }
+
+    async fn delete(&self, id: Uuid) -> crate::errors::Result<()> {
+        sqlx::query(r#"DELETE FROM share_links WHERE id = $1"#)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,58 @@
+use async_trait::async_trait;
+use serde_json::Value;
+use uuid::Uuid;
+
+#[async_trait]
+pub trait SyncRepo {
+    async fn find_result(&self, user_id: Uuid, id: Uuid) -> crate::errors::Result<Option<Value>>;
+    /// Stores the result of an operation, returns false when it already had one
+    async fn create_result(
+        &self,
+        user_id: Uuid,
+        id: Uuid,
+        result: Value,
+    ) -> crate::errors::Result<bool>;
+}
+
+#[derive(Clone)]
+pub struct SyncRepository {
+    pool: sqlx::PgPool,
+}
+
+impl SyncRepository {
+    pub fn new(pool: sqlx::PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl SyncRepo for SyncRepository {
+    async fn find_result(&self, user_id: Uuid, id: Uuid) -> crate::errors::Result<Option<Value>> {
+        let result = sqlx::query_scalar(
+            r#"SELECT result FROM sync_operations WHERE user_id = $1 AND id = $2"#,
+        )
+        .bind(user_id)
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(result)
+    }
+
+    async fn create_result(
+        &self,
+        user_id: Uuid,
+        id: Uuid,
+        result: Value,
+    ) -> crate::errors::Result<bool> {
+        let created = sqlx::query(
+            r#"INSERT INTO sync_operations (id, user_id, result) VALUES ($1, $2, $3)
+        ON CONFLICT DO NOTHING"#,
+        )
+        .bind(id)
+        .bind(user_id)
+        .bind(result)
+        .execute(&self.pool)
+        .await?;
+        Ok(created.rows_affected() == 1)
+    }
+}
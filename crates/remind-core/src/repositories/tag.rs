@@ -0,0 +1,116 @@
+use crate::Tag;
+use async_trait::async_trait;
+use uuid::Uuid;
+
+#[async_trait]
+pub trait TagRepo {
+    async fn create(&self, data: Tag) -> crate::errors::Result<()>;
+    async fn find_one(&self, id: Uuid) -> crate::errors::Result<Option<Tag>>;
+    async fn find_one_by_name(
+        &self,
+        workspace_id: Uuid,
+        name: &str,
+    ) -> crate::errors::Result<Option<Tag>>;
+    async fn find_all_in_workspace(&self, workspace_id: Uuid) -> crate::errors::Result<Vec<Tag>>;
+    async fn save(&self, data: Tag) -> crate::errors::Result<()>;
+    async fn delete(&self, id: Uuid) -> crate::errors::Result<()>;
+    async fn add_to_note(&self, note_id: Uuid, tag_id: Uuid) -> crate::errors::Result<()>;
+    async fn remove_from_note(&self, note_id: Uuid, tag_id: Uuid) -> crate::errors::Result<()>;
+}
+
+#[derive(Clone)]
+pub struct TagRepository {
+    pool: sqlx::PgPool,
+}
+
+impl TagRepository {
+    pub fn new(pool: sqlx::PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl TagRepo for TagRepository {
+    async fn create(&self, data: Tag) -> crate::errors::Result<()> {
+        sqlx::query(r#"INSERT INTO tags (id, workspace_id, name, color) VALUES ($1, $2, $3, $4)"#)
+            .bind(data.id)
+            .bind(data.workspace_id)
+            .bind(data.name)
+            .bind(data.color)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn find_one(&self, id: Uuid) -> crate::errors::Result<Option<Tag>> {
+        let tag = sqlx::query_as::<_, Tag>(r#"SELECT * FROM tags WHERE id = $1"#)
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(tag)
+    }
+
+    async fn find_one_by_name(
+        &self,
+        workspace_id: Uuid,
+        name: &str,
+    ) -> crate::errors::Result<Option<Tag>> {
+        let tag = sqlx::query_as::<_, Tag>(
+            r#"SELECT * FROM tags WHERE workspace_id = $1 AND lower(name) = lower($2)"#,
+        )
+        .bind(workspace_id)
+        .bind(name)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(tag)
+    }
+
+    async fn find_all_in_workspace(&self, workspace_id: Uuid) -> crate::errors::Result<Vec<Tag>> {
+        let tags = sqlx::query_as::<_, Tag>(
+            r#"SELECT * FROM tags WHERE workspace_id = $1 ORDER BY lower(name)"#,
+        )
+        .bind(workspace_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(tags)
+    }
+
+    async fn save(&self, data: Tag) -> crate::errors::Result<()> {
+        sqlx::query(r#"UPDATE tags SET name = $2, color = $3 WHERE id = $1"#)
+            .bind(data.id)
+            .bind(data.name)
+            .bind(data.color)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn delete(&self, id: Uuid) -> crate::errors::Result<()> {
+        sqlx::query(r#"DELETE FROM tags WHERE id = $1"#)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn add_to_note(&self, note_id: Uuid, tag_id: Uuid) -> crate::errors::Result<()> {
+        sqlx::query(
+            r#"INSERT INTO note_tags (note_id, tag_id) VALUES ($1, $2) ON CONFLICT DO NOTHING"#,
+        )
+        .bind(note_id)
+        .bind(tag_id)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn remove_from_note(&self, note_id: Uuid, tag_id: Uuid) -> crate::errors::Result<()> {
+        sqlx::query(r#"DELETE FROM note_tags WHERE note_id = $1 AND tag_id = $2"#)
+            .bind(note_id)
+            .bind(tag_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+}
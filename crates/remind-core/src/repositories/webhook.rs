@@ -0,0 +1,218 @@
+use crate::{Webhook, WebhookDelivery};
+use async_trait::async_trait;
+use uuid::Uuid;
+
+#[async_trait]
+pub trait WebhookRepo {
+    async fn create(&self, data: Webhook) -> crate::errors::Result<()>;
+    async fn find_one(&self, id: Uuid) -> crate::errors::Result<Option<Webhook>>;
+    async fn find_all(&self) -> crate::errors::Result<Vec<Webhook>>;
+    async fn find_all_in_workspace(
+        &self,
+        workspace_id: Uuid,
+    ) -> crate::errors::Result<Vec<Webhook>>;
+    async fn delete(&self, id: Uuid) -> crate::errors::Result<()>;
+    /// Queues deliveries and moves the webhook from change `after` on to
+    /// `last_change_id`. Returns false without queueing anything when the
+    /// webhook has moved on in the meantime.
+    async fn enqueue(
+        &self,
+        id: Uuid,
+        after: i64,
+        last_change_id: i64,
+        deliveries: Vec<WebhookDelivery>,
+    ) -> crate::errors::Result<bool>;
+    async fn create_delivery(&self, data: WebhookDelivery) -> crate::errors::Result<()>;
+    /// Takes up to `limit` pending deliveries that are due. They aren't due
+    /// again for `lease_seconds`, so other workers leave them alone meanwhile.
+    /// Oldest first.
+    async fn claim_due(
+        &self,
+        limit: i64,
+        lease_seconds: i64,
+    ) -> crate::errors::Result<Vec<WebhookDelivery>>;
+    async fn save_delivery(&self, data: WebhookDelivery) -> crate::errors::Result<()>;
+    /// Deliveries of the webhook, newest first
+    async fn find_deliveries(
+        &self,
+        webhook_id: Uuid,
+        limit: i64,
+    ) -> crate::errors::Result<Vec<WebhookDelivery>>;
+}
+
+#[derive(Clone)]
+pub struct WebhookRepository {
+    pool: sqlx::PgPool,
+}
+
+impl WebhookRepository {
+    pub fn new(pool: sqlx::PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+const INSERT_DELIVERY: &str = r#"INSERT INTO webhook_deliveries
+    (id, webhook_id, event, payload, status, attempts, next_attempt_at, created_at)
+    VALUES ($1, $2, $3, $4, $5, $6, $7, $8)"#;
+
+fn insert_delivery(
+    data: WebhookDelivery,
+) -> sqlx::query::Query<'static, sqlx::Postgres, sqlx::postgres::PgArguments> {
+    sqlx::query(INSERT_DELIVERY)
+        .bind(data.id)
+        .bind(data.webhook_id)
+        .bind(data.event)
+        .bind(data.payload)
+        .bind(data.status)
+        .bind(data.attempts)
+        .bind(data.next_attempt_at)
+        .bind(data.created_at)
+}
+
+#[async_trait]
+impl WebhookRepo for WebhookRepository {
+    async fn create(&self, data: Webhook) -> crate::errors::Result<()> {
+        sqlx::query(
+            r#"INSERT INTO webhooks (id, workspace_id, url, secret, event_types, last_change_id, created_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)"#,
+        )
+        .bind(data.id)
+        .bind(data.workspace_id)
+        .bind(data.url)
+        .bind(data.secret)
+        .bind(data.event_types)
+        .bind(data.last_change_id)
+        .bind(data.created_at)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn find_one(&self, id: Uuid) -> crate::errors::Result<Option<Webhook>> {
+        let webhook = sqlx::query_as::<_, Webhook>(r#"SELECT * FROM webhooks WHERE id = $1"#)
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(webhook)
+    }
+
+    async fn find_all(&self) -> crate::errors::Result<Vec<Webhook>> {
+        let webhooks = sqlx::query_as::<_, Webhook>(r#"SELECT * FROM webhooks"#)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(webhooks)
+    }
+
+    async fn find_all_in_workspace(
+        &self,
+        workspace_id: Uuid,
+    ) -> crate::errors::Result<Vec<Webhook>> {
+        let webhooks = sqlx::query_as::<_, Webhook>(
+            r#"SELECT * FROM webhooks WHERE workspace_id = $1 ORDER BY created_at"#,
+        )
+        .bind(workspace_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(webhooks)
+    }
+
+    async fn delete(&self, id: Uuid) -> crate::errors::Result<()> {
+        sqlx::query(r#"DELETE FROM webhooks WHERE id = $1"#)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn enqueue(
+        &self,
+        id: Uuid,
+        after: i64,
+        last_change_id: i64,
+        deliveries: Vec<WebhookDelivery>,
+    ) -> crate::errors::Result<bool> {
+        let mut tx = self.pool.begin().await?;
+        let moved = sqlx::query(
+            r#"UPDATE webhooks SET last_change_id = $3 WHERE id = $1 AND last_change_id = $2"#,
+        )
+        .bind(id)
+        .bind(after)
+        .bind(last_change_id)
+        .execute(&mut *tx)
+        .await?;
+        if moved.rows_affected() == 0 {
+            return Ok(false);
+        }
+        for delivery in deliveries {
+            insert_delivery(delivery).execute(&mut *tx).await?;
+        }
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    async fn create_delivery(&self, data: WebhookDelivery) -> crate::errors::Result<()> {
+        insert_delivery(data).execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn claim_due(
+        &self,
+        limit: i64,
+        lease_seconds: i64,
+    ) -> crate::errors::Result<Vec<WebhookDelivery>> {
+        let deliveries = sqlx::query_as::<_, WebhookDelivery>(
+            r#"WITH claimed AS (
+            UPDATE webhook_deliveries
+            SET next_attempt_at = now() + make_interval(secs => $2)
+            WHERE id IN (
+                SELECT id FROM webhook_deliveries
+                WHERE status = 'Pending' AND next_attempt_at <= now()
+                ORDER BY next_attempt_at LIMIT $1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING *
+        )
+        SELECT * FROM claimed ORDER BY created_at, id"#,
+        )
+        .bind(limit)
+        .bind(lease_seconds as f64)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(deliveries)
+    }
+
+    async fn save_delivery(&self, data: WebhookDelivery) -> crate::errors::Result<()> {
+        sqlx::query(
+            r#"UPDATE webhook_deliveries
+        SET status = $2, attempts = $3, next_attempt_at = $4, last_status_code = $5,
+            last_error = $6, delivered_at = $7
+        WHERE id = $1"#,
+        )
+        .bind(data.id)
+        .bind(data.status)
+        .bind(data.attempts)
+        .bind(data.next_attempt_at)
+        .bind(data.last_status_code)
+        .bind(data.last_error)
+        .bind(data.delivered_at)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn find_deliveries(
+        &self,
+        webhook_id: Uuid,
+        limit: i64,
+    ) -> crate::errors::Result<Vec<WebhookDelivery>> {
+        let deliveries = sqlx::query_as::<_, WebhookDelivery>(
+            r#"SELECT * FROM webhook_deliveries WHERE webhook_id = $1
+        ORDER BY created_at DESC, id LIMIT $2"#,
+        )
+        .bind(webhook_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(deliveries)
+    }
+}
@@ -0,0 +1,215 @@
+use crate::errors::{CoreError, Result};
+use crate::images::{ImageDimensions, ImagePipeline, is_processable, process_image};
+use crate::storage::{BlobStore, sniff_content_type};
+use crate::{
+    Attachment, AttachmentCreateDTO, AttachmentDTO, AttachmentRepo, StorageUsageDTO, UploadLimits,
+};
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use std::sync::Arc;
+use uuid::Uuid;
+
+#[derive(Clone)]
+pub struct AttachmentService<R: AttachmentRepo> {
+    repo: R,
+    store: Arc<dyn BlobStore>,
+    limits: UploadLimits,
+}
+
+impl<R: AttachmentRepo> AttachmentService<R> {
+    pub fn new(repo: R, store: Arc<dyn BlobStore>, limits: UploadLimits) -> Self {
+        Self {
+            repo,
+            store,
+            limits,
+        }
+    }
+
+    pub async fn upload(&self, data: AttachmentCreateDTO) -> Result<AttachmentDTO> {
+        if data.data.len() > self.limits.max_file_size {
+            return Err(CoreError::FileTooLarge(self.limits.max_file_size));
+        }
+        let size = data.data.len() as i64;
+
+        let id = Uuid::new_v4();
+        let content_type = sniff_content_type(&data.data);
+        let storage_key = format!("{}/{}", data.workspace_id, id);
+        self.store
+            .put(&storage_key, data.data, &content_type)
+            .await?;
+
+        let attachment = Attachment {
+            id,
+            workspace_id: data.workspace_id,
+            user_id: data.user_id,
+            storage_key: storage_key.clone(),
+            file_name: data.file_name,
+            content_type,
+            size,
+            created_at: Utc::now(),
+            width: None,
+            height: None,
+            variants: Vec::new(),
+            variants_size: 0,
+            orphaned_at: None,
+        };
+        let processable = is_processable(&attachment.content_type);
+        let created = self.repo.create_within_quota(
+            attachment,
+            self.limits.user_quota,
+            self.limits.workspace_quota,
+        );
+        if let Err(e) = created.await {
+            self.store.delete(&storage_key).await?;
+            return Err(e);
+        }
+
+        if processable && let Err(e) = self.process_attachment(id).await {
+            self.delete(id).await?;
+            return Err(e);
+        }
+
+        self.find_one(id).await
+    }
+
+    pub async fn find_one(&self, id: Uuid) -> Result<AttachmentDTO> {
+        match self.repo.find_one(id).await? {
+            None => Err(CoreError::NotFound),
+            Some(a) => Ok(a.into()),
+        }
+    }
+
+    /// Returns the contents, or the smallest thumbnail at least `width` pixels wide
+    pub async fn get_content(
+        &self,
+        id: Uuid,
+        width: Option<u32>,
+    ) -> Result<(AttachmentDTO, Vec<u8>)> {
+        let attachment = match self.repo.find_one(id).await? {
+            None => return Err(CoreError::NotFound),
+            Some(a) => a,
+        };
+        let variant = width.and_then(|w| {
+            let mut variants = attachment.variants.clone();
+            variants.sort();
+            variants.into_iter().find(|v| *v as u32 >= w)
+        });
+        let key = match variant {
+            None => attachment.storage_key.clone(),
+            Some(v) => attachment.variant_key(v),
+        };
+        let data = match self.store.get(&key).await? {
+            None => return Err(CoreError::NotFound),
+            Some(d) => d,
+        };
+        Ok((attachment.into(), data))
+    }
+
+    pub async fn delete(&self, id: Uuid) -> Result<()> {
+        let attachment = match self.repo.find_one(id).await? {
+            None => return Err(CoreError::NotFound),
+            Some(a) => a,
+        };
+        self.delete_files(&attachment).await?;
+        self.repo.delete(id).await
+    }
+
+    async fn delete_files(&self, attachment: &Attachment) -> Result<()> {
+        for width in &attachment.variants {
+            self.store.delete(&attachment.variant_key(*width)).await?;
+        }
+        self.store.delete(&attachment.storage_key).await
+    }
+
+    pub async fn user_usage(&self, user_id: Uuid) -> Result<StorageUsageDTO> {
+        Ok(StorageUsageDTO {
+            used: self.repo.usage_by_user(user_id).await?,
+            quota: self.limits.user_quota,
+        })
+    }
+
+    pub async fn workspace_usage(&self, workspace_id: Uuid) -> Result<StorageUsageDTO> {
+        Ok(StorageUsageDTO {
+            used: self.repo.usage_by_workspace(workspace_id).await?,
+            quota: self.limits.workspace_quota,
+        })
+    }
+
+    /// Deletes attachments that no block or template has referenced for at
+    /// least `grace_period`. Returns the number of deleted attachments.
+    pub async fn collect_garbage(&self, grace_period: Duration) -> Result<usize> {
+        self.repo.mark_orphans().await?;
+        let orphans = self
+            .repo
+            .find_orphaned_before(Utc::now() - grace_period)
+            .await?;
+
+        let mut count = 0;
+        for orphan in orphans {
+            // Files are only deleted with the row, an orphan may be referenced again
+            let Some(attachment) = self.repo.delete_if_unreferenced(orphan.id).await? else {
+                continue;
+            };
+            self.delete_files(&attachment).await?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// Strips metadata, generates thumbnails and records the dimensions of an
+    /// uploaded image. Already processed attachments are left untouched.
+    pub async fn process_attachment(&self, attachment_id: Uuid) -> Result<Option<ImageDimensions>> {
+        let mut attachment = match self.repo.find_one(attachment_id).await? {
+            None => return Err(CoreError::NotFound),
+            Some(a) => a,
+        };
+        if let (Some(width), Some(height)) = (attachment.width, attachment.height) {
+            return Ok(Some(ImageDimensions {
+                width: width as u32,
+                height: height as u32,
+            }));
+        }
+        if !is_processable(&attachment.content_type) {
+            return Ok(None);
+        }
+
+        let data = match self.store.get(&attachment.storage_key).await? {
+            None => return Err(CoreError::NotFound),
+            Some(d) => d,
+        };
+        let content_type = attachment.content_type.clone();
+        let processed = tokio::task::spawn_blocking(move || process_image(&data, &content_type))
+            .await
+            .map_err(|_| CoreError::ServerError)??;
+
+        if let Some(stripped) = processed.stripped {
+            attachment.size = stripped.len() as i64;
+            self.store
+                .put(&attachment.storage_key, stripped, &attachment.content_type)
+                .await?;
+        }
+        for (width, data) in processed.variants {
+            attachment.variants_size += data.len() as i64;
+            self.store
+                .put(
+                    &attachment.variant_key(width as i32),
+                    data,
+                    &attachment.content_type,
+                )
+                .await?;
+            attachment.variants.push(width as i32);
+        }
+        attachment.width = Some(processed.dimensions.width as i32);
+        attachment.height = Some(processed.dimensions.height as i32);
+        self.repo.save(attachment).await?;
+
+        Ok(Some(processed.dimensions))
+    }
+}
+
+#[async_trait]
+impl<R: AttachmentRepo + Send + Sync> ImagePipeline for AttachmentService<R> {
+    async fn process(&self, attachment_id: Uuid) -> Result<Option<ImageDimensions>> {
+        self.process_attachment(attachment_id).await
+    }
+}
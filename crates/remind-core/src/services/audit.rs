@@ -0,0 +1,56 @@
+use crate::audit::AuditSink;
+use crate::errors::Result;
+use crate::{AuditEntryDTO, AuditEvent, AuditFilter, AuditRepo, MAX_AUDIT_ENTRIES_PER_PAGE};
+use async_trait::async_trait;
+use uuid::Uuid;
+
+/// Append-only log of logins and of sensitive changes to workspaces
+#[derive(Clone)]
+pub struct AuditService<R: AuditRepo> {
+    repo: R,
+}
+
+impl<R: AuditRepo> AuditService<R> {
+    pub fn new(repo: R) -> Self {
+        Self { repo }
+    }
+
+    /// Entries of the workspace matching the filter, newest first
+    pub async fn get_in_workspace(
+        &self,
+        workspace_id: Uuid,
+        filter: AuditFilter,
+        limit: i64,
+    ) -> Result<Vec<AuditEntryDTO>> {
+        let entries = self
+            .repo
+            .find_in_workspace(
+                workspace_id,
+                filter,
+                limit.clamp(1, MAX_AUDIT_ENTRIES_PER_PAGE),
+            )
+            .await?;
+        Ok(entries.into_iter().map(AuditEntryDTO::from).collect())
+    }
+
+    /// Logins and tokens of the user, which belong to no workspace
+    pub async fn get_for_user(
+        &self,
+        user_id: Uuid,
+        filter: AuditFilter,
+        limit: i64,
+    ) -> Result<Vec<AuditEntryDTO>> {
+        let entries = self
+            .repo
+            .find_for_user(user_id, filter, limit.clamp(1, MAX_AUDIT_ENTRIES_PER_PAGE))
+            .await?;
+        Ok(entries.into_iter().map(AuditEntryDTO::from).collect())
+    }
+}
+
+#[async_trait]
+impl<R: AuditRepo + Send + Sync> AuditSink for AuditService<R> {
+    async fn record(&self, event: AuditEvent) -> Result<()> {
+        self.repo.create(event).await
+    }
+}
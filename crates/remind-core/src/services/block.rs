@@ -1,39 +1,140 @@
+use crate::audit::{self, AuditSink};
+use crate::diagrams::{SvgRenderer, validate_latex, validate_mermaid};
+use crate::dto::block::{blocks_to_dtos, find_block};
 use crate::errors::{CoreError, Result};
-use crate::{Block, BlockCreateDTO, BlockDTO, BlockRepo, BlockUpdateDTO};
+use crate::images::ImagePipeline;
+use crate::notifications::{Notifier, new_user_mentions};
+use crate::previews::LinkPreviewer;
+use crate::templates::{TemplateMentions, TemplateWriter};
+use crate::{
+    AttachmentDTO, AuditAction, AuditContext, AuditEvent, Block, BlockContent, BlockCreateDTO,
+    BlockDTO, BlockRepo, BlockType, BlockUpdateDTO, CellValue, ChangeRepo, ChangeType, CharId,
+    CodeContent, ImageContent, LinkPreview, MAX_HEADING_LEVEL, MAX_LIST_INDENT, Mention, Note,
+    NotificationCreateDTO, NotificationKind, PlainTextContent, SERVER_REPLICA, TableContent,
+    TableRow, TemplateBlock, TemplateVariables, TextCrdt, TextOp, TextSpan, normalize_spans,
+};
+use async_trait::async_trait;
+use serde_json::json;
+use sqlx::PgConnection;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
 use uuid::Uuid;
 
+/// Link schemes allowed in rich text
+const ALLOWED_LINK_SCHEMES: [&str; 3] = ["http", "https", "mailto"];
+
+/// Times a text merge is retried when other writes of the block race it
+const MAX_MERGE_ATTEMPTS: usize = 5;
+
+/// Times a change made on the stored block is redone when other writes of
+/// the block race it
+const MAX_SAVE_ATTEMPTS: usize = 5;
+
 #[derive(Clone)]
-pub struct BlockService<R: BlockRepo> {
+pub struct BlockService<R: BlockRepo, C: ChangeRepo> {
     repo: R,
+    changes: C,
+    images: Option<Arc<dyn ImagePipeline>>,
+    previews: Option<Arc<dyn LinkPreviewer>>,
+    math: Option<Arc<dyn SvgRenderer>>,
+    diagrams: Option<Arc<dyn SvgRenderer>>,
+    notifier: Option<Arc<dyn Notifier>>,
+    audit: Option<Arc<dyn AuditSink>>,
 }
 
-impl<R: BlockRepo> BlockService<R> {
-    pub fn new(repo: R) -> Self {
-        Self { repo }
+impl<R: BlockRepo, C: ChangeRepo> BlockService<R, C> {
+    pub fn new(repo: R, changes: C) -> Self {
+        Self {
+            repo,
+            changes,
+            images: None,
+            previews: None,
+            math: None,
+            diagrams: None,
+            notifier: None,
+            audit: None,
+        }
+    }
+
+    /// Enables processing of uploaded images referenced by Image blocks
+    pub fn with_image_pipeline(mut self, images: Arc<dyn ImagePipeline>) -> Self {
+        self.images = Some(images);
+        self
+    }
+
+    /// Enables fetching previews of Bookmark blocks
+    pub fn with_link_previewer(mut self, previews: Arc<dyn LinkPreviewer>) -> Self {
+        self.previews = Some(previews);
+        self
+    }
+
+    /// Enables pre-rendering Math blocks to SVG
+    pub fn with_math_renderer(mut self, math: Arc<dyn SvgRenderer>) -> Self {
+        self.math = Some(math);
+        self
+    }
+
+    /// Enables pre-rendering Diagram blocks to SVG
+    pub fn with_diagram_renderer(mut self, diagrams: Arc<dyn SvgRenderer>) -> Self {
+        self.diagrams = Some(diagrams);
+        self
+    }
+
+    /// Enables notifying users mentioned in blocks
+    pub fn with_notifier(mut self, notifier: Arc<dyn Notifier>) -> Self {
+        self.notifier = Some(notifier);
+        self
+    }
+
+    /// Enables recording deletes in the audit log
+    pub fn with_audit_sink(mut self, audit: Arc<dyn AuditSink>) -> Self {
+        self.audit = Some(audit);
+        self
     }
 
     pub async fn create(&self, data: BlockCreateDTO) -> Result<BlockDTO> {
-        let current_blocks = self.get_all_in_note(data.note_id).await?;
-        let position;
-        if current_blocks.len() == 0 {
-            position = 0
-        } else {
-            position = current_blocks.last().unwrap().position + 1
+        self.create_with_id(Uuid::new_v4(), data).await
+    }
+
+    /// Creates a block with an id chosen by the client, like one made offline
+    pub async fn create_with_id(&self, id: Uuid, data: BlockCreateDTO) -> Result<BlockDTO> {
+        if self.repo.find_one(id).await?.is_some() {
+            return Err(CoreError::AlreadyExists);
         }
+        let note_blocks = self.get_all_in_note(data.note_id).await?;
+        let siblings = find_children(&note_blocks, data.parent_block).ok_or_else(|| {
+            CoreError::InvalidBlockParent("Parent must be a toggle in the same note".to_string())
+        })?;
+        let position = match siblings.last() {
+            None => 0,
+            Some(last) => last.position + 1,
+        };
 
         if !data.block_type.is_matching_content_type(&data.content) {
             return Err(CoreError::BlockTypeNotMatches);
         }
+        let mut content = normalize_content(data.content);
+        validate_content(&content)?;
+        self.check_references(&content, data.note_id).await?;
+        fit_list_indent(&mut content, siblings.last().map(|b| &b.content));
 
-        let id = Uuid::new_v4();
+        let links = content.mentions();
         let block = Block {
             id,
             block_type: data.block_type,
-            content: data.content,
+            content: self.process_content(content, None).await?,
             note_id: data.note_id,
+            parent_block: data.parent_block,
             position,
+            version: 1,
         };
-        self.repo.create(block).await?;
+        let mut tx = self.changes.begin().await?;
+        self.repo.create(&mut tx, block).await?;
+        self.repo.save_links(&mut tx, id, links.clone()).await?;
+        self.log(&mut tx, ChangeType::BlockCreated, data.note_id, id)
+            .await?;
+        tx.commit().await?;
+        self.notify_mentions(data.note_id, id, &[], &links).await?;
 
         let dto = self.find_one(id).await?;
         Ok(dto)
@@ -47,19 +148,99 @@ impl<R: BlockRepo> BlockService<R> {
     }
 
     pub async fn get_all_in_note(&self, note_id: Uuid) -> Result<Vec<BlockDTO>> {
-        let blocks = self
-            .repo
-            .find_all_in_note(note_id)
-            .await?
-            .iter()
-            .map(|b| BlockDTO::from(b.clone()))
-            .collect();
+        let blocks = blocks_to_dtos(self.repo.find_all_in_note(note_id).await?);
         Ok(blocks)
     }
 
-    pub async fn delete(&self, id: Uuid) -> Result<()> {
-        self.repo.delete(id).await?;
-        Ok(())
+    /// Deletes a block. The children of a toggle are deleted with it, unless
+    /// `promote_children` is set, then they take the place of the toggle.
+    pub async fn delete(
+        &self,
+        id: Uuid,
+        promote_children: bool,
+        context: &AuditContext,
+    ) -> Result<()> {
+        let block = match self.repo.find_one(id).await? {
+            None => return Err(CoreError::NotFound),
+            Some(b) => b,
+        };
+        let mut tx = self.changes.begin().await?;
+        if promote_children {
+            let note_blocks = self.repo.find_all_in_note(block.note_id).await?;
+            let mut children: Vec<Block> = note_blocks
+                .iter()
+                .filter(|b| b.parent_block == Some(id))
+                .cloned()
+                .collect();
+
+            if !children.is_empty() {
+                let mut siblings = Vec::new();
+                for sibling in note_blocks
+                    .into_iter()
+                    .filter(|b| b.parent_block == block.parent_block)
+                {
+                    if sibling.id == id {
+                        siblings.append(&mut children);
+                    } else {
+                        siblings.push(sibling);
+                    }
+                }
+                for (position, mut sibling) in siblings.into_iter().enumerate() {
+                    sibling.parent_block = block.parent_block;
+                    sibling.position = position as i32;
+                    self.repo.save(&mut tx, sibling).await?;
+                }
+            }
+        }
+
+        self.repo.delete(&mut tx, id).await?;
+        self.log(&mut tx, ChangeType::BlockDeleted, block.note_id, id)
+            .await?;
+        tx.commit().await?;
+
+        let mut event = AuditEvent::new(AuditAction::BlockDeleted, context);
+        event.note_id = Some(block.note_id);
+        event.target_id = Some(id);
+        event.details = json!({
+            "block_type": block.block_type,
+            "promote_children": promote_children,
+        });
+        audit::record(&self.audit, event).await
+    }
+
+    /// Moves a block with its children to the end of a toggle,
+    /// or of the top level when `parent_block` is `None`
+    pub async fn move_block(&self, id: Uuid, parent_block: Option<Uuid>) -> Result<BlockDTO> {
+        for _ in 0..MAX_SAVE_ATTEMPTS {
+            let mut block = match self.repo.find_one(id).await? {
+                None => return Err(CoreError::NotFound),
+                Some(b) => b,
+            };
+            let note_blocks = self.get_all_in_note(block.note_id).await?;
+
+            if let Some(parent) = parent_block {
+                let moved = find_block(&note_blocks, id).map_or(&[][..], |b| &b.children);
+                if parent == id || find_block(moved, parent).is_some() {
+                    return Err(CoreError::InvalidBlockParent(
+                        "A block can't be moved into itself".to_string(),
+                    ));
+                }
+            }
+            let siblings = find_children(&note_blocks, parent_block).ok_or_else(|| {
+                CoreError::InvalidBlockParent(
+                    "Parent must be a toggle in the same note".to_string(),
+                )
+            })?;
+            let last = siblings.iter().rev().find(|b| b.id != id);
+
+            block.position = last.map_or(0, |b| b.position + 1);
+            block.parent_block = parent_block;
+            fit_list_indent(&mut block.content, last.map(|b| &b.content));
+            if self.save_if_unchanged(block).await? {
+                return self.find_one(id).await;
+            }
+        }
+        Err(CoreError::ConcurrentEdits)
     }
 
     pub async fn save(&self, block: BlockDTO) -> Result<()> {
@@ -68,14 +249,30 @@ impl<R: BlockRepo> BlockService<R> {
             return Err(CoreError::NotFound);
         }
 
+        let previous = b.unwrap();
+        let (links, note_id) = (block.content.mentions(), previous.note_id);
+        let mut tx = self.changes.begin().await?;
         self.repo
-            .save(Block {
-                id: block.id,
-                block_type: block.block_type,
-                content: block.content,
-                position: block.position,
-                note_id: b.unwrap().note_id,
-            })
+            .save(
+                &mut tx,
+                Block {
+                    id: block.id,
+                    block_type: block.block_type,
+                    content: block.content,
+                    position: block.position,
+                    parent_block: block.parent_block,
+                    note_id,
+                    version: block.version,
+                },
+            )
+            .await?;
+        self.repo
+            .save_links(&mut tx, block.id, links.clone())
+            .await?;
+        self.log(&mut tx, ChangeType::BlockUpdated, note_id, block.id)
+            .await?;
+        tx.commit().await?;
+        self.notify_mentions(note_id, block.id, &previous.content.mentions(), &links)
             .await
     }
 
@@ -84,21 +281,625 @@ impl<R: BlockRepo> BlockService<R> {
             None => return Err(CoreError::NotFound),
             Some(b) => b,
         };
+        // Checked again when saving, this only saves the work on the content
+        if data.version.is_some_and(|v| v != block.version) {
+            return Err(CoreError::VersionMismatch);
+        }
 
+        let was_toggle = block.block_type == BlockType::Toggle;
+        let mentioned = block.content.mentions();
         if let Some(block_type) = data.block_type {
             block.block_type = block_type
         }
 
         if let Some(content) = data.content {
-            block.content = content
+            let mut content = normalize_content(content);
+            validate_content(&content)?;
+            if matches!(&content, BlockContent::Synced(c) if c.source_block == block.id) {
+                return Err(CoreError::InvalidBlockContent(
+                    "A block can't mirror itself".to_string(),
+                ));
+            }
+            self.check_references(&content, block.note_id).await?;
+            if content.list_indent().is_some() {
+                let blocks = self.get_all_in_note(block.note_id).await?;
+                let siblings = find_children(&blocks, block.parent_block).unwrap_or_default();
+                let previous = siblings.iter().rev().find(|b| b.position < block.position);
+                fit_list_indent(&mut content, previous.map(|b| &b.content));
+            }
+            block.content = self.process_content(content, Some(&block.content)).await?
         }
 
         if !block.block_type.is_matching_content_type(&block.content) {
             return Err(CoreError::BlockTypeNotMatches);
         }
+        if was_toggle && block.block_type != BlockType::Toggle {
+            let note_blocks = self.repo.find_all_in_note(block.note_id).await?;
+            if note_blocks.iter().any(|b| b.parent_block == Some(block.id)) {
+                return Err(CoreError::InvalidBlockParent(
+                    "Move or delete the children of the toggle first".to_string(),
+                ));
+            }
+        }
+
+        let (id, note_id, links) = (block.id, block.note_id, block.content.mentions());
+        let mut tx = self.changes.begin().await?;
+        match data.version {
+            None => self.repo.save(&mut tx, block).await?,
+            Some(version) => {
+                if !self.repo.save_if_version(&mut tx, block, version).await? {
+                    return Err(CoreError::VersionMismatch);
+                }
+            }
+        }
+        self.repo.save_links(&mut tx, id, links.clone()).await?;
+        self.log(&mut tx, ChangeType::BlockUpdated, note_id, id)
+            .await?;
+        tx.commit().await?;
+        self.notify_mentions(note_id, id, &mentioned, &links)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Text CRDT of a PlainText or Code block, for clients to address
+    /// characters in text operations
+    pub async fn get_text_state(&self, id: Uuid) -> Result<TextCrdt> {
+        let block = match self.repo.find_one(id).await? {
+            None => return Err(CoreError::NotFound),
+            Some(b) => b,
+        };
+        let (state, _, _) = self.load_text_state(&block).await?;
+        Ok(state)
+    }
+
+    /// Merges text operations into a PlainText or Code block. Returns the
+    /// block and the operations that changed its text, for the other replicas.
+    pub async fn merge_text(&self, id: Uuid, ops: Vec<TextOp>) -> Result<(BlockDTO, Vec<TextOp>)> {
+        for _ in 0..MAX_MERGE_ATTEMPTS {
+            let mut block = match self.repo.find_one(id).await? {
+                None => return Err(CoreError::NotFound),
+                Some(b) => b,
+            };
+            let (mut state, version, mut applied) = self.load_text_state(&block).await?;
+
+            let (old_ids, mentioned) = (state.visible_ids(), block.content.mentions());
+            for op in &ops {
+                if state.apply(op).map_err(CoreError::TextOutOfSync)? {
+                    applied.push(op.clone());
+                }
+            }
+            block.content = merged_content(block.content, &old_ids, &state);
+            validate_content(&block.content)?;
+
+            let (note_id, links) = (block.note_id, block.content.mentions());
+            let mut tx = self.changes.begin().await?;
+            if self.repo.save_text(&mut tx, block, &state, version).await? {
+                self.repo.save_links(&mut tx, id, links.clone()).await?;
+                self.log(&mut tx, ChangeType::BlockUpdated, note_id, id)
+                    .await?;
+                tx.commit().await?;
+                self.notify_mentions(note_id, id, &mentioned, &links)
+                    .await?;
+                return Ok((self.find_one(id).await?, applied));
+            }
+            tx.rollback().await?;
+        }
+        Err(CoreError::TextOutOfSync(
+            "Too many concurrent edits, try again".to_string(),
+        ))
+    }
+
+    /// Stored text state of the block with its version. Plain updates replace
+    /// the content without touching the state, the returned operations bring
+    /// it up to date.
+    async fn load_text_state(&self, block: &Block) -> Result<(TextCrdt, Option<i64>, Vec<TextOp>)> {
+        let text = block.content.mergeable_text().ok_or_else(|| {
+            CoreError::InvalidBlockContent(
+                "Only PlainText and Code blocks take text operations".to_string(),
+            )
+        })?;
+        let (mut state, version) = match self.repo.find_text_state(block.id).await? {
+            None => (TextCrdt::from_text(&text), None),
+            Some((state, version)) => (state, Some(version)),
+        };
+
+        let ops = match state.text() == text {
+            true => Vec::new(),
+            false => state.replace(SERVER_REPLICA, &text),
+        };
+        Ok((state, version, ops))
+    }
+
+    /// Sets a cell of a Table block, `None` clears it
+    pub async fn update_table_cell(
+        &self,
+        id: Uuid,
+        row_id: Uuid,
+        column_id: Uuid,
+        value: Option<CellValue>,
+    ) -> Result<BlockDTO> {
+        self.update_table(id, |table| {
+            match &value {
+                Some(value) => table.validate_cell(column_id, value),
+                None if table.column(column_id).is_none() => {
+                    Err(format!("Unknown column {column_id}"))
+                }
+                None => Ok(()),
+            }
+            .map_err(CoreError::InvalidBlockContent)?;
+
+            let row = table.row_mut(row_id).ok_or(CoreError::NotFound)?;
+            match &value {
+                Some(value) => row.cells.insert(column_id, value.clone()),
+                None => row.cells.remove(&column_id),
+            };
+            Ok(())
+        })
+        .await
+    }
+
+    /// Appends a row to a Table block
+    pub async fn add_table_row(
+        &self,
+        id: Uuid,
+        cells: BTreeMap<Uuid, CellValue>,
+    ) -> Result<BlockDTO> {
+        self.update_table(id, |table| {
+            for (column_id, value) in &cells {
+                table
+                    .validate_cell(*column_id, value)
+                    .map_err(CoreError::InvalidBlockContent)?;
+            }
+            table.rows.push(TableRow {
+                id: Uuid::new_v4(),
+                cells: cells.clone(),
+            });
+            Ok(())
+        })
+        .await
+    }
+
+    pub async fn delete_table_row(&self, id: Uuid, row_id: Uuid) -> Result<BlockDTO> {
+        self.update_table(id, |table| {
+            let before = table.rows.len();
+            table.rows.retain(|r| r.id != row_id);
+            if table.rows.len() == before {
+                return Err(CoreError::NotFound);
+            }
+            Ok(())
+        })
+        .await
+    }
+
+    /// Applies the change to the stored table, again on the latest one when
+    /// the block was saved by someone else in between
+    async fn update_table(
+        &self,
+        id: Uuid,
+        change: impl Fn(&mut TableContent) -> Result<()>,
+    ) -> Result<BlockDTO> {
+        for _ in 0..MAX_SAVE_ATTEMPTS {
+            let mut block = match self.repo.find_one(id).await? {
+                None => return Err(CoreError::NotFound),
+                Some(b) => b,
+            };
+            let BlockContent::Table(table) = &mut block.content else {
+                return Err(CoreError::InvalidBlockContent(
+                    "Block is not a table".to_string(),
+                ));
+            };
+
+            change(table)?;
+            if self.save_if_unchanged(block).await? {
+                return self.find_one(id).await;
+            }
+        }
+        Err(CoreError::ConcurrentEdits)
+    }
+
+    /// Fetches the preview of a Bookmark block again
+    pub async fn refresh_preview(&self, id: Uuid) -> Result<BlockDTO> {
+        let mut fetched: Option<(String, LinkPreview)> = None;
+        for _ in 0..MAX_SAVE_ATTEMPTS {
+            let mut block = match self.repo.find_one(id).await? {
+                None => return Err(CoreError::NotFound),
+                Some(b) => b,
+            };
+            let BlockContent::Bookmark(bookmark) = &mut block.content else {
+                return Err(CoreError::InvalidBlockContent(
+                    "Block is not a bookmark".to_string(),
+                ));
+            };
+
+            // The preview is only fetched again when the link changed meanwhile
+            let preview = match fetched.take() {
+                Some((url, preview)) if url == bookmark.url => preview,
+                _ => self.fetch_preview(&bookmark.url).await?,
+            };
+            bookmark.preview = Some(preview.clone());
+            fetched = Some((bookmark.url.clone(), preview));
+            if self.save_if_unchanged(block).await? {
+                return self.find_one(id).await;
+            }
+        }
+        Err(CoreError::ConcurrentEdits)
+    }
+
+    /// Checks the references of the content of a block of the note, see
+    /// [`Self::check_workspace_references`]
+    async fn check_references(&self, content: &BlockContent, note_id: Uuid) -> Result<()> {
+        if content.attachment_id().is_none() && !matches!(content, BlockContent::Synced(_)) {
+            return Ok(());
+        }
+        let workspace_id = self.repo.find_note_workspace(note_id).await?;
+        let workspace_id = workspace_id.ok_or(CoreError::NotFound)?;
+        self.check_workspace_references(content, workspace_id).await
+    }
+
+    /// Checks that attachments and synced sources referenced by the content
+    /// are in the workspace, that images reference image files and that
+    /// synced blocks don't mirror other synced blocks
+    async fn check_workspace_references(
+        &self,
+        content: &BlockContent,
+        workspace_id: Uuid,
+    ) -> Result<()> {
+        if let Some(id) = content.attachment_id() {
+            let attachment = match self.repo.find_attachment(id).await? {
+                Some(a) if a.workspace_id == workspace_id => AttachmentDTO::from(a),
+                _ => return Err(CoreError::NotFound),
+            };
+            if matches!(content, BlockContent::Image(_)) && !attachment.is_image() {
+                return Err(CoreError::NotAnImage);
+            }
+        }
+        if let BlockContent::Synced(c) = content {
+            let source = match self.repo.find_one(c.source_block).await? {
+                None => return Err(CoreError::NotFound),
+                Some(b) => b,
+            };
+            if self.repo.find_note_workspace(source.note_id).await? != Some(workspace_id) {
+                return Err(CoreError::NotFound);
+            }
+            if matches!(source.content, BlockContent::Synced(_)) {
+                return Err(CoreError::InvalidBlockContent(
+                    "A synced block can't mirror another synced block".to_string(),
+                ));
+            }
+        }
+        Ok(())
+    }
 
-        self.repo.save(block).await?;
+    /// Saves a block changed since it was read, unless it was saved by
+    /// someone else in between. Returns whether it did.
+    async fn save_if_unchanged(&self, block: Block) -> Result<bool> {
+        let (id, note_id, version) = (block.id, block.note_id, block.version);
+        let mut tx = self.changes.begin().await?;
+        if !self.repo.save_if_version(&mut tx, block, version).await? {
+            tx.rollback().await?;
+            return Ok(false);
+        }
+        self.log(&mut tx, ChangeType::BlockUpdated, note_id, id)
+            .await?;
+        tx.commit().await?;
+        Ok(true)
+    }
 
+    /// Notifies the users mentioned in `after` that weren't in `before`
+    async fn notify_mentions(
+        &self,
+        note_id: Uuid,
+        id: Uuid,
+        before: &[Mention],
+        after: &[Mention],
+    ) -> Result<()> {
+        let Some(notifier) = &self.notifier else {
+            return Ok(());
+        };
+        for user_id in new_user_mentions(before, after) {
+            notifier
+                .notify(NotificationCreateDTO {
+                    user_id,
+                    kind: NotificationKind::Mention,
+                    actor_id: None,
+                    note_id: Some(note_id),
+                    block_id: Some(id),
+                    comment_id: None,
+                })
+                .await?;
+        }
         Ok(())
     }
+
+    /// Adds a change of a block to the change log of its workspace
+    async fn log(
+        &self,
+        conn: &mut PgConnection,
+        change_type: ChangeType,
+        note_id: Uuid,
+        id: Uuid,
+    ) -> Result<()> {
+        self.changes
+            .create_in_note(conn, change_type, note_id, Some(id))
+            .await
+    }
+
+    /// Fills in data only the server knows, `previous` is the stored content
+    async fn process_content(
+        &self,
+        content: BlockContent,
+        previous: Option<&BlockContent>,
+    ) -> Result<BlockContent> {
+        match content {
+            BlockContent::Image(image) => self.process_image(image).await.map(BlockContent::Image),
+            BlockContent::Bookmark(mut bookmark) => {
+                bookmark.preview = match previous {
+                    Some(BlockContent::Bookmark(p))
+                        if p.url == bookmark.url && p.preview.is_some() =>
+                    {
+                        p.preview.clone()
+                    }
+                    // A page without a preview is still a valid bookmark
+                    _ => self.fetch_preview(&bookmark.url).await.ok(),
+                };
+                Ok(BlockContent::Bookmark(bookmark))
+            }
+            BlockContent::Math(mut math) => {
+                let previous = match previous {
+                    Some(BlockContent::Math(p)) => Some((p.code.as_str(), &p.svg)),
+                    _ => None,
+                };
+                math.svg = render_svg(&self.math, &math.code, previous).await;
+                Ok(BlockContent::Math(math))
+            }
+            BlockContent::Diagram(mut diagram) => {
+                let previous = match previous {
+                    Some(BlockContent::Diagram(p)) => Some((p.code.as_str(), &p.svg)),
+                    _ => None,
+                };
+                diagram.svg = render_svg(&self.diagrams, &diagram.code, previous).await;
+                Ok(BlockContent::Diagram(diagram))
+            }
+            content => Ok(content),
+        }
+    }
+
+    /// Runs uploaded images through the image pipeline and records their dimensions
+    async fn process_image(&self, mut image: ImageContent) -> Result<ImageContent> {
+        let (Some(images), Some(attachment_id)) = (&self.images, image.attachment_id) else {
+            return Ok(image);
+        };
+
+        if let Some(dimensions) = images.process(attachment_id).await? {
+            image.width = Some(dimensions.width);
+            image.height = Some(dimensions.height);
+        }
+        Ok(image)
+    }
+
+    async fn fetch_preview(&self, url: &str) -> Result<LinkPreview> {
+        match &self.previews {
+            None => Err(CoreError::LinkPreview(
+                "Link previews are disabled".to_string(),
+            )),
+            Some(previews) => previews.preview(url).await,
+        }
+    }
+}
+
+#[async_trait]
+impl<R: BlockRepo + Send + Sync, C: ChangeRepo + Send + Sync> TemplateWriter
+    for BlockService<R, C>
+{
+    async fn write_blocks(
+        &self,
+        conn: &mut PgConnection,
+        note: &Note,
+        blocks: Vec<TemplateBlock>,
+        variables: &TemplateVariables,
+    ) -> Result<TemplateMentions> {
+        let mut mentions = Vec::new();
+        let mut levels = vec![(None, blocks)];
+        while let Some((parent_block, blocks)) = levels.pop() {
+            let mut previous: Option<BlockContent> = None;
+            for (position, template_block) in blocks.into_iter().enumerate() {
+                let content = variables.fill_content(template_block.content.clone());
+                if !template_block.block_type.is_matching_content_type(&content) {
+                    return Err(CoreError::BlockTypeNotMatches);
+                }
+                let mut content = normalize_content(content);
+                validate_content(&content)?;
+                self.check_workspace_references(&content, note.workspace_id)
+                    .await?;
+                fit_list_indent(&mut content, previous.as_ref());
+
+                let links = content.mentions();
+                // Previews and SVGs of the template are reused while unchanged
+                let content = self
+                    .process_content(content, Some(&template_block.content))
+                    .await?;
+                let block = Block {
+                    id: Uuid::new_v4(),
+                    block_type: template_block.block_type,
+                    content: content.clone(),
+                    note_id: note.id,
+                    parent_block,
+                    position: position as i32,
+                    version: 1,
+                };
+                let id = block.id;
+                self.repo.create(&mut *conn, block).await?;
+                self.repo.save_links(&mut *conn, id, links.clone()).await?;
+                self.log(&mut *conn, ChangeType::BlockCreated, note.id, id)
+                    .await?;
+                mentions.push((id, links));
+                previous = Some(content);
+                if !template_block.children.is_empty() {
+                    levels.push((Some(id), template_block.children));
+                }
+            }
+        }
+        Ok(mentions)
+    }
+
+    async fn notify_template_mentions(
+        &self,
+        note_id: Uuid,
+        mentions: TemplateMentions,
+    ) -> Result<()> {
+        for (id, links) in mentions {
+            self.notify_mentions(note_id, id, &[], &links).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Blocks directly under `parent` in a note tree, the top level for `None`.
+/// Returns `None` when the parent is not a toggle of the note.
+fn find_children(blocks: &[BlockDTO], parent: Option<Uuid>) -> Option<&[BlockDTO]> {
+    let Some(parent) = parent else {
+        return Some(blocks);
+    };
+    find_block(blocks, parent)
+        .filter(|b| matches!(b.content, BlockContent::Toggle(_)))
+        .map(|b| b.children.as_slice())
+}
+
+/// Renders the code to SVG, reusing the previous SVG while the code is unchanged.
+/// Without a renderer, or when rendering fails, exports fall back to the source.
+async fn render_svg(
+    renderer: &Option<Arc<dyn SvgRenderer>>,
+    code: &str,
+    previous: Option<(&str, &Option<String>)>,
+) -> Option<String> {
+    let renderer = renderer.as_ref()?;
+    match previous {
+        Some((previous_code, Some(svg))) if previous_code == code => Some(svg.clone()),
+        _ => renderer.render(code).await.ok(),
+    }
+}
+
+fn normalize_content(mut content: BlockContent) -> BlockContent {
+    if let Some(spans) = content.spans_mut() {
+        *spans = normalize_spans(std::mem::take(spans));
+    }
+    content
+}
+
+fn validate_content(content: &BlockContent) -> Result<()> {
+    match content {
+        BlockContent::Heading(c) if !(1..=MAX_HEADING_LEVEL).contains(&c.level) => {
+            return Err(CoreError::InvalidBlockContent(format!(
+                "Heading level must be between 1 and {MAX_HEADING_LEVEL}"
+            )));
+        }
+        BlockContent::Callout(c) if c.icon.trim().is_empty() => {
+            return Err(CoreError::InvalidBlockContent(
+                "Callout icon must not be empty".to_string(),
+            ));
+        }
+        BlockContent::BulletedList(c) | BlockContent::NumberedList(c)
+            if c.indent > MAX_LIST_INDENT =>
+        {
+            return Err(CoreError::InvalidBlockContent(format!(
+                "List indent must not exceed {MAX_LIST_INDENT}"
+            )));
+        }
+        BlockContent::Table(c) => c.validate().map_err(CoreError::InvalidBlockContent)?,
+        BlockContent::Math(c) => validate_latex(&c.code).map_err(CoreError::InvalidBlockContent)?,
+        BlockContent::Diagram(c) => {
+            validate_mermaid(&c.code).map_err(CoreError::InvalidBlockContent)?
+        }
+        BlockContent::Bookmark(c) => {
+            let valid =
+                url::Url::parse(&c.url).is_ok_and(|u| matches!(u.scheme(), "http" | "https"));
+            if !valid {
+                return Err(CoreError::InvalidBlockContent(format!(
+                    "Invalid bookmark link {}",
+                    c.url
+                )));
+            }
+        }
+        _ => {}
+    }
+    if let Some(spans) = content.spans() {
+        validate_spans(spans)?;
+    }
+    Ok(())
+}
+
+/// Nests a list item at most one level below the block before it
+fn fit_list_indent(content: &mut BlockContent, previous: Option<&BlockContent>) {
+    let max = previous
+        .and_then(|p| p.list_indent())
+        .map_or(0, |indent| indent + 1);
+    if let BlockContent::BulletedList(c) | BlockContent::NumberedList(c) = content {
+        c.indent = c.indent.min(max);
+    }
+}
+
+fn validate_spans(spans: &[TextSpan]) -> Result<()> {
+    for span in spans {
+        let mut marks = HashSet::new();
+        if !span.marks.iter().all(|m| marks.insert(*m)) {
+            return Err(CoreError::InvalidRichText("Duplicate mark".to_string()));
+        }
+        if span.href.is_some() && span.mention.is_some() {
+            return Err(CoreError::InvalidRichText(
+                "A mention can't be a link".to_string(),
+            ));
+        }
+        if let Some(href) = &span.href {
+            let url = url::Url::parse(href)
+                .map_err(|_| CoreError::InvalidRichText(format!("Invalid link {href}")))?;
+            if !ALLOWED_LINK_SCHEMES.contains(&url.scheme()) {
+                return Err(CoreError::InvalidRichText(format!(
+                    "Link scheme {} is not allowed",
+                    url.scheme()
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Content with the merged text. Characters that were there before keep their
+/// formatting, new ones take the marks of the character before them.
+fn merged_content(content: BlockContent, old_ids: &[CharId], state: &TextCrdt) -> BlockContent {
+    match content {
+        BlockContent::PlainText(c) => {
+            let chars = c.spans.iter().flat_map(|s| s.text.chars().map(move |_| s));
+            let formats: HashMap<CharId, &TextSpan> = old_ids.iter().copied().zip(chars).collect();
+
+            let mut marks = Vec::new();
+            let spans = state
+                .items()
+                .iter()
+                .filter(|i| !i.deleted)
+                .map(|item| {
+                    let span = match formats.get(&item.id) {
+                        Some(format) => TextSpan {
+                            text: item.ch.to_string(),
+                            ..(*format).clone()
+                        },
+                        None => TextSpan {
+                            marks: marks.clone(),
+                            ..TextSpan::plain(item.ch)
+                        },
+                    };
+                    marks.clone_from(&span.marks);
+                    span
+                })
+                .collect();
+            BlockContent::PlainText(PlainTextContent {
+                spans: normalize_spans(spans),
+            })
+        }
+        BlockContent::Code(c) => BlockContent::Code(CodeContent {
+            code: state.text(),
+            ..c
+        }),
+        content => content,
+    }
 }
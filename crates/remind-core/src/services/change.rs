@@ -0,0 +1,39 @@
+use crate::errors::Result;
+use crate::{ChangeDTO, ChangeRepo};
+use uuid::Uuid;
+
+/// Most changes returned at once
+pub const MAX_CHANGES_PER_PAGE: i64 = 500;
+
+/// Reads the change log of workspaces. Changes are logged by the note and
+/// block services as they happen.
+#[derive(Clone)]
+pub struct ChangeService<C: ChangeRepo> {
+    repo: C,
+}
+
+impl<C: ChangeRepo> ChangeService<C> {
+    pub fn new(repo: C) -> Self {
+        Self { repo }
+    }
+
+    /// Changes of the workspaces after the one with id `after`, oldest
+    /// first, at most `limit` up to `MAX_CHANGES_PER_PAGE`
+    pub async fn get_after(
+        &self,
+        workspace_ids: &[Uuid],
+        after: i64,
+        limit: i64,
+    ) -> Result<Vec<ChangeDTO>> {
+        let changes = self
+            .repo
+            .find_all_after(workspace_ids, after, limit.clamp(1, MAX_CHANGES_PER_PAGE))
+            .await?;
+        Ok(changes.into_iter().map(ChangeDTO::from).collect())
+    }
+
+    /// Id of the latest change, to follow the log from now on
+    pub async fn get_last_id(&self, workspace_ids: &[Uuid]) -> Result<i64> {
+        self.repo.find_last_id(workspace_ids).await
+    }
+}
@@ -0,0 +1,402 @@
+use crate::errors::{CoreError, Result};
+use crate::{
+    CollectionGroupDTO, CollectionProperty, CollectionPropertyDTO, CollectionRepo,
+    CollectionRowDTO, CollectionView, CollectionViewDTO, NoteRepo, PropertyCreateDTO, PropertyType,
+    PropertyUpdateDTO, PropertyValue, ViewCreateDTO, ViewFilter, ViewResultDTO, ViewSort, ViewType,
+    ViewUpdateDTO, compare_values,
+};
+use sqlx::types::Json;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+/// Turns a note into a collection of its child notes: typed properties on
+/// the children and saved views over them
+#[derive(Clone)]
+pub struct CollectionService<C: CollectionRepo, N: NoteRepo> {
+    repo: C,
+    note_repo: N,
+}
+
+impl<C: CollectionRepo, N: NoteRepo> CollectionService<C, N> {
+    pub fn new(repo: C, note_repo: N) -> Self {
+        Self { repo, note_repo }
+    }
+
+    pub async fn create_property(&self, data: PropertyCreateDTO) -> Result<CollectionPropertyDTO> {
+        if self.note_repo.find_one(data.note_id).await?.is_none() {
+            return Err(CoreError::NotFound);
+        }
+        let options = validate_property(&data.name, data.property_type, data.options)?;
+        let position = self.repo.find_properties(data.note_id).await?.len() as i32;
+
+        let id = Uuid::new_v4();
+        let property = CollectionProperty {
+            id,
+            note_id: data.note_id,
+            name: data.name,
+            property_type: data.property_type,
+            options,
+            position,
+        };
+        self.repo.create_property(property).await?;
+        self.find_property(id).await
+    }
+
+    pub async fn find_property(&self, id: Uuid) -> Result<CollectionPropertyDTO> {
+        match self.repo.find_property(id).await? {
+            None => Err(CoreError::NotFound),
+            Some(property) => Ok(property.into()),
+        }
+    }
+
+    pub async fn get_properties(&self, note_id: Uuid) -> Result<Vec<CollectionPropertyDTO>> {
+        let properties = self
+            .repo
+            .find_properties(note_id)
+            .await?
+            .into_iter()
+            .map(CollectionPropertyDTO::from)
+            .collect();
+        Ok(properties)
+    }
+
+    pub async fn update_property(
+        &self,
+        id: Uuid,
+        data: PropertyUpdateDTO,
+    ) -> Result<CollectionPropertyDTO> {
+        let mut property = match self.repo.find_property(id).await? {
+            None => return Err(CoreError::NotFound),
+            Some(p) => p,
+        };
+
+        if let Some(name) = data.name {
+            property.name = name;
+        }
+        let options = data.options.unwrap_or(property.options);
+        property.options = validate_property(&property.name, property.property_type, options)?;
+
+        self.repo.save_property(property).await?;
+        self.find_property(id).await
+    }
+
+    pub async fn delete_property(&self, id: Uuid) -> Result<()> {
+        self.repo.delete_property(id).await
+    }
+
+    /// Sets a property of a child note, `None` clears it
+    pub async fn set_value(
+        &self,
+        note_id: Uuid,
+        property_id: Uuid,
+        value: Option<PropertyValue>,
+    ) -> Result<()> {
+        let property = match self.repo.find_property(property_id).await? {
+            None => return Err(CoreError::NotFound),
+            Some(p) => p,
+        };
+        let note = match self.note_repo.find_one(note_id).await? {
+            None => return Err(CoreError::NotFound),
+            Some(n) => n,
+        };
+        if note.parent_note != Some(property.note_id) {
+            return Err(CoreError::InvalidProperty(
+                "Note is not in this collection".to_string(),
+            ));
+        }
+
+        match value {
+            Some(value) => {
+                value
+                    .validate(&property)
+                    .map_err(CoreError::InvalidProperty)?;
+                self.repo.set_value(note_id, property_id, value).await
+            }
+            None => self.repo.delete_value(note_id, property_id).await,
+        }
+    }
+
+    pub async fn get_values(&self, note_id: Uuid) -> Result<HashMap<Uuid, PropertyValue>> {
+        let values = self
+            .repo
+            .find_values(&[note_id])
+            .await?
+            .into_iter()
+            .map(|v| (v.property_id, v.value.0))
+            .collect();
+        Ok(values)
+    }
+
+    pub async fn create_view(&self, data: ViewCreateDTO) -> Result<CollectionViewDTO> {
+        if self.note_repo.find_one(data.note_id).await?.is_none() {
+            return Err(CoreError::NotFound);
+        }
+        let group_by = self
+            .validate_view(
+                data.note_id,
+                &data.name,
+                data.view_type,
+                &data.filters,
+                &data.sorts,
+                data.group_by,
+            )
+            .await?;
+        let position = self.repo.find_views(data.note_id).await?.len() as i32;
+
+        let id = Uuid::new_v4();
+        let view = CollectionView {
+            id,
+            note_id: data.note_id,
+            name: data.name,
+            view_type: data.view_type,
+            filters: Json(data.filters),
+            sorts: Json(data.sorts),
+            group_by,
+            position,
+        };
+        self.repo.create_view(view).await?;
+        self.find_view(id).await
+    }
+
+    pub async fn find_view(&self, id: Uuid) -> Result<CollectionViewDTO> {
+        match self.repo.find_view(id).await? {
+            None => Err(CoreError::NotFound),
+            Some(view) => Ok(view.into()),
+        }
+    }
+
+    pub async fn get_views(&self, note_id: Uuid) -> Result<Vec<CollectionViewDTO>> {
+        let views = self
+            .repo
+            .find_views(note_id)
+            .await?
+            .into_iter()
+            .map(CollectionViewDTO::from)
+            .collect();
+        Ok(views)
+    }
+
+    pub async fn update_view(&self, id: Uuid, data: ViewUpdateDTO) -> Result<CollectionViewDTO> {
+        let mut view = match self.repo.find_view(id).await? {
+            None => return Err(CoreError::NotFound),
+            Some(v) => v,
+        };
+        view.group_by = self
+            .validate_view(
+                view.note_id,
+                &data.name,
+                data.view_type,
+                &data.filters,
+                &data.sorts,
+                data.group_by,
+            )
+            .await?;
+        view.name = data.name;
+        view.view_type = data.view_type;
+        view.filters = Json(data.filters);
+        view.sorts = Json(data.sorts);
+
+        self.repo.save_view(view).await?;
+        self.find_view(id).await
+    }
+
+    pub async fn delete_view(&self, id: Uuid) -> Result<()> {
+        self.repo.delete_view(id).await
+    }
+
+    /// Evaluates a view: filters and sorts the child notes of the collection
+    /// and splits them into columns for boards. Filters and sorts on deleted
+    /// properties are ignored.
+    pub async fn query_view(&self, id: Uuid) -> Result<ViewResultDTO> {
+        let view = self.find_view(id).await?;
+        let properties = self.get_properties(view.note_id).await?;
+        let property_ids: HashSet<Uuid> = properties.iter().map(|p| p.id).collect();
+
+        let mut children = self.note_repo.find_all_children(view.note_id).await?;
+        // Rows that sort equal keep title order
+        children.sort_by(|a, b| a.title.cmp(&b.title));
+        let child_ids: Vec<Uuid> = children.iter().map(|n| n.id).collect();
+        let mut values: HashMap<Uuid, HashMap<Uuid, PropertyValue>> = HashMap::new();
+        for value in self.repo.find_values(&child_ids).await? {
+            values
+                .entry(value.note_id)
+                .or_default()
+                .insert(value.property_id, value.value.0);
+        }
+
+        let filters: Vec<&ViewFilter> = view
+            .filters
+            .iter()
+            .filter(|f| property_ids.contains(&f.property_id))
+            .collect();
+        let mut rows: Vec<CollectionRowDTO> = children
+            .into_iter()
+            .map(|note| CollectionRowDTO {
+                values: values.remove(&note.id).unwrap_or_default(),
+                note_id: note.id,
+                title: note.title,
+                icon_type: note.icon_type,
+                icon_data: note.icon_data,
+            })
+            .filter(|row| {
+                filters
+                    .iter()
+                    .all(|f| f.matches(row.values.get(&f.property_id)))
+            })
+            .collect();
+
+        let sorts: Vec<&ViewSort> = view
+            .sorts
+            .iter()
+            .filter(|s| property_ids.contains(&s.property_id))
+            .collect();
+        rows.sort_by(|a, b| {
+            sorts
+                .iter()
+                .map(|s| compare_rows(a, b, s))
+                .find(|o| o.is_ne())
+                .unwrap_or(Ordering::Equal)
+        });
+
+        let group_property = properties
+            .iter()
+            .find(|p| Some(p.id) == view.group_by && view.view_type == ViewType::Board);
+        let groups = match group_property {
+            None => Vec::new(),
+            Some(property) => group_rows(&rows, property),
+        };
+
+        Ok(ViewResultDTO {
+            view,
+            properties,
+            rows,
+            groups,
+        })
+    }
+
+    /// Checks that a view only references properties of its collection,
+    /// returns the property to group by
+    async fn validate_view(
+        &self,
+        note_id: Uuid,
+        name: &str,
+        view_type: ViewType,
+        filters: &[ViewFilter],
+        sorts: &[ViewSort],
+        group_by: Option<Uuid>,
+    ) -> Result<Option<Uuid>> {
+        if name.trim().is_empty() {
+            return Err(CoreError::InvalidView(
+                "View name must not be empty".to_string(),
+            ));
+        }
+        let properties = self.repo.find_properties(note_id).await?;
+        let find = |id: Uuid| {
+            properties
+                .iter()
+                .find(|p| p.id == id)
+                .ok_or_else(|| CoreError::InvalidView(format!("Unknown property {id}")))
+        };
+
+        for filter in filters {
+            find(filter.property_id)?;
+            if filter.needs_value() && filter.value.is_none() {
+                return Err(CoreError::InvalidView(format!(
+                    "Filter {:?} needs a value",
+                    filter.op
+                )));
+            }
+        }
+        for sort in sorts {
+            find(sort.property_id)?;
+        }
+
+        if view_type != ViewType::Board {
+            return Ok(None);
+        }
+        let Some(group_by) = group_by else {
+            return Err(CoreError::InvalidView(
+                "Board views must be grouped by a property".to_string(),
+            ));
+        };
+        if find(group_by)?.property_type != PropertyType::Select {
+            return Err(CoreError::InvalidView(
+                "Board views can only be grouped by a Select property".to_string(),
+            ));
+        }
+        Ok(Some(group_by))
+    }
+}
+
+/// Validates a property definition, returns its options
+fn validate_property(
+    name: &str,
+    property_type: PropertyType,
+    options: Vec<String>,
+) -> Result<Vec<String>> {
+    if name.trim().is_empty() {
+        return Err(CoreError::InvalidProperty(
+            "Property name must not be empty".to_string(),
+        ));
+    }
+    if !property_type.has_options() {
+        if !options.is_empty() {
+            return Err(CoreError::InvalidProperty(format!(
+                "{property_type:?} properties have no options"
+            )));
+        }
+        return Ok(options);
+    }
+
+    let mut seen = HashSet::new();
+    for option in &options {
+        if option.trim().is_empty() || !seen.insert(option) {
+            return Err(CoreError::InvalidProperty(format!(
+                "Invalid or duplicate option {option:?}"
+            )));
+        }
+    }
+    Ok(options)
+}
+
+/// Orders two rows by one sort, rows without a value always go last
+fn compare_rows(a: &CollectionRowDTO, b: &CollectionRowDTO, sort: &ViewSort) -> Ordering {
+    let a = a.values.get(&sort.property_id);
+    let b = b.values.get(&sort.property_id);
+    let ordering = compare_values(a, b);
+    if sort.descending && a.is_some() && b.is_some() {
+        ordering.reverse()
+    } else {
+        ordering
+    }
+}
+
+/// One column per option in option order, then one for everything else
+fn group_rows(
+    rows: &[CollectionRowDTO],
+    property: &CollectionPropertyDTO,
+) -> Vec<CollectionGroupDTO> {
+    let mut groups: Vec<CollectionGroupDTO> = property
+        .options
+        .iter()
+        .map(|option| CollectionGroupDTO {
+            value: Some(option.clone()),
+            rows: Vec::new(),
+        })
+        .chain(std::iter::once(CollectionGroupDTO {
+            value: None,
+            rows: Vec::new(),
+        }))
+        .collect();
+
+    for row in rows {
+        let index = match row.values.get(&property.id) {
+            Some(PropertyValue::Text(value)) => property.options.iter().position(|o| o == value),
+            _ => None,
+        };
+        let last = groups.len() - 1;
+        groups[index.unwrap_or(last)].rows.push(row.clone());
+    }
+    groups
+}
@@ -0,0 +1,215 @@
+use crate::errors::{CoreError, Result};
+use crate::notifications::Notifier;
+use crate::{
+    BlockRepo, Comment, CommentCreateDTO, CommentDTO, CommentRepo, CommentThreadDTO,
+    MAX_COMMENT_LENGTH, NoteRepo, NotificationCreateDTO, NotificationKind,
+};
+use chrono::Utc;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Discussion threads on notes and their blocks. Whether a user may see and
+/// write comments at all is up to the caller, the service only makes sure
+/// comments are edited and deleted by their author.
+#[derive(Clone)]
+pub struct CommentService<R: CommentRepo, N: NoteRepo, B: BlockRepo> {
+    repo: R,
+    note_repo: N,
+    block_repo: B,
+    notifier: Option<Arc<dyn Notifier>>,
+}
+
+impl<R: CommentRepo, N: NoteRepo, B: BlockRepo> CommentService<R, N, B> {
+    pub fn new(repo: R, note_repo: N, block_repo: B) -> Self {
+        Self {
+            repo,
+            note_repo,
+            block_repo,
+            notifier: None,
+        }
+    }
+
+    /// Enables notifying the participants of a thread about replies
+    pub fn with_notifier(mut self, notifier: Arc<dyn Notifier>) -> Self {
+        self.notifier = Some(notifier);
+        self
+    }
+
+    /// Starts a thread on the note or one of its blocks
+    pub async fn create(&self, data: CommentCreateDTO) -> Result<CommentDTO> {
+        let body = validate_body(&data.body)?;
+        if self.note_repo.find_one(data.note_id).await?.is_none() {
+            return Err(CoreError::NotFound);
+        }
+        if let Some(block_id) = data.block_id {
+            match self.block_repo.find_one(block_id).await? {
+                Some(block) if block.note_id == data.note_id => {}
+                _ => return Err(CoreError::NotFound),
+            }
+        }
+
+        let comment = Comment {
+            id: Uuid::new_v4(),
+            note_id: data.note_id,
+            block_id: data.block_id,
+            thread_id: None,
+            author_id: data.author_id,
+            body,
+            orphaned: false,
+            resolved_at: None,
+            resolved_by: None,
+            created_at: Utc::now(),
+            edited_at: None,
+        };
+        self.repo.create(comment.clone()).await?;
+        Ok(comment.into())
+    }
+
+    /// Replies to a comment. Replies to replies go to the end of their thread.
+    pub async fn reply(&self, id: Uuid, author_id: Uuid, body: &str) -> Result<CommentDTO> {
+        let body = validate_body(body)?;
+        let replied = self.find(id).await?;
+
+        let thread_id = replied.thread_id.unwrap_or(replied.id);
+        let comment = Comment {
+            id: Uuid::new_v4(),
+            note_id: replied.note_id,
+            block_id: None,
+            thread_id: Some(thread_id),
+            author_id,
+            body,
+            orphaned: false,
+            resolved_at: None,
+            resolved_by: None,
+            created_at: Utc::now(),
+            edited_at: None,
+        };
+        self.repo.create(comment.clone()).await?;
+        self.notify_participants(thread_id, &comment).await?;
+        Ok(comment.into())
+    }
+
+    pub async fn find_one(&self, id: Uuid) -> Result<CommentDTO> {
+        Ok(self.find(id).await?.into())
+    }
+
+    /// Threads of the note, oldest first. Threads of deleted blocks are
+    /// included, marked orphaned.
+    pub async fn get_all_in_note(&self, note_id: Uuid) -> Result<Vec<CommentThreadDTO>> {
+        let comments = self.repo.find_all_in_note(note_id).await?;
+        let (threads, replies): (Vec<Comment>, Vec<Comment>) =
+            comments.into_iter().partition(|c| c.thread_id.is_none());
+
+        let mut threads: Vec<CommentThreadDTO> = threads
+            .into_iter()
+            .map(|c| CommentThreadDTO {
+                comment: c.into(),
+                replies: Vec::new(),
+            })
+            .collect();
+        for reply in replies {
+            if let Some(thread) = threads
+                .iter_mut()
+                .find(|t| Some(t.comment.id) == reply.thread_id)
+            {
+                thread.replies.push(reply.into());
+            }
+        }
+        Ok(threads)
+    }
+
+    /// Changes the body of a comment of the user
+    pub async fn edit(&self, id: Uuid, user_id: Uuid, body: &str) -> Result<CommentDTO> {
+        let body = validate_body(body)?;
+        let mut comment = self.find(id).await?;
+        if comment.author_id != user_id {
+            return Err(CoreError::AccessDenied);
+        }
+
+        comment.body = body;
+        comment.edited_at = Some(Utc::now());
+        self.repo.save(comment.clone()).await?;
+        Ok(comment.into())
+    }
+
+    /// Deletes a comment of the user, with its replies if it starts a thread
+    pub async fn delete(&self, id: Uuid, user_id: Uuid) -> Result<()> {
+        let comment = self.find(id).await?;
+        if comment.author_id != user_id {
+            return Err(CoreError::AccessDenied);
+        }
+        self.repo.delete(id).await
+    }
+
+    /// Resolves or reopens the thread started by the comment
+    pub async fn set_resolved(
+        &self,
+        id: Uuid,
+        user_id: Uuid,
+        resolved: bool,
+    ) -> Result<CommentDTO> {
+        let mut comment = self.find(id).await?;
+        if comment.thread_id.is_some() {
+            return Err(CoreError::InvalidComment(
+                "Only threads can be resolved, not replies".to_string(),
+            ));
+        }
+
+        if resolved != comment.resolved_at.is_some() {
+            comment.resolved_at = resolved.then(Utc::now);
+            comment.resolved_by = resolved.then_some(user_id);
+            self.repo.save(comment.clone()).await?;
+        }
+        Ok(comment.into())
+    }
+
+    /// Notifies those who wrote in the thread, but the author of the reply
+    async fn notify_participants(&self, thread_id: Uuid, reply: &Comment) -> Result<()> {
+        let Some(notifier) = &self.notifier else {
+            return Ok(());
+        };
+        let mut participants = Vec::new();
+        for comment in self.repo.find_all_in_note(reply.note_id).await? {
+            let in_thread = comment.id == thread_id || comment.thread_id == Some(thread_id);
+            if in_thread && !participants.contains(&comment.author_id) {
+                participants.push(comment.author_id);
+            }
+        }
+
+        for user_id in participants {
+            notifier
+                .notify(NotificationCreateDTO {
+                    user_id,
+                    kind: NotificationKind::CommentReply,
+                    actor_id: Some(reply.author_id),
+                    note_id: Some(reply.note_id),
+                    block_id: None,
+                    comment_id: Some(reply.id),
+                })
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn find(&self, id: Uuid) -> Result<Comment> {
+        match self.repo.find_one(id).await? {
+            None => Err(CoreError::NotFound),
+            Some(c) => Ok(c),
+        }
+    }
+}
+
+fn validate_body(body: &str) -> Result<String> {
+    let body = body.trim();
+    if body.is_empty() {
+        return Err(CoreError::InvalidComment(
+            "Comment must not be empty".to_string(),
+        ));
+    }
+    if body.chars().count() > MAX_COMMENT_LENGTH {
+        return Err(CoreError::InvalidComment(format!(
+            "Comment must not be longer than {MAX_COMMENT_LENGTH} characters"
+        )));
+    }
+    Ok(body.to_string())
+}
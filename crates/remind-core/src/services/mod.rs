@@ -1,4 +1,14 @@
+pub mod attachment;
+pub mod audit;
 pub mod block;
+pub mod change;
+pub mod collection;
+pub mod comment;
 pub mod note;
+pub mod notification;
+pub mod share_link;
+pub mod sync;
+pub mod tag;
 pub mod user;
+pub mod webhook;
 pub mod workspace;
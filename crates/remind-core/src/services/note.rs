@@ -1,20 +1,67 @@
+use crate::audit::{self, AuditSink};
+use crate::dto::block::{blocks_to_dtos, find_block};
 use crate::entities::note::NoteIconType;
 use crate::errors::{CoreError, Result};
-use crate::{Block, BlockDTO, BlockRepo, Note, NoteCreateDTO, NoteDTO, NoteRepo, NoteUpdateDTO};
+use crate::render::RenderFormat;
+use crate::templates::TemplateWriter;
+use crate::{
+    AuditAction, AuditContext, AuditEvent, BacklinkDTO, Block, BlockContent, BlockDTO, BlockRepo,
+    ChangeRepo, ChangeType, MAX_TEMPLATE_NAME_LENGTH, Mention, Note, NoteCreateDTO, NoteDTO,
+    NoteRepo, NoteTemplate, NoteTemplateCreateDTO, NoteTemplateDTO, NoteUpdateDTO, TagDTO,
+    TagMatch, TemplateBlock, TemplateVariables, clamp_list_indents,
+};
+use chrono::Utc;
+use serde_json::json;
+use sqlx::types::Json;
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::sync::Arc;
 use uuid::Uuid;
 
+/// Longest text of a mentioning block shown with a backlink
+const MAX_SNIPPET_LENGTH: usize = 160;
+
 #[derive(Clone)]
-pub struct NoteService<R: NoteRepo, B: BlockRepo> {
+pub struct NoteService<R: NoteRepo, B: BlockRepo, C: ChangeRepo> {
     repo: R,
     block_repo: B,
+    changes: C,
+    audit: Option<Arc<dyn AuditSink>>,
+    templates: Option<Arc<dyn TemplateWriter>>,
 }
 
-impl<R: NoteRepo, B: BlockRepo> NoteService<R, B> {
-    pub fn new(repo: R, block_repo: B) -> Self {
-        Self { repo, block_repo }
+impl<R: NoteRepo, B: BlockRepo, C: ChangeRepo> NoteService<R, B, C> {
+    pub fn new(repo: R, block_repo: B, changes: C) -> Self {
+        Self {
+            repo,
+            block_repo,
+            changes,
+            audit: None,
+            templates: None,
+        }
+    }
+
+    /// Enables recording deletes and exports in the audit log
+    pub fn with_audit_sink(mut self, audit: Arc<dyn AuditSink>) -> Self {
+        self.audit = Some(audit);
+        self
+    }
+
+    /// Enables creating notes from templates
+    pub fn with_template_writer(mut self, templates: Arc<dyn TemplateWriter>) -> Self {
+        self.templates = Some(templates);
+        self
     }
 
     pub async fn create(&self, data: NoteCreateDTO) -> Result<NoteDTO> {
+        self.create_with_id(Uuid::new_v4(), data).await
+    }
+
+    /// Creates a note with an id chosen by the client, like one made offline
+    pub async fn create_with_id(&self, id: Uuid, data: NoteCreateDTO) -> Result<NoteDTO> {
+        if self.repo.find_one(id).await?.is_some() {
+            return Err(CoreError::AlreadyExists);
+        }
         if let Some(parent_note_id) = data.parent_note {
             let parent_note = self.repo.find_one(parent_note_id).await?;
             if parent_note.is_none() {
@@ -22,16 +69,60 @@ impl<R: NoteRepo, B: BlockRepo> NoteService<R, B> {
             }
         }
 
-        let id = Uuid::new_v4();
-        let note = Note {
+        let template = match data.template_id {
+            None => None,
+            Some(template_id) => match self.repo.find_template(template_id).await? {
+                Some(t) if t.workspace_id == data.workspace_id => Some(t),
+                _ => return Err(CoreError::NotFound),
+            },
+        };
+        if template.is_some() && self.templates.is_none() {
+            return Err(CoreError::InvalidTemplate(
+                "Notes can't be created from templates".to_string(),
+            ));
+        }
+        let variables = TemplateVariables {
+            now: Utc::now(),
+            user: data.author,
+        };
+
+        let mut note = Note {
             id,
             title: data.title,
             icon_type: NoteIconType::Emoji,
             icon_data: "📦".to_string(),
             workspace_id: data.workspace_id,
             parent_note: data.parent_note,
+            version: 1,
         };
-        self.repo.create(note).await?;
+        if let Some(template) = &template {
+            if note.title.trim().is_empty() {
+                note.title = variables.fill(&template.title);
+            }
+            note.icon_type = template.icon_type.clone();
+            note.icon_data = template.icon_data.clone();
+        }
+        let mut tx = self.changes.begin().await?;
+        self.repo.create(&mut tx, note.clone()).await?;
+        self.changes
+            .create(
+                &mut tx,
+                data.workspace_id,
+                ChangeType::NoteCreated,
+                id,
+                None,
+            )
+            .await?;
+        let mut mentions = Vec::new();
+        if let (Some(template), Some(templates)) = (template, &self.templates) {
+            mentions = templates
+                .write_blocks(&mut tx, &note, template.blocks.0, &variables)
+                .await?;
+        }
+        tx.commit().await?;
+        if let Some(templates) = &self.templates {
+            templates.notify_template_mentions(id, mentions).await?;
+        }
         let dto = self.find_one(id).await?;
         Ok(dto)
     }
@@ -42,57 +133,168 @@ impl<R: NoteRepo, B: BlockRepo> NoteService<R, B> {
             Some(n) => n,
         };
 
-        let blocks: Vec<BlockDTO> = self
-            .block_repo
-            .find_all_in_note(id)
-            .await?
-            .iter()
-            .map(|b| BlockDTO::from(b.clone()))
-            .collect();
-        let dto = NoteDTO {
-            id: note.id,
-            title: note.title,
-            icon_type: note.icon_type,
-            icon_data: note.icon_data,
-            workspace_id: note.workspace_id,
-            parent_note: note.parent_note,
-            blocks,
-        };
-
-        Ok(dto)
+        self.build_dto(note).await
     }
 
     pub async fn get_all_in_workspace(&self, workspace_id: Uuid) -> Result<Vec<NoteDTO>> {
         let notes = self.repo.find_all_in_workspace(workspace_id).await?;
 
         let mut dtos: Vec<NoteDTO> = Vec::new();
+        for note in notes {
+            dtos.push(self.build_dto(note).await?)
+        }
+
+        Ok(dtos)
+    }
 
+    /// Notes of the workspace with any or all of the tags,
+    /// every note when no tags are given
+    pub async fn get_all_in_workspace_by_tags(
+        &self,
+        workspace_id: Uuid,
+        mut tags: Vec<Uuid>,
+        matching: TagMatch,
+    ) -> Result<Vec<NoteDTO>> {
+        if tags.is_empty() {
+            return self.get_all_in_workspace(workspace_id).await;
+        }
+        tags.sort();
+        tags.dedup();
+        let notes = self
+            .repo
+            .find_all_with_tags(workspace_id, tags, matching)
+            .await?;
+
+        let mut dtos: Vec<NoteDTO> = Vec::new();
         for note in notes {
-            let blocks: Vec<BlockDTO> = self
-                .block_repo
-                .find_all_in_note(note.id)
-                .await?
-                .iter()
-                .map(|b| BlockDTO::from(b.clone()))
-                .collect();
-
-            let dto = NoteDTO {
-                id: note.id,
-                title: note.title,
-                icon_type: note.icon_type,
-                icon_data: note.icon_data,
-                workspace_id: note.workspace_id,
-                parent_note: note.parent_note,
-                blocks,
-            };
-            dtos.push(dto)
+            dtos.push(self.build_dto(note).await?)
         }
 
         Ok(dtos)
     }
 
-    pub async fn delete(&self, id: Uuid) -> Result<()> {
-        self.repo.delete(id).await
+    /// Other notes of the workspace mentioning the note, ordered by title
+    pub async fn get_backlinks(&self, id: Uuid) -> Result<Vec<BacklinkDTO>> {
+        let note = match self.repo.find_one(id).await? {
+            None => return Err(CoreError::NotFound),
+            Some(n) => n,
+        };
+
+        let blocks = self
+            .block_repo
+            .find_all_linking_to(Mention::Note(id))
+            .await?;
+        let mut backlinks: Vec<BacklinkDTO> = Vec::new();
+        for block in blocks.into_iter().filter(|b| b.note_id != id) {
+            let snippet = snippet(&block.content.plain_text());
+            if let Some(last) = backlinks.last_mut()
+                && last.note_id == block.note_id
+            {
+                last.snippets.push(snippet);
+                continue;
+            }
+            let source = match self.repo.find_one(block.note_id).await? {
+                Some(n) if n.workspace_id == note.workspace_id => n,
+                _ => continue,
+            };
+            backlinks.push(BacklinkDTO {
+                note_id: source.id,
+                title: source.title,
+                snippets: vec![snippet],
+            });
+        }
+        backlinks.sort_by(|a, b| a.title.cmp(&b.title));
+
+        Ok(backlinks)
+    }
+
+    pub async fn delete(&self, id: Uuid, context: &AuditContext) -> Result<()> {
+        let Some(note) = self.repo.find_one(id).await? else {
+            return Ok(());
+        };
+        let mut tx = self.changes.begin().await?;
+        self.repo.delete(&mut tx, id).await?;
+        self.changes
+            .create(
+                &mut tx,
+                note.workspace_id,
+                ChangeType::NoteDeleted,
+                id,
+                None,
+            )
+            .await?;
+        tx.commit().await?;
+
+        let mut event = AuditEvent::new(AuditAction::NoteDeleted, context);
+        event.workspace_id = Some(note.workspace_id);
+        event.note_id = Some(id);
+        event.target_id = Some(id);
+        event.details = json!({ "title": note.title });
+        audit::record(&self.audit, event).await
+    }
+
+    /// Renders the note for download, recorded in the audit log
+    pub async fn export(
+        &self,
+        id: Uuid,
+        format: RenderFormat,
+        context: &AuditContext,
+    ) -> Result<String> {
+        let note = self.find_one(id).await?;
+        let mut event = AuditEvent::new(AuditAction::NoteExported, context);
+        event.workspace_id = Some(note.workspace_id);
+        event.note_id = Some(id);
+        event.target_id = Some(id);
+        event.details = json!({ "format": format });
+        audit::record(&self.audit, event).await?;
+        Ok(format.render(&note))
+    }
+
+    /// Saves the note as a template of its workspace. Sub-notes aren't part
+    /// of it.
+    pub async fn save_as_template(&self, data: NoteTemplateCreateDTO) -> Result<NoteTemplateDTO> {
+        let name = data.name.trim().to_string();
+        if name.is_empty() || name.chars().count() > MAX_TEMPLATE_NAME_LENGTH {
+            return Err(CoreError::InvalidTemplate(format!(
+                "Name must be 1 to {MAX_TEMPLATE_NAME_LENGTH} characters"
+            )));
+        }
+        let note = self.find_one(data.note_id).await?;
+
+        let template = NoteTemplate {
+            id: Uuid::new_v4(),
+            workspace_id: note.workspace_id,
+            name,
+            title: data.title.unwrap_or(note.title),
+            icon_type: note.icon_type,
+            icon_data: note.icon_data,
+            blocks: Json(to_template_blocks(&note.blocks)),
+            created_by: Some(data.created_by),
+            created_at: Utc::now(),
+        };
+        let id = template.id;
+        self.repo.create_template(template).await?;
+        self.find_template(id).await
+    }
+
+    pub async fn find_template(&self, id: Uuid) -> Result<NoteTemplateDTO> {
+        match self.repo.find_template(id).await? {
+            None => Err(CoreError::NotFound),
+            Some(template) => Ok(template.into()),
+        }
+    }
+
+    pub async fn get_templates_in_workspace(
+        &self,
+        workspace_id: Uuid,
+    ) -> Result<Vec<NoteTemplateDTO>> {
+        let templates = self.repo.find_templates_in_workspace(workspace_id).await?;
+        Ok(templates.into_iter().map(NoteTemplateDTO::from).collect())
+    }
+
+    pub async fn delete_template(&self, id: Uuid) -> Result<()> {
+        self.find_template(id).await?;
+        self.repo.delete_template(id).await
     }
 
     pub async fn update(&self, id: Uuid, data: NoteUpdateDTO) -> Result<()> {
@@ -101,41 +303,294 @@ impl<R: NoteRepo, B: BlockRepo> NoteService<R, B> {
             Some(n) => n,
         };
 
+        let renamed = data.title.as_ref().is_some_and(|t| *t != note.title);
         if let Some(title) = data.title {
             note.title = title;
         };
 
-        self.repo.save(note).await
+        let workspace_id = note.workspace_id;
+        let mut tx = self.changes.begin().await?;
+        match data.version {
+            None => self.repo.save(&mut tx, note).await?,
+            Some(version) => {
+                if !self.repo.save_if_version(&mut tx, note, version).await? {
+                    return Err(CoreError::VersionMismatch);
+                }
+            }
+        }
+        if renamed {
+            self.changes
+                .create(&mut tx, workspace_id, ChangeType::NoteRenamed, id, None)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn build_dto(&self, note: Note) -> Result<NoteDTO> {
+        let mut blocks = blocks_to_dtos(self.block_repo.find_all_in_note(note.id).await?);
+        resolve_synced_blocks(&self.repo, &self.block_repo, note.workspace_id, &mut blocks).await?;
+        resolve_mentions(&self.repo, note.workspace_id, &mut blocks).await?;
+        let tags = self.repo.find_tags(note.id).await?;
+
+        Ok(NoteDTO {
+            id: note.id,
+            title: note.title,
+            icon_type: note.icon_type,
+            icon_data: note.icon_data,
+            workspace_id: note.workspace_id,
+            parent_note: note.parent_note,
+            blocks,
+            tags: tags.into_iter().map(TagDTO::from).collect(),
+            version: note.version,
+        })
     }
 
-    pub async fn reorder_blocks(&self, id: Uuid, blocks: Vec<Uuid>) -> Result<()> {
-        let note_blocks = self.block_repo.find_all_in_note(id).await?;
-        for b in note_blocks.clone() {
-            if !blocks.contains(&b.id) {
-                return Err(CoreError::ServerError);
+    /// Moves the blocks directly under `parent_block`, or on the top level
+    /// for `None`, into the given order. Toggles keep their children and list
+    /// items bring their nested items along, so moving a parent moves its
+    /// whole subtree. The order is part of the note, so `version` is checked
+    /// against the note and bumps it, blocks only get a new version when
+    /// their list indent has to change.
+    pub async fn reorder_blocks(
+        &self,
+        id: Uuid,
+        parent_block: Option<Uuid>,
+        blocks: Vec<Uuid>,
+        version: Option<i64>,
+    ) -> Result<()> {
+        if self.repo.find_one(id).await?.is_none() {
+            return Err(CoreError::NotFound);
+        }
+        // Bumped first, reorders of the note wait for each other from here on
+        let mut tx = self.changes.begin().await?;
+        if !self.repo.bump_version(&mut tx, id, version).await? {
+            return Err(CoreError::VersionMismatch);
+        }
+
+        let mut note_blocks = self.block_repo.find_all_in_note(id).await?;
+        if let Some(parent) = parent_block
+            && !note_blocks.iter().any(|b| b.id == parent)
+        {
+            return Err(CoreError::NotFound);
+        }
+        note_blocks.retain(|b| b.parent_block == parent_block);
+        if let Some(missing) = note_blocks.iter().find(|b| !blocks.contains(&b.id)) {
+            return Err(CoreError::InvalidBlockOrder(format!(
+                "Block {} is missing from the order",
+                missing.id
+            )));
+        }
+        let indents: HashMap<Uuid, Option<u8>> = note_blocks
+            .iter()
+            .map(|b| (b.id, b.content.list_indent()))
+            .collect();
+        clamp_list_indents(note_blocks.iter_mut().map(|b| &mut b.content));
+
+        let order = order_with_children(&note_blocks, &blocks);
+        let mut slots: Vec<Option<Block>> = note_blocks.into_iter().map(Some).collect();
+        let mut new: Vec<Block> = order.into_iter().filter_map(|i| slots[i].take()).collect();
+        clamp_list_indents(new.iter_mut().map(|b| &mut b.content));
+
+        for (position, mut new_block) in new.into_iter().enumerate() {
+            new_block.position = position as i32;
+            match indents[&new_block.id] == new_block.content.list_indent() {
+                true => {
+                    self.block_repo
+                        .save_position(&mut tx, new_block.id, new_block.position)
+                        .await?
+                }
+                false => self.block_repo.save(&mut tx, new_block).await?,
             }
         }
 
-        let mut new = Vec::new();
-        for b in note_blocks {
-            let position = match blocks.iter().position(|&i| i == b.id) {
-                None => continue,
-                Some(p) => p,
+        self.changes
+            .create_in_note(&mut tx, ChangeType::BlocksReordered, id, parent_block)
+            .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+}
+
+/// Fills in the live sources of the Synced blocks in a note tree. Sources
+/// outside the workspace are never shown, whatever the block points to.
+pub(crate) async fn resolve_synced_blocks<N: NoteRepo, B: BlockRepo>(
+    note_repo: &N,
+    block_repo: &B,
+    workspace_id: Uuid,
+    blocks: &mut [BlockDTO],
+) -> Result<()> {
+    let mut ids = Vec::new();
+    collect_synced_sources(blocks, &mut ids);
+    if ids.is_empty() {
+        return Ok(());
+    }
+
+    // Sources are taken from the tree of their note, so toggles keep their children
+    let mut trees: HashMap<Uuid, Vec<BlockDTO>> = HashMap::new();
+    let mut sources: HashMap<Uuid, BlockDTO> = HashMap::new();
+    for id in ids {
+        let Some(source) = block_repo.find_one(id).await? else {
+            continue;
+        };
+        if let Entry::Vacant(entry) = trees.entry(source.note_id) {
+            let in_workspace = note_repo
+                .find_one(source.note_id)
+                .await?
+                .is_some_and(|n| n.workspace_id == workspace_id);
+            let tree = match in_workspace {
+                true => blocks_to_dtos(block_repo.find_all_in_note(source.note_id).await?),
+                false => Vec::new(),
             };
-            let block = Block {
-                id: b.id,
-                block_type: b.block_type,
-                content: b.content,
-                note_id: b.note_id,
-                position: position as i32,
+            entry.insert(tree);
+        }
+        if let Some(source) = find_block(&trees[&source.note_id], id) {
+            sources.insert(id, source.clone());
+        }
+    }
+    attach_synced_sources(blocks, &sources);
+    Ok(())
+}
+
+/// Shows mentioned notes under their current title and marks mentions of
+/// deleted notes, or of notes outside the workspace, as broken
+pub(crate) async fn resolve_mentions<N: NoteRepo>(
+    note_repo: &N,
+    workspace_id: Uuid,
+    blocks: &mut [BlockDTO],
+) -> Result<()> {
+    let mut ids = Vec::new();
+    collect_mentioned_notes(blocks, &mut ids);
+
+    let mut titles: HashMap<Uuid, Option<String>> = HashMap::new();
+    for id in ids {
+        let title = note_repo
+            .find_one(id)
+            .await?
+            .filter(|n| n.workspace_id == workspace_id)
+            .map(|n| n.title);
+        titles.insert(id, title);
+    }
+    apply_mentioned_titles(blocks, &titles);
+    Ok(())
+}
+
+fn to_template_blocks(blocks: &[BlockDTO]) -> Vec<TemplateBlock> {
+    blocks
+        .iter()
+        .map(|b| TemplateBlock {
+            block_type: b.block_type.clone(),
+            content: b.content.clone(),
+            children: to_template_blocks(&b.children),
+        })
+        .collect()
+}
+
+fn collect_mentioned_notes(blocks: &[BlockDTO], ids: &mut Vec<Uuid>) {
+    for block in blocks {
+        for mention in block.content.mentions() {
+            if let Mention::Note(id) = mention
+                && !ids.contains(&id)
+            {
+                ids.push(id);
+            }
+        }
+        collect_mentioned_notes(&block.children, ids);
+        if let Some(source) = block.synced_source.as_deref() {
+            collect_mentioned_notes(std::slice::from_ref(source), ids);
+        }
+    }
+}
+
+fn apply_mentioned_titles(blocks: &mut [BlockDTO], titles: &HashMap<Uuid, Option<String>>) {
+    for block in blocks {
+        for span in block.content.spans_mut().into_iter().flatten() {
+            let Some(Mention::Note(id)) = span.mention else {
+                continue;
             };
-            new.push(block);
+            match titles.get(&id) {
+                Some(Some(title)) => span.text = title.clone(),
+                _ => span.broken = true,
+            }
+        }
+        apply_mentioned_titles(&mut block.children, titles);
+        if let Some(source) = block.synced_source.as_deref_mut() {
+            apply_mentioned_titles(std::slice::from_mut(source), titles);
+        }
+    }
+}
+
+/// Text of a block cut to [`MAX_SNIPPET_LENGTH`] characters
+fn snippet(text: &str) -> String {
+    match text.char_indices().nth(MAX_SNIPPET_LENGTH) {
+        None => text.to_string(),
+        Some((end, _)) => format!("{}…", text[..end].trim_end()),
+    }
+}
+
+fn collect_synced_sources(blocks: &[BlockDTO], ids: &mut Vec<Uuid>) {
+    for block in blocks {
+        if let BlockContent::Synced(c) = &block.content
+            && !ids.contains(&c.source_block)
+        {
+            ids.push(c.source_block);
         }
+        collect_synced_sources(&block.children, ids);
+    }
+}
 
-        for new_block in new {
-            self.block_repo.save(new_block).await?;
+fn attach_synced_sources(blocks: &mut [BlockDTO], sources: &HashMap<Uuid, BlockDTO>) {
+    for block in blocks {
+        if let BlockContent::Synced(c) = &block.content {
+            block.synced_source = sources.get(&c.source_block).cloned().map(Box::new);
         }
+        attach_synced_sources(&mut block.children, sources);
+    }
+}
 
-        Ok(())
+/// Indices of `blocks` in the requested order, with every list item followed
+/// by the items nested under it
+fn order_with_children(blocks: &[Block], requested: &[Uuid]) -> Vec<usize> {
+    let mut rank: HashMap<Uuid, usize> = HashMap::new();
+    for (i, id) in requested.iter().enumerate() {
+        rank.entry(*id).or_insert(i);
+    }
+    let rank_of = |i: &usize| rank[&blocks[*i].id];
+
+    let mut by_request: Vec<usize> = (0..blocks.len()).collect();
+    by_request.sort_by_key(rank_of);
+
+    let mut placed = vec![false; blocks.len()];
+    let mut order = Vec::with_capacity(blocks.len());
+    for i in by_request {
+        if placed[i] {
+            continue;
+        }
+        let mut subtree: Vec<usize> = (i..subtree_end(blocks, i))
+            .filter(|j| !placed[*j])
+            .collect();
+        subtree.sort_by_key(rank_of);
+        for j in subtree {
+            placed[j] = true;
+            order.push(j);
+        }
+    }
+    order
+}
+
+/// End (exclusive) of the block at `start` and the list items nested under it
+fn subtree_end(blocks: &[Block], start: usize) -> usize {
+    let Some(indent) = blocks[start].content.list_indent() else {
+        return start + 1;
+    };
+    let mut end = start + 1;
+    while end < blocks.len()
+        && blocks[end]
+            .content
+            .list_indent()
+            .is_some_and(|child| child > indent)
+    {
+        end += 1;
     }
+    end
 }
@@ -0,0 +1,120 @@
+use crate::errors::{CoreError, Result};
+use crate::notifications::Notifier;
+use crate::{
+    MAX_NOTIFICATIONS_PER_PAGE, Notification, NotificationCreateDTO, NotificationDTO,
+    NotificationKind, NotificationPageDTO, NotificationPreferenceDTO, NotificationRepo,
+};
+use async_trait::async_trait;
+use chrono::Utc;
+use uuid::Uuid;
+
+/// Inbox of in-app notifications of every user
+#[derive(Clone)]
+pub struct NotificationService<R: NotificationRepo> {
+    repo: R,
+}
+
+impl<R: NotificationRepo> NotificationService<R> {
+    pub fn new(repo: R) -> Self {
+        Self { repo }
+    }
+
+    /// Notifies the user, unless they caused it or turned its kind off.
+    /// Returns the notification when one was created.
+    pub async fn create(&self, data: NotificationCreateDTO) -> Result<Option<NotificationDTO>> {
+        if data.actor_id == Some(data.user_id) {
+            return Ok(None);
+        }
+
+        let notification = Notification {
+            id: Uuid::new_v4(),
+            user_id: data.user_id,
+            kind: data.kind,
+            actor_id: data.actor_id,
+            note_id: data.note_id,
+            block_id: data.block_id,
+            comment_id: data.comment_id,
+            created_at: Utc::now(),
+            read_at: None,
+        };
+        match self.repo.create(notification.clone()).await? {
+            true => Ok(Some(notification.into())),
+            false => Ok(None),
+        }
+    }
+
+    /// Notifications of the user older than `before`, newest first, with
+    /// the number of unread ones
+    pub async fn get_page(
+        &self,
+        user_id: Uuid,
+        before: Option<Uuid>,
+        limit: i64,
+    ) -> Result<NotificationPageDTO> {
+        let limit = limit.clamp(1, MAX_NOTIFICATIONS_PER_PAGE);
+        let notifications: Vec<NotificationDTO> = self
+            .repo
+            .find_page(user_id, before, limit)
+            .await?
+            .into_iter()
+            .map(NotificationDTO::from)
+            .collect();
+        let next_cursor = match notifications.len() as i64 == limit {
+            true => notifications.last().map(|n| n.id),
+            false => None,
+        };
+        Ok(NotificationPageDTO {
+            notifications,
+            unread_count: self.repo.count_unread(user_id).await?,
+            next_cursor,
+        })
+    }
+
+    pub async fn mark_read(&self, id: Uuid, user_id: Uuid) -> Result<()> {
+        match self.repo.mark_read(id, user_id).await? {
+            true => Ok(()),
+            false => Err(CoreError::NotFound),
+        }
+    }
+
+    /// Returns the number of notifications that were unread
+    pub async fn mark_all_read(&self, user_id: Uuid) -> Result<u64> {
+        self.repo.mark_all_read(user_id).await
+    }
+
+    /// Whether each kind notifies the user, all do unless turned off
+    pub async fn get_preferences(&self, user_id: Uuid) -> Result<Vec<NotificationPreferenceDTO>> {
+        let chosen = self.repo.find_preferences(user_id).await?;
+        let preferences = NotificationKind::ALL
+            .into_iter()
+            .map(|kind| NotificationPreferenceDTO {
+                kind,
+                enabled: chosen
+                    .iter()
+                    .find(|(k, _)| *k == kind)
+                    .is_none_or(|(_, enabled)| *enabled),
+            })
+            .collect();
+        Ok(preferences)
+    }
+
+    pub async fn set_preferences(
+        &self,
+        user_id: Uuid,
+        preferences: Vec<NotificationPreferenceDTO>,
+    ) -> Result<Vec<NotificationPreferenceDTO>> {
+        for preference in preferences {
+            self.repo
+                .save_preference(user_id, preference.kind, preference.enabled)
+                .await?;
+        }
+        self.get_preferences(user_id).await
+    }
+}
+
+#[async_trait]
+impl<R: NotificationRepo + Send + Sync> Notifier for NotificationService<R> {
+    async fn notify(&self, data: NotificationCreateDTO) -> Result<()> {
+        self.create(data).await.map(|_| ())
+    }
+}
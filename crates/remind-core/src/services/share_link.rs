@@ -0,0 +1,218 @@
+use crate::audit::{self, AuditSink};
+use crate::dto::block::{BlockDTO, blocks_to_dtos};
+use crate::dto::share_link::to_public_blocks;
+use crate::errors::{CoreError, Result};
+use crate::services::note::{resolve_mentions, resolve_synced_blocks};
+use crate::{
+    AuditAction, AuditContext, AuditEvent, BlockRepo, NoteRepo, PublicNoteDTO, ShareLink,
+    ShareLinkCreateDTO, ShareLinkDTO, ShareLinkRepo,
+};
+use chrono::Utc;
+use rand::Rng;
+use rand::distributions::Alphanumeric;
+use remind_auth::{hash_password, verify_password};
+use serde_json::json;
+use std::collections::HashSet;
+use std::sync::Arc;
+use uuid::Uuid;
+
+const SLUG_LENGTH: usize = 24;
+
+#[derive(Clone)]
+pub struct ShareLinkService<S: ShareLinkRepo, N: NoteRepo, B: BlockRepo> {
+    repo: S,
+    note_repo: N,
+    block_repo: B,
+    audit: Option<Arc<dyn AuditSink>>,
+}
+
+impl<S: ShareLinkRepo, N: NoteRepo, B: BlockRepo> ShareLinkService<S, N, B> {
+    pub fn new(repo: S, note_repo: N, block_repo: B) -> Self {
+        Self {
+            repo,
+            note_repo,
+            block_repo,
+            audit: None,
+        }
+    }
+
+    /// Enables recording created links in the audit log
+    pub fn with_audit_sink(mut self, audit: Arc<dyn AuditSink>) -> Self {
+        self.audit = Some(audit);
+        self
+    }
+
+    pub async fn create(
+        &self,
+        data: ShareLinkCreateDTO,
+        context: &AuditContext,
+    ) -> Result<ShareLinkDTO> {
+        let Some(note) = self.note_repo.find_one(data.note_id).await? else {
+            return Err(CoreError::NotFound);
+        };
+
+        let password = match data.password {
+            None => None,
+            Some(p) => Some(hash_password(p.as_bytes()).map_err(|_| CoreError::ServerError)?),
+        };
+
+        let id = Uuid::new_v4();
+        let link = ShareLink {
+            id,
+            slug: generate_slug(),
+            note_id: data.note_id,
+            created_by: data.created_by,
+            password,
+            include_subpages: data.include_subpages,
+            expires_at: data.expires_at,
+            created_at: Utc::now(),
+        };
+        self.repo.create(link).await?;
+
+        let link = self.find_one(id).await?;
+        let mut event = AuditEvent::new(AuditAction::ShareLinkCreated, context);
+        event.workspace_id = Some(note.workspace_id);
+        event.note_id = Some(note.id);
+        event.target_id = Some(id);
+        event.details = json!({
+            "has_password": link.has_password,
+            "include_subpages": link.include_subpages,
+            "expires_at": link.expires_at,
+        });
+        audit::record(&self.audit, event).await?;
+        Ok(link)
+    }
+
+    pub async fn find_one(&self, id: Uuid) -> Result<ShareLinkDTO> {
+        match self.repo.find_one(id).await? {
+            None => Err(CoreError::NotFound),
+            Some(link) => Ok(link.into()),
+        }
+    }
+
+    pub async fn get_all_in_note(&self, note_id: Uuid) -> Result<Vec<ShareLinkDTO>> {
+        let links = self
+            .repo
+            .find_all_by_note(note_id)
+            .await?
+            .into_iter()
+            .map(ShareLinkDTO::from)
+            .collect();
+        Ok(links)
+    }
+
+    pub async fn revoke(&self, id: Uuid) -> Result<()> {
+        self.repo.delete(id).await
+    }
+
+    /// Resolves a public slug into the shared note.
+    /// Unknown and expired links are both reported as `NotFound`.
+    pub async fn resolve(&self, slug: String, password: Option<String>) -> Result<PublicNoteDTO> {
+        let link = self.open(slug, password).await?;
+        let mut visited = HashSet::new();
+        let mut attachments = HashSet::new();
+        self.build_public_note(&link, link.note_id, &mut visited, &mut attachments)
+            .await
+    }
+
+    /// Checks that the attachment is used by the shared note, so it can be
+    /// read through the link
+    pub async fn check_shared_attachment(
+        &self,
+        slug: String,
+        password: Option<String>,
+        attachment_id: Uuid,
+    ) -> Result<()> {
+        let link = self.open(slug, password).await?;
+        let mut visited = HashSet::new();
+        let mut attachments = HashSet::new();
+        self.build_public_note(&link, link.note_id, &mut visited, &mut attachments)
+            .await?;
+        match attachments.contains(&attachment_id) {
+            true => Ok(()),
+            false => Err(CoreError::NotFound),
+        }
+    }
+
+    /// Finds the link of the slug, checking its password
+    async fn open(&self, slug: String, password: Option<String>) -> Result<ShareLink> {
+        let link = match self.repo.find_one_by_slug(slug).await? {
+            Some(l) if !l.is_expired() => l,
+            _ => return Err(CoreError::NotFound),
+        };
+
+        if let Some(hashed) = link.password.clone() {
+            let password = password.ok_or(CoreError::SharePasswordInvalid)?;
+            if verify_password(password, hashed).is_err() {
+                return Err(CoreError::SharePasswordInvalid);
+            }
+        }
+        Ok(link)
+    }
+
+    /// Builds the public view of a shared note, collecting the attachments
+    /// it shows
+    async fn build_public_note(
+        &self,
+        link: &ShareLink,
+        note_id: Uuid,
+        visited: &mut HashSet<Uuid>,
+        attachments: &mut HashSet<Uuid>,
+    ) -> Result<PublicNoteDTO> {
+        visited.insert(note_id);
+        let note = match self.note_repo.find_one(note_id).await? {
+            None => return Err(CoreError::NotFound),
+            Some(n) => n,
+        };
+        let mut blocks = blocks_to_dtos(self.block_repo.find_all_in_note(note_id).await?);
+        resolve_synced_blocks(
+            &self.note_repo,
+            &self.block_repo,
+            note.workspace_id,
+            &mut blocks,
+        )
+        .await?;
+        resolve_mentions(&self.note_repo, note.workspace_id, &mut blocks).await?;
+        collect_attachments(&blocks, attachments);
+
+        let mut subpages = Vec::new();
+        if link.include_subpages {
+            for child in self.note_repo.find_all_children(note_id).await? {
+                if visited.contains(&child.id) {
+                    continue;
+                }
+                let child =
+                    Box::pin(self.build_public_note(link, child.id, visited, attachments)).await?;
+                subpages.push(child);
+            }
+        }
+
+        Ok(PublicNoteDTO {
+            id: note.id,
+            title: note.title,
+            icon_type: note.icon_type,
+            icon_data: note.icon_data,
+            blocks: to_public_blocks(blocks, &link.slug),
+            subpages,
+        })
+    }
+}
+
+/// Attachments shown by the blocks, through Synced blocks too
+fn collect_attachments(blocks: &[BlockDTO], ids: &mut HashSet<Uuid>) {
+    for block in blocks {
+        let shown = block.synced_source.as_deref().unwrap_or(block);
+        if let Some(id) = shown.content.attachment_id() {
+            ids.insert(id);
+        }
+        collect_attachments(&shown.children, ids);
+    }
+}
+
+fn generate_slug() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(SLUG_LENGTH)
+        .map(char::from)
+        .collect()
+}
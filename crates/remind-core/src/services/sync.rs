@@ -0,0 +1,32 @@
+use crate::SyncRepo;
+use crate::errors::Result;
+use serde_json::Value;
+use uuid::Uuid;
+
+/// Remembers the results of the operations pushed by offline clients. The
+/// operations themselves go through the note and block services, which log
+/// them to the change log clients pull from.
+#[derive(Clone)]
+pub struct SyncService<S: SyncRepo> {
+    repo: S,
+}
+
+impl<S: SyncRepo> SyncService<S> {
+    pub fn new(repo: S) -> Self {
+        Self { repo }
+    }
+
+    /// Result of an operation of the user applied before
+    pub async fn get_result(&self, user_id: Uuid, id: Uuid) -> Result<Option<Value>> {
+        self.repo.find_result(user_id, id).await
+    }
+
+    /// Keeps the result of an applied operation. When the same operation was
+    /// applied concurrently the result stored first wins and is returned.
+    pub async fn save_result(&self, user_id: Uuid, id: Uuid, result: Value) -> Result<Value> {
+        if self.repo.create_result(user_id, id, result.clone()).await? {
+            return Ok(result);
+        }
+        Ok(self.repo.find_result(user_id, id).await?.unwrap_or(result))
+    }
+}
@@ -0,0 +1,145 @@
+use crate::errors::{CoreError, Result};
+use crate::{
+    DEFAULT_TAG_COLOR, MAX_TAG_NAME_LENGTH, NoteRepo, Tag, TagCreateDTO, TagDTO, TagRepo,
+    TagUpdateDTO,
+};
+use uuid::Uuid;
+
+/// Tags of a workspace and their assignment to notes
+#[derive(Clone)]
+pub struct TagService<T: TagRepo, N: NoteRepo> {
+    repo: T,
+    note_repo: N,
+}
+
+impl<T: TagRepo, N: NoteRepo> TagService<T, N> {
+    pub fn new(repo: T, note_repo: N) -> Self {
+        Self { repo, note_repo }
+    }
+
+    pub async fn create(&self, data: TagCreateDTO) -> Result<TagDTO> {
+        let name = validate_name(&data.name)?;
+        let color = match data.color {
+            None => DEFAULT_TAG_COLOR.to_string(),
+            Some(color) => validate_color(&color)?,
+        };
+        self.check_name_free(data.workspace_id, &name, None).await?;
+
+        let id = Uuid::new_v4();
+        let tag = Tag {
+            id,
+            workspace_id: data.workspace_id,
+            name,
+            color,
+        };
+        self.repo.create(tag).await?;
+        self.find_one(id).await
+    }
+
+    pub async fn find_one(&self, id: Uuid) -> Result<TagDTO> {
+        match self.repo.find_one(id).await? {
+            None => Err(CoreError::NotFound),
+            Some(tag) => Ok(tag.into()),
+        }
+    }
+
+    pub async fn get_all_in_workspace(&self, workspace_id: Uuid) -> Result<Vec<TagDTO>> {
+        let tags = self
+            .repo
+            .find_all_in_workspace(workspace_id)
+            .await?
+            .into_iter()
+            .map(TagDTO::from)
+            .collect();
+        Ok(tags)
+    }
+
+    pub async fn update(&self, id: Uuid, data: TagUpdateDTO) -> Result<TagDTO> {
+        let mut tag = match self.repo.find_one(id).await? {
+            None => return Err(CoreError::NotFound),
+            Some(t) => t,
+        };
+
+        if let Some(name) = data.name {
+            let name = validate_name(&name)?;
+            self.check_name_free(tag.workspace_id, &name, Some(id))
+                .await?;
+            tag.name = name;
+        }
+        if let Some(color) = data.color {
+            tag.color = validate_color(&color)?;
+        }
+
+        self.repo.save(tag).await?;
+        self.find_one(id).await
+    }
+
+    /// Deletes the tag and removes it from all notes
+    pub async fn delete(&self, id: Uuid) -> Result<()> {
+        self.repo.delete(id).await
+    }
+
+    pub async fn tag_note(&self, note_id: Uuid, tag_id: Uuid) -> Result<()> {
+        let tag = self.find_note_tag(note_id, tag_id).await?;
+        self.repo.add_to_note(note_id, tag.id).await
+    }
+
+    pub async fn untag_note(&self, note_id: Uuid, tag_id: Uuid) -> Result<()> {
+        let tag = self.find_note_tag(note_id, tag_id).await?;
+        self.repo.remove_from_note(note_id, tag.id).await
+    }
+
+    /// Finds the tag and checks it belongs to the workspace of the note
+    async fn find_note_tag(&self, note_id: Uuid, tag_id: Uuid) -> Result<Tag> {
+        let note = match self.note_repo.find_one(note_id).await? {
+            None => return Err(CoreError::NotFound),
+            Some(n) => n,
+        };
+        match self.repo.find_one(tag_id).await? {
+            Some(tag) if tag.workspace_id == note.workspace_id => Ok(tag),
+            _ => Err(CoreError::NotFound),
+        }
+    }
+
+    async fn check_name_free(
+        &self,
+        workspace_id: Uuid,
+        name: &str,
+        tag_id: Option<Uuid>,
+    ) -> Result<()> {
+        match self.repo.find_one_by_name(workspace_id, name).await? {
+            Some(existing) if Some(existing.id) != tag_id => Err(CoreError::InvalidTag(format!(
+                "A tag named {name} already exists"
+            ))),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn validate_name(name: &str) -> Result<String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(CoreError::InvalidTag(
+            "Tag name must not be empty".to_string(),
+        ));
+    }
+    if name.chars().count() > MAX_TAG_NAME_LENGTH {
+        return Err(CoreError::InvalidTag(format!(
+            "Tag name must not be longer than {MAX_TAG_NAME_LENGTH} characters"
+        )));
+    }
+    Ok(name.to_string())
+}
+
+/// Accepts `#rrggbb` colors, stored in lowercase
+fn validate_color(color: &str) -> Result<String> {
+    let valid = color
+        .strip_prefix('#')
+        .is_some_and(|hex| hex.len() == 6 && hex.chars().all(|c| c.is_ascii_hexdigit()));
+    if !valid {
+        return Err(CoreError::InvalidTag(format!(
+            "Color {color} is not a hex color like #ff8800"
+        )));
+    }
+    Ok(color.to_ascii_lowercase())
+}
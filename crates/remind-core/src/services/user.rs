@@ -1,15 +1,22 @@
+use crate::audit::{self, AuditSink};
 use crate::errors::AuthError;
 use crate::errors::CoreError;
 use crate::errors::Result;
 use crate::repositories::user::UserRepo;
-use crate::{User, UserCreateDTO, UserDTO, UserLoginEmailDTO, UserLoginUsernameDTO};
+use crate::{
+    AuditAction, AuditContext, AuditEvent, User, UserCreateDTO, UserDTO, UserLoginEmailDTO,
+    UserLoginUsernameDTO,
+};
 use remind_auth::{Claims, JwtProcessor, hash_password, verify_password};
+use serde_json::json;
+use std::sync::Arc;
 use uuid::Uuid;
 
 #[derive(Clone)]
 pub struct UserService<R: UserRepo> {
     repo: R,
     jwt_processor: JwtProcessor,
+    audit: Option<Arc<dyn AuditSink>>,
 }
 
 impl<R: UserRepo> UserService<R> {
@@ -17,18 +24,31 @@ impl<R: UserRepo> UserService<R> {
         Self {
             repo,
             jwt_processor,
+            audit: None,
         }
     }
 
+    /// Enables recording logins in the audit log
+    pub fn with_audit_sink(mut self, audit: Arc<dyn AuditSink>) -> Self {
+        self.audit = Some(audit);
+        self
+    }
+
     pub async fn register(&self, data: UserCreateDTO) -> Result<UserDTO> {
-        if let Some(_) = self
+        if self
             .repo
             .find_one_by_username(data.username.clone())
             .await?
+            .is_some()
         {
             return Err(AuthError::UsernameOccupied.into());
         };
-        if let Some(_) = self.repo.find_one_by_email(data.email.clone()).await? {
+        if self
+            .repo
+            .find_one_by_email(data.email.clone())
+            .await?
+            .is_some()
+        {
             return Err(AuthError::EmailExists.into());
         };
         let password = hash_password(data.password.as_ref()).map_err(|_| CoreError::ServerError)?;
@@ -60,36 +80,56 @@ impl<R: UserRepo> UserService<R> {
         }
     }
 
-    pub async fn login_by_username(&self, data: UserLoginUsernameDTO) -> Result<String> {
+    pub async fn login_by_username(
+        &self,
+        data: UserLoginUsernameDTO,
+        context: &AuditContext,
+    ) -> Result<String> {
         let user = self
             .repo
             .find_one_by_username(data.username.clone())
             .await?;
-        if user.is_none() {
-            return Err(AuthError::WrongCredentials.into());
-        }
-        let user = user.unwrap();
-        if !verify_password(data.password, user.password).is_ok() {
-            return Err(AuthError::WrongCredentials.into());
-        }
-
-        let claims = Claims::new(data.username);
-        let token = self.jwt_processor.create_token(&claims)?;
-        Ok(token)
+        self.login(user, data.password, &data.username, context)
+            .await
     }
 
-    pub async fn login_by_email(&self, data: UserLoginEmailDTO) -> Result<String> {
+    pub async fn login_by_email(
+        &self,
+        data: UserLoginEmailDTO,
+        context: &AuditContext,
+    ) -> Result<String> {
         let user = self.repo.find_one_by_email(data.email.clone()).await?;
-        if user.is_none() {
-            return Err(AuthError::WrongCredentials.into());
-        }
-        let user = user.unwrap();
-        if !verify_password(data.password, user.password).is_ok() {
-            return Err(AuthError::WrongCredentials.into());
-        }
+        self.login(user, data.password, &data.email, context).await
+    }
+
+    /// Checks the password and creates a token, `login` is what the user
+    /// was looked up by
+    async fn login(
+        &self,
+        user: Option<User>,
+        password: String,
+        login: &str,
+        context: &AuditContext,
+    ) -> Result<String> {
+        let user = match user {
+            Some(user) if verify_password(password, user.password.clone()).is_ok() => user,
+            user => {
+                let mut event = AuditEvent::new(AuditAction::LoginFailed, context);
+                event.target_id = user.map(|u| u.id);
+                event.details = json!({ "login": login });
+                audit::record(&self.audit, event).await?;
+                return Err(AuthError::WrongCredentials.into());
+            }
+        };
 
+        let context = context.with_actor(user.id);
         let claims = Claims::new(user.username);
         let token = self.jwt_processor.create_token(&claims)?;
+        for action in [AuditAction::LoginSucceeded, AuditAction::TokenCreated] {
+            let mut event = AuditEvent::new(action, &context);
+            event.target_id = Some(user.id);
+            audit::record(&self.audit, event).await?;
+        }
         Ok(token)
     }
 }
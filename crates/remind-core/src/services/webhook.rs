@@ -0,0 +1,301 @@
+use crate::errors::{CoreError, Result};
+use crate::webhooks::{WebhookRequest, WebhookSender, sign};
+use crate::{
+    ChangeDTO, ChangeRepo, DeliveryStatus, MAX_CHANGES_PER_PAGE, MIN_WEBHOOK_SECRET_LENGTH,
+    WEBHOOK_TEST_EVENT, Webhook, WebhookCreateDTO, WebhookDTO, WebhookDelivery, WebhookDeliveryDTO,
+    WebhookRepo,
+};
+use chrono::{Duration, Utc};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::sync::Arc;
+use url::Url;
+use uuid::Uuid;
+
+/// Most deliveries sent per run of the worker
+const DELIVERY_BATCH_SIZE: i64 = 50;
+/// Seconds a claimed delivery is left to its worker before others retry it
+const DELIVERY_LEASE_SECONDS: i64 = 5 * 60;
+/// Most deliveries of a webhook listed at once
+pub const MAX_DELIVERIES_PER_PAGE: i64 = 100;
+
+/// How failed deliveries are retried
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    /// Attempts before a delivery is given up, the first one included
+    pub max_attempts: i32,
+    /// Wait before the first retry, doubled for every retry after it
+    pub first_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 8,
+            first_delay: Duration::seconds(30),
+            max_delay: Duration::hours(6),
+        }
+    }
+}
+
+/// Webhooks of workspaces. Changes are picked up from the change log and
+/// queued as deliveries, which a worker sends and retries.
+#[derive(Clone)]
+pub struct WebhookService<W: WebhookRepo, C: ChangeRepo> {
+    repo: W,
+    changes: C,
+    sender: Arc<dyn WebhookSender>,
+    retries: RetryPolicy,
+}
+
+impl<W: WebhookRepo, C: ChangeRepo> WebhookService<W, C> {
+    pub fn new(repo: W, changes: C, sender: Arc<dyn WebhookSender>, retries: RetryPolicy) -> Self {
+        Self {
+            repo,
+            changes,
+            sender,
+            retries,
+        }
+    }
+
+    /// Registers an endpoint. It is notified of the changes made from now on.
+    pub async fn create(&self, data: WebhookCreateDTO) -> Result<WebhookDTO> {
+        let url = Url::parse(data.url.trim())
+            .map_err(|_| CoreError::InvalidWebhook("Invalid URL".to_string()))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(CoreError::InvalidWebhook(
+                "URL must be http or https".to_string(),
+            ));
+        }
+        if data.secret.chars().count() < MIN_WEBHOOK_SECRET_LENGTH {
+            return Err(CoreError::InvalidWebhook(format!(
+                "Secret must have at least {MIN_WEBHOOK_SECRET_LENGTH} characters"
+            )));
+        }
+        let mut event_types = Vec::new();
+        for event_type in data.event_types {
+            if !event_types.contains(&event_type) {
+                event_types.push(event_type);
+            }
+        }
+        if event_types.is_empty() {
+            return Err(CoreError::InvalidWebhook(
+                "At least one event type is required".to_string(),
+            ));
+        }
+
+        let webhook = Webhook {
+            id: Uuid::new_v4(),
+            workspace_id: data.workspace_id,
+            url: url.to_string(),
+            secret: data.secret,
+            event_types,
+            last_change_id: self.changes.find_last_id(&[data.workspace_id]).await?,
+            created_at: Utc::now(),
+        };
+        self.repo.create(webhook.clone()).await?;
+        Ok(webhook.into())
+    }
+
+    pub async fn find_one(&self, id: Uuid) -> Result<WebhookDTO> {
+        match self.repo.find_one(id).await? {
+            None => Err(CoreError::NotFound),
+            Some(w) => Ok(w.into()),
+        }
+    }
+
+    pub async fn get_all_in_workspace(&self, workspace_id: Uuid) -> Result<Vec<WebhookDTO>> {
+        let webhooks = self.repo.find_all_in_workspace(workspace_id).await?;
+        Ok(webhooks.into_iter().map(WebhookDTO::from).collect())
+    }
+
+    pub async fn delete(&self, id: Uuid) -> Result<()> {
+        self.repo.delete(id).await
+    }
+
+    /// Latest deliveries of the webhook, up to `MAX_DELIVERIES_PER_PAGE`
+    pub async fn get_deliveries(&self, id: Uuid, limit: i64) -> Result<Vec<WebhookDeliveryDTO>> {
+        let deliveries = self
+            .repo
+            .find_deliveries(id, limit.clamp(1, MAX_DELIVERIES_PER_PAGE))
+            .await?;
+        Ok(deliveries
+            .into_iter()
+            .map(WebhookDeliveryDTO::from)
+            .collect())
+    }
+
+    /// Sends a test event right away and returns how it went. It is retried
+    /// like any other delivery when it fails.
+    pub async fn send_test(&self, id: Uuid) -> Result<WebhookDeliveryDTO> {
+        let webhook = match self.repo.find_one(id).await? {
+            None => return Err(CoreError::NotFound),
+            Some(w) => w,
+        };
+        let mut delivery = new_delivery(&webhook, WEBHOOK_TEST_EVENT, |delivery_id| {
+            json!({
+                "id": delivery_id,
+                "event": WEBHOOK_TEST_EVENT,
+                "workspace_id": webhook.workspace_id,
+                "webhook_id": webhook.id,
+                "created_at": Utc::now(),
+            })
+        });
+        // Leased like a claimed delivery, the worker mustn't send it meanwhile
+        delivery.next_attempt_at = Utc::now() + Duration::seconds(DELIVERY_LEASE_SECONDS);
+        self.repo.create_delivery(delivery.clone()).await?;
+        let delivery = self.deliver(delivery, &webhook).await?;
+        Ok(delivery.into())
+    }
+
+    /// Queues a delivery for every change a webhook subscribed to and hasn't
+    /// seen yet. Returns the number of deliveries queued.
+    pub async fn enqueue_changes(&self) -> Result<usize> {
+        let mut queued = 0;
+        for webhook in self.repo.find_all().await? {
+            let mut after = webhook.last_change_id;
+            loop {
+                let changes = self
+                    .changes
+                    .find_all_after(&[webhook.workspace_id], after, MAX_CHANGES_PER_PAGE)
+                    .await?;
+                let Some(last) = changes.last().map(|c| c.id) else {
+                    break;
+                };
+                let deliveries: Vec<WebhookDelivery> = changes
+                    .into_iter()
+                    .map(ChangeDTO::from)
+                    .filter(|c| webhook.event_types.contains(&c.change_type))
+                    .map(|change| {
+                        new_delivery(&webhook, change.change_type.as_str(), |delivery_id| {
+                            change_payload(delivery_id, &change)
+                        })
+                    })
+                    .collect();
+                let count = deliveries.len();
+                // Another worker got there first
+                if !self
+                    .repo
+                    .enqueue(webhook.id, after, last, deliveries)
+                    .await?
+                {
+                    break;
+                }
+                queued += count;
+                after = last;
+            }
+        }
+        Ok(queued)
+    }
+
+    /// Sends the deliveries that are due. Returns the number of attempts.
+    pub async fn deliver_due(&self) -> Result<usize> {
+        let deliveries = self
+            .repo
+            .claim_due(DELIVERY_BATCH_SIZE, DELIVERY_LEASE_SECONDS)
+            .await?;
+        let count = deliveries.len();
+        let mut webhooks: HashMap<Uuid, Option<Webhook>> = HashMap::new();
+        for delivery in deliveries {
+            let webhook = match webhooks.entry(delivery.webhook_id) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => {
+                    entry.insert(self.repo.find_one(delivery.webhook_id).await?)
+                }
+            };
+            // Deleted meanwhile, its deliveries went with it
+            if let Some(webhook) = webhook {
+                self.deliver(delivery, webhook).await?;
+            }
+        }
+        Ok(count)
+    }
+
+    /// Makes one attempt and records its outcome
+    async fn deliver(
+        &self,
+        mut delivery: WebhookDelivery,
+        webhook: &Webhook,
+    ) -> Result<WebhookDelivery> {
+        let body = delivery.payload.to_string();
+        let timestamp = Utc::now().timestamp();
+        let outcome = self
+            .sender
+            .send(WebhookRequest {
+                url: webhook.url.clone(),
+                event: delivery.event.clone(),
+                delivery_id: delivery.id,
+                timestamp,
+                signature: sign(&webhook.secret, timestamp, body.as_bytes()),
+                body,
+            })
+            .await;
+
+        delivery.attempts += 1;
+        delivery.last_status_code = outcome.as_ref().ok().map(|s| *s as i32);
+        match outcome {
+            Ok(status) if (200..300).contains(&status) => {
+                delivery.status = DeliveryStatus::Delivered;
+                delivery.last_error = None;
+                delivery.delivered_at = Some(Utc::now());
+            }
+            outcome => {
+                delivery.last_error = Some(match outcome {
+                    Ok(status) => format!("Endpoint responded with {status}"),
+                    Err(e) => e,
+                });
+                if delivery.attempts >= self.retries.max_attempts {
+                    delivery.status = DeliveryStatus::Failed;
+                } else {
+                    delivery.next_attempt_at = Utc::now() + self.retry_delay(delivery.attempts);
+                }
+            }
+        }
+        self.repo.save_delivery(delivery.clone()).await?;
+        Ok(delivery)
+    }
+
+    /// Wait after the given number of failed attempts
+    fn retry_delay(&self, attempts: i32) -> Duration {
+        let factor = 2i32.saturating_pow(attempts.clamp(1, 31) as u32 - 1);
+        (self.retries.first_delay * factor).min(self.retries.max_delay)
+    }
+}
+
+fn new_delivery(
+    webhook: &Webhook,
+    event: &str,
+    payload: impl FnOnce(Uuid) -> Value,
+) -> WebhookDelivery {
+    let id = Uuid::new_v4();
+    let now = Utc::now();
+    WebhookDelivery {
+        id,
+        webhook_id: webhook.id,
+        event: event.to_string(),
+        payload: payload(id),
+        status: DeliveryStatus::Pending,
+        attempts: 0,
+        next_attempt_at: now,
+        last_status_code: None,
+        last_error: None,
+        created_at: now,
+        delivered_at: None,
+    }
+}
+
+fn change_payload(delivery_id: Uuid, change: &ChangeDTO) -> Value {
+    json!({
+        "id": delivery_id,
+        "event": change.change_type.as_str(),
+        "workspace_id": change.workspace_id,
+        "created_at": change.created_at,
+        "change": {
+            "id": change.id,
+            "note_id": change.note_id,
+            "block_id": change.block_id,
+        },
+    })
+}
@@ -0,0 +1,31 @@
+use async_trait::async_trait;
+
+pub(crate) mod local;
+pub(crate) mod s3;
+
+/// Storage for uploaded file contents, addressed by an opaque key
+#[async_trait]
+pub trait BlobStore: Send + Sync {
+    async fn put(&self, key: &str, data: Vec<u8>, content_type: &str) -> crate::errors::Result<()>;
+    async fn get(&self, key: &str) -> crate::errors::Result<Option<Vec<u8>>>;
+    async fn delete(&self, key: &str) -> crate::errors::Result<()>;
+}
+
+/// Raster image types safe to show inline. Anything else, markup in
+/// particular, could run script on the origin serving it.
+pub const INLINE_CONTENT_TYPES: [&str; 4] = ["image/png", "image/jpeg", "image/gif", "image/webp"];
+
+/// Whether an upload of the content type may be shown inline
+pub fn is_inline_content_type(content_type: &str) -> bool {
+    INLINE_CONTENT_TYPES.contains(&content_type)
+}
+
+/// Detects the content type from the file's magic bytes.
+/// Falls back to `application/octet-stream` when unknown.
+pub fn sniff_content_type(data: &[u8]) -> String {
+    match infer::get(data) {
+        Some(kind) => kind.mime_type().to_string(),
+        None if std::str::from_utf8(data).is_ok() => "text/plain".to_string(),
+        None => "application/octet-stream".to_string(),
+    }
+}
@@ -0,0 +1,159 @@
+use crate::errors::{CoreError, Result};
+use crate::storage::BlobStore;
+use async_trait::async_trait;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use reqwest::{Method, StatusCode};
+use sha2::{Digest, Sha256};
+
+#[derive(Clone, Debug)]
+pub struct S3Config {
+    /// Base URL of the S3-compatible service, e.g. `http://localhost:9000`
+    pub endpoint: String,
+    pub bucket: String,
+    pub region: String,
+    pub access_key: String,
+    pub secret_key: String,
+}
+
+/// Stores blobs in an S3-compatible bucket using path-style requests
+/// signed with AWS Signature Version 4
+#[derive(Clone)]
+pub struct S3BlobStore {
+    config: S3Config,
+    client: reqwest::Client,
+}
+
+impl S3BlobStore {
+    pub fn new(config: S3Config) -> Self {
+        Self {
+            config,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    async fn send(
+        &self,
+        method: Method,
+        key: &str,
+        body: Vec<u8>,
+        content_type: Option<&str>,
+    ) -> Result<reqwest::Response> {
+        let endpoint = reqwest::Url::parse(&self.config.endpoint)
+            .map_err(|e| CoreError::Storage(e.to_string()))?;
+        let host = match (endpoint.host_str(), endpoint.port()) {
+            (Some(h), Some(p)) => format!("{h}:{p}"),
+            (Some(h), None) => h.to_string(),
+            (None, _) => return Err(CoreError::Storage("Endpoint has no host".to_string())),
+        };
+        let path = format!(
+            "/{}/{}",
+            uri_encode(&self.config.bucket),
+            key.split('/').map(uri_encode).collect::<Vec<_>>().join("/")
+        );
+
+        let now = Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let payload_hash = hex::encode(Sha256::digest(&body));
+
+        let canonical_request = format!(
+            "{method}\n{path}\n\nhost:{host}\nx-amz-content-sha256:{payload_hash}\nx-amz-date:{amz_date}\n\nhost;x-amz-content-sha256;x-amz-date\n{payload_hash}"
+        );
+        let scope = format!("{date}/{}/s3/aws4_request", self.config.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{amz_date}\n{scope}\n{}",
+            hex::encode(Sha256::digest(canonical_request.as_bytes()))
+        );
+
+        let mut key = hmac_sha256(
+            format!("AWS4{}", self.config.secret_key).as_bytes(),
+            date.as_bytes(),
+        );
+        for part in [self.config.region.as_str(), "s3", "aws4_request"] {
+            key = hmac_sha256(&key, part.as_bytes());
+        }
+        let signature = hex::encode(hmac_sha256(&key, string_to_sign.as_bytes()));
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{scope}, SignedHeaders=host;x-amz-content-sha256;x-amz-date, Signature={signature}",
+            self.config.access_key
+        );
+
+        let url = format!("{}{}", self.config.endpoint.trim_end_matches('/'), path);
+        let mut request = self
+            .client
+            .request(method, url)
+            .header("x-amz-date", amz_date)
+            .header("x-amz-content-sha256", payload_hash)
+            .header("authorization", authorization);
+        if let Some(content_type) = content_type {
+            request = request.header("content-type", content_type);
+        }
+
+        request
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| CoreError::Storage(e.to_string()))
+    }
+}
+
+#[async_trait]
+impl BlobStore for S3BlobStore {
+    async fn put(&self, key: &str, data: Vec<u8>, content_type: &str) -> Result<()> {
+        let response = self
+            .send(Method::PUT, key, data, Some(content_type))
+            .await?;
+        if !response.status().is_success() {
+            return Err(CoreError::Storage(format!(
+                "PUT {key} failed with {}",
+                response.status()
+            )));
+        }
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        let response = self.send(Method::GET, key, Vec::new(), None).await?;
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(None),
+            s if s.is_success() => {
+                let bytes = response
+                    .bytes()
+                    .await
+                    .map_err(|e| CoreError::Storage(e.to_string()))?;
+                Ok(Some(bytes.to_vec()))
+            }
+            s => Err(CoreError::Storage(format!("GET {key} failed with {s}"))),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        let response = self.send(Method::DELETE, key, Vec::new(), None).await?;
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(()),
+            s if s.is_success() => Ok(()),
+            s => Err(CoreError::Storage(format!("DELETE {key} failed with {s}"))),
+        }
+    }
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any size");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Percent-encodes everything except RFC 3986 unreserved characters
+fn uri_encode(segment: &str) -> String {
+    let mut out = String::with_capacity(segment.len());
+    for b in segment.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                out.push(b as char)
+            }
+            _ => out.push_str(&format!("%{b:02X}")),
+        }
+    }
+    out
+}
@@ -0,0 +1,30 @@
+use crate::errors::Result;
+use crate::{Mention, Note, TemplateBlock, TemplateVariables};
+use async_trait::async_trait;
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+/// Blocks created from a template, with the mentions of each
+pub type TemplateMentions = Vec<(Uuid, Vec<Mention>)>;
+
+/// Creates the blocks of templates in new notes, checked and processed like
+/// blocks created by clients
+#[async_trait]
+pub trait TemplateWriter: Send + Sync {
+    /// Creates the blocks with the variables filled in, in the transaction
+    /// the note is created in
+    async fn write_blocks(
+        &self,
+        conn: &mut PgConnection,
+        note: &Note,
+        blocks: Vec<TemplateBlock>,
+        variables: &TemplateVariables,
+    ) -> Result<TemplateMentions>;
+
+    /// Notifies the users mentioned by the blocks, once the note is committed
+    async fn notify_template_mentions(
+        &self,
+        note_id: Uuid,
+        mentions: TemplateMentions,
+    ) -> Result<()>;
+}
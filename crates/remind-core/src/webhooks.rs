@@ -0,0 +1,135 @@
+use crate::previews::is_public;
+use async_trait::async_trait;
+use hmac::{Hmac, Mac};
+use reqwest::header::{CONTENT_TYPE, USER_AGENT};
+use reqwest::redirect::Policy;
+use sha2::Sha256;
+use std::net::SocketAddr;
+use std::time::Duration;
+use url::{Host, Url};
+use uuid::Uuid;
+
+/// Type of the change sent, like `note_created`, or `ping`. ReMind has no
+/// reminders yet, so there are no reminder events either.
+pub const EVENT_HEADER: &str = "X-ReMind-Event";
+pub const DELIVERY_HEADER: &str = "X-ReMind-Delivery";
+/// Unix time in seconds the request was signed at. Endpoints should refuse
+/// old timestamps, so captured requests can't be replayed.
+pub const TIMESTAMP_HEADER: &str = "X-ReMind-Timestamp";
+/// `sha256=` and the hex HMAC-SHA256 of the timestamp, a `.` and the body,
+/// keyed with the secret
+pub const SIGNATURE_HEADER: &str = "X-ReMind-Signature";
+
+/// Signed payload on its way to a webhook endpoint
+#[derive(Clone, Debug)]
+pub struct WebhookRequest {
+    pub url: String,
+    pub event: String,
+    pub delivery_id: Uuid,
+    pub timestamp: i64,
+    pub signature: String,
+    pub body: String,
+}
+
+/// Posts webhook payloads
+#[async_trait]
+pub trait WebhookSender: Send + Sync {
+    /// Sends the request, returns the status code the endpoint answered with
+    async fn send(&self, request: WebhookRequest) -> Result<u16, String>;
+}
+
+#[derive(Clone, Debug)]
+pub struct SenderLimits {
+    pub timeout: Duration,
+    /// Allows private, loopback and link-local addresses.
+    /// Only meant for tests and trusted networks.
+    pub allow_private_addresses: bool,
+}
+
+impl Default for SenderLimits {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(10),
+            allow_private_addresses: false,
+        }
+    }
+}
+
+/// Posts payloads over HTTP like the link previewer fetches pages: the host
+/// is resolved and checked first and the connection pinned to the checked
+/// addresses. Redirects aren't followed.
+#[derive(Clone)]
+pub struct HttpWebhookSender {
+    limits: SenderLimits,
+}
+
+impl HttpWebhookSender {
+    pub fn new(limits: SenderLimits) -> Self {
+        Self { limits }
+    }
+
+    async fn resolve(&self, url: &Url) -> Result<Vec<SocketAddr>, String> {
+        let port = url
+            .port_or_known_default()
+            .ok_or("URL without port".to_string())?;
+        let addrs: Vec<SocketAddr> = match url.host() {
+            Some(Host::Domain(domain)) => tokio::net::lookup_host((domain, port))
+                .await
+                .map_err(|e| e.to_string())?
+                .collect(),
+            Some(Host::Ipv4(ip)) => vec![SocketAddr::new(ip.into(), port)],
+            Some(Host::Ipv6(ip)) => vec![SocketAddr::new(ip.into(), port)],
+            None => Vec::new(),
+        };
+
+        if addrs.is_empty() {
+            return Err("Host not found".to_string());
+        }
+        if !self.limits.allow_private_addresses && !addrs.iter().all(|a| is_public(a.ip())) {
+            return Err("Address is not allowed".to_string());
+        }
+        Ok(addrs)
+    }
+}
+
+#[async_trait]
+impl WebhookSender for HttpWebhookSender {
+    async fn send(&self, request: WebhookRequest) -> Result<u16, String> {
+        let url = Url::parse(&request.url).map_err(|e| e.to_string())?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err("URL must be http or https".to_string());
+        }
+        let addrs = self.resolve(&url).await?;
+
+        let mut client = reqwest::Client::builder()
+            .redirect(Policy::none())
+            .timeout(self.limits.timeout)
+            .no_proxy();
+        if let Some(Host::Domain(domain)) = url.host() {
+            client = client.resolve_to_addrs(domain, &addrs);
+        }
+        let client = client.build().map_err(|e| e.to_string())?;
+        let response = client
+            .post(url)
+            .header(USER_AGENT, "ReMind webhooks")
+            .header(CONTENT_TYPE, "application/json")
+            .header(EVENT_HEADER, request.event)
+            .header(DELIVERY_HEADER, request.delivery_id.to_string())
+            .header(TIMESTAMP_HEADER, request.timestamp.to_string())
+            .header(SIGNATURE_HEADER, request.signature)
+            .body(request.body)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        Ok(response.status().as_u16())
+    }
+}
+
+/// Value of the signature header for a payload sent at `timestamp`
+pub fn sign(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC takes keys of any size");
+    mac.update(format!("{timestamp}.").as_bytes());
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
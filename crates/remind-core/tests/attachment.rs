@@ -0,0 +1,450 @@
+mod fixtures;
+
+use crate::fixtures::{
+    MAX_UPLOAD_SIZE, create_attachment_repo, create_attachment_service,
+    create_attachment_service_with_limits, create_block_service, create_note_service,
+    create_user_fixture, create_user_repository, create_workspace_fixture, create_workspace_repo,
+    png_bytes, spawn_s3_stand_in,
+};
+use chrono::Duration;
+use remind_core::errors::CoreError;
+use remind_core::storage::{is_inline_content_type, sniff_content_type};
+use remind_core::{
+    AttachmentCreateDTO, AttachmentRepo, AuditContext, BlobStore, BlockContent, BlockCreateDTO,
+    BlockType, FileContent, ImageContent, NoteCreateDTO, NoteTemplateCreateDTO, S3BlobStore,
+    S3Config, UploadLimits,
+};
+use sqlx::PgPool;
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_upload_and_download_attachment(pool: PgPool) {
+    let user = create_user_fixture(create_user_repository(pool.clone())).await;
+    let workspace = create_workspace_fixture(create_workspace_repo(pool.clone()), user.id).await;
+    let service = create_attachment_service(pool);
+    let png = png_bytes(1, 1);
+
+    let attachment = service
+        .upload(AttachmentCreateDTO {
+            workspace_id: workspace.id,
+            user_id: user.id,
+            file_name: "pixel.png".to_string(),
+            data: png.clone(),
+        })
+        .await
+        .unwrap();
+    assert_eq!(attachment.content_type, "image/png");
+    assert_eq!(attachment.width, Some(1));
+    assert!(attachment.is_image());
+
+    let (found, data) = service.get_content(attachment.id, None).await.unwrap();
+    assert_eq!(found.id, attachment.id);
+    assert_eq!(data.len() as i64, found.size);
+
+    service.delete(attachment.id).await.unwrap();
+    assert!(service.get_content(attachment.id, None).await.is_err());
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_upload_size_limit(pool: PgPool) {
+    let user = create_user_fixture(create_user_repository(pool.clone())).await;
+    let workspace = create_workspace_fixture(create_workspace_repo(pool.clone()), user.id).await;
+    let service = create_attachment_service(pool);
+
+    let too_large = service
+        .upload(AttachmentCreateDTO {
+            workspace_id: workspace.id,
+            user_id: user.id,
+            file_name: "big.bin".to_string(),
+            data: vec![0; MAX_UPLOAD_SIZE + 1],
+        })
+        .await;
+    assert!(too_large.is_err());
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_storage_quotas(pool: PgPool) {
+    let user = create_user_fixture(create_user_repository(pool.clone())).await;
+    let workspace = create_workspace_fixture(create_workspace_repo(pool.clone()), user.id).await;
+    let service = create_attachment_service_with_limits(
+        pool,
+        UploadLimits {
+            max_file_size: 100,
+            user_quota: 150,
+            workspace_quota: 1000,
+        },
+    );
+    let dto = AttachmentCreateDTO {
+        workspace_id: workspace.id,
+        user_id: user.id,
+        file_name: "notes.txt".to_string(),
+        data: vec![b'a'; 100],
+    };
+
+    service.upload(dto.clone()).await.unwrap();
+    let usage = service.user_usage(user.id).await.unwrap();
+    assert_eq!(usage.used, 100);
+    assert_eq!(usage.quota, 150);
+    assert_eq!(
+        service.workspace_usage(workspace.id).await.unwrap().used,
+        100
+    );
+
+    let exceeded = service.upload(dto).await;
+    assert!(matches!(exceeded, Err(CoreError::StorageQuotaExceeded)));
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_storage_quota_with_concurrent_uploads(pool: PgPool) {
+    let user = create_user_fixture(create_user_repository(pool.clone())).await;
+    let workspace = create_workspace_fixture(create_workspace_repo(pool.clone()), user.id).await;
+    let service = create_attachment_service_with_limits(
+        pool,
+        UploadLimits {
+            max_file_size: 100,
+            user_quota: 250,
+            workspace_quota: 1000,
+        },
+    );
+
+    let uploads: Vec<_> = (0..8)
+        .map(|_| {
+            let service = service.clone();
+            let dto = AttachmentCreateDTO {
+                workspace_id: workspace.id,
+                user_id: user.id,
+                file_name: "notes.txt".to_string(),
+                data: vec![b'a'; 100],
+            };
+            tokio::spawn(async move { service.upload(dto).await })
+        })
+        .collect();
+    let mut uploaded = 0;
+    for upload in uploads {
+        match upload.await.unwrap() {
+            Ok(_) => uploaded += 1,
+            Err(e) => assert!(matches!(e, CoreError::StorageQuotaExceeded), "{e:?}"),
+        }
+    }
+    assert_eq!(uploaded, 2);
+    assert_eq!(service.user_usage(user.id).await.unwrap().used, 200);
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_collect_garbage(pool: PgPool) {
+    let user = create_user_fixture(create_user_repository(pool.clone())).await;
+    let workspace = create_workspace_fixture(create_workspace_repo(pool.clone()), user.id).await;
+    let note = create_note_service(pool.clone())
+        .create(NoteCreateDTO {
+            title: "Note".to_string(),
+            workspace_id: workspace.id,
+            parent_note: None,
+            template_id: None,
+            author: None,
+        })
+        .await
+        .unwrap();
+    let service = create_attachment_service(pool.clone());
+    let block_service = create_block_service(pool);
+
+    let attachment = service
+        .upload(AttachmentCreateDTO {
+            workspace_id: workspace.id,
+            user_id: user.id,
+            file_name: "notes.txt".to_string(),
+            data: b"hello".to_vec(),
+        })
+        .await
+        .unwrap();
+    let block = block_service
+        .create(BlockCreateDTO {
+            block_type: BlockType::File,
+            content: BlockContent::File(FileContent {
+                attachment_id: attachment.id,
+                name: "notes.txt".to_string(),
+            }),
+            note_id: note.id,
+            parent_block: None,
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(service.collect_garbage(Duration::zero()).await.unwrap(), 0);
+
+    block_service
+        .delete(block.id, false, &AuditContext::default())
+        .await
+        .unwrap();
+    assert_eq!(
+        service.collect_garbage(Duration::hours(1)).await.unwrap(),
+        0
+    );
+    assert!(service.find_one(attachment.id).await.is_ok());
+
+    assert_eq!(service.collect_garbage(Duration::zero()).await.unwrap(), 1);
+    assert!(service.find_one(attachment.id).await.is_err());
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_collect_garbage_keeps_referenced(pool: PgPool) {
+    let user = create_user_fixture(create_user_repository(pool.clone())).await;
+    let workspace = create_workspace_fixture(create_workspace_repo(pool.clone()), user.id).await;
+    let note_service = create_note_service(pool.clone());
+    let note = note_service
+        .create(NoteCreateDTO {
+            title: "Note".to_string(),
+            workspace_id: workspace.id,
+            parent_note: None,
+            template_id: None,
+            author: None,
+        })
+        .await
+        .unwrap();
+    let service = create_attachment_service(pool.clone());
+    let block_service = create_block_service(pool.clone());
+
+    let attachment = service
+        .upload(AttachmentCreateDTO {
+            workspace_id: workspace.id,
+            user_id: user.id,
+            file_name: "notes.txt".to_string(),
+            data: b"hello".to_vec(),
+        })
+        .await
+        .unwrap();
+    let block = block_service
+        .create(BlockCreateDTO {
+            block_type: BlockType::File,
+            content: BlockContent::File(FileContent {
+                attachment_id: attachment.id,
+                name: "notes.txt".to_string(),
+            }),
+            note_id: note.id,
+            parent_block: None,
+        })
+        .await
+        .unwrap();
+
+    // An orphan referenced again before it is deleted is kept
+    let repo = create_attachment_repo(pool.clone());
+    assert!(
+        repo.delete_if_unreferenced(attachment.id)
+            .await
+            .unwrap()
+            .is_none()
+    );
+
+    // Templates keep what their blocks reference
+    let template = note_service
+        .save_as_template(NoteTemplateCreateDTO {
+            note_id: note.id,
+            name: "Files".to_string(),
+            title: None,
+            created_by: user.id,
+        })
+        .await
+        .unwrap();
+    block_service
+        .delete(block.id, false, &AuditContext::default())
+        .await
+        .unwrap();
+    assert_eq!(service.collect_garbage(Duration::zero()).await.unwrap(), 0);
+    assert!(service.get_content(attachment.id, None).await.is_ok());
+
+    note_service.delete_template(template.id).await.unwrap();
+    assert_eq!(service.collect_garbage(Duration::zero()).await.unwrap(), 1);
+    assert!(service.find_one(attachment.id).await.is_err());
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_template_keeps_attachment_of_deleted_note(pool: PgPool) {
+    let user = create_user_fixture(create_user_repository(pool.clone())).await;
+    let workspace = create_workspace_fixture(create_workspace_repo(pool.clone()), user.id).await;
+    let note_service = create_note_service(pool.clone());
+    let service = create_attachment_service(pool.clone());
+    let block_service = create_block_service(pool);
+    let note_dto = |template_id| NoteCreateDTO {
+        title: "Note".to_string(),
+        workspace_id: workspace.id,
+        parent_note: None,
+        template_id,
+        author: None,
+    };
+
+    let source = note_service.create(note_dto(None)).await.unwrap();
+    let attachment = service
+        .upload(AttachmentCreateDTO {
+            workspace_id: workspace.id,
+            user_id: user.id,
+            file_name: "notes.txt".to_string(),
+            data: b"hello".to_vec(),
+        })
+        .await
+        .unwrap();
+    block_service
+        .create(BlockCreateDTO {
+            block_type: BlockType::File,
+            content: BlockContent::File(FileContent {
+                attachment_id: attachment.id,
+                name: "notes.txt".to_string(),
+            }),
+            note_id: source.id,
+            parent_block: None,
+        })
+        .await
+        .unwrap();
+    let template = note_service
+        .save_as_template(NoteTemplateCreateDTO {
+            note_id: source.id,
+            name: "Files".to_string(),
+            title: None,
+            created_by: user.id,
+        })
+        .await
+        .unwrap();
+
+    note_service
+        .delete(source.id, &AuditContext::default())
+        .await
+        .unwrap();
+    assert_eq!(service.collect_garbage(Duration::zero()).await.unwrap(), 0);
+    assert!(service.get_content(attachment.id, None).await.is_ok());
+
+    // Notes made from the template still get the file
+    let note = note_service
+        .create(note_dto(Some(template.id)))
+        .await
+        .unwrap();
+    assert_eq!(note.blocks[0].content.attachment_id(), Some(attachment.id));
+
+    note_service.delete_template(template.id).await.unwrap();
+    assert_eq!(service.collect_garbage(Duration::zero()).await.unwrap(), 0);
+    note_service
+        .delete(note.id, &AuditContext::default())
+        .await
+        .unwrap();
+    assert_eq!(service.collect_garbage(Duration::zero()).await.unwrap(), 1);
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_block_attachment_checks(pool: PgPool) {
+    let user = create_user_fixture(create_user_repository(pool.clone())).await;
+    let workspace = create_workspace_fixture(create_workspace_repo(pool.clone()), user.id).await;
+    let other = create_workspace_fixture(create_workspace_repo(pool.clone()), user.id).await;
+    let service = create_attachment_service(pool.clone());
+    let note_service = create_note_service(pool.clone());
+    let block_service = create_block_service(pool);
+
+    let text = service
+        .upload(AttachmentCreateDTO {
+            workspace_id: workspace.id,
+            user_id: user.id,
+            file_name: "notes.txt".to_string(),
+            data: b"hello".to_vec(),
+        })
+        .await
+        .unwrap();
+    assert_eq!(text.content_type, "text/plain");
+
+    let mut notes = Vec::new();
+    for workspace_id in [workspace.id, other.id] {
+        let note = note_service
+            .create(NoteCreateDTO {
+                title: "Files".to_string(),
+                workspace_id,
+                parent_note: None,
+                template_id: None,
+                author: None,
+            })
+            .await
+            .unwrap();
+        notes.push(note);
+    }
+    let block = |block_type, content, note_id| BlockCreateDTO {
+        block_type,
+        content,
+        note_id,
+        parent_block: None,
+    };
+
+    let file = BlockContent::File(FileContent {
+        attachment_id: text.id,
+        name: "notes.txt".to_string(),
+    });
+    assert!(
+        block_service
+            .create(block(BlockType::File, file.clone(), notes[0].id))
+            .await
+            .is_ok()
+    );
+    // Attachments of other workspaces are not found
+    assert!(matches!(
+        block_service
+            .create(block(BlockType::File, file, notes[1].id))
+            .await,
+        Err(CoreError::NotFound)
+    ));
+
+    let image = BlockContent::Image(ImageContent {
+        url: format!("/attachments/{}/content", text.id),
+        alt: None,
+        attachment_id: Some(text.id),
+        width: None,
+        height: None,
+    });
+    assert!(matches!(
+        block_service
+            .create(block(BlockType::Image, image, notes[0].id))
+            .await,
+        Err(CoreError::NotAnImage)
+    ));
+}
+
+#[tokio::test]
+async fn test_s3_blob_store() {
+    let (endpoint, objects) = spawn_s3_stand_in().await;
+    let store = S3BlobStore::new(S3Config {
+        endpoint,
+        bucket: "remind".to_string(),
+        region: "us-east-1".to_string(),
+        access_key: "test-key".to_string(),
+        secret_key: "test-secret".to_string(),
+    });
+
+    store
+        .put("workspace/file", b"data".to_vec(), "text/plain")
+        .await
+        .unwrap();
+    assert!(
+        objects
+            .lock()
+            .unwrap()
+            .contains_key("remind/workspace/file")
+    );
+    assert_eq!(
+        store.get("workspace/file").await.unwrap(),
+        Some(b"data".to_vec())
+    );
+
+    store.delete("workspace/file").await.unwrap();
+    assert_eq!(store.get("workspace/file").await.unwrap(), None);
+}
+
+#[test]
+fn test_sniff_content_type() {
+    assert_eq!(sniff_content_type(&png_bytes(1, 1)), "image/png");
+    assert_eq!(sniff_content_type(b"plain text"), "text/plain");
+    assert_eq!(
+        sniff_content_type(&[0xff, 0x00, 0xfe]),
+        "application/octet-stream"
+    );
+
+    // Markup is never shown inline
+    assert!(is_inline_content_type(&sniff_content_type(&png_bytes(
+        1, 1
+    ))));
+    let html = sniff_content_type(b"<html><script>alert(1)</script></html>");
+    assert!(!is_inline_content_type(&html));
+    let xml = sniff_content_type(b"<?xml version=\"1.0\"?><svg></svg>");
+    assert!(!is_inline_content_type(&xml));
+    assert!(!is_inline_content_type("text/plain"));
+}
@@ -0,0 +1,216 @@
+use crate::fixtures::{
+    create_audit_service, create_block_service_with_audit, create_note_service_with_audit,
+    create_share_link_service_with_audit, create_user_fixture, create_user_repository,
+    create_user_service_with_audit, create_workspace_fixture, create_workspace_repo,
+};
+use remind_core::audit::AuditSink;
+use remind_core::render::RenderFormat;
+use remind_core::{
+    AuditAction, AuditContext, AuditEvent, AuditFilter, BlockContent, BlockCreateDTO, BlockType,
+    NoteCreateDTO, PlainTextContent, ShareLinkCreateDTO, TextSpan, UserLoginUsernameDTO,
+};
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+mod fixtures;
+
+fn context(actor_id: Option<Uuid>) -> AuditContext {
+    AuditContext {
+        actor_id,
+        ip: Some("203.0.113.7".to_string()),
+        user_agent: Some("curl/8.5.0".to_string()),
+    }
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_logins_are_recorded(pool: PgPool) {
+    let user = create_user_fixture(create_user_repository(pool.clone())).await;
+    let service = create_user_service_with_audit(pool.clone());
+    let login = |password: &str| UserLoginUsernameDTO {
+        username: user.username.clone(),
+        password: password.to_string(),
+    };
+
+    assert!(
+        service
+            .login_by_username(login("wrong"), &context(None))
+            .await
+            .is_err()
+    );
+    service
+        .login_by_username(login("password"), &context(None))
+        .await
+        .unwrap();
+
+    // Logins belong to the account, not to a workspace
+    let entries = create_audit_service(pool.clone())
+        .get_for_user(user.id, AuditFilter::default(), 10)
+        .await
+        .unwrap();
+    let ip = Some("203.0.113.7".to_string());
+    assert_eq!(
+        entries
+            .into_iter()
+            .map(|e| (e.action, e.actor_id, e.target_id, e.ip))
+            .collect::<Vec<_>>(),
+        vec![
+            (
+                AuditAction::TokenCreated,
+                Some(user.id),
+                Some(user.id),
+                ip.clone()
+            ),
+            (
+                AuditAction::LoginSucceeded,
+                Some(user.id),
+                Some(user.id),
+                ip.clone()
+            ),
+            (AuditAction::LoginFailed, None, Some(user.id), ip),
+        ]
+    );
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_workspace_actions_are_recorded(pool: PgPool) {
+    let user = create_user_fixture(create_user_repository(pool.clone())).await;
+    let workspace = create_workspace_fixture(create_workspace_repo(pool.clone()), user.id).await;
+    let notes = create_note_service_with_audit(pool.clone());
+    let blocks = create_block_service_with_audit(pool.clone());
+    let share_links = create_share_link_service_with_audit(pool.clone());
+    let audit = create_audit_service(pool.clone());
+    let ctx = context(Some(user.id));
+
+    let note = notes
+        .create(NoteCreateDTO {
+            title: "Payroll".to_string(),
+            workspace_id: workspace.id,
+            parent_note: None,
+            template_id: None,
+            author: None,
+        })
+        .await
+        .unwrap();
+    let block = blocks
+        .create(BlockCreateDTO {
+            block_type: BlockType::PlainText,
+            content: BlockContent::PlainText(PlainTextContent {
+                spans: vec![TextSpan::plain("Salaries")],
+            }),
+            note_id: note.id,
+            parent_block: None,
+        })
+        .await
+        .unwrap();
+    notes
+        .export(note.id, RenderFormat::Html, &ctx)
+        .await
+        .unwrap();
+    share_links
+        .create(
+            ShareLinkCreateDTO {
+                note_id: note.id,
+                created_by: user.id,
+                password: Some("secret".to_string()),
+                include_subpages: false,
+                expires_at: None,
+            },
+            &ctx,
+        )
+        .await
+        .unwrap();
+    blocks.delete(block.id, false, &ctx).await.unwrap();
+    notes.delete(note.id, &ctx).await.unwrap();
+
+    // The workspace of deleted notes is still known
+    let entries = audit
+        .get_in_workspace(workspace.id, AuditFilter::default(), 10)
+        .await
+        .unwrap();
+    let actions: Vec<_> = entries.iter().map(|e| e.action).collect();
+    assert_eq!(
+        actions,
+        vec![
+            AuditAction::NoteDeleted,
+            AuditAction::BlockDeleted,
+            AuditAction::ShareLinkCreated,
+            AuditAction::NoteExported,
+        ]
+    );
+    assert!(entries.iter().all(|e| e.note_id == Some(note.id)));
+    assert!(entries.iter().all(|e| e.actor_id == Some(user.id)));
+    assert_eq!(entries[0].details, json!({ "title": "Payroll" }));
+    assert_eq!(entries[1].target_id, Some(block.id));
+    assert_eq!(entries[2].details["has_password"], json!(true));
+    assert_eq!(entries[3].details["format"], json!("html"));
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_audit_log_is_filtered_and_append_only(pool: PgPool) {
+    let user = create_user_fixture(create_user_repository(pool.clone())).await;
+    let workspace = create_workspace_fixture(create_workspace_repo(pool.clone()), user.id).await;
+    let audit = create_audit_service(pool.clone());
+    let other_actor = Uuid::new_v4();
+
+    for (action, actor_id) in [
+        (AuditAction::NoteExported, user.id),
+        (AuditAction::NoteDeleted, other_actor),
+        (AuditAction::NoteExported, user.id),
+        (AuditAction::BlockDeleted, user.id),
+    ] {
+        let mut event = AuditEvent::new(action, &context(Some(actor_id)));
+        event.workspace_id = Some(workspace.id);
+        audit.record(event).await.unwrap();
+    }
+
+    let filter = AuditFilter {
+        actions: vec![AuditAction::NoteExported, AuditAction::NoteDeleted],
+        ..Default::default()
+    };
+    let page = audit
+        .get_in_workspace(workspace.id, filter.clone(), 2)
+        .await
+        .unwrap();
+    assert_eq!(
+        page.iter().map(|e| e.action).collect::<Vec<_>>(),
+        vec![AuditAction::NoteExported, AuditAction::NoteDeleted]
+    );
+    let next = AuditFilter {
+        before: page.last().map(|e| e.id),
+        ..filter
+    };
+    let page = audit.get_in_workspace(workspace.id, next, 2).await.unwrap();
+    assert_eq!(page.len(), 1);
+
+    let by_actor = AuditFilter {
+        actor_id: Some(other_actor),
+        ..Default::default()
+    };
+    let entries = audit
+        .get_in_workspace(workspace.id, by_actor, 10)
+        .await
+        .unwrap();
+    assert_eq!(entries.len(), 1);
+    assert!(
+        audit
+            .get_in_workspace(Uuid::new_v4(), AuditFilter::default(), 10)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+
+    // Entries can't be changed or removed
+    assert!(
+        sqlx::query("UPDATE audit_log SET actor_id = NULL")
+            .execute(&pool)
+            .await
+            .is_err()
+    );
+    assert!(
+        sqlx::query("DELETE FROM audit_log")
+            .execute(&pool)
+            .await
+            .is_err()
+    );
+}
@@ -3,9 +3,12 @@ use crate::fixtures::{
     create_workspace_fixture, create_workspace_repo,
 };
 use remind_core::{
-    BlockContent, BlockCreateDTO, BlockType, ImageContent, NoteCreateDTO, NoteDTO, PlainTextContent,
+    BlockContent, BlockCreateDTO, BlockType, ImageContent, NoteCreateDTO, NoteDTO,
+    PlainTextContent, TextMark, TextSpan,
 };
+use serde_json::json;
 use sqlx::PgPool;
+use uuid::Uuid;
 
 mod fixtures;
 
@@ -19,9 +22,11 @@ async fn create_note(pool: PgPool) -> NoteDTO {
         title: "Note".to_string(),
         workspace_id: workspace.id,
         parent_note: None,
+        template_id: None,
+        author: None,
     };
-    let note = service.create(dto.clone()).await.unwrap();
-    note
+
+    service.create(dto.clone()).await.unwrap()
 }
 
 #[sqlx::test(migrations = "../../migrations")]
@@ -31,10 +36,9 @@ async fn test_create_block(pool: PgPool) {
 
     let dto = BlockCreateDTO {
         block_type: BlockType::PlainText,
-        content: BlockContent::PlainText(PlainTextContent {
-            text: "Test".to_string(),
-        }),
+        content: BlockContent::PlainText(PlainTextContent::plain("Test")),
         note_id: note.id,
+        parent_block: None,
     };
     let block = block_service.create(dto.clone()).await.unwrap();
     assert_eq!(block.block_type, dto.block_type);
@@ -45,9 +49,110 @@ async fn test_create_block(pool: PgPool) {
         content: BlockContent::Image(ImageContent {
             url: "https://github.com/sqlmerr/remind".to_string(),
             alt: None,
+            attachment_id: None,
+            width: None,
+            height: None,
         }),
         note_id: note.id,
+        parent_block: None,
     };
     let fail = block_service.create(dto2.clone()).await;
     assert!(fail.is_err()) // Type not matches content type
 }
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_legacy_plain_text_rows(pool: PgPool) {
+    let note = create_note(pool.clone()).await;
+    let block_service = create_block_service(pool.clone());
+
+    let id = Uuid::new_v4();
+    sqlx::query(
+        r#"INSERT INTO blocks (id, block_type, content, note_id, position) VALUES ($1, 'PlainText', $2, $3, 0)"#,
+    )
+    .bind(id)
+    .bind(json!({"type": "PlainText", "text": "old row"}))
+    .bind(note.id)
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let block = block_service.find_one(id).await.unwrap();
+    let BlockContent::PlainText(content) = &block.content else {
+        panic!("Expected plain text content");
+    };
+    assert_eq!(content.spans, vec![TextSpan::plain("old row")]);
+
+    let serialized = serde_json::to_value(&block.content).unwrap();
+    assert_eq!(serialized["version"], 2);
+    assert_eq!(serialized["text"], "old row");
+    assert_eq!(serialized["spans"][0]["text"], "old row");
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_rich_text_validation(pool: PgPool) {
+    let note = create_note(pool.clone()).await;
+    let block_service = create_block_service(pool);
+
+    let rich = |spans: Vec<TextSpan>| BlockCreateDTO {
+        block_type: BlockType::PlainText,
+        content: BlockContent::PlainText(PlainTextContent { spans }),
+        note_id: note.id,
+        parent_block: None,
+    };
+
+    let block = block_service
+        .create(rich(vec![
+            TextSpan {
+                text: "bold ".to_string(),
+                marks: vec![TextMark::Bold],
+                href: None,
+                mention: None,
+                broken: false,
+            },
+            TextSpan {
+                text: "text".to_string(),
+                marks: vec![TextMark::Bold],
+                href: None,
+                mention: None,
+                broken: false,
+            },
+            TextSpan::plain(""),
+            TextSpan {
+                text: " link".to_string(),
+                marks: vec![],
+                href: Some("https://example.com".to_string()),
+                mention: None,
+                broken: false,
+            },
+        ]))
+        .await
+        .unwrap();
+    let BlockContent::PlainText(content) = &block.content else {
+        panic!("Expected plain text content");
+    };
+    assert_eq!(content.spans.len(), 2);
+    assert_eq!(content.spans[0].text, "bold text");
+    assert_eq!(block.content.plain_text(), "bold text link");
+
+    let duplicate_marks = block_service
+        .create(rich(vec![TextSpan {
+            text: "x".to_string(),
+            marks: vec![TextMark::Italic, TextMark::Italic],
+            href: None,
+            mention: None,
+            broken: false,
+        }]))
+        .await;
+    assert!(duplicate_marks.is_err());
+
+    let script_link = block_service
+        .create(rich(vec![TextSpan {
+            text: "x".to_string(),
+            marks: vec![],
+            href: Some("javascript:alert(1)".to_string()),
+            mention: None,
+            broken: false,
+        }]))
+        .await;
+    assert!(script_link.is_err());
+}
@@ -0,0 +1,185 @@
+use crate::fixtures::{
+    create_block_service_with_previews, create_note_service, create_user_fixture,
+    create_user_repository, create_workspace_fixture, create_workspace_repo, spawn_page_stand_in,
+    test_limits,
+};
+use remind_core::errors::CoreError;
+use remind_core::previews::{HttpLinkPreviewer, LinkPreviewer, PreviewLimits, is_public};
+use remind_core::{
+    BlockContent, BlockCreateDTO, BlockDTO, BlockRepository, BlockService, BlockType,
+    BlockUpdateDTO, BookmarkContent, ChangeRepository, NoteCreateDTO, NoteDTO,
+};
+use sqlx::PgPool;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+mod fixtures;
+
+async fn create_note(pool: PgPool) -> NoteDTO {
+    let user_repo = create_user_repository(pool.clone());
+    let user = create_user_fixture(user_repo).await;
+    let service = create_note_service(pool.clone());
+    let workspace = create_workspace_fixture(create_workspace_repo(pool), user.id).await;
+
+    let dto = NoteCreateDTO {
+        title: "Reading list".to_string(),
+        workspace_id: workspace.id,
+        parent_note: None,
+        template_id: None,
+        author: None,
+    };
+
+    service.create(dto.clone()).await.unwrap()
+}
+
+async fn create_bookmark(
+    service: &BlockService<BlockRepository, ChangeRepository>,
+    note_id: uuid::Uuid,
+    url: &str,
+) -> BlockDTO {
+    service
+        .create(BlockCreateDTO {
+            block_type: BlockType::Bookmark,
+            content: BlockContent::Bookmark(BookmarkContent {
+                url: url.to_string(),
+                preview: None,
+            }),
+            note_id,
+            parent_block: None,
+        })
+        .await
+        .unwrap()
+}
+
+fn bookmark(block: &BlockDTO) -> &BookmarkContent {
+    match &block.content {
+        BlockContent::Bookmark(c) => c,
+        content => panic!("Expected a bookmark, got {content:?}"),
+    }
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_bookmark_preview_is_cached(pool: PgPool) {
+    let (base, hits) = spawn_page_stand_in().await;
+    let note = create_note(pool.clone()).await;
+    let service = create_block_service_with_previews(pool, test_limits());
+
+    let block = create_bookmark(&service, note.id, &format!("{base}/redirect")).await;
+    let preview = bookmark(&block).preview.clone().unwrap();
+    assert_eq!(preview.title.as_deref(), Some("Rust & Friends"));
+    assert_eq!(preview.description.as_deref(), Some("A page about Rust"));
+    assert_eq!(
+        preview.favicon.as_deref(),
+        Some(format!("{base}/static/icon.png").as_str())
+    );
+    assert_eq!(hits.load(Ordering::SeqCst), 1);
+
+    // Same URL: the cached preview is kept, client previews are ignored
+    let mut content = bookmark(&block).clone();
+    content.preview.as_mut().unwrap().title = Some("Spoofed".to_string());
+    service
+        .update(BlockUpdateDTO {
+            id: block.id,
+            block_type: None,
+            content: Some(BlockContent::Bookmark(content)),
+            version: None,
+        })
+        .await
+        .unwrap();
+    let block = service.find_one(block.id).await.unwrap();
+    assert_eq!(bookmark(&block).preview, Some(preview.clone()));
+    assert_eq!(hits.load(Ordering::SeqCst), 1);
+
+    let block = service.refresh_preview(block.id).await.unwrap();
+    assert_eq!(hits.load(Ordering::SeqCst), 2);
+    assert!(bookmark(&block).preview.as_ref().unwrap().fetched_at > preview.fetched_at);
+
+    // Only http and https links are bookmarks
+    let result = service
+        .update(BlockUpdateDTO {
+            id: block.id,
+            block_type: None,
+            content: Some(BlockContent::Bookmark(BookmarkContent {
+                url: "file:///etc/passwd".to_string(),
+                preview: None,
+            })),
+            version: None,
+        })
+        .await;
+    assert!(matches!(result, Err(CoreError::InvalidBlockContent(_))));
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_bookmark_private_addresses_blocked(pool: PgPool) {
+    let (base, hits) = spawn_page_stand_in().await;
+    let port = base.rsplit(':').next().unwrap().to_string();
+    let note = create_note(pool.clone()).await;
+    let service = create_block_service_with_previews(pool, PreviewLimits::default());
+
+    for url in [
+        format!("{base}/page"),
+        format!("http://127.0.0.1:{port}/page"),
+        format!("http://[::1]:{port}/page"),
+        "http://10.0.0.1/page".to_string(),
+        "http://169.254.169.254/latest/meta-data".to_string(),
+        "http://[::ffff:127.0.0.1]/page".to_string(),
+        // Prefixes that embed an IPv4 address
+        "http://[64:ff9b::7f00:1]/page".to_string(),
+        "http://[2002:7f00:1::]/page".to_string(),
+        "http://[2001:0:4136:e378:8000:63bf:80ff:fffe]/page".to_string(),
+        "http://[::7f00:1]/page".to_string(),
+        "http://[::a00:1]/page".to_string(),
+    ] {
+        // The block is still saved, just without a preview
+        let block = create_bookmark(&service, note.id, &url).await;
+        assert!(bookmark(&block).preview.is_none(), "{url}");
+
+        let result = service.refresh_preview(block.id).await;
+        assert!(
+            matches!(result, Err(CoreError::LinkPreviewBlocked)),
+            "{url}"
+        );
+    }
+    assert_eq!(hits.load(Ordering::SeqCst), 0);
+}
+
+#[test]
+fn test_public_addresses() {
+    for ip in [
+        "93.184.215.14",
+        "2606:4700:4700::1111",
+        "2001:4860:4860::8888",
+    ] {
+        assert!(is_public(ip.parse().unwrap()), "{ip}");
+    }
+    for ip in [
+        "100.64.0.1",
+        "fd00::1",
+        "::ffff:10.0.0.1",
+        "64:ff9b:1::a00:1",
+        "2002:a00:1::",
+        "2001::1",
+        "::7f00:1",
+    ] {
+        assert!(!is_public(ip.parse().unwrap()), "{ip}");
+    }
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_bookmark_preview_limits(_pool: PgPool) {
+    let (base, _) = spawn_page_stand_in().await;
+    let previewer = HttpLinkPreviewer::new(PreviewLimits {
+        max_body_size: 64 * 1024,
+        timeout: Duration::from_millis(500),
+        ..test_limits()
+    });
+
+    for path in ["/loop", "/slow", "/image", "/missing"] {
+        let result = previewer.preview(&format!("{base}{path}")).await;
+        assert!(matches!(result, Err(CoreError::LinkPreview(_))), "{path}");
+    }
+
+    // Large pages are cut off instead of read whole
+    let preview = previewer.preview(&format!("{base}/big")).await.unwrap();
+    assert!(preview.title.is_none());
+}
@@ -0,0 +1,280 @@
+use crate::fixtures::{
+    create_block_service, create_change_repo, create_change_service, create_note_service,
+    create_user_fixture, create_user_repository, create_workspace_fixture, create_workspace_repo,
+};
+use remind_core::{
+    AuditContext, BlockContent, BlockCreateDTO, BlockDTO, BlockRepository, BlockService, BlockType,
+    ChangeDTO, ChangeRepo, ChangeRepository, ChangeType, NoteCreateDTO, NoteDTO, NoteUpdateDTO,
+    PlainTextContent, Workspace,
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+mod fixtures;
+
+async fn create_workspace(pool: PgPool) -> Workspace {
+    let user = create_user_fixture(create_user_repository(pool.clone())).await;
+    create_workspace_fixture(create_workspace_repo(pool), user.id).await
+}
+
+async fn create_note(pool: PgPool, workspace_id: Uuid, title: &str) -> NoteDTO {
+    create_note_service(pool)
+        .create(NoteCreateDTO {
+            title: title.to_string(),
+            workspace_id,
+            parent_note: None,
+            template_id: None,
+            author: None,
+        })
+        .await
+        .unwrap()
+}
+
+async fn add_text(
+    service: &BlockService<BlockRepository, ChangeRepository>,
+    note_id: Uuid,
+    text: &str,
+) -> BlockDTO {
+    service
+        .create(BlockCreateDTO {
+            block_type: BlockType::PlainText,
+            content: BlockContent::PlainText(PlainTextContent::plain(text)),
+            note_id,
+            parent_block: None,
+        })
+        .await
+        .unwrap()
+}
+
+fn kinds(changes: &[ChangeDTO]) -> Vec<ChangeType> {
+    changes.iter().map(|c| c.change_type).collect()
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_note_changes_are_logged(pool: PgPool) {
+    let workspace = create_workspace(pool.clone()).await;
+    let changes = create_change_service(pool.clone());
+    let service = create_note_service(pool.clone());
+    assert_eq!(changes.get_last_id(&[workspace.id]).await.unwrap(), 0);
+
+    let note = create_note(pool.clone(), workspace.id, "Plan").await;
+    let rename = |title: &str| NoteUpdateDTO {
+        title: Some(title.to_string()),
+        version: None,
+    };
+    service.update(note.id, rename("Plan B")).await.unwrap();
+    // Saving the same title isn't a rename
+    service.update(note.id, rename("Plan B")).await.unwrap();
+    service
+        .delete(note.id, &AuditContext::default())
+        .await
+        .unwrap();
+
+    let logged = changes.get_after(&[workspace.id], 0, 100).await.unwrap();
+    assert_eq!(
+        kinds(&logged),
+        vec![
+            ChangeType::NoteCreated,
+            ChangeType::NoteRenamed,
+            ChangeType::NoteDeleted
+        ]
+    );
+    assert!(logged.iter().all(|c| c.note_id == note.id));
+    assert!(logged.iter().all(|c| c.block_id.is_none()));
+    assert!(logged.windows(2).all(|w| w[0].id < w[1].id));
+    assert_eq!(
+        changes.get_last_id(&[workspace.id]).await.unwrap(),
+        logged[2].id
+    );
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_block_changes_are_logged(pool: PgPool) {
+    let workspace = create_workspace(pool.clone()).await;
+    let note = create_note(pool.clone(), workspace.id, "Plan").await;
+    let changes = create_change_service(pool.clone());
+    let service = create_block_service(pool.clone());
+    let after = changes.get_last_id(&[workspace.id]).await.unwrap();
+
+    let first = add_text(&service, note.id, "One").await;
+    let second = add_text(&service, note.id, "Two").await;
+    service.move_block(second.id, None).await.unwrap();
+    service
+        .delete(second.id, false, &AuditContext::default())
+        .await
+        .unwrap();
+    create_note_service(pool.clone())
+        .reorder_blocks(note.id, None, vec![first.id], None)
+        .await
+        .unwrap();
+
+    let logged = changes
+        .get_after(&[workspace.id], after, 100)
+        .await
+        .unwrap();
+    assert_eq!(
+        kinds(&logged),
+        vec![
+            ChangeType::BlockCreated,
+            ChangeType::BlockCreated,
+            ChangeType::BlockUpdated,
+            ChangeType::BlockDeleted,
+            ChangeType::BlocksReordered
+        ]
+    );
+    let blocks: Vec<Option<Uuid>> = logged.iter().map(|c| c.block_id).collect();
+    assert_eq!(
+        blocks,
+        vec![
+            Some(first.id),
+            Some(second.id),
+            Some(second.id),
+            Some(second.id),
+            None
+        ]
+    );
+    assert!(logged.iter().all(|c| c.workspace_id == workspace.id));
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_changes_are_read_in_pages(pool: PgPool) {
+    let workspace = create_workspace(pool.clone()).await;
+    let user_id = workspace.user_id;
+    let other = create_workspace_fixture(create_workspace_repo(pool.clone()), user_id).await;
+    let changes = create_change_service(pool.clone());
+
+    for title in ["One", "Two", "Three"] {
+        create_note(pool.clone(), workspace.id, title).await;
+        create_note(pool.clone(), other.id, title).await;
+    }
+
+    // Clients resume after the last change they've seen
+    let page = changes.get_after(&[workspace.id], 0, 2).await.unwrap();
+    assert_eq!(page.len(), 2);
+    let rest = changes
+        .get_after(&[workspace.id], page[1].id, 2)
+        .await
+        .unwrap();
+    assert_eq!(rest.len(), 1);
+    assert!(rest[0].id > page[1].id);
+    assert!(
+        changes
+            .get_after(&[workspace.id], rest[0].id, 2)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+
+    // Each workspace only sees its own changes
+    let theirs = changes.get_after(&[other.id], 0, 100).await.unwrap();
+    assert_eq!(theirs.len(), 3);
+    assert!(theirs.iter().all(|c| c.workspace_id == other.id));
+    assert_eq!(
+        changes.get_last_id(&[other.id]).await.unwrap(),
+        theirs[2].id
+    );
+    assert!(
+        changes
+            .get_after(&[Uuid::new_v4()], 0, 0)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_changes_are_read_in_commit_order(pool: PgPool) {
+    let workspace = create_workspace(pool.clone()).await;
+    let note = create_note(pool.clone(), workspace.id, "Plan").await;
+    let changes = create_change_service(pool.clone());
+    let repo = create_change_repo(pool.clone());
+    let after = changes.get_last_id(&[workspace.id]).await.unwrap();
+
+    // The first change logged commits last
+    let mut first = repo.begin().await.unwrap();
+    repo.create(
+        &mut first,
+        workspace.id,
+        ChangeType::NoteRenamed,
+        note.id,
+        None,
+    )
+    .await
+    .unwrap();
+    let mut second = repo.begin().await.unwrap();
+    repo.create(
+        &mut second,
+        workspace.id,
+        ChangeType::NoteDeleted,
+        note.id,
+        None,
+    )
+    .await
+    .unwrap();
+    second.commit().await.unwrap();
+
+    let seen = changes
+        .get_after(&[workspace.id], after, 100)
+        .await
+        .unwrap();
+    assert_eq!(kinds(&seen), vec![ChangeType::NoteDeleted]);
+
+    // Resuming after the change seen still gets the one committed later
+    first.commit().await.unwrap();
+    let rest = changes
+        .get_after(&[workspace.id], seen[0].id, 100)
+        .await
+        .unwrap();
+    assert_eq!(kinds(&rest), vec![ChangeType::NoteRenamed]);
+
+    // Changes rolled back are never logged
+    let mut rolled_back = repo.begin().await.unwrap();
+    repo.create(
+        &mut rolled_back,
+        workspace.id,
+        ChangeType::NoteDeleted,
+        note.id,
+        None,
+    )
+    .await
+    .unwrap();
+    rolled_back.rollback().await.unwrap();
+    assert!(
+        changes
+            .get_after(&[workspace.id], rest[0].id, 100)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_readers_dont_wait_for_sequencing(pool: PgPool) {
+    let workspace = create_workspace(pool.clone()).await;
+    let changes = create_change_service(pool.clone());
+    let after = changes.get_last_id(&[workspace.id]).await.unwrap();
+    create_note(pool.clone(), workspace.id, "Plan").await;
+
+    // Another reader is placing changes
+    let mut sequencing = pool.begin().await.unwrap();
+    sqlx::query("SELECT pg_advisory_xact_lock($1)")
+        .bind(0x6368_616e_6765_i64)
+        .execute(&mut *sequencing)
+        .await
+        .unwrap();
+    let workspaces = [workspace.id];
+    let read = changes.get_after(&workspaces, after, 100);
+    let seen = tokio::time::timeout(std::time::Duration::from_secs(5), read)
+        .await
+        .expect("Reader waited for the sequencing lock")
+        .unwrap();
+    assert!(seen.is_empty());
+
+    // The change is placed once the lock is free again
+    sequencing.rollback().await.unwrap();
+    let seen = changes
+        .get_after(&[workspace.id], after, 100)
+        .await
+        .unwrap();
+    assert_eq!(kinds(&seen), vec![ChangeType::NoteCreated]);
+}
@@ -0,0 +1,91 @@
+use remind_core::collab::{CollabHub, Collaborator, NoteEvent, NoteSession};
+use tokio::sync::broadcast::error::RecvError;
+use uuid::Uuid;
+
+fn collaborator(username: &str) -> Collaborator {
+    Collaborator {
+        user_id: Uuid::new_v4(),
+        username: username.to_string(),
+    }
+}
+
+async fn presence(session: &mut NoteSession) -> Vec<String> {
+    match session.recv().await.unwrap() {
+        NoteEvent::Presence(users) => users.into_iter().map(|u| u.username).collect(),
+        event => panic!("expected presence, got {event:?}"),
+    }
+}
+
+#[tokio::test]
+async fn test_events_reach_every_viewer() {
+    let hub = CollabHub::new();
+    let note_id = Uuid::new_v4();
+    let mut first = hub.join(note_id, collaborator("ada"));
+    let mut second = hub.join(note_id, collaborator("bob"));
+    let mut other_note = hub.join(Uuid::new_v4(), collaborator("eve"));
+
+    assert_eq!(presence(&mut first).await, vec!["ada"]);
+    assert_eq!(presence(&mut first).await, vec!["ada", "bob"]);
+    assert_eq!(presence(&mut second).await, vec!["ada", "bob"]);
+    assert_eq!(presence(&mut other_note).await, vec!["eve"]);
+
+    let block_id = Uuid::new_v4();
+    hub.publish(note_id, NoteEvent::BlockDeleted { id: block_id });
+    for session in [&mut first, &mut second] {
+        assert!(matches!(
+            session.recv().await,
+            Ok(NoteEvent::BlockDeleted { id }) if id == block_id
+        ));
+    }
+    // Other notes only get their own events
+    hub.publish(other_note.note_id(), NoteEvent::Presence(Vec::new()));
+    assert_eq!(presence(&mut other_note).await, Vec::<String>::new());
+}
+
+#[tokio::test]
+async fn test_presence_follows_connections() {
+    let hub = CollabHub::new();
+    let note_id = Uuid::new_v4();
+    let ada = collaborator("ada");
+
+    let zoe = collaborator("zoe");
+    let mut watcher = hub.join(note_id, zoe.clone());
+    let first_tab = hub.join(note_id, ada.clone());
+    let second_tab = hub.join(note_id, ada.clone());
+    // A user with two tabs open is listed once
+    assert_eq!(hub.presence(note_id), vec![ada.clone(), zoe]);
+
+    drop(first_tab);
+    assert_eq!(hub.presence(note_id).len(), 2);
+    drop(second_tab);
+    assert_eq!(hub.presence(note_id).len(), 1);
+
+    let mut seen = Vec::new();
+    for _ in 0..5 {
+        seen.push(presence(&mut watcher).await);
+    }
+    assert_eq!(seen.last().unwrap(), &vec!["zoe"]);
+    assert_eq!(seen[1], vec!["ada", "zoe"]);
+
+    // The room goes away with its last viewer
+    drop(watcher);
+    assert!(hub.presence(note_id).is_empty());
+    hub.publish(note_id, NoteEvent::BlockDeleted { id: Uuid::new_v4() });
+}
+
+#[tokio::test]
+async fn test_slow_viewer_has_to_resync() {
+    let hub = CollabHub::new();
+    let note_id = Uuid::new_v4();
+    let mut session = hub.join(note_id, collaborator("ada"));
+
+    for _ in 0..1000 {
+        hub.publish(note_id, NoteEvent::BlockDeleted { id: Uuid::new_v4() });
+    }
+    assert!(matches!(session.recv().await, Err(RecvError::Lagged(_))));
+    assert!(matches!(
+        session.recv().await,
+        Ok(NoteEvent::BlockDeleted { .. })
+    ));
+    assert_eq!(session.note_id(), note_id);
+}
@@ -0,0 +1,384 @@
+use crate::fixtures::{
+    create_collection_service, create_note_service, create_user_fixture, create_user_repository,
+    create_workspace_fixture, create_workspace_repo,
+};
+use remind_core::{
+    CollectionPropertyDTO, FilterOp, NoteCreateDTO, NoteDTO, PropertyCreateDTO, PropertyType,
+    PropertyUpdateDTO, PropertyValue, ViewCreateDTO, ViewFilter, ViewSort, ViewType, ViewUpdateDTO,
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+mod fixtures;
+
+/// Collection note with three child notes
+async fn create_collection(pool: PgPool) -> (NoteDTO, Vec<NoteDTO>) {
+    let user_repo = create_user_repository(pool.clone());
+    let user = create_user_fixture(user_repo).await;
+    let service = create_note_service(pool.clone());
+    let workspace = create_workspace_fixture(create_workspace_repo(pool), user.id).await;
+
+    let collection = service
+        .create(NoteCreateDTO {
+            title: "Tasks".to_string(),
+            workspace_id: workspace.id,
+            parent_note: None,
+            template_id: None,
+            author: None,
+        })
+        .await
+        .unwrap();
+    let mut children = Vec::new();
+    for title in ["Write docs", "Fix login", "Release"] {
+        let child = service
+            .create(NoteCreateDTO {
+                title: title.to_string(),
+                workspace_id: workspace.id,
+                parent_note: Some(collection.id),
+                template_id: None,
+                author: None,
+            })
+            .await
+            .unwrap();
+        children.push(child);
+    }
+    (collection, children)
+}
+
+async fn create_property(
+    pool: PgPool,
+    note_id: Uuid,
+    name: &str,
+    property_type: PropertyType,
+    options: &[&str],
+) -> CollectionPropertyDTO {
+    create_collection_service(pool)
+        .create_property(PropertyCreateDTO {
+            note_id,
+            name: name.to_string(),
+            property_type,
+            options: options.iter().map(|o| o.to_string()).collect(),
+        })
+        .await
+        .unwrap()
+}
+
+fn text(value: &str) -> Option<PropertyValue> {
+    Some(PropertyValue::Text(value.to_string()))
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_property_values(pool: PgPool) {
+    let (collection, children) = create_collection(pool.clone()).await;
+    let service = create_collection_service(pool.clone());
+
+    let status = create_property(
+        pool.clone(),
+        collection.id,
+        "Status",
+        PropertyType::Select,
+        &["Todo", "Done"],
+    )
+    .await;
+    let tags = create_property(
+        pool.clone(),
+        collection.id,
+        "Tags",
+        PropertyType::MultiSelect,
+        &["bug", "docs"],
+    )
+    .await;
+    let due = create_property(pool.clone(), collection.id, "Due", PropertyType::Date, &[]).await;
+    assert_eq!(due.position, 2);
+
+    let note = children[0].id;
+    service
+        .set_value(note, status.id, text("Done"))
+        .await
+        .unwrap();
+    service
+        .set_value(
+            note,
+            tags.id,
+            Some(PropertyValue::List(vec!["docs".to_string()])),
+        )
+        .await
+        .unwrap();
+    service
+        .set_value(note, due.id, text("2025-07-01"))
+        .await
+        .unwrap();
+    let values = service.get_values(note).await.unwrap();
+    assert_eq!(values.len(), 3);
+    assert_eq!(values[&status.id], PropertyValue::Text("Done".to_string()));
+
+    service.set_value(note, due.id, None).await.unwrap();
+    assert_eq!(service.get_values(note).await.unwrap().len(), 2);
+
+    assert!(
+        service
+            .set_value(note, status.id, text("Blocked"))
+            .await
+            .is_err()
+    );
+    assert!(
+        service
+            .set_value(note, due.id, text("tomorrow"))
+            .await
+            .is_err()
+    );
+    let duplicate_tags = PropertyValue::List(vec!["bug".to_string(), "bug".to_string()]);
+    assert!(
+        service
+            .set_value(note, tags.id, Some(duplicate_tags))
+            .await
+            .is_err()
+    );
+    // The collection itself is not one of its rows
+    assert!(
+        service
+            .set_value(collection.id, status.id, text("Todo"))
+            .await
+            .is_err()
+    );
+
+    let options_on_text = service
+        .create_property(PropertyCreateDTO {
+            note_id: collection.id,
+            name: "Notes".to_string(),
+            property_type: PropertyType::Text,
+            options: vec!["a".to_string()],
+        })
+        .await;
+    assert!(options_on_text.is_err());
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_removed_options_clear_values(pool: PgPool) {
+    let (collection, children) = create_collection(pool.clone()).await;
+    let service = create_collection_service(pool.clone());
+    let status = create_property(
+        pool.clone(),
+        collection.id,
+        "Status",
+        PropertyType::Select,
+        &["Todo", "Doing", "Done"],
+    )
+    .await;
+    let tags = create_property(
+        pool.clone(),
+        collection.id,
+        "Tags",
+        PropertyType::MultiSelect,
+        &["bug", "docs", "ui"],
+    )
+    .await;
+    let list = |items: &[&str]| {
+        Some(PropertyValue::List(
+            items.iter().map(|i| i.to_string()).collect(),
+        ))
+    };
+
+    let (first, second) = (children[0].id, children[1].id);
+    service
+        .set_value(first, status.id, text("Doing"))
+        .await
+        .unwrap();
+    service
+        .set_value(second, status.id, text("Done"))
+        .await
+        .unwrap();
+    service
+        .set_value(first, tags.id, list(&["ui", "bug", "docs"]))
+        .await
+        .unwrap();
+    service
+        .set_value(second, tags.id, list(&["docs"]))
+        .await
+        .unwrap();
+
+    let update = |options: &[&str]| PropertyUpdateDTO {
+        name: None,
+        options: Some(options.iter().map(|o| o.to_string()).collect()),
+    };
+    service
+        .update_property(status.id, update(&["Todo", "Done"]))
+        .await
+        .unwrap();
+    service
+        .update_property(tags.id, update(&["bug", "ui"]))
+        .await
+        .unwrap();
+
+    let values = service.get_values(first).await.unwrap();
+    assert!(!values.contains_key(&status.id));
+    assert_eq!(values.get(&tags.id), list(&["ui", "bug"]).as_ref());
+    let values = service.get_values(second).await.unwrap();
+    assert_eq!(values.get(&status.id), text("Done").as_ref());
+    assert!(!values.contains_key(&tags.id));
+
+    // What is left can be set again as is
+    service
+        .set_value(first, tags.id, list(&["ui", "bug"]))
+        .await
+        .unwrap();
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_view_filters_and_sorts(pool: PgPool) {
+    let (collection, children) = create_collection(pool.clone()).await;
+    let service = create_collection_service(pool.clone());
+
+    let tags = create_property(
+        pool.clone(),
+        collection.id,
+        "Tags",
+        PropertyType::MultiSelect,
+        &["bug", "docs"],
+    )
+    .await;
+    let estimate = create_property(
+        pool.clone(),
+        collection.id,
+        "Estimate",
+        PropertyType::Number,
+        &[],
+    )
+    .await;
+
+    let estimates = [3.0, 1.0, 8.0];
+    for (child, value) in children.iter().zip(estimates) {
+        service
+            .set_value(child.id, estimate.id, Some(PropertyValue::Number(value)))
+            .await
+            .unwrap();
+    }
+    service
+        .set_value(
+            children[1].id,
+            tags.id,
+            Some(PropertyValue::List(vec!["bug".to_string()])),
+        )
+        .await
+        .unwrap();
+
+    let view = service
+        .create_view(ViewCreateDTO {
+            note_id: collection.id,
+            name: "Small first".to_string(),
+            view_type: ViewType::Table,
+            filters: vec![ViewFilter {
+                property_id: estimate.id,
+                op: FilterOp::LessThan,
+                value: Some(PropertyValue::Number(5.0)),
+            }],
+            sorts: vec![ViewSort {
+                property_id: estimate.id,
+                descending: false,
+            }],
+            group_by: None,
+        })
+        .await
+        .unwrap();
+    let result = service.query_view(view.id).await.unwrap();
+    let titles: Vec<&str> = result.rows.iter().map(|r| r.title.as_str()).collect();
+    assert_eq!(titles, vec!["Fix login", "Write docs"]);
+    assert!(result.groups.is_empty());
+
+    let view = service
+        .update_view(
+            view.id,
+            ViewUpdateDTO {
+                name: "Untagged".to_string(),
+                view_type: ViewType::List,
+                filters: vec![ViewFilter {
+                    property_id: tags.id,
+                    op: FilterOp::IsEmpty,
+                    value: None,
+                }],
+                sorts: vec![ViewSort {
+                    property_id: estimate.id,
+                    descending: true,
+                }],
+                group_by: None,
+            },
+        )
+        .await
+        .unwrap();
+    let result = service.query_view(view.id).await.unwrap();
+    let titles: Vec<&str> = result.rows.iter().map(|r| r.title.as_str()).collect();
+    assert_eq!(titles, vec!["Release", "Write docs"]);
+
+    // Filters on deleted properties no longer apply
+    service.delete_property(tags.id).await.unwrap();
+    let result = service.query_view(view.id).await.unwrap();
+    assert_eq!(result.rows.len(), 3);
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_board_view(pool: PgPool) {
+    let (collection, children) = create_collection(pool.clone()).await;
+    let service = create_collection_service(pool.clone());
+
+    let status = create_property(
+        pool.clone(),
+        collection.id,
+        "Status",
+        PropertyType::Select,
+        &["Todo", "Doing", "Done"],
+    )
+    .await;
+    let assignee = create_property(
+        pool.clone(),
+        collection.id,
+        "Assignee",
+        PropertyType::Person,
+        &[],
+    )
+    .await;
+
+    service
+        .set_value(children[0].id, status.id, text("Doing"))
+        .await
+        .unwrap();
+    service
+        .set_value(children[2].id, status.id, text("Doing"))
+        .await
+        .unwrap();
+    service
+        .set_value(
+            children[1].id,
+            assignee.id,
+            text(&Uuid::new_v4().to_string()),
+        )
+        .await
+        .unwrap();
+
+    let board = |group_by: Option<Uuid>| ViewCreateDTO {
+        note_id: collection.id,
+        name: "Board".to_string(),
+        view_type: ViewType::Board,
+        filters: vec![],
+        sorts: vec![],
+        group_by,
+    };
+    assert!(service.create_view(board(None)).await.is_err());
+    assert!(service.create_view(board(Some(assignee.id))).await.is_err());
+
+    let view = service.create_view(board(Some(status.id))).await.unwrap();
+    let result = service.query_view(view.id).await.unwrap();
+    let groups: Vec<(Option<&str>, usize)> = result
+        .groups
+        .iter()
+        .map(|g| (g.value.as_deref(), g.rows.len()))
+        .collect();
+    assert_eq!(
+        groups,
+        vec![
+            (Some("Todo"), 0),
+            (Some("Doing"), 2),
+            (Some("Done"), 0),
+            (None, 1)
+        ]
+    );
+}
@@ -0,0 +1,299 @@
+use crate::fixtures::{
+    create_block_service, create_comment_service, create_note_service, create_user_fixture,
+    create_user_repository, create_workspace_fixture, create_workspace_repo,
+};
+use remind_auth::hash_password;
+use remind_core::errors::CoreError;
+use remind_core::{
+    AuditContext, BlockContent, BlockCreateDTO, BlockDTO, BlockRepository, BlockService, BlockType,
+    ChangeRepository, CommentCreateDTO, CommentThreadDTO, NoteCreateDTO, NoteDTO, PlainTextContent,
+    TextSpan, ToggleContent, User, UserRepo,
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+mod fixtures;
+
+/// Note of a new workspace and the id of its owner
+async fn create_note(pool: PgPool) -> (Uuid, NoteDTO) {
+    let user_repo = create_user_repository(pool.clone());
+    let user = create_user_fixture(user_repo).await;
+    let service = create_note_service(pool.clone());
+    let workspace = create_workspace_fixture(create_workspace_repo(pool), user.id).await;
+
+    let dto = NoteCreateDTO {
+        title: "Runbook".to_string(),
+        workspace_id: workspace.id,
+        parent_note: None,
+        template_id: None,
+        author: None,
+    };
+    (user.id, service.create(dto).await.unwrap())
+}
+
+async fn add(
+    service: &BlockService<BlockRepository, ChangeRepository>,
+    note_id: Uuid,
+    parent_block: Option<Uuid>,
+    toggle: bool,
+    text: &str,
+) -> BlockDTO {
+    let spans = vec![TextSpan::plain(text)];
+    let (block_type, content) = match toggle {
+        true => (
+            BlockType::Toggle,
+            BlockContent::Toggle(ToggleContent { spans }),
+        ),
+        false => (
+            BlockType::PlainText,
+            BlockContent::PlainText(PlainTextContent { spans }),
+        ),
+    };
+    service
+        .create(BlockCreateDTO {
+            block_type,
+            content,
+            note_id,
+            parent_block,
+        })
+        .await
+        .unwrap()
+}
+
+fn comment(note_id: Uuid, block_id: Option<Uuid>, author_id: Uuid, body: &str) -> CommentCreateDTO {
+    CommentCreateDTO {
+        note_id,
+        block_id,
+        author_id,
+        body: body.to_string(),
+    }
+}
+
+/// Bodies of the threads, replies in brackets after them
+fn bodies(threads: &[CommentThreadDTO]) -> Vec<String> {
+    threads
+        .iter()
+        .map(|t| {
+            let replies: Vec<&str> = t.replies.iter().map(|r| r.body.as_str()).collect();
+            format!("{} [{}]", t.comment.body, replies.join(", "))
+        })
+        .collect()
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_comment_threads(pool: PgPool) {
+    let (user_id, note) = create_note(pool.clone()).await;
+    let other = create_note_service(pool.clone())
+        .create(NoteCreateDTO {
+            title: "Other".to_string(),
+            workspace_id: note.workspace_id,
+            parent_note: None,
+            template_id: None,
+            author: None,
+        })
+        .await
+        .unwrap();
+    let blocks = create_block_service(pool.clone());
+    let step = add(&blocks, note.id, None, false, "Restart the service").await;
+    let service = create_comment_service(pool.clone());
+
+    let on_note = service
+        .create(comment(note.id, None, user_id, "  Still accurate? "))
+        .await
+        .unwrap();
+    assert_eq!(on_note.body, "Still accurate?");
+    let on_block = service
+        .create(comment(note.id, Some(step.id), user_id, "Which service?"))
+        .await
+        .unwrap();
+    let reply = service
+        .reply(on_block.id, user_id, "The API")
+        .await
+        .unwrap();
+    assert_eq!(reply.thread_id, Some(on_block.id));
+    // Replies to replies stay in the thread
+    let nested = service.reply(reply.id, user_id, "Thanks").await.unwrap();
+    assert_eq!(nested.thread_id, Some(on_block.id));
+
+    let threads = service.get_all_in_note(note.id).await.unwrap();
+    assert_eq!(
+        bodies(&threads),
+        vec!["Still accurate? []", "Which service? [The API, Thanks]"]
+    );
+    assert_eq!(threads[1].comment.block_id, Some(step.id));
+    assert!(service.get_all_in_note(other.id).await.unwrap().is_empty());
+
+    for (data, invalid) in [
+        (comment(note.id, None, user_id, "   "), true),
+        (
+            comment(note.id, Some(step.id), user_id, &"a".repeat(10_001)),
+            true,
+        ),
+        (
+            comment(other.id, Some(step.id), user_id, "Wrong note"),
+            false,
+        ),
+        (comment(Uuid::new_v4(), None, user_id, "No note"), false),
+    ] {
+        let result = service.create(data).await;
+        match invalid {
+            true => assert!(matches!(result, Err(CoreError::InvalidComment(_)))),
+            false => assert!(matches!(result, Err(CoreError::NotFound))),
+        }
+    }
+
+    // Threads are resolved and reopened, not single replies
+    let resolved = service
+        .set_resolved(on_block.id, user_id, true)
+        .await
+        .unwrap();
+    assert!(resolved.resolved_at.is_some());
+    assert_eq!(resolved.resolved_by, Some(user_id));
+    let reopened = service
+        .set_resolved(on_block.id, user_id, false)
+        .await
+        .unwrap();
+    assert_eq!(reopened.resolved_at, None);
+    assert_eq!(reopened.resolved_by, None);
+    assert!(matches!(
+        service.set_resolved(reply.id, user_id, true).await,
+        Err(CoreError::InvalidComment(_))
+    ));
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_only_authors_change_comments(pool: PgPool) {
+    let (user_id, note) = create_note(pool.clone()).await;
+    let reviewer = User {
+        id: Uuid::new_v4(),
+        username: "reviewer".to_string(),
+        email: "reviewer@example.com".to_string(),
+        password: hash_password("password".as_bytes()).unwrap(),
+    };
+    create_user_repository(pool.clone())
+        .create(reviewer.clone())
+        .await
+        .unwrap();
+    let service = create_comment_service(pool.clone());
+
+    let thread = service
+        .create(comment(note.id, None, user_id, "Step 3 is outdated"))
+        .await
+        .unwrap();
+    let reply = service
+        .reply(thread.id, reviewer.id, "Fixed it")
+        .await
+        .unwrap();
+
+    assert!(matches!(
+        service.edit(thread.id, reviewer.id, "Hijacked").await,
+        Err(CoreError::AccessDenied)
+    ));
+    assert!(matches!(
+        service.delete(thread.id, reviewer.id).await,
+        Err(CoreError::AccessDenied)
+    ));
+    let edited = service
+        .edit(reply.id, reviewer.id, "Fixed it, see step 4")
+        .await
+        .unwrap();
+    assert!(edited.edited_at.is_some());
+    assert!(matches!(
+        service.edit(reply.id, reviewer.id, "").await,
+        Err(CoreError::InvalidComment(_))
+    ));
+    // Anyone on the note can resolve a thread
+    service
+        .set_resolved(thread.id, reviewer.id, true)
+        .await
+        .unwrap();
+
+    let threads = service.get_all_in_note(note.id).await.unwrap();
+    assert_eq!(
+        bodies(&threads),
+        vec!["Step 3 is outdated [Fixed it, see step 4]"]
+    );
+    assert_eq!(threads[0].comment.resolved_by, Some(reviewer.id));
+
+    // The thread goes with its replies
+    service.delete(thread.id, user_id).await.unwrap();
+    assert!(matches!(
+        service.find_one(reply.id).await,
+        Err(CoreError::NotFound)
+    ));
+    assert!(service.get_all_in_note(note.id).await.unwrap().is_empty());
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_deleted_blocks_orphan_threads(pool: PgPool) {
+    let (user_id, note) = create_note(pool.clone()).await;
+    let blocks = create_block_service(pool.clone());
+    let toggle = add(&blocks, note.id, None, true, "Rollback").await;
+    let child = add(&blocks, note.id, Some(toggle.id), false, "Revert").await;
+    let promoted = add(&blocks, note.id, None, true, "Checks").await;
+    let kept = add(&blocks, note.id, Some(promoted.id), false, "Ping").await;
+    let service = create_comment_service(pool.clone());
+
+    let mut threads = Vec::new();
+    for (block_id, body) in [
+        (None, "Note"),
+        (Some(child.id), "Child"),
+        (Some(promoted.id), "Promoted"),
+        (Some(kept.id), "Kept"),
+    ] {
+        let thread = service
+            .create(comment(note.id, block_id, user_id, body))
+            .await
+            .unwrap();
+        service.reply(thread.id, user_id, "Reply").await.unwrap();
+        threads.push(thread);
+    }
+    service
+        .set_resolved(threads[2].id, user_id, true)
+        .await
+        .unwrap();
+    let resolved_at = service.find_one(threads[2].id).await.unwrap().resolved_at;
+
+    // Children go with their toggle, promoted ones stay
+    blocks
+        .delete(toggle.id, false, &AuditContext::default())
+        .await
+        .unwrap();
+    blocks
+        .delete(promoted.id, true, &AuditContext::default())
+        .await
+        .unwrap();
+
+    let threads = service.get_all_in_note(note.id).await.unwrap();
+    assert_eq!(
+        bodies(&threads),
+        vec![
+            "Note [Reply]",
+            "Child [Reply]",
+            "Promoted [Reply]",
+            "Kept [Reply]"
+        ]
+    );
+    let state: Vec<(bool, bool)> = threads
+        .iter()
+        .map(|t| (t.comment.orphaned, t.comment.resolved_at.is_some()))
+        .collect();
+    assert_eq!(
+        state,
+        vec![(false, false), (true, true), (true, true), (false, false)]
+    );
+    assert_eq!(threads[1].comment.block_id, Some(child.id));
+    // Resolved before, the time is kept
+    assert_eq!(threads[2].comment.resolved_at, resolved_at);
+
+    // Orphaned threads can still be reopened and replied to
+    let reopened = service
+        .set_resolved(threads[1].comment.id, user_id, false)
+        .await
+        .unwrap();
+    assert!(reopened.orphaned);
+    service
+        .reply(threads[1].comment.id, user_id, "Moved to the new runbook")
+        .await
+        .unwrap();
+}
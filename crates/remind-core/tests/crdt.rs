@@ -0,0 +1,357 @@
+use crate::fixtures::{
+    create_block_repo, create_block_service, create_change_repo, create_note_service,
+    create_user_fixture, create_user_repository, create_workspace_fixture, create_workspace_repo,
+};
+use async_trait::async_trait;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use remind_core::errors::{CoreError, Result};
+use remind_core::{
+    Attachment, Block, BlockContent, BlockCreateDTO, BlockDTO, BlockRepo, BlockRepository,
+    BlockService, BlockType, BlockUpdateDTO, CharId, CodeContent, Mention, NoteCreateDTO,
+    PlainTextContent, TextCrdt, TextMark, TextOp, TextSpan,
+};
+use sqlx::{PgConnection, PgPool};
+use std::sync::Mutex;
+use uuid::Uuid;
+
+mod fixtures;
+
+/// Random local edit on the replica
+fn random_edit(rng: &mut StdRng, crdt: &mut TextCrdt, replica: Uuid) -> Vec<TextOp> {
+    let len = crdt.text().chars().count();
+    if len > 0 && rng.gen_bool(0.4) {
+        let index = rng.gen_range(0..len);
+        crdt.delete(index, rng.gen_range(1..=3))
+    } else {
+        let text: String = (0..rng.gen_range(1..=4))
+            .map(|_| rng.gen_range(b'a'..=b'z') as char)
+            .collect();
+        crdt.insert(replica, rng.gen_range(0..=len), &text)
+    }
+}
+
+#[test]
+fn test_random_histories_converge() {
+    for seed in 0..50 {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let replicas: Vec<Uuid> = (0..4).map(|_| Uuid::from_u128(rng.r#gen())).collect();
+        let mut states = vec![TextCrdt::from_text("shared start"); replicas.len()];
+
+        for _ in 0..10 {
+            // Every replica edits concurrently, then receives the edits of the
+            // others interleaved in a random order, each replica's in sequence
+            let mut logs: Vec<Vec<TextOp>> = Vec::new();
+            for (state, replica) in states.iter_mut().zip(&replicas) {
+                let mut log = Vec::new();
+                for _ in 0..rng.gen_range(0..4) {
+                    log.extend(random_edit(&mut rng, state, *replica));
+                }
+                logs.push(log);
+            }
+
+            for (receiver, state) in states.iter_mut().enumerate() {
+                let mut queues: Vec<std::slice::Iter<TextOp>> = logs
+                    .iter()
+                    .enumerate()
+                    .filter(|(sender, _)| *sender != receiver)
+                    .map(|(_, log)| log.iter())
+                    .collect();
+                queues.shuffle(&mut rng);
+                while !queues.is_empty() {
+                    let queue = rng.gen_range(0..queues.len());
+                    match queues[queue].next() {
+                        Some(op) => {
+                            state.apply(op).unwrap();
+                        }
+                        None => drop(queues.remove(queue)),
+                    }
+                }
+            }
+            assert!(states.windows(2).all(|w| w[0] == w[1]), "seed {seed}");
+        }
+    }
+}
+
+#[test]
+fn test_text_ops() {
+    let (ada, bob) = (Uuid::from_u128(1), Uuid::from_u128(2));
+    let mut first = TextCrdt::from_text("ac");
+    let mut second = first.clone();
+
+    // Concurrent inserts at the same place keep each insert together
+    let ops_first = first.insert(ada, 1, "bb");
+    let ops_second = second.insert(bob, 1, "XY");
+    for op in &ops_second {
+        assert!(first.apply(op).unwrap());
+    }
+    assert!(second.apply(ops_first.last().unwrap()).is_err());
+    for op in &ops_first {
+        second.apply(op).unwrap();
+    }
+    assert_eq!(first.text(), second.text());
+    assert!(first.text() == "aXYbbc" || first.text() == "abbXYc");
+
+    // Applying twice changes nothing
+    let delete = first.delete(0, 1);
+    assert!(second.apply(&delete[0]).unwrap());
+    assert!(!second.apply(&delete[0]).unwrap());
+    assert!(!second.apply(&ops_second[0]).unwrap());
+    assert_eq!(first, second);
+
+    let ops = first.replace(ada, "XYbbc!");
+    assert_eq!(ops.len(), 1);
+    assert_eq!(first.text(), "XYbbc!");
+    assert_eq!(TextCrdt::from_text("same"), TextCrdt::from_text("same"));
+}
+
+async fn create_block(pool: PgPool, content: BlockContent) -> BlockDTO {
+    let user = create_user_fixture(create_user_repository(pool.clone())).await;
+    let workspace = create_workspace_fixture(create_workspace_repo(pool.clone()), user.id).await;
+    let note = create_note_service(pool.clone())
+        .create(NoteCreateDTO {
+            title: "Draft".to_string(),
+            workspace_id: workspace.id,
+            parent_note: None,
+            template_id: None,
+            author: None,
+        })
+        .await
+        .unwrap();
+    let block_type = match content {
+        BlockContent::Code(_) => BlockType::Code,
+        _ => BlockType::PlainText,
+    };
+    create_block_service(pool)
+        .create(BlockCreateDTO {
+            block_type,
+            content,
+            note_id: note.id,
+            parent_block: None,
+        })
+        .await
+        .unwrap()
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_merge_text_into_blocks(pool: PgPool) {
+    let block = create_block(
+        pool.clone(),
+        BlockContent::PlainText(PlainTextContent {
+            spans: vec![
+                TextSpan {
+                    marks: vec![TextMark::Bold],
+                    ..TextSpan::plain("Hello")
+                },
+                TextSpan::plain(" world"),
+            ],
+        }),
+    )
+    .await;
+    let service = create_block_service(pool.clone());
+
+    // Two clients start from the same state and edit concurrently
+    let mut ada = service.get_text_state(block.id).await.unwrap();
+    let mut bob = ada.clone();
+    let ada_ops = ada.insert(Uuid::new_v4(), 5, " there");
+    let bob_ops = bob.delete(5, 6);
+    service.merge_text(block.id, bob_ops).await.unwrap();
+    let (merged, applied) = service.merge_text(block.id, ada_ops.clone()).await.unwrap();
+    assert_eq!(applied, ada_ops);
+
+    // New text takes the marks of the text before it
+    let BlockContent::PlainText(content) = &merged.content else {
+        panic!("expected plain text");
+    };
+    assert_eq!(
+        content.spans,
+        vec![TextSpan {
+            marks: vec![TextMark::Bold],
+            ..TextSpan::plain("Hello there")
+        }]
+    );
+    for op in &applied {
+        bob.apply(op).unwrap();
+    }
+    assert_eq!(bob.text(), "Hello there");
+
+    // Plain updates are picked up and sent along with the next merge
+    service
+        .update(BlockUpdateDTO {
+            id: block.id,
+            block_type: None,
+            content: Some(BlockContent::PlainText(PlainTextContent::plain(
+                "Hello there!",
+            ))),
+            version: None,
+        })
+        .await
+        .unwrap();
+    let ops = bob.insert(Uuid::new_v4(), 0, ">");
+    let (merged, applied) = service.merge_text(block.id, ops).await.unwrap();
+    assert_eq!(merged.content.plain_text(), ">Hello there!");
+    assert_eq!(applied.len(), 2);
+    for op in &applied {
+        bob.apply(op).unwrap();
+    }
+    assert_eq!(bob, service.get_text_state(block.id).await.unwrap());
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_merge_text_checks(pool: PgPool) {
+    let block = create_block(
+        pool.clone(),
+        BlockContent::Code(CodeContent {
+            code: "fn main() {}".to_string(),
+            language: "rust".to_string(),
+        }),
+    )
+    .await;
+    let service = create_block_service(pool.clone());
+
+    let mut client = service.get_text_state(block.id).await.unwrap();
+    let ops = client.replace(Uuid::new_v4(), "fn main() { run() }");
+    let (merged, _) = service.merge_text(block.id, ops).await.unwrap();
+    let BlockContent::Code(code) = &merged.content else {
+        panic!("expected code");
+    };
+    assert_eq!(code.code, "fn main() { run() }");
+    assert_eq!(code.language, "rust");
+
+    // Characters the server never saw can't be addressed, nothing is merged
+    let mut ops = client.insert(Uuid::new_v4(), 0, "// ");
+    ops.push(TextOp::Delete {
+        id: CharId {
+            counter: 1,
+            replica: Uuid::new_v4(),
+        },
+    });
+    let result = service.merge_text(block.id, ops).await;
+    assert!(matches!(result, Err(CoreError::TextOutOfSync(_))));
+    assert_eq!(
+        service
+            .find_one(block.id)
+            .await
+            .unwrap()
+            .content
+            .plain_text(),
+        "fn main() { run() }"
+    );
+    assert!(matches!(
+        service.merge_text(Uuid::new_v4(), Vec::new()).await,
+        Err(CoreError::NotFound)
+    ));
+}
+
+/// Block repository running a plain update right after a merge read the text
+/// state, as if it came in concurrently
+struct InterleavedRepo {
+    inner: BlockRepository,
+    pool: PgPool,
+    update: Mutex<Option<BlockUpdateDTO>>,
+}
+
+#[async_trait]
+impl BlockRepo for InterleavedRepo {
+    async fn create(&self, conn: &mut PgConnection, data: Block) -> Result<()> {
+        self.inner.create(conn, data).await
+    }
+    async fn find_one(&self, id: Uuid) -> Result<Option<Block>> {
+        self.inner.find_one(id).await
+    }
+    async fn find_all_in_note(&self, note_id: Uuid) -> Result<Vec<Block>> {
+        self.inner.find_all_in_note(note_id).await
+    }
+    async fn delete(&self, conn: &mut PgConnection, id: Uuid) -> Result<()> {
+        self.inner.delete(conn, id).await
+    }
+    async fn save(&self, conn: &mut PgConnection, data: Block) -> Result<()> {
+        self.inner.save(conn, data).await
+    }
+    async fn save_position(&self, conn: &mut PgConnection, id: Uuid, position: i32) -> Result<()> {
+        self.inner.save_position(conn, id, position).await
+    }
+    async fn save_if_version(
+        &self,
+        conn: &mut PgConnection,
+        data: Block,
+        version: i64,
+    ) -> Result<bool> {
+        self.inner.save_if_version(conn, data, version).await
+    }
+    async fn save_links(
+        &self,
+        conn: &mut PgConnection,
+        block_id: Uuid,
+        links: Vec<Mention>,
+    ) -> Result<()> {
+        self.inner.save_links(conn, block_id, links).await
+    }
+    async fn find_all_linking_to(&self, target: Mention) -> Result<Vec<Block>> {
+        self.inner.find_all_linking_to(target).await
+    }
+    async fn find_note_workspace(&self, note_id: Uuid) -> Result<Option<Uuid>> {
+        self.inner.find_note_workspace(note_id).await
+    }
+    async fn find_attachment(&self, id: Uuid) -> Result<Option<Attachment>> {
+        self.inner.find_attachment(id).await
+    }
+    async fn find_text_state(&self, block_id: Uuid) -> Result<Option<(TextCrdt, i64)>> {
+        let state = self.inner.find_text_state(block_id).await?;
+        let update = self.update.lock().unwrap().take();
+        if let Some(update) = update {
+            create_block_service(self.pool.clone())
+                .update(update)
+                .await?;
+        }
+        Ok(state)
+    }
+    async fn save_text(
+        &self,
+        conn: &mut PgConnection,
+        data: Block,
+        state: &TextCrdt,
+        version: Option<i64>,
+    ) -> Result<bool> {
+        self.inner.save_text(conn, data, state, version).await
+    }
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_merge_text_keeps_concurrent_updates(pool: PgPool) {
+    let block = create_block(
+        pool.clone(),
+        BlockContent::PlainText(PlainTextContent::plain("Hello")),
+    )
+    .await;
+    // The text state is stored once a first merge went through
+    let plain = create_block_service(pool.clone());
+    let (block, _) = plain.merge_text(block.id, Vec::new()).await.unwrap();
+    let mut client = plain.get_text_state(block.id).await.unwrap();
+    let ops = client.insert(Uuid::new_v4(), 5, "!");
+
+    let repo = InterleavedRepo {
+        inner: create_block_repo(pool.clone()),
+        pool: pool.clone(),
+        update: Mutex::new(Some(BlockUpdateDTO {
+            id: block.id,
+            block_type: None,
+            content: Some(BlockContent::PlainText(PlainTextContent::plain(
+                "Hello world",
+            ))),
+            version: None,
+        })),
+    };
+    let service = BlockService::new(repo, create_change_repo(pool.clone()));
+
+    // The update lands between reading the block and saving the merge, the
+    // merge is retried on top of it
+    let (merged, applied) = service.merge_text(block.id, ops).await.unwrap();
+    assert_eq!(merged.content.plain_text(), "Hello! world");
+    assert_eq!(merged.version, block.version + 2);
+    for op in &applied {
+        client.apply(op).unwrap();
+    }
+    assert_eq!(client.text(), "Hello! world");
+}
@@ -0,0 +1,250 @@
+use crate::fixtures::{
+    create_block_service, create_block_service_with_renderers, create_note_service,
+    create_user_fixture, create_user_repository, create_workspace_fixture, create_workspace_repo,
+};
+use remind_core::diagrams::{
+    CommandSvgRenderer, SvgRenderer, sanitize_svg, validate_latex, validate_mermaid,
+};
+use remind_core::errors::CoreError;
+use remind_core::render::{render_html, render_markdown};
+use remind_core::{
+    BlockContent, BlockCreateDTO, BlockType, BlockUpdateDTO, DiagramContent, MathContent,
+    NoteCreateDTO, NoteDTO,
+};
+use sqlx::PgPool;
+use std::time::Duration;
+
+mod fixtures;
+
+async fn create_note(pool: PgPool) -> NoteDTO {
+    let user_repo = create_user_repository(pool.clone());
+    let user = create_user_fixture(user_repo).await;
+    let service = create_note_service(pool.clone());
+    let workspace = create_workspace_fixture(create_workspace_repo(pool), user.id).await;
+
+    let dto = NoteCreateDTO {
+        title: "Design".to_string(),
+        workspace_id: workspace.id,
+        parent_note: None,
+        template_id: None,
+        author: None,
+    };
+
+    service.create(dto.clone()).await.unwrap()
+}
+
+fn math(code: &str) -> BlockCreateDTO {
+    BlockCreateDTO {
+        block_type: BlockType::Math,
+        content: BlockContent::Math(MathContent {
+            code: code.to_string(),
+            svg: Some("<svg onload=\"alert(1)\"></svg>".to_string()),
+        }),
+        note_id: Default::default(),
+        parent_block: None,
+    }
+}
+
+fn diagram(code: &str) -> BlockContent {
+    BlockContent::Diagram(DiagramContent {
+        code: code.to_string(),
+        svg: None,
+    })
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_math_and_diagram_validation(pool: PgPool) {
+    for code in [
+        r"\frac{a}{b} + \sqrt{x^2}",
+        r"\begin{pmatrix} a & b \\ c & d \end{pmatrix}",
+        r"\left( \frac{1}{2} \right) \leftarrow \{ x \}",
+        r"x^2 % a comment with { and $",
+    ] {
+        assert!(validate_latex(code).is_ok(), "{code}");
+    }
+    for code in [
+        r"\frac{a}{b",
+        r"x}",
+        r"\input{/etc/passwd}",
+        r"\begin{matrix} a \end{pmatrix}",
+        r"\left( x",
+        r"$x$",
+        "  ",
+    ] {
+        assert!(validate_latex(code).is_err(), "{code}");
+    }
+
+    for code in [
+        "graph TD\n  A[Start] --> B{Ok?}\n  B -->|Yes| C>Done]\n  B -- \"No (retry]\" --> A",
+        "%% comment\nsequenceDiagram\n  Alice->>Bob: Hello (there",
+        "pie\n  \"Rust\" : 80",
+    ] {
+        assert!(validate_mermaid(code).is_ok(), "{code}");
+    }
+    for code in [
+        "graphs TD\n  A --> B",
+        "graph XY\n  A --> B",
+        "flowchart LR\n  A[Start --> B",
+        "%%{init: {'securityLevel': 'loose'}}%%\ngraph TD\n  A --> B",
+        "",
+    ] {
+        assert!(validate_mermaid(code).is_err(), "{code}");
+    }
+
+    let note = create_note(pool.clone()).await;
+    let service = create_block_service(pool);
+    let result = service
+        .create(BlockCreateDTO {
+            note_id: note.id,
+            ..math(r"\write18{rm -rf /}")
+        })
+        .await;
+    assert!(matches!(result, Err(CoreError::InvalidBlockContent(_))));
+    let result = service
+        .create(BlockCreateDTO {
+            block_type: BlockType::Diagram,
+            content: diagram("graph TD\n  A((Start) --> B"),
+            note_id: note.id,
+            parent_block: None,
+        })
+        .await;
+    assert!(matches!(result, Err(CoreError::InvalidBlockContent(_))));
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_math_svg_rendered_once(pool: PgPool) {
+    let note = create_note(pool.clone()).await;
+    let (service, renderer) = create_block_service_with_renderers(pool.clone());
+
+    // The SVG sent by the client is replaced by the rendered one
+    let block = service
+        .create(BlockCreateDTO {
+            note_id: note.id,
+            ..math(r"E = mc^2")
+        })
+        .await
+        .unwrap();
+    let BlockContent::Math(content) = &block.content else {
+        panic!("Expected a math block");
+    };
+    assert_eq!(content.svg.as_deref(), Some("<svg><desc>8</desc></svg>"));
+    assert_eq!(renderer.calls(), 1);
+
+    let update = |code: &str| BlockUpdateDTO {
+        id: block.id,
+        block_type: None,
+        content: Some(BlockContent::Math(MathContent {
+            code: code.to_string(),
+            svg: None,
+        })),
+        version: None,
+    };
+    service.update(update(r"E = mc^2")).await.unwrap();
+    assert_eq!(renderer.calls(), 1);
+    service.update(update(r"E = m c^{2}")).await.unwrap();
+    assert_eq!(renderer.calls(), 2);
+
+    // Without a renderer exports keep the source for client side rendering
+    create_block_service(pool.clone())
+        .create(BlockCreateDTO {
+            block_type: BlockType::Diagram,
+            content: diagram("graph LR\n  A[a < b] --> B"),
+            note_id: note.id,
+            parent_block: None,
+        })
+        .await
+        .unwrap();
+    let note = create_note_service(pool).find_one(note.id).await.unwrap();
+
+    let html = render_html(&note);
+    assert!(html.contains("<figure class=\"math\"><svg><desc>11</desc></svg></figure>"));
+    assert!(html.contains("<pre class=\"mermaid\">graph LR\n  A[a &lt; b] --&gt; B</pre>"));
+    assert!(!html.contains("onload"));
+
+    let markdown = render_markdown(&note);
+    assert!(markdown.contains("$$\nE = m c^{2}\n$$"));
+    assert!(markdown.contains("```mermaid\ngraph LR\n  A[a < b] --> B\n```"));
+}
+
+#[tokio::test]
+async fn test_command_svg_renderer() {
+    let sh = |script: &str, timeout: Duration| {
+        CommandSvgRenderer::new(
+            "sh".to_string(),
+            vec!["-c".to_string(), script.to_string()],
+            timeout,
+        )
+    };
+    let second = Duration::from_secs(1);
+
+    // The source arrives on stdin
+    let renderer = sh(
+        r#"printf '<?xml version="1.0"?>\n<svg><text>%s</text></svg>\n' "$(cat)""#,
+        second,
+    );
+    let svg = renderer.render("x^2").await.unwrap();
+    assert_eq!(svg, "<svg><text>x^2</text></svg>");
+
+    // Scripts are taken out of the output
+    let renderer = sh(
+        "cat > /dev/null; echo '<svg onclick =\"x()\"><script>x()</script></svg>'",
+        second,
+    );
+    assert_eq!(renderer.render("x^2").await.unwrap(), "<svg></svg>");
+
+    for renderer in [
+        sh("cat > /dev/null; echo 'not an svg'", second),
+        sh("cat > /dev/null; echo '<svg><g></svg>'", second),
+        sh("cat > /dev/null; exit 1", second),
+        sh("sleep 5", Duration::from_millis(200)),
+    ] {
+        let result = renderer.render("x^2").await;
+        assert!(matches!(result, Err(CoreError::Render(_))));
+    }
+
+    let missing = CommandSvgRenderer::new("remind-no-such-renderer".to_string(), vec![], second);
+    assert!(matches!(
+        missing.render("x^2").await,
+        Err(CoreError::Render(_))
+    ));
+}
+
+#[test]
+fn test_svg_sanitizing() {
+    // Handlers after any separator, in any case
+    for svg in [
+        "<svg><rect\nonload=\"x()\"/></svg>",
+        "<svg><rect\tonload=\"x()\"/></svg>",
+        "<svg><rect ONLOAD=\"x()\"/></svg>",
+    ] {
+        assert_eq!(sanitize_svg(svg).unwrap(), "<svg><rect/></svg>");
+    }
+    // `<svg/onload=..>` isn't XML, and isn't let through either
+    assert!(sanitize_svg("<svg/onload=\"x()\"></svg>").is_err());
+    assert!(sanitize_svg("<p>not an svg</p>").is_err());
+
+    // Foreign content goes with everything in it
+    let svg = r#"<svg><foreignObject><div onclick="x()">A</div></foreignObject><iframe src="https://example.com"/><Script>x()</Script><text>B</text></svg>"#;
+    assert_eq!(sanitize_svg(svg).unwrap(), "<svg><text>B</text></svg>");
+
+    // Only links within the SVG
+    let svg = r##"<svg xmlns:xlink="http://www.w3.org/1999/xlink"><use href="data:image/svg+xml,&lt;svg onload=x()&gt;"/><a xlink:href="javascript:x()"><text>C</text></a><use xlink:href="#glyph" fill="url(#paint)"/></svg>"##;
+    assert_eq!(
+        sanitize_svg(svg).unwrap(),
+        r##"<svg xmlns:xlink="http://www.w3.org/1999/xlink"><use/><use xlink:href="#glyph" fill="url(#paint)"/></svg>"##
+    );
+
+    // CSS loading anything is dropped, text stays escaped
+    let svg = r#"<svg><style>@import url(https://example.com/x.css);</style><style>.a { fill: red }</style><rect style="fill: url(https://example.com/x)"/><text>1 &lt; 2</text></svg>"#;
+    assert_eq!(
+        sanitize_svg(svg).unwrap(),
+        "<svg><style></style><style>.a { fill: red }</style><rect/><text>1 &lt; 2</text></svg>"
+    );
+
+    // So is CSS spelling `url(` or `@import` with escapes or entities
+    let svg = r##"<svg><style>.a { background: \75 rl(https://example.com/x) }</style><style>@\69mport "https://example.com/x.css";</style><style>.b { fill: &#117;rl(https://example.com/x) }</style><style>.c { fill: url("#paint") }</style><rect style="fill: u\72l(https://example.com/x)"/><rect fill="url(https://example.com/x#a)"/><rect fill="url(#paint)"/></svg>"##;
+    assert_eq!(
+        sanitize_svg(svg).unwrap(),
+        r##"<svg><style></style><style></style><style></style><style>.c { fill: url(&quot;#paint&quot;) }</style><rect/><rect/><rect fill="url(#paint)"/></svg>"##
+    );
+}
@@ -0,0 +1,117 @@
+#![allow(dead_code)]
+
+use axum::Router;
+use axum::body::Bytes;
+use axum::extract::{Path, State};
+use axum::http::{HeaderMap, Method, StatusCode};
+use axum::routing::any;
+use image::codecs::jpeg::JpegEncoder;
+use image::{DynamicImage, ImageFormat, RgbImage};
+use remind_core::{AttachmentRepository, AttachmentService, LocalBlobStore, PgPool, UploadLimits};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+pub const MAX_UPLOAD_SIZE: usize = 1024 * 1024;
+
+pub fn create_attachment_repo(pool: PgPool) -> AttachmentRepository {
+    AttachmentRepository::new(pool)
+}
+
+pub fn create_local_blob_store() -> LocalBlobStore {
+    LocalBlobStore::new(std::env::temp_dir().join(format!("remind-test-{}", Uuid::new_v4())))
+}
+
+pub fn create_attachment_service(pool: PgPool) -> AttachmentService<AttachmentRepository> {
+    create_attachment_service_with_limits(
+        pool,
+        UploadLimits {
+            max_file_size: MAX_UPLOAD_SIZE,
+            user_quota: i64::MAX,
+            workspace_quota: i64::MAX,
+        },
+    )
+}
+
+pub fn create_attachment_service_with_limits(
+    pool: PgPool,
+    limits: UploadLimits,
+) -> AttachmentService<AttachmentRepository> {
+    let repo = create_attachment_repo(pool);
+    AttachmentService::new(repo, Arc::new(create_local_blob_store()), limits)
+}
+
+pub fn png_bytes(width: u32, height: u32) -> Vec<u8> {
+    let image = DynamicImage::ImageRgb8(RgbImage::from_pixel(width, height, [40, 40, 200].into()));
+    let mut png = std::io::Cursor::new(Vec::new());
+    image.write_to(&mut png, ImageFormat::Png).unwrap();
+    png.into_inner()
+}
+
+/// JPEG of the given size carrying an EXIF orientation tag
+/// ("rotate 90° clockwise" when `orientation` is 6)
+pub fn jpeg_with_exif(width: u32, height: u32, orientation: u8) -> Vec<u8> {
+    let image = DynamicImage::ImageRgb8(RgbImage::from_pixel(width, height, [200, 40, 40].into()));
+    let mut jpeg = Vec::new();
+    image
+        .write_with_encoder(JpegEncoder::new(&mut jpeg))
+        .unwrap();
+
+    let mut exif = vec![0xFF, 0xE1, 0x00, 0x22];
+    exif.extend_from_slice(b"Exif\0\0");
+    exif.extend_from_slice(b"MM\0\x2A\0\0\0\x08");
+    exif.extend_from_slice(&[0x00, 0x01, 0x01, 0x12, 0x00, 0x03, 0x00, 0x00, 0x00, 0x01]);
+    exif.extend_from_slice(&[0x00, orientation, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+
+    // Right after the SOI marker
+    jpeg.splice(2..2, exif);
+    jpeg
+}
+
+type Objects = Arc<Mutex<HashMap<String, Vec<u8>>>>;
+
+/// Minimal in-memory stand-in for an S3-compatible server.
+/// Returns its base URL and the stored objects.
+pub async fn spawn_s3_stand_in() -> (String, Objects) {
+    let objects: Objects = Arc::new(Mutex::new(HashMap::new()));
+    let app = Router::new()
+        .route("/{*path}", any(handle_s3_request))
+        .with_state(objects.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    (format!("http://{addr}"), objects)
+}
+
+async fn handle_s3_request(
+    State(objects): State<Objects>,
+    Path(path): Path<String>,
+    method: Method,
+    headers: HeaderMap,
+    body: Bytes,
+) -> (StatusCode, Vec<u8>) {
+    let signed = headers
+        .get("authorization")
+        .and_then(|h| h.to_str().ok())
+        .is_some_and(|h| h.starts_with("AWS4-HMAC-SHA256 Credential=test-key/"));
+    if !signed || !headers.contains_key("x-amz-date") {
+        return (StatusCode::FORBIDDEN, Vec::new());
+    }
+
+    let mut objects = objects.lock().unwrap();
+    match method {
+        Method::PUT => {
+            objects.insert(path, body.to_vec());
+            (StatusCode::OK, Vec::new())
+        }
+        Method::GET => match objects.get(&path) {
+            Some(data) => (StatusCode::OK, data.clone()),
+            None => (StatusCode::NOT_FOUND, Vec::new()),
+        },
+        Method::DELETE => {
+            objects.remove(&path);
+            (StatusCode::NO_CONTENT, Vec::new())
+        }
+        _ => (StatusCode::METHOD_NOT_ALLOWED, Vec::new()),
+    }
+}
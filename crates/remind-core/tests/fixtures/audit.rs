@@ -0,0 +1,50 @@
+#![allow(dead_code)]
+
+use crate::fixtures::block::create_block_repo;
+use crate::fixtures::change::create_change_repo;
+use crate::fixtures::note::create_note_service;
+use crate::fixtures::share_link::create_share_link_service;
+use crate::fixtures::user::create_user_service;
+use remind_core::{
+    AuditRepository, AuditService, BlockRepository, BlockService, ChangeRepository, NoteRepository,
+    NoteService, PgPool, ShareLinkRepository, ShareLinkService, UserRepository, UserService,
+};
+use std::sync::Arc;
+
+pub fn create_audit_repo(pool: PgPool) -> AuditRepository {
+    AuditRepository::new(pool)
+}
+
+pub fn create_audit_service(pool: PgPool) -> AuditService<AuditRepository> {
+    AuditService::new(create_audit_repo(pool))
+}
+
+/// User service recording logins
+pub fn create_user_service_with_audit(pool: PgPool) -> UserService<UserRepository> {
+    create_user_service(pool.clone()).with_audit_sink(Arc::new(create_audit_service(pool)))
+}
+
+/// Note service recording deletes and exports
+pub fn create_note_service_with_audit(
+    pool: PgPool,
+) -> NoteService<NoteRepository, BlockRepository, ChangeRepository> {
+    create_note_service(pool.clone()).with_audit_sink(Arc::new(create_audit_service(pool)))
+}
+
+/// Block service recording deletes
+pub fn create_block_service_with_audit(
+    pool: PgPool,
+) -> BlockService<BlockRepository, ChangeRepository> {
+    BlockService::new(
+        create_block_repo(pool.clone()),
+        create_change_repo(pool.clone()),
+    )
+    .with_audit_sink(Arc::new(create_audit_service(pool)))
+}
+
+/// Share link service recording new links
+pub fn create_share_link_service_with_audit(
+    pool: PgPool,
+) -> ShareLinkService<ShareLinkRepository, NoteRepository, BlockRepository> {
+    create_share_link_service(pool.clone()).with_audit_sink(Arc::new(create_audit_service(pool)))
+}
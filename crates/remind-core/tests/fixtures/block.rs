@@ -1,12 +1,23 @@
 #![allow(dead_code)]
 
-use remind_core::{BlockRepository, BlockService, PgPool};
+use crate::fixtures::attachment::create_attachment_service;
+use crate::fixtures::change::create_change_repo;
+use remind_core::{BlockRepository, BlockService, ChangeRepository, PgPool};
+use std::sync::Arc;
 
 pub fn create_block_repo(pool: PgPool) -> BlockRepository {
     BlockRepository::new(pool)
 }
 
-pub fn create_block_service(pool: PgPool) -> BlockService<BlockRepository> {
-    let repo = create_block_repo(pool);
-    BlockService::new(repo)
+pub fn create_block_service(pool: PgPool) -> BlockService<BlockRepository, ChangeRepository> {
+    let repo = create_block_repo(pool.clone());
+    BlockService::new(repo, create_change_repo(pool))
+}
+
+pub fn create_block_service_with_images(
+    pool: PgPool,
+) -> BlockService<BlockRepository, ChangeRepository> {
+    let repo = create_block_repo(pool.clone());
+    BlockService::new(repo, create_change_repo(pool.clone()))
+        .with_image_pipeline(Arc::new(create_attachment_service(pool)))
 }
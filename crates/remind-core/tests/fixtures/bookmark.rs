@@ -0,0 +1,83 @@
+#![allow(dead_code)]
+
+use crate::fixtures::block::create_block_repo;
+use crate::fixtures::change::create_change_repo;
+use axum::Router;
+use axum::extract::State;
+use axum::http::{StatusCode, header};
+use axum::response::{IntoResponse, Redirect};
+use axum::routing::get;
+use remind_core::previews::{HttpLinkPreviewer, PreviewLimits};
+use remind_core::{BlockRepository, BlockService, ChangeRepository, PgPool};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+pub const PAGE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<title>Fallback title</title>
+<meta property="og:title" content="Rust &amp; Friends">
+<meta name="description" content="A page about Rust">
+<link rel="icon" href="/static/icon.png">
+</head>
+<body><h1>Hello</h1></body>
+</html>"#;
+
+pub fn create_block_service_with_previews(
+    pool: PgPool,
+    limits: PreviewLimits,
+) -> BlockService<BlockRepository, ChangeRepository> {
+    BlockService::new(create_block_repo(pool.clone()), create_change_repo(pool))
+        .with_link_previewer(Arc::new(HttpLinkPreviewer::new(limits)))
+}
+
+/// Limits for the stand-in, which only listens on loopback
+pub fn test_limits() -> PreviewLimits {
+    PreviewLimits {
+        timeout: Duration::from_secs(1),
+        allow_private_addresses: true,
+        ..PreviewLimits::default()
+    }
+}
+
+/// Web server serving pages to preview.
+/// Returns its base URL and the number of times `/page` was requested.
+pub async fn spawn_page_stand_in() -> (String, Arc<AtomicUsize>) {
+    let hits = Arc::new(AtomicUsize::new(0));
+    let app = Router::new()
+        .route("/page", get(page))
+        .route("/redirect", get(|| async { Redirect::temporary("/page") }))
+        .route("/loop", get(|| async { Redirect::temporary("/loop") }))
+        .route(
+            "/slow",
+            get(|| async {
+                tokio::time::sleep(Duration::from_secs(5)).await;
+                html(PAGE.to_string())
+            }),
+        )
+        .route(
+            "/big",
+            get(|| async { html("<p>ReMind</p>".repeat(100_000) + "<title>Too far</title>") }),
+        )
+        .route(
+            "/image",
+            get(|| async { ([(header::CONTENT_TYPE, "image/png")], vec![0u8; 16]) }),
+        )
+        .route("/missing", get(|| async { StatusCode::NOT_FOUND }))
+        .with_state(hits.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    // By name, so the resolved address is what gets pinned and checked
+    (format!("http://localhost:{port}"), hits)
+}
+
+async fn page(State(hits): State<Arc<AtomicUsize>>) -> impl IntoResponse {
+    hits.fetch_add(1, Ordering::SeqCst);
+    html(PAGE.to_string())
+}
+
+fn html(body: String) -> impl IntoResponse {
+    ([(header::CONTENT_TYPE, "text/html; charset=utf-8")], body)
+}
@@ -0,0 +1,11 @@
+#![allow(dead_code)]
+
+use remind_core::{ChangeRepository, ChangeService, PgPool};
+
+pub fn create_change_repo(pool: PgPool) -> ChangeRepository {
+    ChangeRepository::new(pool)
+}
+
+pub fn create_change_service(pool: PgPool) -> ChangeService<ChangeRepository> {
+    ChangeService::new(create_change_repo(pool))
+}
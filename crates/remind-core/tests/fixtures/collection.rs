@@ -0,0 +1,16 @@
+#![allow(dead_code)]
+
+use crate::fixtures::note::create_note_repo;
+use remind_core::{CollectionRepository, CollectionService, NoteRepository, PgPool};
+
+pub fn create_collection_repo(pool: PgPool) -> CollectionRepository {
+    CollectionRepository::new(pool)
+}
+
+pub fn create_collection_service(
+    pool: PgPool,
+) -> CollectionService<CollectionRepository, NoteRepository> {
+    let repo = create_collection_repo(pool.clone());
+    let note_repo = create_note_repo(pool);
+    CollectionService::new(repo, note_repo)
+}
@@ -0,0 +1,17 @@
+#![allow(dead_code)]
+
+use crate::fixtures::block::create_block_repo;
+use crate::fixtures::note::create_note_repo;
+use remind_core::{BlockRepository, CommentRepository, CommentService, NoteRepository, PgPool};
+
+pub fn create_comment_repo(pool: PgPool) -> CommentRepository {
+    CommentRepository::new(pool)
+}
+
+pub fn create_comment_service(
+    pool: PgPool,
+) -> CommentService<CommentRepository, NoteRepository, BlockRepository> {
+    let repo = create_comment_repo(pool.clone());
+    let note_repo = create_note_repo(pool.clone());
+    CommentService::new(repo, note_repo, create_block_repo(pool))
+}
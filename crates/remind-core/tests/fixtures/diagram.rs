@@ -0,0 +1,43 @@
+#![allow(dead_code)]
+
+use crate::fixtures::block::create_block_repo;
+use crate::fixtures::change::create_change_repo;
+use async_trait::async_trait;
+use remind_core::diagrams::SvgRenderer;
+use remind_core::errors::Result;
+use remind_core::{BlockRepository, BlockService, ChangeRepository, PgPool};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Renders every source to the same small SVG and counts the calls
+#[derive(Default)]
+pub struct CountingSvgRenderer {
+    pub calls: AtomicUsize,
+}
+
+impl CountingSvgRenderer {
+    pub fn calls(&self) -> usize {
+        self.calls.load(Ordering::SeqCst)
+    }
+}
+
+#[async_trait]
+impl SvgRenderer for CountingSvgRenderer {
+    async fn render(&self, code: &str) -> Result<String> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        Ok(format!("<svg><desc>{}</desc></svg>", code.len()))
+    }
+}
+
+pub fn create_block_service_with_renderers(
+    pool: PgPool,
+) -> (
+    BlockService<BlockRepository, ChangeRepository>,
+    Arc<CountingSvgRenderer>,
+) {
+    let renderer = Arc::new(CountingSvgRenderer::default());
+    let service = BlockService::new(create_block_repo(pool.clone()), create_change_repo(pool))
+        .with_math_renderer(renderer.clone())
+        .with_diagram_renderer(renderer.clone());
+    (service, renderer)
+}
@@ -1,11 +1,35 @@
 #![allow(unused_imports)]
 
+mod attachment;
+mod audit;
 mod block;
+mod bookmark;
+mod change;
+mod collection;
+mod comment;
+mod diagram;
 mod note;
+mod notification;
+mod share_link;
+mod sync;
+mod tag;
 mod user;
+mod webhook;
 mod workspace;
 
+pub use attachment::*;
+pub use audit::*;
 pub use block::*;
+pub use bookmark::*;
+pub use change::*;
+pub use collection::*;
+pub use comment::*;
+pub use diagram::*;
 pub use note::*;
+pub use notification::*;
+pub use share_link::*;
+pub use sync::*;
+pub use tag::*;
 pub use user::*;
+pub use webhook::*;
 pub use workspace::*;
@@ -1,14 +1,19 @@
 #![allow(dead_code)]
 
-use crate::fixtures::block::create_block_repo;
-use remind_core::{BlockRepository, NoteRepository, NoteService, PgPool};
+use crate::fixtures::block::{create_block_repo, create_block_service};
+use crate::fixtures::change::create_change_repo;
+use remind_core::{BlockRepository, ChangeRepository, NoteRepository, NoteService, PgPool};
+use std::sync::Arc;
 
 pub fn create_note_repo(pool: PgPool) -> NoteRepository {
     NoteRepository::new(pool)
 }
 
-pub fn create_note_service(pool: PgPool) -> NoteService<NoteRepository, BlockRepository> {
+pub fn create_note_service(
+    pool: PgPool,
+) -> NoteService<NoteRepository, BlockRepository, ChangeRepository> {
     let repo = create_note_repo(pool.clone());
-    let block_repo = create_block_repo(pool);
-    NoteService::new(repo, block_repo)
+    let block_repo = create_block_repo(pool.clone());
+    NoteService::new(repo, block_repo, create_change_repo(pool.clone()))
+        .with_template_writer(Arc::new(create_block_service(pool)))
 }
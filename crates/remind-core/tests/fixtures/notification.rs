@@ -0,0 +1,36 @@
+#![allow(dead_code)]
+
+use crate::fixtures::block::create_block_repo;
+use crate::fixtures::change::create_change_repo;
+use crate::fixtures::comment::create_comment_service;
+use remind_core::{
+    BlockRepository, BlockService, ChangeRepository, CommentRepository, CommentService,
+    NoteRepository, NotificationRepository, NotificationService, PgPool,
+};
+use std::sync::Arc;
+
+pub fn create_notification_repo(pool: PgPool) -> NotificationRepository {
+    NotificationRepository::new(pool)
+}
+
+pub fn create_notification_service(pool: PgPool) -> NotificationService<NotificationRepository> {
+    NotificationService::new(create_notification_repo(pool))
+}
+
+/// Block service notifying mentioned users
+pub fn create_block_service_with_notifications(
+    pool: PgPool,
+) -> BlockService<BlockRepository, ChangeRepository> {
+    BlockService::new(
+        create_block_repo(pool.clone()),
+        create_change_repo(pool.clone()),
+    )
+    .with_notifier(Arc::new(create_notification_service(pool)))
+}
+
+/// Comment service notifying the participants of threads
+pub fn create_comment_service_with_notifications(
+    pool: PgPool,
+) -> CommentService<CommentRepository, NoteRepository, BlockRepository> {
+    create_comment_service(pool.clone()).with_notifier(Arc::new(create_notification_service(pool)))
+}
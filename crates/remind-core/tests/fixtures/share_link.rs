@@ -0,0 +1,18 @@
+#![allow(dead_code)]
+
+use crate::fixtures::block::create_block_repo;
+use crate::fixtures::note::create_note_repo;
+use remind_core::{BlockRepository, NoteRepository, PgPool, ShareLinkRepository, ShareLinkService};
+
+pub fn create_share_link_repo(pool: PgPool) -> ShareLinkRepository {
+    ShareLinkRepository::new(pool)
+}
+
+pub fn create_share_link_service(
+    pool: PgPool,
+) -> ShareLinkService<ShareLinkRepository, NoteRepository, BlockRepository> {
+    let repo = create_share_link_repo(pool.clone());
+    let note_repo = create_note_repo(pool.clone());
+    let block_repo = create_block_repo(pool);
+    ShareLinkService::new(repo, note_repo, block_repo)
+}
@@ -0,0 +1,11 @@
+#![allow(dead_code)]
+
+use remind_core::{PgPool, SyncRepository, SyncService};
+
+pub fn create_sync_repo(pool: PgPool) -> SyncRepository {
+    SyncRepository::new(pool)
+}
+
+pub fn create_sync_service(pool: PgPool) -> SyncService<SyncRepository> {
+    SyncService::new(create_sync_repo(pool))
+}
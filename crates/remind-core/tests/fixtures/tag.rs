@@ -0,0 +1,14 @@
+#![allow(dead_code)]
+
+use crate::fixtures::note::create_note_repo;
+use remind_core::{NoteRepository, PgPool, TagRepository, TagService};
+
+pub fn create_tag_repo(pool: PgPool) -> TagRepository {
+    TagRepository::new(pool)
+}
+
+pub fn create_tag_service(pool: PgPool) -> TagService<TagRepository, NoteRepository> {
+    let repo = create_tag_repo(pool.clone());
+    let note_repo = create_note_repo(pool);
+    TagService::new(repo, note_repo)
+}
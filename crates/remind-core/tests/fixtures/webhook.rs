@@ -0,0 +1,81 @@
+#![allow(dead_code)]
+
+use crate::fixtures::change::create_change_repo;
+use axum::Router;
+use axum::body::Bytes;
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode};
+use axum::routing::post;
+use chrono::Duration;
+use remind_core::webhooks::{HttpWebhookSender, SenderLimits};
+use remind_core::{ChangeRepository, PgPool, RetryPolicy, WebhookRepository, WebhookService};
+use std::sync::atomic::{AtomicU16, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Request received by the stand-in endpoint
+#[derive(Clone, Debug)]
+pub struct ReceivedWebhook {
+    pub headers: HeaderMap,
+    pub body: Bytes,
+}
+
+#[derive(Clone, Default)]
+pub struct WebhookReceiver {
+    pub received: Arc<Mutex<Vec<ReceivedWebhook>>>,
+    status: Arc<AtomicU16>,
+}
+
+impl WebhookReceiver {
+    /// Status code of the following answers
+    pub fn answer_with(&self, status: StatusCode) {
+        self.status.store(status.as_u16(), Ordering::SeqCst);
+    }
+
+    pub fn received(&self) -> Vec<ReceivedWebhook> {
+        self.received.lock().unwrap().clone()
+    }
+}
+
+/// Service sending to loopback, retrying failures right away up to 3 times
+pub fn create_webhook_service(pool: PgPool) -> WebhookService<WebhookRepository, ChangeRepository> {
+    WebhookService::new(
+        WebhookRepository::new(pool.clone()),
+        create_change_repo(pool),
+        Arc::new(HttpWebhookSender::new(SenderLimits {
+            timeout: std::time::Duration::from_secs(1),
+            allow_private_addresses: true,
+        })),
+        RetryPolicy {
+            max_attempts: 3,
+            first_delay: Duration::zero(),
+            max_delay: Duration::zero(),
+        },
+    )
+}
+
+/// Endpoint recording the webhooks it gets, answering 200 until told otherwise.
+/// Returns its URL.
+pub async fn spawn_webhook_receiver() -> (String, WebhookReceiver) {
+    let receiver = WebhookReceiver::default();
+    receiver.answer_with(StatusCode::OK);
+    let app = Router::new()
+        .route("/hook", post(receive))
+        .with_state(receiver.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    (format!("http://localhost:{port}/hook"), receiver)
+}
+
+async fn receive(
+    State(receiver): State<WebhookReceiver>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> StatusCode {
+    receiver
+        .received
+        .lock()
+        .unwrap()
+        .push(ReceivedWebhook { headers, body });
+    StatusCode::from_u16(receiver.status.load(Ordering::SeqCst)).unwrap()
+}
@@ -0,0 +1,133 @@
+mod fixtures;
+
+use crate::fixtures::{
+    create_attachment_service, create_block_service_with_images, create_note_service,
+    create_user_fixture, create_user_repository, create_workspace_fixture, create_workspace_repo,
+    jpeg_with_exif,
+};
+use remind_core::images::process_image;
+use remind_core::{
+    AttachmentCreateDTO, BlockContent, BlockCreateDTO, BlockType, ImageContent, NoteCreateDTO,
+};
+use sqlx::PgPool;
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack.windows(needle.len()).any(|w| w == needle)
+}
+
+#[test]
+fn test_process_image_strips_exif_and_applies_orientation() {
+    let jpeg = jpeg_with_exif(1000, 500, 6);
+    assert!(contains(&jpeg, b"Exif"));
+
+    let processed = process_image(&jpeg, "image/jpeg").unwrap();
+    assert_eq!(processed.dimensions.width, 500);
+    assert_eq!(processed.dimensions.height, 1000);
+
+    let stripped = processed.stripped.unwrap();
+    assert!(!contains(&stripped, b"Exif"));
+
+    let widths: Vec<u32> = processed.variants.iter().map(|(w, _)| *w).collect();
+    assert_eq!(widths, vec![320]);
+
+    assert!(process_image(b"not an image", "image/png").is_err());
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_uploaded_image_variants(pool: PgPool) {
+    let user = create_user_fixture(create_user_repository(pool.clone())).await;
+    let workspace = create_workspace_fixture(create_workspace_repo(pool.clone()), user.id).await;
+    let service = create_attachment_service(pool);
+
+    let attachment = service
+        .upload(AttachmentCreateDTO {
+            workspace_id: workspace.id,
+            user_id: user.id,
+            file_name: "photo.jpg".to_string(),
+            data: jpeg_with_exif(1400, 700, 1),
+        })
+        .await
+        .unwrap();
+    assert_eq!(attachment.width, Some(1400));
+    assert_eq!(attachment.height, Some(700));
+
+    let (_, original) = service.get_content(attachment.id, None).await.unwrap();
+    assert!(!contains(&original, b"Exif"));
+    assert_eq!(image::load_from_memory(&original).unwrap().width(), 1400);
+
+    let (_, thumbnail) = service.get_content(attachment.id, Some(500)).await.unwrap();
+    assert_eq!(image::load_from_memory(&thumbnail).unwrap().width(), 640);
+
+    let (_, too_wide) = service
+        .get_content(attachment.id, Some(4000))
+        .await
+        .unwrap();
+    assert_eq!(image::load_from_memory(&too_wide).unwrap().width(), 1400);
+
+    // Thumbnails count towards the quotas
+    let mut stored = original.len();
+    for width in [1, 321, 641] {
+        stored += service
+            .get_content(attachment.id, Some(width))
+            .await
+            .unwrap()
+            .1
+            .len();
+    }
+    assert_eq!(
+        service.user_usage(user.id).await.unwrap().used,
+        stored as i64
+    );
+    assert_eq!(
+        service.workspace_usage(workspace.id).await.unwrap().used,
+        stored as i64
+    );
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_image_block_records_dimensions(pool: PgPool) {
+    let user = create_user_fixture(create_user_repository(pool.clone())).await;
+    let workspace = create_workspace_fixture(create_workspace_repo(pool.clone()), user.id).await;
+    let note = create_note_service(pool.clone())
+        .create(NoteCreateDTO {
+            title: "Note".to_string(),
+            workspace_id: workspace.id,
+            parent_note: None,
+            template_id: None,
+            author: None,
+        })
+        .await
+        .unwrap();
+    let attachment = create_attachment_service(pool.clone())
+        .upload(AttachmentCreateDTO {
+            workspace_id: workspace.id,
+            user_id: user.id,
+            file_name: "photo.jpg".to_string(),
+            data: jpeg_with_exif(800, 600, 1),
+        })
+        .await
+        .unwrap();
+    let block_service = create_block_service_with_images(pool);
+
+    let block = block_service
+        .create(BlockCreateDTO {
+            block_type: BlockType::Image,
+            content: BlockContent::Image(ImageContent {
+                url: format!("/attachments/{}/content", attachment.id),
+                alt: None,
+                attachment_id: Some(attachment.id),
+                width: None,
+                height: None,
+            }),
+            note_id: note.id,
+            parent_block: None,
+        })
+        .await
+        .unwrap();
+
+    let BlockContent::Image(content) = block.content else {
+        panic!("Expected image content");
+    };
+    assert_eq!(content.width, Some(800));
+    assert_eq!(content.height, Some(600));
+}
@@ -0,0 +1,173 @@
+use crate::fixtures::{
+    create_block_service, create_note_service, create_user_fixture, create_user_repository,
+    create_workspace_fixture, create_workspace_repo,
+};
+use remind_core::render::{render_html, render_markdown};
+use remind_core::{
+    BlockContent, BlockCreateDTO, BlockDTO, BlockType, ListItemContent, NoteCreateDTO, NoteDTO,
+    PlainTextContent, TextSpan,
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+mod fixtures;
+
+async fn create_note(pool: PgPool) -> NoteDTO {
+    let user_repo = create_user_repository(pool.clone());
+    let user = create_user_fixture(user_repo).await;
+    let service = create_note_service(pool.clone());
+    let workspace = create_workspace_fixture(create_workspace_repo(pool), user.id).await;
+
+    let dto = NoteCreateDTO {
+        title: "Outline".to_string(),
+        workspace_id: workspace.id,
+        parent_note: None,
+        template_id: None,
+        author: None,
+    };
+
+    service.create(dto.clone()).await.unwrap()
+}
+
+async fn add_item(pool: PgPool, note_id: Uuid, numbered: bool, text: &str, indent: u8) -> BlockDTO {
+    let content = ListItemContent {
+        spans: vec![TextSpan::plain(text)],
+        indent,
+    };
+    let (block_type, content) = if numbered {
+        (BlockType::NumberedList, BlockContent::NumberedList(content))
+    } else {
+        (BlockType::BulletedList, BlockContent::BulletedList(content))
+    };
+    create_block_service(pool)
+        .create(BlockCreateDTO {
+            block_type,
+            content,
+            note_id,
+            parent_block: None,
+        })
+        .await
+        .unwrap()
+}
+
+fn outline(note: &NoteDTO) -> Vec<(String, u8, Option<u32>)> {
+    note.blocks
+        .iter()
+        .map(|b| {
+            (
+                b.content.plain_text(),
+                b.content.list_indent().unwrap_or_default(),
+                b.number,
+            )
+        })
+        .collect()
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_list_numbering(pool: PgPool) {
+    let note = create_note(pool.clone()).await;
+    let note_service = create_note_service(pool.clone());
+
+    // Too deep for the first item, gets clamped to the top level
+    add_item(pool.clone(), note.id, true, "One", 2).await;
+    add_item(pool.clone(), note.id, true, "One.One", 1).await;
+    add_item(pool.clone(), note.id, false, "Bullet", 2).await;
+    add_item(pool.clone(), note.id, true, "One.Two", 1).await;
+    add_item(pool.clone(), note.id, true, "Two", 0).await;
+    create_block_service(pool.clone())
+        .create(BlockCreateDTO {
+            block_type: BlockType::PlainText,
+            content: BlockContent::PlainText(PlainTextContent::plain("Break")),
+            note_id: note.id,
+            parent_block: None,
+        })
+        .await
+        .unwrap();
+    add_item(pool.clone(), note.id, true, "Restart", 0).await;
+
+    let note = note_service.find_one(note.id).await.unwrap();
+    assert_eq!(
+        outline(&note),
+        vec![
+            ("One".to_string(), 0, Some(1)),
+            ("One.One".to_string(), 1, Some(1)),
+            ("Bullet".to_string(), 2, None),
+            ("One.Two".to_string(), 1, Some(2)),
+            ("Two".to_string(), 0, Some(2)),
+            ("Break".to_string(), 0, None),
+            ("Restart".to_string(), 0, Some(1)),
+        ]
+    );
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_reorder_moves_children(pool: PgPool) {
+    let note = create_note(pool.clone()).await;
+    let note_service = create_note_service(pool.clone());
+
+    let first = add_item(pool.clone(), note.id, true, "First", 0).await;
+    let child = add_item(pool.clone(), note.id, true, "Child", 1).await;
+    let grandchild = add_item(pool.clone(), note.id, false, "Grandchild", 2).await;
+    let second = add_item(pool.clone(), note.id, true, "Second", 0).await;
+
+    // Only the parent is moved, its children follow it
+    note_service
+        .reorder_blocks(
+            note.id,
+            None,
+            vec![second.id, first.id, child.id, grandchild.id],
+            None,
+        )
+        .await
+        .unwrap();
+    let reordered = note_service.find_one(note.id).await.unwrap();
+    assert_eq!(
+        outline(&reordered),
+        vec![
+            ("Second".to_string(), 0, Some(1)),
+            ("First".to_string(), 0, Some(2)),
+            ("Child".to_string(), 1, Some(1)),
+            ("Grandchild".to_string(), 2, None),
+        ]
+    );
+
+    note_service
+        .reorder_blocks(
+            note.id,
+            None,
+            vec![first.id, second.id, child.id, grandchild.id],
+            None,
+        )
+        .await
+        .unwrap();
+    let reordered = note_service.find_one(note.id).await.unwrap();
+    let texts: Vec<String> = reordered
+        .blocks
+        .iter()
+        .map(|b| b.content.plain_text())
+        .collect();
+    assert_eq!(texts, vec!["First", "Child", "Grandchild", "Second"]);
+    let positions: Vec<i32> = reordered.blocks.iter().map(|b| b.position).collect();
+    assert_eq!(positions, vec![0, 1, 2, 3]);
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_render_lists(pool: PgPool) {
+    let note = create_note(pool.clone()).await;
+    let note_service = create_note_service(pool.clone());
+
+    add_item(pool.clone(), note.id, true, "One", 0).await;
+    add_item(pool.clone(), note.id, false, "Nested", 1).await;
+    add_item(pool.clone(), note.id, true, "Two", 0).await;
+
+    let note = note_service.find_one(note.id).await.unwrap();
+    assert_eq!(
+        render_markdown(&note),
+        "# Outline\n\n1. One\n    - Nested\n2. Two\n"
+    );
+    assert!(
+        render_html(&note).contains(
+            "<ol>\n<li>One<ul>\n<li>Nested</li></ul>\n</li>\n<li>Two</li></ol>\n</article>"
+        )
+    );
+}
@@ -0,0 +1,228 @@
+use crate::fixtures::{
+    create_block_service, create_note_service, create_user_fixture, create_user_repository,
+    create_workspace_fixture, create_workspace_repo,
+};
+use remind_core::errors::CoreError;
+use remind_core::render::{render_html, render_markdown};
+use remind_core::{
+    AuditContext, BlockContent, BlockCreateDTO, BlockDTO, BlockRepository, BlockService, BlockType,
+    BlockUpdateDTO, ChangeRepository, Mention, NoteCreateDTO, NoteDTO, NoteUpdateDTO,
+    PlainTextContent, TextSpan,
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+mod fixtures;
+
+/// Notes of one workspace with the given titles, and the id of its owner
+async fn create_notes(pool: PgPool, titles: &[&str]) -> (Uuid, Vec<NoteDTO>) {
+    let user_repo = create_user_repository(pool.clone());
+    let user = create_user_fixture(user_repo).await;
+    let service = create_note_service(pool.clone());
+    let workspace = create_workspace_fixture(create_workspace_repo(pool), user.id).await;
+
+    let mut notes = Vec::new();
+    for title in titles {
+        let dto = NoteCreateDTO {
+            title: title.to_string(),
+            workspace_id: workspace.id,
+            parent_note: None,
+            template_id: None,
+            author: None,
+        };
+        notes.push(service.create(dto).await.unwrap());
+    }
+    (user.id, notes)
+}
+
+fn text(spans: Vec<TextSpan>) -> BlockContent {
+    BlockContent::PlainText(PlainTextContent { spans })
+}
+
+async fn add(
+    service: &BlockService<BlockRepository, ChangeRepository>,
+    note_id: Uuid,
+    spans: Vec<TextSpan>,
+) -> BlockDTO {
+    service
+        .create(BlockCreateDTO {
+            block_type: BlockType::PlainText,
+            content: text(spans),
+            note_id,
+            parent_block: None,
+        })
+        .await
+        .unwrap()
+}
+
+fn spans(block: &BlockDTO) -> &[TextSpan] {
+    block.content.spans().unwrap()
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_backlinks_follow_block_changes(pool: PgPool) {
+    let (_, notes) = create_notes(pool.clone(), &["Roadmap", "Meeting", "Retro"]).await;
+    let (roadmap, meeting, retro) = (&notes[0], &notes[1], &notes[2]);
+    let service = create_block_service(pool.clone());
+    let note_service = create_note_service(pool.clone());
+    let mention = || TextSpan::mention("Roadmap", Mention::Note(roadmap.id));
+
+    let first = add(
+        &service,
+        meeting.id,
+        vec![TextSpan::plain("Went through the "), mention()],
+    )
+    .await;
+    add(
+        &service,
+        meeting.id,
+        vec![mention(), TextSpan::plain(" is late")],
+    )
+    .await;
+    let retro_block = add(&service, retro.id, vec![TextSpan::plain("See "), mention()]).await;
+    // Mentions of the note itself are no backlinks
+    add(&service, roadmap.id, vec![mention()]).await;
+
+    let backlinks = note_service.get_backlinks(roadmap.id).await.unwrap();
+    assert_eq!(backlinks.len(), 2);
+    assert_eq!(backlinks[0].note_id, meeting.id);
+    assert_eq!(
+        backlinks[0].snippets,
+        vec!["Went through the Roadmap", "Roadmap is late"]
+    );
+    assert_eq!(backlinks[1].title, "Retro");
+    assert_eq!(backlinks[1].snippets, vec!["See Roadmap"]);
+
+    service
+        .update(BlockUpdateDTO {
+            id: first.id,
+            block_type: None,
+            content: Some(text(vec![TextSpan::plain("Went through it")])),
+            version: None,
+        })
+        .await
+        .unwrap();
+    service
+        .delete(retro_block.id, false, &AuditContext::default())
+        .await
+        .unwrap();
+    let backlinks = note_service.get_backlinks(roadmap.id).await.unwrap();
+    assert_eq!(backlinks.len(), 1);
+    assert_eq!(backlinks[0].snippets, vec!["Roadmap is late"]);
+    assert!(matches!(
+        note_service.get_backlinks(Uuid::new_v4()).await,
+        Err(CoreError::NotFound)
+    ));
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_mentions_survive_renames(pool: PgPool) {
+    let (user_id, notes) = create_notes(pool.clone(), &["Draft", "Index"]).await;
+    let (draft, index) = (&notes[0], &notes[1]);
+    let service = create_block_service(pool.clone());
+    let note_service = create_note_service(pool.clone());
+
+    add(
+        &service,
+        index.id,
+        vec![
+            TextSpan::plain("Read "),
+            TextSpan::mention("Draft", Mention::Note(draft.id)),
+        ],
+    )
+    .await;
+    note_service
+        .update(
+            draft.id,
+            NoteUpdateDTO {
+                title: Some("Final".to_string()),
+                version: None,
+            },
+        )
+        .await
+        .unwrap();
+
+    let note = note_service.find_one(index.id).await.unwrap();
+    let mention = &spans(&note.blocks[0])[1];
+    assert_eq!(mention.text, "Final");
+    assert!(!mention.broken);
+    assert!(render_html(&note).contains(&format!(
+        "<span class=\"mention\" data-note=\"{}\">Final</span>",
+        draft.id
+    )));
+    assert_eq!(
+        note_service.get_backlinks(draft.id).await.unwrap()[0].snippets,
+        vec!["Read Draft"]
+    );
+
+    // Deleted targets and notes of other workspaces show as broken
+    let other_workspace =
+        create_workspace_fixture(create_workspace_repo(pool.clone()), user_id).await;
+    let hidden = note_service
+        .create(NoteCreateDTO {
+            title: "Hidden".to_string(),
+            workspace_id: other_workspace.id,
+            parent_note: None,
+            template_id: None,
+            author: None,
+        })
+        .await
+        .unwrap();
+    add(
+        &service,
+        index.id,
+        vec![TextSpan::mention("Secret", Mention::Note(hidden.id))],
+    )
+    .await;
+    note_service
+        .delete(draft.id, &AuditContext::default())
+        .await
+        .unwrap();
+
+    let note = note_service.find_one(index.id).await.unwrap();
+    // Broken mentions keep the text they were saved with
+    let mention = &spans(&note.blocks[0])[1];
+    assert_eq!(mention.text, "Draft");
+    assert!(mention.broken);
+    assert!(spans(&note.blocks[1])[0].broken);
+    assert_eq!(spans(&note.blocks[1])[0].text, "Secret");
+    assert!(render_html(&note).contains("<span class=\"mention broken\""));
+    assert!(render_markdown(&note).contains("Read ~~Draft~~"));
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_mention_spans(pool: PgPool) {
+    let (_, notes) = create_notes(pool.clone(), &["People"]).await;
+    let service = create_block_service(pool.clone());
+    let user_id = Uuid::new_v4();
+
+    let span: TextSpan = serde_json::from_value(serde_json::json!({
+        "text": "@ada",
+        "mention": { "type": "user", "id": user_id },
+        "broken": true,
+    }))
+    .unwrap();
+    assert_eq!(span.mention, Some(Mention::User(user_id)));
+
+    // Broken is never taken from clients
+    let block = add(&service, notes[0].id, vec![span]).await;
+    assert!(!spans(&block)[0].broken);
+    assert_eq!(
+        serde_json::to_value(&spans(&block)[0]).unwrap(),
+        serde_json::json!({ "text": "@ada", "mention": { "type": "user", "id": user_id } })
+    );
+    assert_eq!(block.content.mentions(), vec![Mention::User(user_id)]);
+
+    let result = service
+        .create(BlockCreateDTO {
+            block_type: BlockType::PlainText,
+            content: text(vec![TextSpan {
+                href: Some("https://example.com".to_string()),
+                ..TextSpan::mention("@ada", Mention::User(user_id))
+            }]),
+            note_id: notes[0].id,
+            parent_block: None,
+        })
+        .await;
+    assert!(matches!(result, Err(CoreError::InvalidRichText(_))));
+}
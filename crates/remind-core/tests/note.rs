@@ -2,7 +2,7 @@ use crate::fixtures::{
     create_note_service, create_user_fixture, create_user_repository, create_workspace_fixture,
     create_workspace_repo,
 };
-use remind_core::NoteCreateDTO;
+use remind_core::{AuditContext, NoteCreateDTO};
 use sqlx::PgPool;
 use uuid::Uuid;
 
@@ -19,6 +19,8 @@ async fn test_create_note(pool: PgPool) {
         title: "Note".to_string(),
         workspace_id: workspace.id,
         parent_note: None,
+        template_id: None,
+        author: None,
     };
     let note = service.create(dto.clone()).await.unwrap();
     assert_eq!(note.title, dto.title);
@@ -37,6 +39,8 @@ async fn test_find_one_note(pool: PgPool) {
         title: "Note".to_string(),
         workspace_id: workspace.id,
         parent_note: None,
+        template_id: None,
+        author: None,
     };
     let note = service.create(dto.clone()).await.unwrap();
     let note_found = service.find_one(note.id).await.unwrap();
@@ -58,6 +62,8 @@ async fn test_get_all_notes_in_workspace(pool: PgPool) {
             title: "Note1".to_string(),
             workspace_id: workspace.id,
             parent_note: None,
+            template_id: None,
+            author: None,
         })
         .await
         .unwrap();
@@ -66,6 +72,8 @@ async fn test_get_all_notes_in_workspace(pool: PgPool) {
             title: "Note2".to_string(),
             workspace_id: workspace.id,
             parent_note: None,
+            template_id: None,
+            author: None,
         })
         .await
         .unwrap();
@@ -74,6 +82,8 @@ async fn test_get_all_notes_in_workspace(pool: PgPool) {
             title: "Note3".to_string(),
             workspace_id: workspace.id,
             parent_note: None,
+            template_id: None,
+            author: None,
         })
         .await
         .unwrap();
@@ -96,9 +106,11 @@ async fn test_delete_note(pool: PgPool) {
         title: "Note".to_string(),
         workspace_id: workspace.id,
         parent_note: None,
+        template_id: None,
+        author: None,
     };
     let note = service.create(dto.clone()).await.unwrap();
 
-    let res = service.delete(note.id).await;
+    let res = service.delete(note.id, &AuditContext::default()).await;
     assert!(res.is_ok());
 }
@@ -0,0 +1,303 @@
+use crate::fixtures::{
+    create_block_service_with_notifications, create_comment_service_with_notifications,
+    create_note_service, create_notification_service, create_user_fixture, create_user_repository,
+    create_workspace_fixture, create_workspace_repo,
+};
+use remind_auth::hash_password;
+use remind_core::errors::CoreError;
+use remind_core::{
+    BlockContent, BlockCreateDTO, BlockType, BlockUpdateDTO, CommentCreateDTO, Mention,
+    NoteCreateDTO, NoteDTO, NotificationCreateDTO, NotificationKind, NotificationPreferenceDTO,
+    PlainTextContent, TextSpan, User, UserRepo,
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+mod fixtures;
+
+/// Note of a workspace, its owner and a second user
+async fn create_note(pool: PgPool) -> (Uuid, Uuid, NoteDTO) {
+    let user_repo = create_user_repository(pool.clone());
+    let user = create_user_fixture(user_repo.clone()).await;
+    let reviewer = User {
+        id: Uuid::new_v4(),
+        username: "reviewer".to_string(),
+        email: "reviewer@example.com".to_string(),
+        password: hash_password("password".as_bytes()).unwrap(),
+    };
+    user_repo.create(reviewer.clone()).await.unwrap();
+    let workspace = create_workspace_fixture(create_workspace_repo(pool.clone()), user.id).await;
+
+    let dto = NoteCreateDTO {
+        title: "Runbook".to_string(),
+        workspace_id: workspace.id,
+        parent_note: None,
+        template_id: None,
+        author: None,
+    };
+    let note = create_note_service(pool).create(dto).await.unwrap();
+    (user.id, reviewer.id, note)
+}
+
+fn text(spans: Vec<TextSpan>) -> BlockContent {
+    BlockContent::PlainText(PlainTextContent { spans })
+}
+
+fn notification(user_id: Uuid, kind: NotificationKind) -> NotificationCreateDTO {
+    NotificationCreateDTO {
+        user_id,
+        kind,
+        actor_id: None,
+        note_id: None,
+        block_id: None,
+        comment_id: None,
+    }
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_mentions_notify_users(pool: PgPool) {
+    let (user_id, reviewer_id, note) = create_note(pool.clone()).await;
+    let blocks = create_block_service_with_notifications(pool.clone());
+    let service = create_notification_service(pool.clone());
+
+    let block = blocks
+        .create(BlockCreateDTO {
+            block_type: BlockType::PlainText,
+            content: text(vec![
+                TextSpan::mention("@reviewer", Mention::User(reviewer_id)),
+                TextSpan::plain(" please check"),
+            ]),
+            note_id: note.id,
+            parent_block: None,
+        })
+        .await
+        .unwrap();
+    let page = service.get_page(reviewer_id, None, 10).await.unwrap();
+    assert_eq!(page.unread_count, 1);
+    let mention = &page.notifications[0];
+    assert_eq!(mention.kind, NotificationKind::Mention);
+    assert_eq!(mention.note_id, Some(note.id));
+    assert_eq!(mention.block_id, Some(block.id));
+
+    // Users already mentioned aren't notified again, unknown ones never
+    blocks
+        .update(BlockUpdateDTO {
+            id: block.id,
+            block_type: None,
+            content: Some(text(vec![
+                TextSpan::mention("@reviewer", Mention::User(reviewer_id)),
+                TextSpan::mention("@owner", Mention::User(user_id)),
+                TextSpan::mention("@gone", Mention::User(Uuid::new_v4())),
+                TextSpan::mention("Other note", Mention::Note(note.id)),
+            ])),
+            version: None,
+        })
+        .await
+        .unwrap();
+    assert_eq!(
+        service
+            .get_page(reviewer_id, None, 10)
+            .await
+            .unwrap()
+            .unread_count,
+        1
+    );
+    assert_eq!(
+        service
+            .get_page(user_id, None, 10)
+            .await
+            .unwrap()
+            .unread_count,
+        1
+    );
+
+    // Turned off, mentions don't notify anymore
+    service
+        .set_preferences(
+            reviewer_id,
+            vec![NotificationPreferenceDTO {
+                kind: NotificationKind::Mention,
+                enabled: false,
+            }],
+        )
+        .await
+        .unwrap();
+    blocks
+        .create(BlockCreateDTO {
+            block_type: BlockType::PlainText,
+            content: text(vec![TextSpan::mention(
+                "@reviewer",
+                Mention::User(reviewer_id),
+            )]),
+            note_id: note.id,
+            parent_block: None,
+        })
+        .await
+        .unwrap();
+    assert_eq!(
+        service
+            .get_page(reviewer_id, None, 10)
+            .await
+            .unwrap()
+            .unread_count,
+        1
+    );
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_replies_notify_participants(pool: PgPool) {
+    let (user_id, reviewer_id, note) = create_note(pool.clone()).await;
+    let comments = create_comment_service_with_notifications(pool.clone());
+    let service = create_notification_service(pool.clone());
+
+    let thread = comments
+        .create(CommentCreateDTO {
+            note_id: note.id,
+            block_id: None,
+            author_id: user_id,
+            body: "Is step 2 still needed?".to_string(),
+        })
+        .await
+        .unwrap();
+    // Starting a thread notifies nobody
+    assert_eq!(
+        service
+            .get_page(user_id, None, 10)
+            .await
+            .unwrap()
+            .unread_count,
+        0
+    );
+
+    let reply = comments
+        .reply(thread.id, reviewer_id, "No, removed it")
+        .await
+        .unwrap();
+    let page = service.get_page(user_id, None, 10).await.unwrap();
+    assert_eq!(page.unread_count, 1);
+    let notified = &page.notifications[0];
+    assert_eq!(notified.kind, NotificationKind::CommentReply);
+    assert_eq!(notified.actor_id, Some(reviewer_id));
+    assert_eq!(notified.comment_id, Some(reply.id));
+    assert_eq!(notified.note_id, Some(note.id));
+    assert_eq!(
+        service
+            .get_page(reviewer_id, None, 10)
+            .await
+            .unwrap()
+            .unread_count,
+        0
+    );
+
+    comments.reply(reply.id, user_id, "Thanks").await.unwrap();
+    assert_eq!(
+        service
+            .get_page(user_id, None, 10)
+            .await
+            .unwrap()
+            .unread_count,
+        1
+    );
+    assert_eq!(
+        service
+            .get_page(reviewer_id, None, 10)
+            .await
+            .unwrap()
+            .unread_count,
+        1
+    );
+
+    // Notifications go with their comment
+    comments.delete(thread.id, user_id).await.unwrap();
+    assert!(
+        service
+            .get_page(user_id, None, 10)
+            .await
+            .unwrap()
+            .notifications
+            .is_empty()
+    );
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_inbox_pages_and_read_state(pool: PgPool) {
+    let (user_id, reviewer_id, _) = create_note(pool.clone()).await;
+    let service = create_notification_service(pool.clone());
+
+    let mut created = Vec::new();
+    for _ in 0..5 {
+        let notification = service
+            .create(notification(user_id, NotificationKind::Mention))
+            .await
+            .unwrap()
+            .unwrap();
+        created.push(notification.id);
+    }
+    // Users aren't notified of what they did
+    let own = NotificationCreateDTO {
+        actor_id: Some(user_id),
+        ..notification(user_id, NotificationKind::CommentReply)
+    };
+    assert!(service.create(own).await.unwrap().is_none());
+
+    let mut listed = Vec::new();
+    let mut cursor = None;
+    loop {
+        let page = service.get_page(user_id, cursor, 2).await.unwrap();
+        assert_eq!(page.unread_count, 5);
+        listed.extend(page.notifications.iter().map(|n| n.id));
+        cursor = page.next_cursor;
+        if cursor.is_none() {
+            break;
+        }
+    }
+    created.reverse();
+    assert_eq!(listed, created);
+
+    service.mark_read(created[0], user_id).await.unwrap();
+    assert!(matches!(
+        service.mark_read(created[1], reviewer_id).await,
+        Err(CoreError::NotFound)
+    ));
+    let page = service.get_page(user_id, None, 1).await.unwrap();
+    assert_eq!(page.unread_count, 4);
+    assert!(page.notifications[0].read_at.is_some());
+    assert_eq!(service.mark_all_read(user_id).await.unwrap(), 4);
+    assert_eq!(service.mark_all_read(user_id).await.unwrap(), 0);
+    assert_eq!(
+        service
+            .get_page(user_id, None, 10)
+            .await
+            .unwrap()
+            .unread_count,
+        0
+    );
+
+    let enabled = |preferences: Vec<NotificationPreferenceDTO>| -> Vec<bool> {
+        preferences.iter().map(|p| p.enabled).collect()
+    };
+    assert_eq!(
+        enabled(service.get_preferences(user_id).await.unwrap()),
+        vec![true, true]
+    );
+    let turned_off = NotificationPreferenceDTO {
+        kind: NotificationKind::CommentReply,
+        enabled: false,
+    };
+    let preferences = service
+        .set_preferences(user_id, vec![turned_off])
+        .await
+        .unwrap();
+    assert_eq!(enabled(preferences), vec![true, false]);
+    assert!(
+        service
+            .create(notification(user_id, NotificationKind::CommentReply))
+            .await
+            .unwrap()
+            .is_none()
+    );
+    assert_eq!(
+        enabled(service.get_preferences(reviewer_id).await.unwrap()),
+        vec![true, true]
+    );
+}
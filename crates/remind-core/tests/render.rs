@@ -0,0 +1,263 @@
+use crate::fixtures::{
+    create_block_service, create_note_service, create_user_fixture, create_user_repository,
+    create_workspace_fixture, create_workspace_repo,
+};
+use remind_core::render::{render_html, render_markdown, render_table_csv};
+use remind_core::{
+    BlockContent, BlockCreateDTO, BlockType, CalloutColor, CalloutContent, CellValue, ColumnType,
+    DividerContent, HeadingContent, ImageContent, NoteCreateDTO, NoteDTO, PlainTextContent,
+    QuoteContent, TableColumn, TableContent, TableRow, TextMark, TextSpan,
+};
+use sqlx::PgPool;
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+mod fixtures;
+
+async fn create_note(pool: PgPool) -> NoteDTO {
+    let user_repo = create_user_repository(pool.clone());
+    let user = create_user_fixture(user_repo).await;
+    let service = create_note_service(pool.clone());
+    let workspace = create_workspace_fixture(create_workspace_repo(pool), user.id).await;
+
+    let dto = NoteCreateDTO {
+        title: "Note <draft>".to_string(),
+        workspace_id: workspace.id,
+        parent_note: None,
+        template_id: None,
+        author: None,
+    };
+
+    service.create(dto.clone()).await.unwrap()
+}
+
+/// Creates one block of every document block type
+async fn create_document(pool: PgPool) -> NoteDTO {
+    let note = create_note(pool.clone()).await;
+    let block_service = create_block_service(pool.clone());
+
+    let contents = vec![
+        (
+            BlockType::Heading,
+            BlockContent::Heading(HeadingContent {
+                level: 1,
+                spans: vec![TextSpan::plain("Intro")],
+            }),
+        ),
+        (
+            BlockType::PlainText,
+            BlockContent::PlainText(PlainTextContent {
+                spans: vec![
+                    TextSpan {
+                        text: "bold".to_string(),
+                        marks: vec![TextMark::Bold],
+                        href: None,
+                        mention: None,
+                        broken: false,
+                    },
+                    TextSpan::plain(" & "),
+                    TextSpan {
+                        text: "link".to_string(),
+                        marks: vec![],
+                        href: Some("https://example.com".to_string()),
+                        mention: None,
+                        broken: false,
+                    },
+                ],
+            }),
+        ),
+        (
+            BlockType::Quote,
+            BlockContent::Quote(QuoteContent {
+                spans: vec![TextSpan::plain("Quoted")],
+            }),
+        ),
+        (
+            BlockType::Callout,
+            BlockContent::Callout(CalloutContent {
+                icon: "💡".to_string(),
+                color: CalloutColor::Yellow,
+                spans: vec![TextSpan::plain("Tip")],
+            }),
+        ),
+        (BlockType::Divider, BlockContent::Divider(DividerContent {})),
+    ];
+    for (block_type, content) in contents {
+        block_service
+            .create(BlockCreateDTO {
+                block_type,
+                content,
+                note_id: note.id,
+                parent_block: None,
+            })
+            .await
+            .unwrap();
+    }
+
+    create_note_service(pool).find_one(note.id).await.unwrap()
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_document_blocks(pool: PgPool) {
+    let note = create_document(pool.clone()).await;
+    let block_service = create_block_service(pool);
+
+    assert_eq!(note.blocks.len(), 5);
+    assert_eq!(note.blocks[0].block_type, BlockType::Heading);
+    assert_eq!(note.blocks[3].block_type, BlockType::Callout);
+    assert_eq!(note.blocks[4].block_type, BlockType::Divider);
+    assert_eq!(note.blocks[3].content.plain_text(), "Tip");
+
+    let too_deep = block_service
+        .create(BlockCreateDTO {
+            block_type: BlockType::Heading,
+            content: BlockContent::Heading(HeadingContent {
+                level: 4,
+                spans: vec![TextSpan::plain("Too deep")],
+            }),
+            note_id: note.id,
+            parent_block: None,
+        })
+        .await;
+    assert!(too_deep.is_err());
+
+    let mismatched = block_service
+        .create(BlockCreateDTO {
+            block_type: BlockType::Quote,
+            content: BlockContent::Divider(DividerContent {}),
+            note_id: note.id,
+            parent_block: None,
+        })
+        .await;
+    assert!(mismatched.is_err());
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_render_markdown(pool: PgPool) {
+    let note = create_document(pool).await;
+
+    let markdown = render_markdown(&note);
+    assert_eq!(
+        markdown,
+        "# Note \\<draft\\>\n\n## Intro\n\n**bold** & [link](https://example.com)\n\n> Quoted\n\n> 💡 Tip\n\n---\n"
+    );
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_render_markdown_code_and_links(pool: PgPool) {
+    let note = create_note(pool.clone()).await;
+    let block_service = create_block_service(pool.clone());
+    let code = |text: &str| TextSpan {
+        text: text.to_string(),
+        marks: vec![TextMark::Code],
+        href: None,
+        mention: None,
+        broken: false,
+    };
+    let contents = vec![
+        (
+            BlockType::PlainText,
+            BlockContent::PlainText(PlainTextContent {
+                spans: vec![
+                    code("a``b"),
+                    TextSpan::plain(" "),
+                    code("`tick"),
+                    TextSpan::plain(" "),
+                    code("x"),
+                ],
+            }),
+        ),
+        (
+            BlockType::PlainText,
+            BlockContent::PlainText(PlainTextContent {
+                spans: vec![TextSpan {
+                    text: "docs".to_string(),
+                    marks: vec![],
+                    href: Some("https://example.com/a) [b](javascript:x".to_string()),
+                    mention: None,
+                    broken: false,
+                }],
+            }),
+        ),
+        (
+            BlockType::Image,
+            BlockContent::Image(ImageContent {
+                url: "https://example.com/a b).png><x".to_string(),
+                alt: Some("Chart".to_string()),
+                attachment_id: None,
+                width: None,
+                height: None,
+            }),
+        ),
+    ];
+    for (block_type, content) in contents {
+        block_service
+            .create(BlockCreateDTO {
+                block_type,
+                content,
+                note_id: note.id,
+                parent_block: None,
+            })
+            .await
+            .unwrap();
+    }
+
+    let note = create_note_service(pool).find_one(note.id).await.unwrap();
+    assert_eq!(
+        render_markdown(&note),
+        "# Note \\<draft\\>\n\n``` a``b ``` `` `tick `` `x`\n\n\
+         [docs](<https://example.com/a)%20[b](javascript:x>)\n\n\
+         ![Chart](<https://example.com/a%20b).png%3E%3Cx>)\n"
+    );
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_render_html(pool: PgPool) {
+    let note = create_document(pool).await;
+
+    let html = render_html(&note);
+    assert!(html.contains("<title>Note &lt;draft&gt;</title>"));
+    assert!(html.contains("<h2>Intro</h2>"));
+    assert!(
+        html.contains(
+            "<p><strong>bold</strong> &amp; <a href=\"https://example.com\">link</a></p>"
+        )
+    );
+    assert!(html.contains("<blockquote>Quoted</blockquote>"));
+    assert!(html.contains("<aside class=\"callout callout-yellow\">"));
+    assert!(html.contains("<hr>"));
+}
+
+#[test]
+fn test_csv_formulas_are_not_run() {
+    let column = TableColumn {
+        id: Uuid::new_v4(),
+        name: "=Name".to_string(),
+        column_type: ColumnType::Text,
+    };
+    let rows = [
+        "=HYPERLINK(\"http://example.com\")",
+        "+1",
+        "-2+3",
+        "@SUM(A1)",
+        "\tcmd",
+        "\rcmd",
+        "a=b",
+    ]
+    .into_iter()
+    .map(|text| TableRow {
+        id: Uuid::new_v4(),
+        cells: BTreeMap::from([(column.id, CellValue::Text(text.to_string()))]),
+    })
+    .collect();
+    let table = TableContent {
+        columns: vec![column],
+        rows,
+    };
+
+    assert_eq!(
+        render_table_csv(&table),
+        "\"'=Name\"\r\n\"'=HYPERLINK(\"\"http://example.com\"\")\"\r\n\"'+1\"\r\n\"'-2+3\"\r\n\
+         \"'@SUM(A1)\"\r\n\"'\tcmd\"\r\n\"'\rcmd\"\r\na=b\r\n"
+    );
+}
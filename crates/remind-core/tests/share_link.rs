@@ -0,0 +1,307 @@
+mod fixtures;
+
+use crate::fixtures::{
+    create_attachment_service, create_block_service, create_note_service,
+    create_share_link_service, create_user_fixture, create_user_repository,
+    create_workspace_fixture, create_workspace_repo,
+};
+use chrono::{Duration, Utc};
+use remind_core::errors::CoreError;
+use remind_core::{
+    AttachmentCreateDTO, AuditContext, BlockContent, BlockCreateDTO, BlockType, FileContent,
+    Mention, NoteCreateDTO, PlainTextContent, PublicBlockContent, ShareLinkCreateDTO,
+    SyncedContent, TextSpan,
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_create_and_resolve_share_link(pool: PgPool) {
+    let user = create_user_fixture(create_user_repository(pool.clone())).await;
+    let workspace = create_workspace_fixture(create_workspace_repo(pool.clone()), user.id).await;
+    let note_service = create_note_service(pool.clone());
+    let service = create_share_link_service(pool);
+
+    let note = note_service
+        .create(NoteCreateDTO {
+            title: "Shared".to_string(),
+            workspace_id: workspace.id,
+            parent_note: None,
+            template_id: None,
+            author: None,
+        })
+        .await
+        .unwrap();
+    let child = note_service
+        .create(NoteCreateDTO {
+            title: "Child".to_string(),
+            workspace_id: workspace.id,
+            parent_note: Some(note.id),
+            template_id: None,
+            author: None,
+        })
+        .await
+        .unwrap();
+
+    let link = service
+        .create(
+            ShareLinkCreateDTO {
+                note_id: note.id,
+                created_by: user.id,
+                password: None,
+                include_subpages: true,
+                expires_at: None,
+            },
+            &AuditContext::default(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(link.note_id, note.id);
+    assert!(!link.has_password);
+    assert!(link.slug.len() >= 20);
+
+    let public = service.resolve(link.slug.clone(), None).await.unwrap();
+    assert_eq!(public.id, note.id);
+    assert_eq!(public.subpages.len(), 1);
+    assert_eq!(public.subpages[0].id, child.id);
+
+    let not_found = service.resolve("unknown".to_string(), None).await;
+    assert!(not_found.is_err());
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_shared_blocks_hide_ids(pool: PgPool) {
+    let user = create_user_fixture(create_user_repository(pool.clone())).await;
+    let workspace = create_workspace_fixture(create_workspace_repo(pool.clone()), user.id).await;
+    let note_service = create_note_service(pool.clone());
+    let block_service = create_block_service(pool.clone());
+    let attachments = create_attachment_service(pool.clone());
+    let service = create_share_link_service(pool);
+    let note_dto = |title: &str| NoteCreateDTO {
+        title: title.to_string(),
+        workspace_id: workspace.id,
+        parent_note: None,
+        template_id: None,
+        author: None,
+    };
+    let upload = |file_name: &str| AttachmentCreateDTO {
+        workspace_id: workspace.id,
+        user_id: user.id,
+        file_name: file_name.to_string(),
+        data: b"hello".to_vec(),
+    };
+
+    let note = note_service.create(note_dto("Shared")).await.unwrap();
+    let other = note_service.create(note_dto("Agenda")).await.unwrap();
+    let source = block_service
+        .create(BlockCreateDTO {
+            block_type: BlockType::PlainText,
+            content: BlockContent::PlainText(PlainTextContent {
+                spans: vec![TextSpan::plain("Budget")],
+            }),
+            note_id: other.id,
+            parent_block: None,
+        })
+        .await
+        .unwrap();
+    let file = attachments.upload(upload("notes.txt")).await.unwrap();
+    let unused = attachments.upload(upload("other.txt")).await.unwrap();
+    for (block_type, content) in [
+        (
+            BlockType::PlainText,
+            BlockContent::PlainText(PlainTextContent {
+                spans: vec![
+                    TextSpan::mention("alice", Mention::User(user.id)),
+                    TextSpan::plain(" wrote "),
+                    TextSpan::mention("Old title", Mention::Note(other.id)),
+                ],
+            }),
+        ),
+        (
+            BlockType::File,
+            BlockContent::File(FileContent {
+                attachment_id: file.id,
+                name: "notes.txt".to_string(),
+            }),
+        ),
+        (
+            BlockType::Synced,
+            BlockContent::Synced(SyncedContent {
+                source_block: source.id,
+            }),
+        ),
+    ] {
+        block_service
+            .create(BlockCreateDTO {
+                block_type,
+                content,
+                note_id: note.id,
+                parent_block: None,
+            })
+            .await
+            .unwrap();
+    }
+    let link = service
+        .create(
+            ShareLinkCreateDTO {
+                note_id: note.id,
+                created_by: user.id,
+                password: None,
+                include_subpages: false,
+                expires_at: None,
+            },
+            &AuditContext::default(),
+        )
+        .await
+        .unwrap();
+
+    let public = service.resolve(link.slug.clone(), None).await.unwrap();
+    assert_eq!(public.blocks.len(), 3);
+    // Mentions show their current label only
+    let PublicBlockContent::PlainText(text) = &public.blocks[0].content else {
+        panic!("expected text");
+    };
+    let text: String = text.spans.iter().map(|s| s.text.as_str()).collect();
+    assert_eq!(text, "alice wrote Agenda");
+    let PublicBlockContent::File(shared) = &public.blocks[1].content else {
+        panic!("expected a file");
+    };
+    assert_eq!(
+        shared.url,
+        format!("/public/{}/attachments/{}/content", link.slug, file.id)
+    );
+    // Synced blocks show the content of their source
+    assert!(matches!(public.blocks[2].block_type, BlockType::PlainText));
+    assert!(matches!(
+        &public.blocks[2].content,
+        PublicBlockContent::PlainText(c) if c.spans[0].text == "Budget"
+    ));
+    let contents: Vec<_> = public.blocks.iter().map(|b| &b.content).collect();
+    let json = serde_json::to_string(&contents).unwrap();
+    for id in [user.id, other.id, source.id] {
+        assert!(!json.contains(&id.to_string()));
+    }
+
+    // Only uploads shown by the note are read through the link
+    service
+        .check_shared_attachment(link.slug.clone(), None, file.id)
+        .await
+        .unwrap();
+    assert!(matches!(
+        service
+            .check_shared_attachment(link.slug.clone(), None, unused.id)
+            .await,
+        Err(CoreError::NotFound)
+    ));
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_share_link_password_and_expiry(pool: PgPool) {
+    let user = create_user_fixture(create_user_repository(pool.clone())).await;
+    let workspace = create_workspace_fixture(create_workspace_repo(pool.clone()), user.id).await;
+    let note_service = create_note_service(pool.clone());
+    let service = create_share_link_service(pool);
+
+    let note = note_service
+        .create(NoteCreateDTO {
+            title: "Secret".to_string(),
+            workspace_id: workspace.id,
+            parent_note: None,
+            template_id: None,
+            author: None,
+        })
+        .await
+        .unwrap();
+
+    let protected = service
+        .create(
+            ShareLinkCreateDTO {
+                note_id: note.id,
+                created_by: user.id,
+                password: Some("hunter2".to_string()),
+                include_subpages: false,
+                expires_at: None,
+            },
+            &AuditContext::default(),
+        )
+        .await
+        .unwrap();
+    assert!(protected.has_password);
+    assert!(service.resolve(protected.slug.clone(), None).await.is_err());
+    assert!(
+        service
+            .resolve(protected.slug.clone(), Some("wrong".to_string()))
+            .await
+            .is_err()
+    );
+    let public = service
+        .resolve(protected.slug, Some("hunter2".to_string()))
+        .await
+        .unwrap();
+    assert!(public.subpages.is_empty());
+
+    let expired = service
+        .create(
+            ShareLinkCreateDTO {
+                note_id: note.id,
+                created_by: user.id,
+                password: None,
+                include_subpages: false,
+                expires_at: Some(Utc::now() - Duration::minutes(1)),
+            },
+            &AuditContext::default(),
+        )
+        .await
+        .unwrap();
+    assert!(service.resolve(expired.slug, None).await.is_err());
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_list_and_revoke_share_links(pool: PgPool) {
+    let user = create_user_fixture(create_user_repository(pool.clone())).await;
+    let workspace = create_workspace_fixture(create_workspace_repo(pool.clone()), user.id).await;
+    let note_service = create_note_service(pool.clone());
+    let service = create_share_link_service(pool);
+
+    let note = note_service
+        .create(NoteCreateDTO {
+            title: "Note".to_string(),
+            workspace_id: workspace.id,
+            parent_note: None,
+            template_id: None,
+            author: None,
+        })
+        .await
+        .unwrap();
+    let dto = ShareLinkCreateDTO {
+        note_id: note.id,
+        created_by: user.id,
+        password: None,
+        include_subpages: false,
+        expires_at: None,
+    };
+    let first = service
+        .create(dto.clone(), &AuditContext::default())
+        .await
+        .unwrap();
+    service
+        .create(dto.clone(), &AuditContext::default())
+        .await
+        .unwrap();
+    assert_eq!(service.get_all_in_note(note.id).await.unwrap().len(), 2);
+
+    service.revoke(first.id).await.unwrap();
+    assert_eq!(service.get_all_in_note(note.id).await.unwrap().len(), 1);
+    assert!(service.resolve(first.slug, None).await.is_err());
+
+    let missing_note = service
+        .create(
+            ShareLinkCreateDTO {
+                note_id: Uuid::new_v4(),
+                ..dto
+            },
+            &AuditContext::default(),
+        )
+        .await;
+    assert!(missing_note.is_err());
+}
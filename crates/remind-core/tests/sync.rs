@@ -0,0 +1,167 @@
+use crate::fixtures::{
+    create_block_service, create_change_service, create_note_service, create_sync_service,
+    create_user_fixture, create_user_repository, create_workspace_fixture, create_workspace_repo,
+};
+use remind_core::errors::CoreError;
+use remind_core::{
+    BlockContent, BlockCreateDTO, BlockType, NoteCreateDTO, PlainTextContent, Workspace,
+};
+use serde_json::json;
+use sqlx::PgPool;
+use std::collections::HashSet;
+use uuid::Uuid;
+
+mod fixtures;
+
+async fn create_workspace(pool: PgPool) -> Workspace {
+    let user = create_user_fixture(create_user_repository(pool.clone())).await;
+    create_workspace_fixture(create_workspace_repo(pool), user.id).await
+}
+
+fn note(workspace_id: Uuid, title: &str) -> NoteCreateDTO {
+    NoteCreateDTO {
+        title: title.to_string(),
+        workspace_id,
+        parent_note: None,
+        template_id: None,
+        author: None,
+    }
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_create_with_client_ids(pool: PgPool) {
+    let workspace = create_workspace(pool.clone()).await;
+    let notes = create_note_service(pool.clone());
+    let blocks = create_block_service(pool.clone());
+
+    let note_id = Uuid::new_v4();
+    let created = notes
+        .create_with_id(note_id, note(workspace.id, "Offline"))
+        .await
+        .unwrap();
+    assert_eq!(created.id, note_id);
+    let block_id = Uuid::new_v4();
+    let block = blocks
+        .create_with_id(
+            block_id,
+            BlockCreateDTO {
+                block_type: BlockType::PlainText,
+                content: BlockContent::PlainText(PlainTextContent::plain("Written on a train")),
+                note_id,
+                parent_block: None,
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(block.id, block_id);
+
+    // Taken ids are refused instead of overwriting
+    assert!(matches!(
+        notes
+            .create_with_id(note_id, note(workspace.id, "Again"))
+            .await,
+        Err(CoreError::AlreadyExists)
+    ));
+    assert!(matches!(
+        blocks
+            .create_with_id(
+                block_id,
+                BlockCreateDTO {
+                    block_type: BlockType::PlainText,
+                    content: BlockContent::PlainText(PlainTextContent::plain("Again")),
+                    note_id,
+                    parent_block: None,
+                },
+            )
+            .await,
+        Err(CoreError::AlreadyExists)
+    ));
+    assert_eq!(notes.find_one(note_id).await.unwrap().title, "Offline");
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_operation_results_are_kept(pool: PgPool) {
+    let workspace = create_workspace(pool.clone()).await;
+    let service = create_sync_service(pool.clone());
+    let operation = Uuid::new_v4();
+
+    assert!(
+        service
+            .get_result(workspace.user_id, operation)
+            .await
+            .unwrap()
+            .is_none()
+    );
+    let first = json!({ "id": operation, "status": "applied", "version": 1 });
+    let saved = service
+        .save_result(workspace.user_id, operation, first.clone())
+        .await
+        .unwrap();
+    assert_eq!(saved, first);
+
+    // A concurrent retry doesn't replace the first result
+    let saved = service
+        .save_result(
+            workspace.user_id,
+            operation,
+            json!({ "id": operation, "status": "rejected" }),
+        )
+        .await
+        .unwrap();
+    assert_eq!(saved, first);
+    assert_eq!(
+        service
+            .get_result(workspace.user_id, operation)
+            .await
+            .unwrap(),
+        Some(first)
+    );
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_pulls_never_skip_changes(pool: PgPool) {
+    let workspace = create_workspace(pool.clone()).await;
+    let other =
+        create_workspace_fixture(create_workspace_repo(pool.clone()), workspace.user_id).await;
+    let workspaces = [workspace.id, other.id];
+    let changes = create_change_service(pool.clone());
+    let notes = create_note_service(pool.clone());
+
+    // Notes are created concurrently while a client keeps pulling
+    let writers = (0..20).map(|i| {
+        let notes = notes.clone();
+        let workspace_id = workspaces[i % 2];
+        tokio::spawn(async move {
+            notes
+                .create(note(workspace_id, &format!("Note {i}")))
+                .await
+                .unwrap()
+                .id
+        })
+    });
+    let writers: Vec<_> = writers.collect();
+
+    let mut cursor = 0;
+    let mut pulled = Vec::new();
+    let mut created = HashSet::new();
+    for writer in writers {
+        created.insert(writer.await.unwrap());
+        let page = changes.get_after(&workspaces, cursor, 3).await.unwrap();
+        if let Some(last) = page.last() {
+            cursor = last.id;
+        }
+        pulled.extend(page);
+    }
+    loop {
+        let page = changes.get_after(&workspaces, cursor, 3).await.unwrap();
+        let Some(last) = page.last() else { break };
+        cursor = last.id;
+        pulled.extend(page);
+    }
+
+    let seen: HashSet<Uuid> = pulled.iter().map(|c| c.note_id).collect();
+    assert_eq!(pulled.len(), 20);
+    assert_eq!(seen, created);
+    assert_eq!(changes.get_last_id(&workspaces).await.unwrap(), cursor);
+    assert_eq!(changes.get_last_id(&[]).await.unwrap(), 0);
+}
@@ -0,0 +1,239 @@
+use crate::fixtures::{
+    create_block_repo, create_block_service, create_note_service, create_share_link_service,
+    create_user_fixture, create_user_repository, create_workspace_fixture, create_workspace_repo,
+};
+use remind_core::errors::CoreError;
+use remind_core::render::{render_html, render_markdown};
+use remind_core::{
+    AuditContext, Block, BlockContent, BlockCreateDTO, BlockDTO, BlockRepo, BlockRepository,
+    BlockService, BlockType, BlockUpdateDTO, ChangeRepository, NoteCreateDTO, NoteDTO,
+    PlainTextContent, PublicBlockContent, ShareLinkCreateDTO, SyncedContent, TextSpan,
+    ToggleContent,
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+mod fixtures;
+
+/// Two notes of one workspace and the id of its owner
+async fn create_notes(pool: PgPool) -> (Uuid, NoteDTO, NoteDTO) {
+    let user_repo = create_user_repository(pool.clone());
+    let user = create_user_fixture(user_repo).await;
+    let service = create_note_service(pool.clone());
+    let workspace = create_workspace_fixture(create_workspace_repo(pool), user.id).await;
+
+    let create = |title: &str| NoteCreateDTO {
+        title: title.to_string(),
+        workspace_id: workspace.id,
+        parent_note: None,
+        template_id: None,
+        author: None,
+    };
+    let source = service.create(create("Handbook")).await.unwrap();
+    let mirror = service.create(create("Onboarding")).await.unwrap();
+    (user.id, source, mirror)
+}
+
+async fn add_text(
+    service: &BlockService<BlockRepository, ChangeRepository>,
+    note_id: Uuid,
+    parent_block: Option<Uuid>,
+    text: &str,
+) -> BlockDTO {
+    service
+        .create(BlockCreateDTO {
+            block_type: BlockType::PlainText,
+            content: BlockContent::PlainText(PlainTextContent {
+                spans: vec![TextSpan::plain(text)],
+            }),
+            note_id,
+            parent_block,
+        })
+        .await
+        .unwrap()
+}
+
+fn synced(source_block: Uuid) -> BlockContent {
+    BlockContent::Synced(SyncedContent { source_block })
+}
+
+fn new_synced(note_id: Uuid, source_block: Uuid) -> BlockCreateDTO {
+    BlockCreateDTO {
+        block_type: BlockType::Synced,
+        content: synced(source_block),
+        note_id,
+        parent_block: None,
+    }
+}
+
+async fn add_synced(
+    service: &BlockService<BlockRepository, ChangeRepository>,
+    note_id: Uuid,
+    source_block: Uuid,
+) -> BlockDTO {
+    service
+        .create(new_synced(note_id, source_block))
+        .await
+        .unwrap()
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_synced_block_mirrors_source(pool: PgPool) {
+    let (_, source_note, note) = create_notes(pool.clone()).await;
+    let service = create_block_service(pool.clone());
+    let note_service = create_note_service(pool.clone());
+
+    let toggle = service
+        .create(BlockCreateDTO {
+            block_type: BlockType::Toggle,
+            content: BlockContent::Toggle(ToggleContent {
+                spans: vec![TextSpan::plain("Vacation")],
+            }),
+            note_id: source_note.id,
+            parent_block: None,
+        })
+        .await
+        .unwrap();
+    let child = add_text(&service, source_note.id, Some(toggle.id), "Ask first").await;
+    add_synced(&service, note.id, toggle.id).await;
+
+    // Edits of the source show up in every mirror
+    service
+        .update(BlockUpdateDTO {
+            id: child.id,
+            block_type: None,
+            content: Some(BlockContent::PlainText(PlainTextContent {
+                spans: vec![TextSpan::plain("Ask a week ahead")],
+            })),
+            version: None,
+        })
+        .await
+        .unwrap();
+    let note = note_service.find_one(note.id).await.unwrap();
+    let source = note.blocks[0].synced_source.as_deref().unwrap();
+    assert_eq!(source.id, toggle.id);
+    assert_eq!(source.children[0].content.plain_text(), "Ask a week ahead");
+
+    let markdown = render_markdown(&note);
+    assert!(markdown.contains("<summary>Vacation</summary>\n\nAsk a week ahead\n"));
+    let html = render_html(&note);
+    assert!(html.contains(
+        "<div class=\"synced\">\n<details>\n<summary>Vacation</summary>\n<p>Ask a week ahead</p>\n</details>\n</div>"
+    ));
+
+    // A deleted source leaves an empty mirror behind
+    service
+        .delete(toggle.id, false, &AuditContext::default())
+        .await
+        .unwrap();
+    let note = note_service.find_one(note.id).await.unwrap();
+    assert_eq!(note.blocks.len(), 1);
+    assert!(note.blocks[0].synced_source.is_none());
+    assert!(!render_html(&note).contains("Vacation"));
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_synced_source_checks(pool: PgPool) {
+    let (user_id, source_note, note) = create_notes(pool.clone()).await;
+    let service = create_block_service(pool.clone());
+    let note_service = create_note_service(pool.clone());
+
+    let text = add_text(&service, source_note.id, None, "Shared").await;
+    let mirror = add_synced(&service, note.id, text.id).await;
+
+    // Mirrors of mirrors and of themselves are rejected
+    let result = service.create(new_synced(note.id, mirror.id)).await;
+    assert!(matches!(result, Err(CoreError::InvalidBlockContent(_))));
+    let result = service
+        .update(BlockUpdateDTO {
+            id: mirror.id,
+            block_type: None,
+            content: Some(synced(mirror.id)),
+            version: None,
+        })
+        .await;
+    assert!(matches!(result, Err(CoreError::InvalidBlockContent(_))));
+
+    // Blocks of other workspaces are not found and never resolved
+    let other_workspace =
+        create_workspace_fixture(create_workspace_repo(pool.clone()), user_id).await;
+    let other_note = note_service
+        .create(NoteCreateDTO {
+            title: "Private".to_string(),
+            workspace_id: other_workspace.id,
+            parent_note: None,
+            template_id: None,
+            author: None,
+        })
+        .await
+        .unwrap();
+    let secret = add_text(&service, other_note.id, None, "Secret").await;
+    for source_block in [secret.id, Uuid::new_v4()] {
+        let result = service.create(new_synced(note.id, source_block)).await;
+        assert!(matches!(result, Err(CoreError::NotFound)));
+        let result = service
+            .update(BlockUpdateDTO {
+                id: mirror.id,
+                block_type: None,
+                content: Some(synced(source_block)),
+                version: None,
+            })
+            .await;
+        assert!(matches!(result, Err(CoreError::NotFound)));
+    }
+
+    // Blocks pointing there anyway, like ones saved before the check, show nothing
+    let block_repo = create_block_repo(pool.clone());
+    let mut conn = pool.acquire().await.unwrap();
+    block_repo
+        .create(
+            &mut conn,
+            Block {
+                id: Uuid::new_v4(),
+                block_type: BlockType::Synced,
+                content: synced(secret.id),
+                note_id: note.id,
+                parent_block: None,
+                position: 1,
+                version: 1,
+            },
+        )
+        .await
+        .unwrap();
+    let note = note_service.find_one(note.id).await.unwrap();
+    assert!(note.blocks[0].synced_source.is_some());
+    assert!(note.blocks[1].synced_source.is_none());
+    assert!(!render_markdown(&note).contains("Secret"));
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_synced_block_in_shared_note(pool: PgPool) {
+    let (user_id, source_note, note) = create_notes(pool.clone()).await;
+    let service = create_block_service(pool.clone());
+    let share_link_service = create_share_link_service(pool.clone());
+
+    let text = add_text(&service, source_note.id, None, "Office hours").await;
+    add_synced(&service, note.id, text.id).await;
+
+    let link = share_link_service
+        .create(
+            ShareLinkCreateDTO {
+                note_id: note.id,
+                created_by: user_id,
+                password: None,
+                include_subpages: false,
+                expires_at: None,
+            },
+            &AuditContext::default(),
+        )
+        .await
+        .unwrap();
+
+    // Public pages show the content without sharing the source note
+    let public = share_link_service.resolve(link.slug, None).await.unwrap();
+    assert!(matches!(
+        &public.blocks[0].content,
+        PublicBlockContent::PlainText(c) if c.spans[0].text == "Office hours"
+    ));
+    assert!(public.subpages.is_empty());
+}
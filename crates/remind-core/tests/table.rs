@@ -0,0 +1,244 @@
+use crate::fixtures::{
+    create_block_service, create_note_service, create_user_fixture, create_user_repository,
+    create_workspace_fixture, create_workspace_repo,
+};
+use remind_core::render::{render_markdown, render_table_csv};
+use remind_core::{
+    BlockContent, BlockCreateDTO, BlockDTO, BlockType, CellValue, ColumnType, NoteCreateDTO,
+    NoteDTO, TableColumn, TableContent, TableRow,
+};
+use sqlx::PgPool;
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+mod fixtures;
+
+async fn create_note(pool: PgPool) -> NoteDTO {
+    let user_repo = create_user_repository(pool.clone());
+    let user = create_user_fixture(user_repo).await;
+    let service = create_note_service(pool.clone());
+    let workspace = create_workspace_fixture(create_workspace_repo(pool), user.id).await;
+
+    let dto = NoteCreateDTO {
+        title: "Budget".to_string(),
+        workspace_id: workspace.id,
+        parent_note: None,
+        template_id: None,
+        author: None,
+    };
+
+    service.create(dto.clone()).await.unwrap()
+}
+
+fn columns() -> Vec<TableColumn> {
+    [
+        ("Item", ColumnType::Text),
+        ("Cost", ColumnType::Number),
+        ("Paid", ColumnType::Checkbox),
+        ("Due", ColumnType::Date),
+    ]
+    .into_iter()
+    .map(|(name, column_type)| TableColumn {
+        id: Uuid::new_v4(),
+        name: name.to_string(),
+        column_type,
+    })
+    .collect()
+}
+
+async fn create_table(pool: PgPool, note_id: Uuid) -> BlockDTO {
+    let columns = columns();
+    let row = TableRow {
+        id: Uuid::new_v4(),
+        cells: BTreeMap::from([
+            (columns[0].id, CellValue::Text("Rent, June".to_string())),
+            (columns[1].id, CellValue::Number(1200.0)),
+            (columns[2].id, CellValue::Checkbox(true)),
+            (columns[3].id, CellValue::Text("2025-06-01".to_string())),
+        ]),
+    };
+    create_block_service(pool)
+        .create(BlockCreateDTO {
+            block_type: BlockType::Table,
+            content: BlockContent::Table(TableContent {
+                columns,
+                rows: vec![row],
+            }),
+            note_id,
+            parent_block: None,
+        })
+        .await
+        .unwrap()
+}
+
+fn table(block: &BlockDTO) -> &TableContent {
+    match &block.content {
+        BlockContent::Table(table) => table,
+        _ => panic!("Expected table content"),
+    }
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_table_cell_validation(pool: PgPool) {
+    let note = create_note(pool.clone()).await;
+    let block_service = create_block_service(pool);
+
+    let mut columns = columns();
+    let invalid = |cells: BTreeMap<Uuid, CellValue>, columns: Vec<TableColumn>| BlockCreateDTO {
+        block_type: BlockType::Table,
+        content: BlockContent::Table(TableContent {
+            columns,
+            rows: vec![TableRow {
+                id: Uuid::new_v4(),
+                cells,
+            }],
+        }),
+        note_id: note.id,
+        parent_block: None,
+    };
+
+    let text_in_number = BTreeMap::from([(columns[1].id, CellValue::Text("a lot".to_string()))]);
+    assert!(
+        block_service
+            .create(invalid(text_in_number, columns.clone()))
+            .await
+            .is_err()
+    );
+
+    let bad_date = BTreeMap::from([(columns[3].id, CellValue::Text("June 1st".to_string()))]);
+    assert!(
+        block_service
+            .create(invalid(bad_date, columns.clone()))
+            .await
+            .is_err()
+    );
+
+    let unknown_column = BTreeMap::from([(Uuid::new_v4(), CellValue::Checkbox(false))]);
+    assert!(
+        block_service
+            .create(invalid(unknown_column, columns.clone()))
+            .await
+            .is_err()
+    );
+
+    columns[1].id = columns[0].id;
+    assert!(
+        block_service
+            .create(invalid(BTreeMap::new(), columns))
+            .await
+            .is_err()
+    );
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_table_cell_updates(pool: PgPool) {
+    let note = create_note(pool.clone()).await;
+    let block_service = create_block_service(pool.clone());
+    let block = create_table(pool, note.id).await;
+    let columns = table(&block).columns.clone();
+    let row_id = table(&block).rows[0].id;
+
+    let block = block_service
+        .update_table_cell(
+            block.id,
+            row_id,
+            columns[1].id,
+            Some(CellValue::Number(950.5)),
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        table(&block).rows[0].cells[&columns[1].id],
+        CellValue::Number(950.5)
+    );
+
+    let block = block_service
+        .update_table_cell(block.id, row_id, columns[2].id, None)
+        .await
+        .unwrap();
+    assert!(!table(&block).rows[0].cells.contains_key(&columns[2].id));
+
+    let wrong_type = block_service
+        .update_table_cell(
+            block.id,
+            row_id,
+            columns[2].id,
+            Some(CellValue::Number(1.0)),
+        )
+        .await;
+    assert!(wrong_type.is_err());
+    let missing_row = block_service
+        .update_table_cell(block.id, Uuid::new_v4(), columns[0].id, None)
+        .await;
+    assert!(missing_row.is_err());
+
+    let block = block_service
+        .add_table_row(
+            block.id,
+            BTreeMap::from([(columns[0].id, CellValue::Text("Internet".to_string()))]),
+        )
+        .await
+        .unwrap();
+    assert_eq!(table(&block).rows.len(), 2);
+
+    let block = block_service
+        .delete_table_row(block.id, row_id)
+        .await
+        .unwrap();
+    assert_eq!(table(&block).rows.len(), 1);
+    assert_eq!(block.content.plain_text(), "Internet");
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_concurrent_table_edits(pool: PgPool) {
+    let note = create_note(pool.clone()).await;
+    let block_service = create_block_service(pool.clone());
+    let block = create_table(pool, note.id).await;
+    let columns = table(&block).columns.clone();
+    let row_id = table(&block).rows[0].id;
+
+    // Edits of different cells made at once are all kept
+    let set = |column: usize, value: CellValue| {
+        block_service.update_table_cell(block.id, row_id, columns[column].id, Some(value))
+    };
+    let (item, cost, paid, added) = tokio::join!(
+        set(0, CellValue::Text("Rent, July".to_string())),
+        set(1, CellValue::Number(1250.0)),
+        set(2, CellValue::Checkbox(false)),
+        block_service.add_table_row(
+            block.id,
+            BTreeMap::from([(columns[0].id, CellValue::Text("Internet".to_string()))]),
+        ),
+    );
+    for result in [item, cost, paid, added] {
+        result.unwrap();
+    }
+
+    let block = block_service.find_one(block.id).await.unwrap();
+    let cells = &table(&block).rows[0].cells;
+    assert_eq!(
+        cells[&columns[0].id],
+        CellValue::Text("Rent, July".to_string())
+    );
+    assert_eq!(cells[&columns[1].id], CellValue::Number(1250.0));
+    assert_eq!(cells[&columns[2].id], CellValue::Checkbox(false));
+    assert_eq!(table(&block).rows.len(), 2);
+    assert_eq!(block.version, 5);
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_table_export(pool: PgPool) {
+    let note = create_note(pool.clone()).await;
+    let block = create_table(pool.clone(), note.id).await;
+
+    assert_eq!(
+        render_table_csv(table(&block)),
+        "Item,Cost,Paid,Due\r\n\"Rent, June\",1200,true,2025-06-01\r\n"
+    );
+
+    let note = create_note_service(pool).find_one(note.id).await.unwrap();
+    assert_eq!(
+        render_markdown(&note),
+        "# Budget\n\n| Item | Cost | Paid | Due |\n| --- | ---: | --- | --- |\n| Rent, June | 1200 | true | 2025-06-01 |\n"
+    );
+}
@@ -0,0 +1,196 @@
+use crate::fixtures::{
+    create_note_service, create_tag_service, create_user_fixture, create_user_repository,
+    create_workspace_fixture, create_workspace_repo,
+};
+use remind_core::errors::CoreError;
+use remind_core::{
+    DEFAULT_TAG_COLOR, NoteCreateDTO, NoteDTO, TagCreateDTO, TagMatch, TagUpdateDTO,
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+mod fixtures;
+
+/// Notes of one workspace with the given titles, and the id of its owner
+async fn create_notes(pool: PgPool, titles: &[&str]) -> (Uuid, Vec<NoteDTO>) {
+    let user_repo = create_user_repository(pool.clone());
+    let user = create_user_fixture(user_repo).await;
+    let service = create_note_service(pool.clone());
+    let workspace = create_workspace_fixture(create_workspace_repo(pool), user.id).await;
+
+    let mut notes = Vec::new();
+    for title in titles {
+        let dto = NoteCreateDTO {
+            title: title.to_string(),
+            workspace_id: workspace.id,
+            parent_note: None,
+            template_id: None,
+            author: None,
+        };
+        notes.push(service.create(dto).await.unwrap());
+    }
+    (user.id, notes)
+}
+
+fn tag(workspace_id: Uuid, name: &str, color: Option<&str>) -> TagCreateDTO {
+    TagCreateDTO {
+        workspace_id,
+        name: name.to_string(),
+        color: color.map(str::to_string),
+    }
+}
+
+fn titles(notes: &[NoteDTO]) -> Vec<&str> {
+    let mut titles: Vec<&str> = notes.iter().map(|n| n.title.as_str()).collect();
+    titles.sort();
+    titles
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_tag_management(pool: PgPool) {
+    let (_, notes) = create_notes(pool.clone(), &["Inbox"]).await;
+    let workspace_id = notes[0].workspace_id;
+    let service = create_tag_service(pool.clone());
+
+    let work = service
+        .create(tag(workspace_id, "  Work ", None))
+        .await
+        .unwrap();
+    assert_eq!(work.name, "Work");
+    assert_eq!(work.color, DEFAULT_TAG_COLOR);
+    let ideas = service
+        .create(tag(workspace_id, "ideas", Some("#FF8800")))
+        .await
+        .unwrap();
+    assert_eq!(ideas.color, "#ff8800");
+
+    // Names are unique per workspace, ignoring case
+    for data in [
+        tag(workspace_id, "WORK", None),
+        tag(workspace_id, " ", None),
+        tag(workspace_id, &"x".repeat(65), None),
+        tag(workspace_id, "Urgent", Some("red")),
+        tag(workspace_id, "Urgent", Some("#ff880")),
+    ] {
+        let result = service.create(data).await;
+        assert!(matches!(result, Err(CoreError::InvalidTag(_))));
+    }
+    let result = service
+        .update(
+            ideas.id,
+            TagUpdateDTO {
+                name: Some("work".to_string()),
+                color: None,
+            },
+        )
+        .await;
+    assert!(matches!(result, Err(CoreError::InvalidTag(_))));
+
+    // Renaming a tag to itself in another case is fine
+    let work = service
+        .update(
+            work.id,
+            TagUpdateDTO {
+                name: Some("WORK".to_string()),
+                color: Some("#00aa00".to_string()),
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        (work.name.as_str(), work.color.as_str()),
+        ("WORK", "#00aa00")
+    );
+
+    let tags = service.get_all_in_workspace(workspace_id).await.unwrap();
+    let names: Vec<&str> = tags.iter().map(|t| t.name.as_str()).collect();
+    assert_eq!(names, vec!["ideas", "WORK"]);
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_tagging_notes(pool: PgPool) {
+    let (user_id, notes) = create_notes(pool.clone(), &["Plan"]).await;
+    let note = &notes[0];
+    let service = create_tag_service(pool.clone());
+    let note_service = create_note_service(pool.clone());
+
+    let urgent = service
+        .create(tag(note.workspace_id, "urgent", None))
+        .await
+        .unwrap();
+    let later = service
+        .create(tag(note.workspace_id, "Later", None))
+        .await
+        .unwrap();
+    for tag_id in [urgent.id, later.id, urgent.id] {
+        service.tag_note(note.id, tag_id).await.unwrap();
+    }
+    let tagged = note_service.find_one(note.id).await.unwrap();
+    let names: Vec<&str> = tagged.tags.iter().map(|t| t.name.as_str()).collect();
+    assert_eq!(names, vec!["Later", "urgent"]);
+
+    service.untag_note(note.id, later.id).await.unwrap();
+    service.delete(urgent.id).await.unwrap();
+    assert!(
+        note_service
+            .find_one(note.id)
+            .await
+            .unwrap()
+            .tags
+            .is_empty()
+    );
+
+    // Tags of other workspaces can't be used
+    let other = create_workspace_fixture(create_workspace_repo(pool.clone()), user_id).await;
+    let foreign = service.create(tag(other.id, "urgent", None)).await.unwrap();
+    for (note_id, tag_id) in [(note.id, foreign.id), (Uuid::new_v4(), later.id)] {
+        let result = service.tag_note(note_id, tag_id).await;
+        assert!(matches!(result, Err(CoreError::NotFound)));
+    }
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_notes_filtered_by_tags(pool: PgPool) {
+    let (_, notes) = create_notes(pool.clone(), &["Both", "Red only", "Blue only", "None"]).await;
+    let workspace_id = notes[0].workspace_id;
+    let service = create_tag_service(pool.clone());
+    let note_service = create_note_service(pool.clone());
+
+    let red = service
+        .create(tag(workspace_id, "red", None))
+        .await
+        .unwrap();
+    let blue = service
+        .create(tag(workspace_id, "blue", None))
+        .await
+        .unwrap();
+    for (note, tag_id) in [
+        (&notes[0], red.id),
+        (&notes[0], blue.id),
+        (&notes[1], red.id),
+        (&notes[2], blue.id),
+    ] {
+        service.tag_note(note.id, tag_id).await.unwrap();
+    }
+
+    let filter = |tags: Vec<Uuid>, matching| {
+        note_service.get_all_in_workspace_by_tags(workspace_id, tags, matching)
+    };
+    let any = filter(vec![red.id, blue.id], TagMatch::Any).await.unwrap();
+    assert_eq!(titles(&any), vec!["Blue only", "Both", "Red only"]);
+    let all = filter(vec![red.id, blue.id, red.id], TagMatch::All)
+        .await
+        .unwrap();
+    assert_eq!(titles(&all), vec!["Both"]);
+    assert_eq!(all[0].tags.len(), 2);
+    let red_notes = filter(vec![red.id], TagMatch::All).await.unwrap();
+    assert_eq!(titles(&red_notes), vec!["Both", "Red only"]);
+
+    assert_eq!(filter(vec![], TagMatch::All).await.unwrap().len(), 4);
+    assert!(
+        filter(vec![Uuid::new_v4()], TagMatch::Any)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+}
@@ -0,0 +1,294 @@
+use crate::fixtures::{
+    create_block_service, create_note_service, create_user_fixture, create_user_repository,
+    create_workspace_fixture, create_workspace_repo,
+};
+use chrono::{TimeZone, Utc};
+use remind_core::errors::CoreError;
+use remind_core::{
+    AuditContext, BlockContent, BlockCreateDTO, BlockType, CheckboxContent, CodeContent, Mention,
+    NoteCreateDTO, NoteTemplateCreateDTO, PlainTextContent, SyncedContent, TemplateVariables,
+    TextSpan, ToggleContent,
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+mod fixtures;
+
+fn note_dto(workspace_id: Uuid, title: &str, template_id: Option<Uuid>) -> NoteCreateDTO {
+    NoteCreateDTO {
+        title: title.to_string(),
+        workspace_id,
+        parent_note: None,
+        template_id,
+        author: Some("alice".to_string()),
+    }
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_note_created_from_template(pool: PgPool) {
+    let user = create_user_fixture(create_user_repository(pool.clone())).await;
+    let workspace = create_workspace_fixture(create_workspace_repo(pool.clone()), user.id).await;
+    let notes = create_note_service(pool.clone());
+    let blocks = create_block_service(pool.clone());
+
+    let source = notes
+        .create(note_dto(workspace.id, "Standup", None))
+        .await
+        .unwrap();
+    blocks
+        .create(BlockCreateDTO {
+            block_type: BlockType::PlainText,
+            content: BlockContent::PlainText(PlainTextContent {
+                spans: vec![
+                    TextSpan::plain("Notes of {{date}} by {{user}}, see "),
+                    TextSpan::mention("Standup", Mention::Note(source.id)),
+                ],
+            }),
+            note_id: source.id,
+            parent_block: None,
+        })
+        .await
+        .unwrap();
+    let toggle = blocks
+        .create(BlockCreateDTO {
+            block_type: BlockType::Toggle,
+            content: BlockContent::Toggle(ToggleContent {
+                spans: vec![TextSpan::plain("Blockers")],
+            }),
+            note_id: source.id,
+            parent_block: None,
+        })
+        .await
+        .unwrap();
+    blocks
+        .create(BlockCreateDTO {
+            block_type: BlockType::Checkbox,
+            content: BlockContent::Checkbox(CheckboxContent {
+                text: "Ask {{team}}".to_string(),
+                status: false,
+            }),
+            note_id: source.id,
+            parent_block: Some(toggle.id),
+        })
+        .await
+        .unwrap();
+
+    let template = notes
+        .save_as_template(NoteTemplateCreateDTO {
+            note_id: source.id,
+            name: " Daily standup ".to_string(),
+            title: Some("Standup {{date}}".to_string()),
+            created_by: user.id,
+        })
+        .await
+        .unwrap();
+    assert_eq!(template.name, "Daily standup");
+    assert_eq!(template.blocks.len(), 2);
+    assert_eq!(template.blocks[1].children.len(), 1);
+
+    let note = notes
+        .create(note_dto(workspace.id, "", Some(template.id)))
+        .await
+        .unwrap();
+    let today = Utc::now().format("%Y-%m-%d").to_string();
+    assert_eq!(note.title, format!("Standup {today}"));
+    assert_eq!(note.icon_data, source.icon_data);
+    assert_eq!(note.blocks.len(), 2);
+    assert_eq!(
+        note.blocks[0].content.plain_text(),
+        format!("Notes of {today} by alice, see Standup")
+    );
+    assert!(note.blocks.iter().all(|b| b.note_id == note.id));
+    let copied_toggle = &note.blocks[1];
+    assert_ne!(copied_toggle.id, toggle.id);
+    assert_eq!(copied_toggle.children.len(), 1);
+    // Unknown variables are left for the user to fill in
+    assert_eq!(
+        copied_toggle.children[0].content.plain_text(),
+        "Ask {{team}}"
+    );
+
+    // Mentions in the copies are backlinks too
+    let backlinks = notes.get_backlinks(source.id).await.unwrap();
+    assert!(backlinks.iter().any(|b| b.note_id == note.id));
+
+    // The source note is left as it was
+    let source = notes.find_one(source.id).await.unwrap();
+    assert_eq!(source.title, "Standup");
+    assert_eq!(
+        source.blocks[0].content.plain_text(),
+        "Notes of {{date}} by {{user}}, see Standup"
+    );
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_templates_stay_in_their_workspace(pool: PgPool) {
+    let user = create_user_fixture(create_user_repository(pool.clone())).await;
+    let workspace = create_workspace_fixture(create_workspace_repo(pool.clone()), user.id).await;
+    let other = create_workspace_fixture(create_workspace_repo(pool.clone()), user.id).await;
+    let notes = create_note_service(pool.clone());
+
+    let source = notes
+        .create(note_dto(workspace.id, "Retro", None))
+        .await
+        .unwrap();
+    let save = |name: &str| NoteTemplateCreateDTO {
+        note_id: source.id,
+        name: name.to_string(),
+        title: None,
+        created_by: user.id,
+    };
+    assert!(matches!(
+        notes.save_as_template(save("  ")).await,
+        Err(CoreError::InvalidTemplate(_))
+    ));
+    let template = notes.save_as_template(save("Retro")).await.unwrap();
+    assert_eq!(template.title, "Retro");
+
+    // Given titles win over the one of the template
+    let note = notes
+        .create(note_dto(workspace.id, "Retro of May", Some(template.id)))
+        .await
+        .unwrap();
+    assert_eq!(note.title, "Retro of May");
+
+    assert!(matches!(
+        notes
+            .create(note_dto(other.id, "", Some(template.id)))
+            .await,
+        Err(CoreError::NotFound)
+    ));
+    assert!(matches!(
+        notes
+            .create(note_dto(workspace.id, "", Some(Uuid::new_v4())))
+            .await,
+        Err(CoreError::NotFound)
+    ));
+
+    let templates = notes
+        .get_templates_in_workspace(workspace.id)
+        .await
+        .unwrap();
+    assert_eq!(templates.len(), 1);
+    assert!(
+        notes
+            .get_templates_in_workspace(other.id)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+    notes.delete_template(template.id).await.unwrap();
+    assert!(matches!(
+        notes.find_template(template.id).await,
+        Err(CoreError::NotFound)
+    ));
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_template_blocks_are_checked(pool: PgPool) {
+    let user = create_user_fixture(create_user_repository(pool.clone())).await;
+    let workspace = create_workspace_fixture(create_workspace_repo(pool.clone()), user.id).await;
+    let notes = create_note_service(pool.clone());
+    let blocks = create_block_service(pool.clone());
+
+    let shared = notes
+        .create(note_dto(workspace.id, "Shared", None))
+        .await
+        .unwrap();
+    let shared_block = blocks
+        .create(BlockCreateDTO {
+            block_type: BlockType::PlainText,
+            content: BlockContent::PlainText(PlainTextContent {
+                spans: vec![TextSpan::plain("Agenda")],
+            }),
+            note_id: shared.id,
+            parent_block: None,
+        })
+        .await
+        .unwrap();
+    let source = notes
+        .create(note_dto(workspace.id, "Meeting", None))
+        .await
+        .unwrap();
+    blocks
+        .create(BlockCreateDTO {
+            block_type: BlockType::Synced,
+            content: BlockContent::Synced(SyncedContent {
+                source_block: shared_block.id,
+            }),
+            note_id: source.id,
+            parent_block: None,
+        })
+        .await
+        .unwrap();
+    let template = notes
+        .save_as_template(NoteTemplateCreateDTO {
+            note_id: source.id,
+            name: "Meeting".to_string(),
+            title: None,
+            created_by: user.id,
+        })
+        .await
+        .unwrap();
+
+    // A block mirroring a deleted block can't be created, nor the note
+    notes
+        .delete(shared.id, &AuditContext::default())
+        .await
+        .unwrap();
+    assert!(matches!(
+        notes
+            .create(note_dto(workspace.id, "", Some(template.id)))
+            .await,
+        Err(CoreError::NotFound)
+    ));
+    let left = notes.get_all_in_workspace(workspace.id).await.unwrap();
+    assert_eq!(left.len(), 1);
+    assert_eq!(left[0].id, source.id);
+}
+
+#[test]
+fn test_template_variables() {
+    let variables = TemplateVariables {
+        now: Utc.with_ymd_and_hms(2025, 6, 23, 9, 5, 0).unwrap(),
+        user: Some("alice".to_string()),
+    };
+    assert_eq!(
+        variables.fill("{{date}} {{ time }} {{user}}"),
+        "2025-06-23 09:05 alice"
+    );
+    assert_eq!(
+        variables.fill("{{other}} and {{date"),
+        "{{other}} and {{date"
+    );
+
+    let anonymous = TemplateVariables {
+        user: None,
+        ..variables
+    };
+    assert_eq!(anonymous.fill("Owner: {{user}}"), "Owner: {{user}}");
+    // Only text is filled in, links and code are kept as written
+    let content = variables.fill_content(BlockContent::PlainText(PlainTextContent {
+        spans: vec![
+            TextSpan::plain("Log of {{date}}"),
+            TextSpan {
+                href: Some("https://example.com/{{date}}".to_string()),
+                ..TextSpan::plain(" {{user}}")
+            },
+        ],
+    }));
+    let BlockContent::PlainText(filled) = content else {
+        panic!("content changed type");
+    };
+    assert_eq!(filled.spans[0].text, "Log of 2025-06-23");
+    assert_eq!(filled.spans[1].text, " alice");
+    assert_eq!(
+        filled.spans[1].href.as_deref(),
+        Some("https://example.com/{{date}}")
+    );
+    let content = variables.fill_content(BlockContent::Code(CodeContent {
+        code: "echo {{date}}".to_string(),
+        language: "sh".to_string(),
+    }));
+    assert!(matches!(content, BlockContent::Code(c) if c.code == "echo {{date}}"));
+}
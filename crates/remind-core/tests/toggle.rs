@@ -0,0 +1,232 @@
+use crate::fixtures::{
+    create_block_service, create_note_service, create_user_fixture, create_user_repository,
+    create_workspace_fixture, create_workspace_repo,
+};
+use remind_core::errors::CoreError;
+use remind_core::render::{render_html, render_markdown};
+use remind_core::{
+    AuditContext, BlockContent, BlockCreateDTO, BlockDTO, BlockRepository, BlockService, BlockType,
+    BlockUpdateDTO, ChangeRepository, NoteCreateDTO, NoteDTO, PlainTextContent, TextSpan,
+    ToggleContent,
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+mod fixtures;
+
+async fn create_note(pool: PgPool) -> NoteDTO {
+    let user_repo = create_user_repository(pool.clone());
+    let user = create_user_fixture(user_repo).await;
+    let service = create_note_service(pool.clone());
+    let workspace = create_workspace_fixture(create_workspace_repo(pool), user.id).await;
+
+    let dto = NoteCreateDTO {
+        title: "FAQ".to_string(),
+        workspace_id: workspace.id,
+        parent_note: None,
+        template_id: None,
+        author: None,
+    };
+
+    service.create(dto.clone()).await.unwrap()
+}
+
+async fn add(
+    service: &BlockService<BlockRepository, ChangeRepository>,
+    note_id: Uuid,
+    parent_block: Option<Uuid>,
+    toggle: bool,
+    text: &str,
+) -> BlockDTO {
+    let spans = vec![TextSpan::plain(text)];
+    let (block_type, content) = match toggle {
+        true => (
+            BlockType::Toggle,
+            BlockContent::Toggle(ToggleContent { spans }),
+        ),
+        false => (
+            BlockType::PlainText,
+            BlockContent::PlainText(PlainTextContent { spans }),
+        ),
+    };
+    service
+        .create(BlockCreateDTO {
+            block_type,
+            content,
+            note_id,
+            parent_block,
+        })
+        .await
+        .unwrap()
+}
+
+/// Texts of the blocks, children in brackets after their toggle
+fn outline(blocks: &[BlockDTO]) -> String {
+    blocks
+        .iter()
+        .map(|b| {
+            let text = b.content.plain_text();
+            match b.children.is_empty() {
+                true => text,
+                false => format!("{text} [{}]", outline(&b.children)),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+async fn note_outline(pool: PgPool, note_id: Uuid) -> String {
+    let note = create_note_service(pool).find_one(note_id).await.unwrap();
+    outline(&note.blocks)
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_toggle_tree(pool: PgPool) {
+    let note = create_note(pool.clone()).await;
+    let service = create_block_service(pool.clone());
+
+    let toggle = add(&service, note.id, None, true, "Question").await;
+    add(&service, note.id, Some(toggle.id), false, "Answer").await;
+    let nested = add(&service, note.id, Some(toggle.id), true, "Details").await;
+    let deep = add(&service, note.id, Some(nested.id), false, "More").await;
+    let after = add(&service, note.id, None, false, "After").await;
+    assert_eq!(deep.position, 0);
+    assert_eq!(after.position, 1);
+
+    let note = create_note_service(pool.clone())
+        .find_one(note.id)
+        .await
+        .unwrap();
+    assert_eq!(
+        outline(&note.blocks),
+        "Question [Answer, Details [More]], After"
+    );
+    assert_eq!(note.blocks[0].children[1].parent_block, Some(toggle.id));
+
+    // Only toggles of the same note have children
+    let other = create_note_service(pool.clone())
+        .create(NoteCreateDTO {
+            title: "Other".to_string(),
+            workspace_id: note.workspace_id,
+            parent_note: None,
+            template_id: None,
+            author: None,
+        })
+        .await
+        .unwrap();
+    for (note_id, parent) in [(note.id, after.id), (other.id, toggle.id)] {
+        let result = service
+            .create(BlockCreateDTO {
+                block_type: BlockType::PlainText,
+                content: BlockContent::PlainText(PlainTextContent {
+                    spans: vec![TextSpan::plain("Lost")],
+                }),
+                note_id,
+                parent_block: Some(parent),
+            })
+            .await;
+        assert!(matches!(result, Err(CoreError::InvalidBlockParent(_))));
+    }
+
+    let markdown = render_markdown(&note);
+    assert!(markdown.contains(
+        "<details>\n<summary>Question</summary>\n\nAnswer\n\n<details>\n<summary>Details</summary>\n\nMore\n\n</details>\n\n</details>\n\nAfter\n"
+    ));
+    let html = render_html(&note);
+    assert!(html.contains(
+        "<details>\n<summary>Question</summary>\n<p>Answer</p>\n<details>\n<summary>Details</summary>\n<p>More</p>\n</details>\n</details>\n<p>After</p>"
+    ));
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_toggle_reorder_and_move(pool: PgPool) {
+    let note = create_note(pool.clone()).await;
+    let service = create_block_service(pool.clone());
+    let note_service = create_note_service(pool.clone());
+
+    let toggle = add(&service, note.id, None, true, "Toggle").await;
+    let one = add(&service, note.id, Some(toggle.id), false, "One").await;
+    let two = add(&service, note.id, Some(toggle.id), true, "Two").await;
+    let after = add(&service, note.id, None, false, "After").await;
+
+    note_service
+        .reorder_blocks(note.id, Some(toggle.id), vec![two.id, one.id], None)
+        .await
+        .unwrap();
+    note_service
+        .reorder_blocks(note.id, None, vec![after.id, toggle.id], None)
+        .await
+        .unwrap();
+    assert_eq!(
+        note_outline(pool.clone(), note.id).await,
+        "After, Toggle [Two, One]"
+    );
+
+    service.move_block(after.id, Some(two.id)).await.unwrap();
+    service.move_block(one.id, None).await.unwrap();
+    assert_eq!(
+        note_outline(pool.clone(), note.id).await,
+        "Toggle [Two [After]], One"
+    );
+
+    // A toggle can't end up inside itself
+    for parent in [toggle.id, two.id] {
+        let result = service.move_block(toggle.id, Some(parent)).await;
+        assert!(matches!(result, Err(CoreError::InvalidBlockParent(_))));
+    }
+    let result = service.move_block(one.id, Some(after.id)).await;
+    assert!(matches!(result, Err(CoreError::InvalidBlockParent(_))));
+
+    // Toggles with children keep their type
+    let result = service
+        .update(BlockUpdateDTO {
+            id: two.id,
+            block_type: Some(BlockType::PlainText),
+            content: Some(BlockContent::PlainText(PlainTextContent {
+                spans: vec![TextSpan::plain("Two")],
+            })),
+            version: None,
+        })
+        .await;
+    assert!(matches!(result, Err(CoreError::InvalidBlockParent(_))));
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_toggle_delete(pool: PgPool) {
+    let note = create_note(pool.clone()).await;
+    let service = create_block_service(pool.clone());
+
+    add(&service, note.id, None, false, "Before").await;
+    let toggle = add(&service, note.id, None, true, "Toggle").await;
+    add(&service, note.id, Some(toggle.id), false, "One").await;
+    let nested = add(&service, note.id, Some(toggle.id), true, "Two").await;
+    let deep = add(&service, note.id, Some(nested.id), false, "Deep").await;
+    add(&service, note.id, None, false, "After").await;
+
+    // Promoted children take the place of the toggle
+    service
+        .delete(toggle.id, true, &AuditContext::default())
+        .await
+        .unwrap();
+    assert_eq!(
+        note_outline(pool.clone(), note.id).await,
+        "Before, One, Two [Deep], After"
+    );
+    let note_blocks = service.get_all_in_note(note.id).await.unwrap();
+    let positions: Vec<i32> = note_blocks.iter().map(|b| b.position).collect();
+    assert_eq!(positions, vec![0, 1, 2, 3]);
+
+    // Otherwise they are deleted with it
+    service
+        .delete(nested.id, false, &AuditContext::default())
+        .await
+        .unwrap();
+    assert_eq!(
+        note_outline(pool.clone(), note.id).await,
+        "Before, One, After"
+    );
+    assert!(matches!(
+        service.find_one(deep.id).await,
+        Err(CoreError::NotFound)
+    ));
+}
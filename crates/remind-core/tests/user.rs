@@ -2,7 +2,9 @@ mod fixtures;
 
 use crate::fixtures::{create_user_fixture, create_user_repository, create_user_service};
 use remind_auth::{DecodingKey, EncodingKey, JwtProcessor};
-use remind_core::{UserCreateDTO, UserLoginEmailDTO, UserLoginUsernameDTO, UserService};
+use remind_core::{
+    AuditContext, UserCreateDTO, UserLoginEmailDTO, UserLoginUsernameDTO, UserService,
+};
 use sqlx::PgPool;
 
 #[sqlx::test(migrations = "../../migrations")]
@@ -32,10 +34,13 @@ async fn test_login_by_username(db: PgPool) {
     let user = fixtures::create_user_fixture(user_repo).await;
 
     let token = user_service
-        .login_by_username(UserLoginUsernameDTO {
-            username: user.username.clone(),
-            password: "password".to_string(),
-        })
+        .login_by_username(
+            UserLoginUsernameDTO {
+                username: user.username.clone(),
+                password: "password".to_string(),
+            },
+            &AuditContext::default(),
+        )
         .await
         .unwrap();
 
@@ -43,18 +48,24 @@ async fn test_login_by_username(db: PgPool) {
     assert_eq!(claims.sub, user.username);
 
     let token2 = user_service
-        .login_by_username(UserLoginUsernameDTO {
-            username: "fakeUsername".to_string(),
-            password: "password".to_string(),
-        })
+        .login_by_username(
+            UserLoginUsernameDTO {
+                username: "fakeUsername".to_string(),
+                password: "password".to_string(),
+            },
+            &AuditContext::default(),
+        )
         .await;
     assert!(token2.is_err());
 
     let token3 = user_service
-        .login_by_username(UserLoginUsernameDTO {
-            username: user.username,
-            password: "fakePassword".to_string(),
-        })
+        .login_by_username(
+            UserLoginUsernameDTO {
+                username: user.username,
+                password: "fakePassword".to_string(),
+            },
+            &AuditContext::default(),
+        )
         .await;
     assert!(token3.is_err())
 }
@@ -71,10 +82,13 @@ async fn test_login_by_email(db: PgPool) {
     let user = create_user_fixture(user_repo).await;
 
     let token = user_service
-        .login_by_email(UserLoginEmailDTO {
-            email: "test2@example.com".to_string(),
-            password: "password".to_string(),
-        })
+        .login_by_email(
+            UserLoginEmailDTO {
+                email: "test2@example.com".to_string(),
+                password: "password".to_string(),
+            },
+            &AuditContext::default(),
+        )
         .await
         .unwrap();
 
@@ -82,18 +96,24 @@ async fn test_login_by_email(db: PgPool) {
     assert_eq!(claims.sub, user.username);
 
     let token2 = user_service
-        .login_by_email(UserLoginEmailDTO {
-            email: "fake@example.com".to_string(),
-            password: "password".to_string(),
-        })
+        .login_by_email(
+            UserLoginEmailDTO {
+                email: "fake@example.com".to_string(),
+                password: "password".to_string(),
+            },
+            &AuditContext::default(),
+        )
         .await;
     assert!(token2.is_err());
 
     let token3 = user_service
-        .login_by_email(UserLoginEmailDTO {
-            email: "test2@example.com".to_string(),
-            password: "fakePassword".to_string(),
-        })
+        .login_by_email(
+            UserLoginEmailDTO {
+                email: "test2@example.com".to_string(),
+                password: "fakePassword".to_string(),
+            },
+            &AuditContext::default(),
+        )
         .await;
     assert!(token3.is_err());
 }
@@ -0,0 +1,154 @@
+use crate::fixtures::{
+    create_block_service, create_note_service, create_user_fixture, create_user_repository,
+    create_workspace_fixture, create_workspace_repo,
+};
+use remind_core::errors::CoreError;
+use remind_core::{
+    BlockContent, BlockCreateDTO, BlockDTO, BlockRepository, BlockService, BlockType,
+    BlockUpdateDTO, ChangeRepository, NoteCreateDTO, NoteDTO, NoteUpdateDTO, PlainTextContent,
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+mod fixtures;
+
+async fn create_note(pool: PgPool) -> NoteDTO {
+    let user = create_user_fixture(create_user_repository(pool.clone())).await;
+    let workspace = create_workspace_fixture(create_workspace_repo(pool.clone()), user.id).await;
+    create_note_service(pool)
+        .create(NoteCreateDTO {
+            title: "Plan".to_string(),
+            workspace_id: workspace.id,
+            parent_note: None,
+            template_id: None,
+            author: None,
+        })
+        .await
+        .unwrap()
+}
+
+async fn add_text(
+    service: &BlockService<BlockRepository, ChangeRepository>,
+    note_id: Uuid,
+    text: &str,
+) -> BlockDTO {
+    service
+        .create(BlockCreateDTO {
+            block_type: BlockType::PlainText,
+            content: BlockContent::PlainText(PlainTextContent::plain(text)),
+            note_id,
+            parent_block: None,
+        })
+        .await
+        .unwrap()
+}
+
+fn edit(id: Uuid, text: &str, version: Option<i64>) -> BlockUpdateDTO {
+    BlockUpdateDTO {
+        id,
+        block_type: None,
+        content: Some(BlockContent::PlainText(PlainTextContent::plain(text))),
+        version,
+    }
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_block_updates_check_version(pool: PgPool) {
+    let note = create_note(pool.clone()).await;
+    let service = create_block_service(pool.clone());
+    let block = add_text(&service, note.id, "Draft").await;
+    assert_eq!(block.version, 1);
+
+    service
+        .update(edit(block.id, "First", Some(1)))
+        .await
+        .unwrap();
+    let updated = service.find_one(block.id).await.unwrap();
+    assert_eq!(updated.version, 2);
+
+    // An edit of the version before is refused and changes nothing
+    let result = service.update(edit(block.id, "Second", Some(1))).await;
+    assert!(matches!(result, Err(CoreError::VersionMismatch)));
+    let current = service.find_one(block.id).await.unwrap();
+    assert_eq!(current.content.plain_text(), "First");
+    assert_eq!(current.version, 2);
+
+    // Every save bumps the version, not only updates
+    service.move_block(block.id, None).await.unwrap();
+    assert_eq!(service.find_one(block.id).await.unwrap().version, 3);
+    service.update(edit(block.id, "Third", None)).await.unwrap();
+    assert_eq!(service.find_one(block.id).await.unwrap().version, 4);
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_concurrent_updates_of_one_version(pool: PgPool) {
+    let note = create_note(pool.clone()).await;
+    let service = create_block_service(pool.clone());
+    let block = add_text(&service, note.id, "Draft").await;
+
+    let (first, second) = tokio::join!(
+        service.update(edit(block.id, "Mine", Some(1))),
+        service.update(edit(block.id, "Theirs", Some(1))),
+    );
+    let results = [first, second];
+    assert_eq!(results.iter().filter(|r| r.is_ok()).count(), 1);
+    assert!(
+        results
+            .iter()
+            .any(|r| matches!(r, Err(CoreError::VersionMismatch)))
+    );
+
+    let current = service.find_one(block.id).await.unwrap();
+    assert_eq!(current.version, 2);
+    let winner = if results[0].is_ok() { "Mine" } else { "Theirs" };
+    assert_eq!(current.content.plain_text(), winner);
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_note_updates_check_version(pool: PgPool) {
+    let note = create_note(pool.clone()).await;
+    let note_service = create_note_service(pool.clone());
+    let service = create_block_service(pool.clone());
+    assert_eq!(note.version, 1);
+
+    let rename = |version| NoteUpdateDTO {
+        title: Some("Plan B".to_string()),
+        version,
+    };
+    note_service.update(note.id, rename(Some(1))).await.unwrap();
+    let result = note_service.update(note.id, rename(Some(1))).await;
+    assert!(matches!(result, Err(CoreError::VersionMismatch)));
+
+    // The order of the blocks belongs to the note
+    let first = add_text(&service, note.id, "One").await;
+    let second = add_text(&service, note.id, "Two").await;
+    let result = note_service
+        .reorder_blocks(note.id, None, vec![second.id, first.id], Some(1))
+        .await;
+    assert!(matches!(result, Err(CoreError::VersionMismatch)));
+    let note = note_service.find_one(note.id).await.unwrap();
+    assert_eq!(note.blocks[0].id, first.id);
+
+    note_service
+        .reorder_blocks(note.id, None, vec![second.id, first.id], Some(2))
+        .await
+        .unwrap();
+    let note = note_service.find_one(note.id).await.unwrap();
+    assert_eq!(note.version, 3);
+    assert_eq!(note.blocks[0].id, second.id);
+    // Moving the blocks doesn't change them
+    assert!(note.blocks.iter().all(|b| b.version == 1));
+
+    // Every block under the parent has to be ordered, nothing is saved otherwise
+    let result = note_service
+        .reorder_blocks(note.id, None, vec![first.id], None)
+        .await;
+    assert!(matches!(result, Err(CoreError::InvalidBlockOrder(_))));
+    assert_eq!(note_service.find_one(note.id).await.unwrap().version, 3);
+    assert!(matches!(
+        note_service
+            .reorder_blocks(Uuid::new_v4(), None, Vec::new(), None)
+            .await,
+        Err(CoreError::NotFound)
+    ));
+}
@@ -0,0 +1,227 @@
+use crate::fixtures::{
+    create_block_service, create_note_service, create_user_fixture, create_user_repository,
+    create_webhook_service, create_workspace_fixture, create_workspace_repo,
+    spawn_webhook_receiver,
+};
+use axum::http::StatusCode;
+use chrono::Utc;
+use remind_core::errors::CoreError;
+use remind_core::webhooks::{
+    EVENT_HEADER, HttpWebhookSender, SIGNATURE_HEADER, SenderLimits, TIMESTAMP_HEADER,
+    WebhookRequest, WebhookSender, sign,
+};
+use remind_core::{
+    BlockContent, BlockCreateDTO, BlockType, ChangeType, DeliveryStatus, NoteCreateDTO,
+    NoteUpdateDTO, PlainTextContent, WebhookCreateDTO, Workspace,
+};
+use serde_json::Value;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+mod fixtures;
+
+const SECRET: &str = "a-very-long-secret";
+
+async fn create_workspace(pool: PgPool) -> Workspace {
+    let user = create_user_fixture(create_user_repository(pool.clone())).await;
+    create_workspace_fixture(create_workspace_repo(pool), user.id).await
+}
+
+fn webhook(workspace_id: Uuid, url: &str, event_types: Vec<ChangeType>) -> WebhookCreateDTO {
+    WebhookCreateDTO {
+        workspace_id,
+        url: url.to_string(),
+        secret: SECRET.to_string(),
+        event_types,
+    }
+}
+
+fn note(workspace_id: Uuid, title: &str) -> NoteCreateDTO {
+    NoteCreateDTO {
+        title: title.to_string(),
+        workspace_id,
+        parent_note: None,
+        template_id: None,
+        author: None,
+    }
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_changes_are_delivered_signed(pool: PgPool) {
+    let workspace = create_workspace(pool.clone()).await;
+    let notes = create_note_service(pool.clone());
+    let service = create_webhook_service(pool.clone());
+    let (url, receiver) = spawn_webhook_receiver().await;
+
+    // Only changes made after the webhook is registered are sent
+    notes.create(note(workspace.id, "Before")).await.unwrap();
+    let hook = service
+        .create(webhook(
+            workspace.id,
+            &url,
+            vec![ChangeType::NoteCreated, ChangeType::NoteRenamed],
+        ))
+        .await
+        .unwrap();
+    let created = notes.create(note(workspace.id, "Plan")).await.unwrap();
+    create_block_service(pool.clone())
+        .create(BlockCreateDTO {
+            block_type: BlockType::PlainText,
+            content: BlockContent::PlainText(PlainTextContent::plain("Not subscribed")),
+            note_id: created.id,
+            parent_block: None,
+        })
+        .await
+        .unwrap();
+    notes
+        .update(
+            created.id,
+            NoteUpdateDTO {
+                title: Some("Plan B".to_string()),
+                version: None,
+            },
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(service.enqueue_changes().await.unwrap(), 2);
+    assert_eq!(service.enqueue_changes().await.unwrap(), 0);
+    assert_eq!(service.deliver_due().await.unwrap(), 2);
+    assert_eq!(service.deliver_due().await.unwrap(), 0);
+
+    let received = receiver.received();
+    let events: Vec<&str> = received
+        .iter()
+        .map(|r| r.headers[EVENT_HEADER].to_str().unwrap())
+        .collect();
+    assert_eq!(events, vec!["note_created", "note_renamed"]);
+    for request in &received {
+        let timestamp: i64 = request.headers[TIMESTAMP_HEADER]
+            .to_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!((Utc::now().timestamp() - timestamp).abs() < 60);
+        assert_eq!(
+            request.headers[SIGNATURE_HEADER].to_str().unwrap(),
+            sign(SECRET, timestamp, &request.body)
+        );
+        // The signature doesn't hold for another time
+        assert_ne!(
+            request.headers[SIGNATURE_HEADER].to_str().unwrap(),
+            sign(SECRET, timestamp + 1, &request.body)
+        );
+        let payload: Value = serde_json::from_slice(&request.body).unwrap();
+        assert_eq!(payload["change"]["note_id"], created.id.to_string());
+        assert_eq!(payload["workspace_id"], workspace.id.to_string());
+    }
+
+    let deliveries = service.get_deliveries(hook.id, 10).await.unwrap();
+    assert_eq!(deliveries.len(), 2);
+    assert!(
+        deliveries
+            .iter()
+            .all(|d| d.status == DeliveryStatus::Delivered)
+    );
+    assert!(deliveries.iter().all(|d| d.last_status_code == Some(200)));
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_failed_deliveries_are_retried(pool: PgPool) {
+    let workspace = create_workspace(pool.clone()).await;
+    let service = create_webhook_service(pool.clone());
+    let (url, receiver) = spawn_webhook_receiver().await;
+    let hook = service
+        .create(webhook(workspace.id, &url, vec![ChangeType::NoteDeleted]))
+        .await
+        .unwrap();
+
+    receiver.answer_with(StatusCode::INTERNAL_SERVER_ERROR);
+    let test = service.send_test(hook.id).await.unwrap();
+    assert_eq!(test.event, "ping");
+    assert_eq!(test.status, DeliveryStatus::Pending);
+    assert_eq!(test.attempts, 1);
+    assert_eq!(test.last_status_code, Some(500));
+
+    // Retried until the last attempt, then given up
+    assert_eq!(service.deliver_due().await.unwrap(), 1);
+    assert_eq!(service.deliver_due().await.unwrap(), 1);
+    assert_eq!(service.deliver_due().await.unwrap(), 0);
+    let deliveries = service.get_deliveries(hook.id, 10).await.unwrap();
+    assert_eq!(deliveries[0].status, DeliveryStatus::Failed);
+    assert_eq!(deliveries[0].attempts, 3);
+    assert!(deliveries[0].last_error.is_some());
+    assert_eq!(receiver.received().len(), 3);
+
+    receiver.answer_with(StatusCode::NO_CONTENT);
+    let test = service.send_test(hook.id).await.unwrap();
+    assert_eq!(test.status, DeliveryStatus::Delivered);
+    assert!(test.delivered_at.is_some());
+    assert_eq!(service.get_deliveries(hook.id, 10).await.unwrap().len(), 2);
+
+    // Deliveries go with their webhook
+    service.delete(hook.id).await.unwrap();
+    assert!(matches!(
+        service.send_test(hook.id).await,
+        Err(CoreError::NotFound)
+    ));
+    assert!(
+        service
+            .get_deliveries(hook.id, 10)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[sqlx::test(migrations = "../../migrations")]
+async fn test_webhook_checks(pool: PgPool) {
+    let workspace = create_workspace(pool.clone()).await;
+    let service = create_webhook_service(pool.clone());
+    let (url, receiver) = spawn_webhook_receiver().await;
+
+    for data in [
+        webhook(
+            workspace.id,
+            "ftp://example.com/hook",
+            vec![ChangeType::NoteCreated],
+        ),
+        webhook(workspace.id, "not a url", vec![ChangeType::NoteCreated]),
+        webhook(workspace.id, &url, Vec::new()),
+        WebhookCreateDTO {
+            secret: "short".to_string(),
+            ..webhook(workspace.id, &url, vec![ChangeType::NoteCreated])
+        },
+    ] {
+        assert!(matches!(
+            service.create(data).await,
+            Err(CoreError::InvalidWebhook(_))
+        ));
+    }
+    let hook = service
+        .create(webhook(
+            workspace.id,
+            &url,
+            vec![ChangeType::BlockUpdated, ChangeType::BlockUpdated],
+        ))
+        .await
+        .unwrap();
+    assert_eq!(hook.event_types, vec![ChangeType::BlockUpdated]);
+    let listed = service.get_all_in_workspace(workspace.id).await.unwrap();
+    assert_eq!(listed.len(), 1);
+
+    // Private addresses are refused unless allowed
+    let sender = HttpWebhookSender::new(SenderLimits::default());
+    let result = sender
+        .send(WebhookRequest {
+            url,
+            event: "ping".to_string(),
+            delivery_id: Uuid::new_v4(),
+            timestamp: 0,
+            signature: sign(SECRET, 0, b"{}"),
+            body: "{}".to_string(),
+        })
+        .await;
+    assert!(result.is_err());
+    assert!(receiver.received().is_empty());
+}
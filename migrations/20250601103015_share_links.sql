@@ -0,0 +1,15 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS share_links (
+    id UUID PRIMARY KEY,
+    slug VARCHAR(64) NOT NULL UNIQUE,
+    note_id UUID NOT NULL,
+    created_by UUID NOT NULL,
+    password VARCHAR(255) NULL,
+    include_subpages BOOLEAN NOT NULL DEFAULT FALSE,
+    expires_at TIMESTAMPTZ NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    CONSTRAINT fk_share_link_note FOREIGN KEY(note_id) REFERENCES notes(id) ON DELETE CASCADE,
+    CONSTRAINT fk_share_link_user FOREIGN KEY(created_by) REFERENCES users(id)
+);
+
+CREATE INDEX share_links_note_idx ON share_links (note_id);
@@ -0,0 +1,17 @@
+-- Add migration script here
+ALTER TYPE block_type ADD VALUE IF NOT EXISTS 'File';
+
+CREATE TABLE IF NOT EXISTS attachments (
+    id UUID PRIMARY KEY,
+    workspace_id UUID NOT NULL,
+    user_id UUID NOT NULL,
+    storage_key VARCHAR(255) NOT NULL UNIQUE,
+    file_name VARCHAR(255) NOT NULL,
+    content_type VARCHAR(255) NOT NULL,
+    size BIGINT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    CONSTRAINT fk_attachment_workspace FOREIGN KEY(workspace_id) REFERENCES workspaces(id) ON DELETE CASCADE,
+    CONSTRAINT fk_attachment_user FOREIGN KEY(user_id) REFERENCES users(id)
+);
+
+CREATE INDEX attachments_workspace_idx ON attachments (workspace_id);
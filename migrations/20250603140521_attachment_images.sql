@@ -0,0 +1,5 @@
+-- Add migration script here
+ALTER TABLE attachments
+    ADD COLUMN width INT NULL,
+    ADD COLUMN height INT NULL,
+    ADD COLUMN variants INT[] NOT NULL DEFAULT '{}'
@@ -0,0 +1,11 @@
+-- Add migration script here
+ALTER TABLE blocks ADD COLUMN attachment_id UUID NULL;
+
+UPDATE blocks SET attachment_id = (content->>'attachment_id')::uuid
+WHERE content->>'attachment_id' IS NOT NULL;
+
+CREATE INDEX blocks_attachment_idx ON blocks (attachment_id);
+
+ALTER TABLE attachments ADD COLUMN orphaned_at TIMESTAMPTZ NULL;
+
+CREATE INDEX attachments_user_idx ON attachments (user_id);
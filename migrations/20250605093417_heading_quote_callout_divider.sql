@@ -0,0 +1,5 @@
+-- Add migration script here
+ALTER TYPE block_type ADD VALUE IF NOT EXISTS 'Heading';
+ALTER TYPE block_type ADD VALUE IF NOT EXISTS 'Quote';
+ALTER TYPE block_type ADD VALUE IF NOT EXISTS 'Callout';
+ALTER TYPE block_type ADD VALUE IF NOT EXISTS 'Divider';
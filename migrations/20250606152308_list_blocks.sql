@@ -0,0 +1,3 @@
+-- Add migration script here
+ALTER TYPE block_type ADD VALUE IF NOT EXISTS 'BulletedList';
+ALTER TYPE block_type ADD VALUE IF NOT EXISTS 'NumberedList';
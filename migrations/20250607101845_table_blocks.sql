@@ -0,0 +1,2 @@
+-- Add migration script here
+ALTER TYPE block_type ADD VALUE IF NOT EXISTS 'Table';
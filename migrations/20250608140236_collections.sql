@@ -0,0 +1,39 @@
+-- Add migration script here
+CREATE TYPE property_type AS ENUM ('Text', 'Number', 'Checkbox', 'Date', 'Select', 'MultiSelect', 'Person');
+CREATE TYPE view_type AS ENUM ('Table', 'Board', 'List');
+
+CREATE TABLE IF NOT EXISTS collection_properties (
+    id UUID PRIMARY KEY,
+    note_id UUID NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    property_type property_type NOT NULL,
+    options TEXT[] NOT NULL DEFAULT '{}',
+    position INT NOT NULL,
+    CONSTRAINT fk_property_note FOREIGN KEY(note_id) REFERENCES notes(id) ON DELETE CASCADE
+);
+
+CREATE INDEX collection_properties_note_idx ON collection_properties (note_id);
+
+CREATE TABLE IF NOT EXISTS note_property_values (
+    note_id UUID NOT NULL,
+    property_id UUID NOT NULL,
+    value JSONB NOT NULL,
+    PRIMARY KEY (note_id, property_id),
+    CONSTRAINT fk_value_note FOREIGN KEY(note_id) REFERENCES notes(id) ON DELETE CASCADE,
+    CONSTRAINT fk_value_property FOREIGN KEY(property_id) REFERENCES collection_properties(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS collection_views (
+    id UUID PRIMARY KEY,
+    note_id UUID NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    view_type view_type NOT NULL,
+    filters JSONB NOT NULL DEFAULT '[]',
+    sorts JSONB NOT NULL DEFAULT '[]',
+    group_by UUID NULL,
+    position INT NOT NULL,
+    CONSTRAINT fk_view_note FOREIGN KEY(note_id) REFERENCES notes(id) ON DELETE CASCADE,
+    CONSTRAINT fk_view_group_by FOREIGN KEY(group_by) REFERENCES collection_properties(id) ON DELETE SET NULL
+);
+
+CREATE INDEX collection_views_note_idx ON collection_views (note_id);
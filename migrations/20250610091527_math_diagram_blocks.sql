@@ -0,0 +1,3 @@
+-- Add migration script here
+ALTER TYPE block_type ADD VALUE IF NOT EXISTS 'Math';
+ALTER TYPE block_type ADD VALUE IF NOT EXISTS 'Diagram';
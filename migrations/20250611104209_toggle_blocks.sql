@@ -0,0 +1,7 @@
+-- Add migration script here
+ALTER TYPE block_type ADD VALUE IF NOT EXISTS 'Toggle';
+
+ALTER TABLE blocks
+    ADD COLUMN parent_block UUID REFERENCES blocks(id) ON DELETE CASCADE;
+
+CREATE INDEX blocks_parent_block_idx ON blocks(parent_block);
@@ -0,0 +1,14 @@
+-- Add migration script here
+CREATE TYPE mention_type AS ENUM ('Note', 'User');
+
+-- Index of the notes and users mentioned by blocks. Targets have no foreign
+-- key, so mentions of deleted notes stay and show up as broken.
+CREATE TABLE IF NOT EXISTS block_links (
+    block_id UUID NOT NULL,
+    target_type mention_type NOT NULL,
+    target_id UUID NOT NULL,
+    PRIMARY KEY (block_id, target_type, target_id),
+    CONSTRAINT fk_block_link_block FOREIGN KEY(block_id) REFERENCES blocks(id) ON DELETE CASCADE
+);
+
+CREATE INDEX block_links_target_idx ON block_links (target_id);
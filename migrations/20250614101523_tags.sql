@@ -0,0 +1,20 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS tags (
+    id UUID PRIMARY KEY,
+    workspace_id UUID NOT NULL,
+    name VARCHAR(64) NOT NULL,
+    color VARCHAR(7) NOT NULL,
+    CONSTRAINT fk_tag_workspace FOREIGN KEY(workspace_id) REFERENCES workspaces(id) ON DELETE CASCADE
+);
+
+CREATE UNIQUE INDEX tags_workspace_name_idx ON tags (workspace_id, lower(name));
+
+CREATE TABLE IF NOT EXISTS note_tags (
+    note_id UUID NOT NULL,
+    tag_id UUID NOT NULL,
+    PRIMARY KEY (note_id, tag_id),
+    CONSTRAINT fk_note_tag_note FOREIGN KEY(note_id) REFERENCES notes(id) ON DELETE CASCADE,
+    CONSTRAINT fk_note_tag_tag FOREIGN KEY(tag_id) REFERENCES tags(id) ON DELETE CASCADE
+);
+
+CREATE INDEX note_tags_tag_idx ON note_tags (tag_id);
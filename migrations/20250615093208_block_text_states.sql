@@ -0,0 +1,9 @@
+-- Add migration script here
+-- Text CRDT of PlainText and Code blocks, created with the first merged
+-- edit. `version` guards against concurrent merges overwriting each other.
+CREATE TABLE IF NOT EXISTS block_text_states (
+    block_id UUID PRIMARY KEY,
+    state JSONB NOT NULL,
+    version BIGINT NOT NULL DEFAULT 0,
+    CONSTRAINT fk_block_text_state_block FOREIGN KEY(block_id) REFERENCES blocks(id) ON DELETE CASCADE
+);
@@ -0,0 +1,5 @@
+-- Add migration script here
+-- Bumped by every save. Clients send the version they edited as `If-Match`,
+-- so edits based on an outdated note or block are refused.
+ALTER TABLE notes ADD COLUMN version BIGINT NOT NULL DEFAULT 1;
+ALTER TABLE blocks ADD COLUMN version BIGINT NOT NULL DEFAULT 1;
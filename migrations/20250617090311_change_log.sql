@@ -0,0 +1,24 @@
+-- Add migration script here
+CREATE TYPE change_type AS ENUM (
+    'NoteCreated',
+    'NoteRenamed',
+    'NoteDeleted',
+    'BlockCreated',
+    'BlockUpdated',
+    'BlockDeleted',
+    'BlocksReordered'
+);
+
+-- Changes of a workspace in the order they happened, for clients catching up.
+-- Notes and blocks have no foreign key, deletions are logged too.
+CREATE TABLE IF NOT EXISTS change_log (
+    id BIGSERIAL PRIMARY KEY,
+    workspace_id UUID NOT NULL,
+    change_type change_type NOT NULL,
+    note_id UUID NOT NULL,
+    block_id UUID,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    CONSTRAINT fk_change_workspace FOREIGN KEY(workspace_id) REFERENCES workspaces(id) ON DELETE CASCADE
+);
+
+CREATE INDEX change_log_workspace_idx ON change_log (workspace_id, id);
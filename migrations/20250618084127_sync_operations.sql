@@ -0,0 +1,11 @@
+-- Add migration script here
+-- Results of the operations pushed by offline clients, so an operation sent
+-- again gets its first result back instead of being applied twice
+CREATE TABLE IF NOT EXISTS sync_operations (
+    id UUID NOT NULL,
+    user_id UUID NOT NULL,
+    result JSONB NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (user_id, id),
+    CONSTRAINT fk_sync_operation_user FOREIGN KEY(user_id) REFERENCES users(id) ON DELETE CASCADE
+);
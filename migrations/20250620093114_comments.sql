@@ -0,0 +1,24 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS comments (
+    id UUID PRIMARY KEY,
+    note_id UUID NOT NULL,
+    -- Block the thread is anchored to. Kept when the block is deleted.
+    block_id UUID,
+    -- Thread a reply belongs to, NULL for the comment starting the thread
+    thread_id UUID,
+    author_id UUID NOT NULL,
+    body TEXT NOT NULL,
+    orphaned BOOLEAN NOT NULL DEFAULT false,
+    resolved_at TIMESTAMPTZ,
+    resolved_by UUID,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    edited_at TIMESTAMPTZ,
+    CONSTRAINT fk_comment_note FOREIGN KEY(note_id) REFERENCES notes(id) ON DELETE CASCADE,
+    CONSTRAINT fk_comment_thread FOREIGN KEY(thread_id) REFERENCES comments(id) ON DELETE CASCADE,
+    CONSTRAINT fk_comment_author FOREIGN KEY(author_id) REFERENCES users(id) ON DELETE CASCADE,
+    CONSTRAINT fk_comment_resolver FOREIGN KEY(resolved_by) REFERENCES users(id) ON DELETE SET NULL
+);
+
+CREATE INDEX comments_note_idx ON comments (note_id, created_at);
+CREATE INDEX comments_block_idx ON comments (block_id) WHERE block_id IS NOT NULL;
+CREATE INDEX comments_thread_idx ON comments (thread_id);
@@ -0,0 +1,31 @@
+-- Add migration script here
+CREATE TYPE notification_kind AS ENUM ('Mention', 'CommentReply');
+
+CREATE TABLE IF NOT EXISTS notifications (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL,
+    kind notification_kind NOT NULL,
+    -- User who caused it, if known
+    actor_id UUID,
+    note_id UUID,
+    block_id UUID,
+    comment_id UUID,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    read_at TIMESTAMPTZ,
+    CONSTRAINT fk_notification_user FOREIGN KEY(user_id) REFERENCES users(id) ON DELETE CASCADE,
+    CONSTRAINT fk_notification_actor FOREIGN KEY(actor_id) REFERENCES users(id) ON DELETE SET NULL,
+    CONSTRAINT fk_notification_note FOREIGN KEY(note_id) REFERENCES notes(id) ON DELETE CASCADE,
+    CONSTRAINT fk_notification_comment FOREIGN KEY(comment_id) REFERENCES comments(id) ON DELETE CASCADE
+);
+
+CREATE INDEX notifications_user_idx ON notifications (user_id, created_at DESC, id DESC);
+CREATE INDEX notifications_unread_idx ON notifications (user_id) WHERE read_at IS NULL;
+
+-- Choices of users, kinds without a row notify
+CREATE TABLE IF NOT EXISTS notification_preferences (
+    user_id UUID NOT NULL,
+    kind notification_kind NOT NULL,
+    enabled BOOLEAN NOT NULL,
+    PRIMARY KEY (user_id, kind),
+    CONSTRAINT fk_notification_preference_user FOREIGN KEY(user_id) REFERENCES users(id) ON DELETE CASCADE
+);
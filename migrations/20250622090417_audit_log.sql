@@ -0,0 +1,36 @@
+-- Add migration script here
+CREATE TYPE audit_action AS ENUM (
+    'LoginSucceeded',
+    'LoginFailed',
+    'TokenCreated',
+    'NoteDeleted',
+    'BlockDeleted',
+    'NoteExported',
+    'ShareLinkCreated'
+);
+
+-- Ids aren't foreign keys, entries outlive what they are about
+CREATE TABLE IF NOT EXISTS audit_log (
+    id BIGSERIAL PRIMARY KEY,
+    action audit_action NOT NULL,
+    actor_id UUID,
+    ip TEXT,
+    user_agent TEXT,
+    workspace_id UUID,
+    note_id UUID,
+    target_id UUID,
+    details JSONB NOT NULL DEFAULT '{}',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX audit_log_workspace_idx ON audit_log (workspace_id, id DESC);
+
+CREATE FUNCTION audit_log_append_only() RETURNS trigger AS $$
+BEGIN
+    RAISE EXCEPTION 'audit_log is append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER audit_log_append_only
+    BEFORE UPDATE OR DELETE ON audit_log
+    FOR EACH ROW EXECUTE FUNCTION audit_log_append_only();
@@ -0,0 +1,18 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS note_templates (
+    id UUID PRIMARY KEY,
+    workspace_id UUID NOT NULL,
+    name TEXT NOT NULL,
+    -- Title of the notes made from the template, may hold variables
+    title TEXT NOT NULL,
+    icon_type note_icon_type NOT NULL,
+    icon_data TEXT NOT NULL,
+    -- Block tree copied from the note the template was saved from
+    blocks JSONB NOT NULL DEFAULT '[]',
+    created_by UUID,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    CONSTRAINT fk_note_template_workspace FOREIGN KEY(workspace_id) REFERENCES workspaces(id) ON DELETE CASCADE,
+    CONSTRAINT fk_note_template_creator FOREIGN KEY(created_by) REFERENCES users(id) ON DELETE SET NULL
+);
+
+CREATE INDEX note_templates_workspace_idx ON note_templates (workspace_id, name);
@@ -0,0 +1,11 @@
+-- Add migration script here
+-- Ids follow the order changes were logged in, not the order their
+-- transactions committed. Readers follow `seq` instead, given to changes once
+-- they are committed, so a cursor never moves past a change that shows up later.
+ALTER TABLE change_log ADD COLUMN seq BIGINT;
+UPDATE change_log SET seq = id;
+
+CREATE UNIQUE INDEX change_log_seq_idx ON change_log (seq);
+DROP INDEX change_log_workspace_idx;
+CREATE INDEX change_log_workspace_seq_idx ON change_log (workspace_id, seq);
+CREATE INDEX change_log_unsequenced_idx ON change_log (id) WHERE seq IS NULL;
@@ -0,0 +1,4 @@
+-- Add migration script here
+-- Blocks go with their note, like its comments and tags do
+ALTER TABLE blocks DROP CONSTRAINT fk_block_note;
+ALTER TABLE blocks ADD CONSTRAINT fk_block_note FOREIGN KEY(note_id) REFERENCES notes(id) ON DELETE CASCADE;
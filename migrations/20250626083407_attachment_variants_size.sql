@@ -0,0 +1,3 @@
+-- Add migration script here
+-- Thumbnails count towards the storage quotas like the upload itself
+ALTER TABLE attachments ADD COLUMN variants_size BIGINT NOT NULL DEFAULT 0;